@@ -0,0 +1,35 @@
+use crate::lexer::Literal;
+use crate::types::{DataType, SignedInteger, UnsignedInteger};
+
+// Helpers shared by the backends that write GNU assembler source.
+
+// 128-bit integers take two registers, everything else fits in one
+pub fn is_wide(dtype: DataType) -> bool {
+    matches!(dtype, DataType::SignedInteger(SignedInteger::i128) | DataType::UnsignedInteger(UnsignedInteger::u128))
+}
+
+pub fn literal_value(literal: &Literal) -> (i128, DataType) {
+    match literal {
+        Literal::SignedInteger((n, dtype)) => (*n, DataType::SignedInteger(*dtype)),
+        Literal::UnsignedInteger((n, dtype)) => (*n as i128, DataType::UnsignedInteger(*dtype)),
+        Literal::Boolean(b) => (*b as i128, DataType::bool),
+        Literal::String(_) => (0, DataType::str),
+        Literal::Null(dtype) => (0, *dtype),
+    }
+}
+
+// the body of an `.asciz` directive holding `string`
+pub fn escape(string: &str) -> String {
+    string.bytes().map(|byte| match byte {
+        b'"' => "\\\"".into(),
+        b'\\' => "\\\\".into(),
+        0x20..=0x7e => (byte as char).to_string(),
+        _ => format!("\\{:03o}", byte),
+    }).collect()
+}
+
+pub fn emit(code: &mut String, instr: impl AsRef<str>) {
+    code.push_str("    ");
+    code.push_str(instr.as_ref());
+    code.push('\n');
+}
\ No newline at end of file
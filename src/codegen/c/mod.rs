@@ -0,0 +1,593 @@
+use std::cmp::max;
+use std::collections::{HashMap, HashSet};
+
+use crate::codegen::asm::{is_wide, literal_value};
+use crate::codegen::llvm::entry::{find_main, Entry};
+use crate::error::CodegenError;
+use crate::format;
+use crate::lexer::Literal;
+use crate::parser::{DefStatement, Expression, Identifier, IfBranch, IfStatement, LetStatement, Operator, Statement, AST};
+use crate::types::{infix_type, is_integer, is_pointer_compatible, is_string_buffer, unary_type, DataType, FloatingPoint, SignedInteger, UnsignedInteger};
+
+// the user's `main` is renamed so the C `main` can call it
+pub const MAIN_FUNCTION: &str = "mamba_main";
+
+// C99 keywords and the names `<stdbool.h>` defines, which identifiers must not take
+const RESERVED: [&str; 40] = [
+    "auto", "break", "case", "char", "const", "continue", "default", "do", "double", "else", "enum", "extern",
+    "float", "for", "goto", "if", "inline", "int", "long", "register", "restrict", "return", "short", "signed",
+    "sizeof", "static", "struct", "switch", "typedef", "union", "unsigned", "void", "volatile", "while",
+    "_Bool", "_Complex", "_Imaginary", "bool", "true", "false",
+];
+
+// Translates a folded AST to a single C99 translation unit. Every expression is printed fully
+// parenthesized, and the arithmetic that may overflow goes through unsigned types, so the C
+// program wraps around like the other backends instead of running into undefined behavior.
+pub struct CGen {
+    ast: AST,
+    entry: Option<Entry>,
+    functions: HashMap<Identifier, (Vec<DataType>, DataType)>,
+    globals: HashMap<Identifier, DataType>,
+    // C names of the variables of the function being generated, innermost scope last
+    scopes: Vec<HashMap<Identifier, (String, DataType)>>,
+    // C names taken in the function being generated, so shadowing variables get fresh ones
+    taken: HashSet<String>,
+    // name and return type of the function being generated
+    current: (Identifier, DataType),
+}
+
+impl CGen {
+    pub fn new(ast: AST) -> CGen {
+        CGen {
+            ast,
+            entry: None,
+            functions: HashMap::new(),
+            globals: HashMap::new(),
+            scopes: Vec::new(),
+            taken: HashSet::new(),
+            current: (String::new(), DataType::void),
+        }
+    }
+
+    // wraps the program's `main` in the C `main`, without one the module is generated as a library
+    pub fn with_entry(mut self, entry: Entry) -> CGen {
+        self.entry = Some(entry);
+        self
+    }
+
+    pub fn generate_c(mut self) -> Result<String, CodegenError> {
+        let ast = std::mem::take(&mut self.ast);
+
+        for stmt in &ast {
+            match stmt {
+                Statement::Def(stmt) if !stmt.type_params.is_empty() => return Err(CodegenError(format!("[C] generic function `{}` is not supported", stmt.name))),
+                Statement::Def(stmt) => {
+                    check_signature(&stmt.name, &stmt.params, stmt.r#type)?;
+                    self.functions.insert(stmt.name.clone(), (stmt.params.iter().map(|(_, dtype)| *dtype).collect(), stmt.r#type));
+                },
+                Statement::Extern(stmt) => {
+                    check_signature(&stmt.name, &stmt.params, stmt.r#type)?;
+                    self.functions.insert(stmt.name.clone(), (stmt.params.iter().map(|(_, dtype)| *dtype).collect(), stmt.r#type));
+                },
+                Statement::Let(stmt) => {
+                    check_type(stmt.r#type, &stmt.ident)?;
+                    self.globals.insert(stmt.ident.clone(), stmt.r#type);
+                },
+                // a declaration only introduces a type, which the values using it are checked for
+                Statement::Struct(_) | Statement::Enum(_) => {},
+                _ => return Err(CodegenError(format!("[C] {} cannot be global", stmt))),
+            }
+        }
+
+        let mut result = String::from("#include <stdbool.h>\n#include <stdint.h>\n");
+
+        // every function is declared up front, since it can be called before its definition
+        let prototypes = ast.iter()
+            .filter_map(|stmt| match stmt {
+                Statement::Extern(stmt) => Some(format!("{};\n", self.signature(&stmt.name, &stmt.params, stmt.r#type))),
+                Statement::Def(stmt) => Some(format!("{};\n", self.signature(&stmt.name, &stmt.params, stmt.r#type))),
+                _ => None,
+            })
+            .collect::<String>();
+
+        if !prototypes.is_empty() {
+            result += "\n";
+            result += &prototypes;
+        }
+
+        let globals = ast.iter()
+            .filter_map(|stmt| match stmt {
+                Statement::Let(stmt) => Some(generate_global(stmt)),
+                _ => None,
+            })
+            .collect::<Result<String, CodegenError>>()?;
+
+        if !globals.is_empty() {
+            result += "\n";
+            result += &globals;
+        }
+
+        for stmt in &ast {
+            if let Statement::Def(stmt) = stmt {
+                result += "\n";
+                result += &self.generate_def(stmt)?;
+            }
+        }
+
+        if let Some(entry) = self.entry {
+            result += &generate_entry(entry, &ast)?;
+        }
+
+        Ok(result)
+    }
+
+    fn symbol(&self, name: &str) -> String {
+        if self.entry.is_some() && name == "main" {
+            MAIN_FUNCTION.into()
+        } else {
+            escape_name(name)
+        }
+    }
+
+    fn signature(&self, name: &str, params: &[(Identifier, DataType)], r#type: DataType) -> String {
+        let params = if params.is_empty() {
+            "void".into()
+        } else {
+            params.iter().map(|(ident, dtype)| declare(*dtype, &escape_name(ident))).collect::<Vec<String>>().join(", ")
+        };
+
+        format!("{}({})", declare(r#type, &self.symbol(name)), params)
+    }
+
+    // picks a C name for a new variable `ident`, which must not hide a variable still in use
+    fn bind(&mut self, ident: &Identifier, dtype: DataType) -> String {
+        let mut name = escape_name(ident);
+        let mut n = 0;
+
+        while self.taken.contains(&name) || self.globals.contains_key(&name) || self.functions.contains_key(&name) {
+            n += 1;
+            name = format!("{}_{}", escape_name(ident), n);
+        }
+
+        self.taken.insert(name.clone());
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(ident.clone(), (name.clone(), dtype));
+        }
+
+        name
+    }
+
+    fn generate_def(&mut self, stmt: &DefStatement) -> Result<String, CodegenError> {
+        self.current = (stmt.name.clone(), stmt.r#type);
+        self.scopes = vec![HashMap::new()];
+        self.taken = HashSet::new();
+
+        // parameters keep their names, the signature has already been declared with them
+        for (ident, dtype) in &stmt.params {
+            self.taken.insert(escape_name(ident));
+            self.scopes[0].insert(ident.clone(), (escape_name(ident), *dtype));
+        }
+
+        let mut result = format!("{} {{\n", self.signature(&stmt.name, &stmt.params, stmt.r#type));
+        self.generate_block(&stmt.stmts, 1, &mut result)?;
+        result += "}\n";
+
+        Ok(result)
+    }
+
+    // variables declared in `stmts` are only visible until the end of the block
+    fn generate_block(&mut self, stmts: &[Statement], depth: usize, code: &mut String) -> Result<(), CodegenError> {
+        self.scopes.push(HashMap::new());
+
+        for stmt in stmts {
+            self.generate_stmt(stmt, depth, code)?;
+        }
+
+        self.scopes.pop();
+        Ok(())
+    }
+
+    fn generate_stmt(&mut self, stmt: &Statement, depth: usize, code: &mut String) -> Result<(), CodegenError> {
+        let indent = "    ".repeat(depth);
+
+        match stmt {
+            Statement::Let(stmt) => {
+                check_type(stmt.r#type, &stmt.ident)?;
+                let (expr, dtype) = self.generate_expr(&stmt.expr)?;
+                let expr = coerce(expr, dtype, stmt.r#type, &stmt.ident)?;
+
+                // the initializer is generated first, so it still sees the variable it shadows
+                let name = self.bind(&stmt.ident, stmt.r#type);
+                code.push_str(&format!("{indent}{} = {};\n", declare(stmt.r#type, &name), expr));
+            },
+            Statement::If(stmt) => {
+                code.push_str(&indent);
+                self.generate_if(stmt, depth, code)?;
+                code.push('\n');
+            },
+            Statement::While(stmt) => {
+                let condition = self.generate_condition(&stmt.condition)?;
+
+                code.push_str(&format!("{indent}while {} {{\n", condition));
+                self.generate_block(&stmt.blocks, depth + 1, code)?;
+                code.push_str(&format!("{indent}}}\n"));
+            },
+            Statement::Return(stmt) => {
+                let (name, r#type) = self.current.clone();
+                let (expr, dtype) = self.generate_expr(&stmt.expr)?;
+
+                // a void function drops whatever it is asked to return
+                if r#type == DataType::void {
+                    code.push_str(&format!("{indent}(void){};\n{indent}return;\n", expr));
+                } else {
+                    code.push_str(&format!("{indent}return {};\n", coerce(expr, dtype, r#type, &name)?));
+                }
+            },
+            Statement::Expression(stmt) => {
+                let (expr, _) = self.generate_expr(&stmt.expr)?;
+                code.push_str(&format!("{indent}{};\n", expr));
+            },
+            Statement::Match(_) => return Err(CodegenError("[C] `match` is not supported".into())),
+            Statement::Destructure(_) => return Err(CodegenError("[C] tuples are not supported".into())),
+            Statement::Def(_) | Statement::Extern(_) | Statement::Struct(_) | Statement::Enum(_) => return Err(CodegenError(format!("[C] {} cannot be local", stmt))),
+            Statement::Assign(_) => return Err(CodegenError("[C] arrays, pointers and structs are not supported".into())),
+        }
+
+        Ok(())
+    }
+
+    // an `elif` chain becomes `else if`, the caller indents the first line and ends the last one
+    fn generate_if(&mut self, stmt: &IfStatement, depth: usize, code: &mut String) -> Result<(), CodegenError> {
+        let indent = "    ".repeat(depth);
+        let condition = self.generate_condition(&stmt.condition)?;
+
+        code.push_str(&format!("if {} {{\n", condition));
+        self.generate_block(&stmt.then, depth + 1, code)?;
+        code.push_str(&format!("{indent}}}"));
+
+        match stmt.r#else.as_ref() {
+            IfBranch::Elif(stmt) => {
+                code.push_str(" else ");
+                self.generate_if(stmt, depth, code)?;
+            },
+            IfBranch::Else(stmts) => {
+                code.push_str(" else {\n");
+                self.generate_block(stmts, depth + 1, code)?;
+                code.push_str(&format!("{indent}}}"));
+            },
+            IfBranch::None => {},
+        }
+
+        Ok(())
+    }
+
+    fn generate_condition(&mut self, expr: &Expression) -> Result<String, CodegenError> {
+        match self.generate_expr(expr)? {
+            (expr, DataType::bool) => Ok(if expr.starts_with('(') { expr } else { format!("({})", expr) }),
+            (_, dtype) => Err(CodegenError(format!("[C] expected a bool condition, found {}", dtype))),
+        }
+    }
+
+    // returns the C expression computing `expr`, parenthesized unless it is a single name, literal or call, and its type
+    fn generate_expr(&mut self, expr: &Expression) -> Result<(String, DataType), CodegenError> {
+        match expr {
+            Expression::Literal((Literal::Null(dtype), _)) => Ok(("((void *)0)".into(), *dtype)),
+            // like all pointers, the address of a variable is only handed to the C functions
+            Expression::Unary(expr) if expr.operator == Operator::AddressOf => {
+                let (name, dtype) = self.addressed_variable(&expr.right)?;
+                Ok((format!("(&{})", name), DataType::pointer(dtype)))
+            },
+            Expression::Unary(expr) if expr.operator == Operator::Dereference => Err(CodegenError(format!("[C] operator {:?} is not supported", expr.operator))),
+            Expression::Struct(_) | Expression::Member(_) | Expression::MethodCall(_) => Err(CodegenError("[C] structs are not supported".into())),
+            Expression::Enum(_) => Err(CodegenError("[C] enums are not supported".into())),
+            Expression::Tuple(_) => Err(CodegenError("[C] tuples are not supported".into())),
+            Expression::Literal((Literal::String(string), _)) => Ok((format!("\"{}\"", escape(string)), DataType::str)),
+            Expression::Literal((literal, _)) => {
+                let (value, dtype) = literal_value(literal);
+                Ok((generate_literal(value, dtype), dtype))
+            },
+            Expression::Identifier(ident) => {
+                if let Some((name, dtype)) = self.scopes.iter().rev().find_map(|scope| scope.get(ident)) {
+                    Ok((name.clone(), *dtype))
+                } else if let Some(&dtype) = self.globals.get(ident) {
+                    Ok((escape_name(ident), dtype))
+                } else {
+                    Err(CodegenError(format!("[C] identifier `{}` not found", ident)))
+                }
+            },
+            Expression::Unary(expr) => {
+                let (right, dtype) = self.generate_expr(&expr.right)?;
+                let result = unary_type(expr.operator, dtype)
+                    .ok_or_else(|| CodegenError(format!("[C] operator {:?} cannot be applied to {}", expr.operator, dtype)))?;
+
+                let code = match expr.operator {
+                    // negating the minimum overflows, so it is computed as `0 - x` in the unsigned type
+                    Operator::UnaryMinus => format!("(({})(0 - ({}){}))", c_type(dtype), unsigned_type(dtype), right),
+                    Operator::BitwiseNot => format!("(({})~{})", c_type(dtype), right),
+                    _ => right,
+                };
+
+                Ok((code, result))
+            },
+            Expression::Infix(expr) => {
+                let (left_code, left) = self.generate_expr(&expr.left)?;
+                let (right_code, right) = self.generate_expr(&expr.right)?;
+
+                let dtype = max(left, right);
+                let result = infix_type(expr.operator, dtype)
+                    .filter(|_| left == right || (is_integer(left) && is_integer(right)))
+                    .ok_or_else(|| CodegenError(format!("[C] operator {:?} cannot be applied to {} and {}", expr.operator, left, right)))?;
+
+                let (left_code, right_code) = (cast(left_code, left, dtype), cast(right_code, right, dtype));
+                let symbol = operator_symbol(expr.operator);
+
+                let code = match expr.operator {
+                    Operator::Plus | Operator::Minus | Operator::Multiply | Operator::LeftShift if c_type(dtype) != unsigned_type(dtype) => {
+                        let unsigned = unsigned_type(dtype);
+                        format!("(({})(({}){} {} ({}){}))", c_type(dtype), unsigned, left_code, symbol, unsigned, right_code)
+                    },
+                    // the operands of narrow types are promoted to `int`, the result is brought back to `dtype`
+                    Operator::Divide | Operator::Modulo | Operator::RightShift if narrower_than_int(dtype) => {
+                        format!("(({})({} {} {}))", c_type(dtype), left_code, symbol, right_code)
+                    },
+                    _ => format!("({} {} {})", left_code, symbol, right_code),
+                };
+
+                Ok((code, result))
+            },
+            Expression::Index(_) | Expression::Array(_) => Err(CodegenError("[C] arrays are not supported".into())),
+            Expression::FormatString(expr) => {
+                let types = expr.values()
+                    .map(|value| self.type_of(value))
+                    .collect::<Result<Vec<DataType>, CodegenError>>()?;
+
+                self.generate_expr(&format::lower(expr, &types).map_err(|err| CodegenError(err.0))?)
+            },
+            Expression::FnCall(expr) => {
+                let (params, r#type) = self.functions.get(&expr.ident).cloned()
+                    .ok_or_else(|| CodegenError(format!("[C] unable to find function `{}`", expr.ident)))?;
+
+                if params.len() != expr.args.len() {
+                    return Err(CodegenError(format!("[C] `{}` takes {} arguments but {} were given", expr.ident, params.len(), expr.args.len())));
+                }
+
+                let args = expr.args.iter().zip(&params)
+                    .map(|(arg, param)| {
+                        let (code, dtype) = self.generate_expr(arg)?;
+                        coerce(code, dtype, *param, &expr.ident)
+                    })
+                    .collect::<Result<Vec<String>, CodegenError>>()?;
+
+                Ok((format!("{}({})", self.symbol(&expr.ident), args.join(", ")), r#type))
+            },
+        }
+    }
+
+    // the type of `expr` without generating it, for the values of an f-string
+    fn type_of(&self, expr: &Expression) -> Result<DataType, CodegenError> {
+        match expr {
+            Expression::Literal((Literal::Null(dtype), _)) => Ok(*dtype),
+            Expression::Unary(expr) if expr.operator == Operator::AddressOf => self.addressed_variable(&expr.right).map(|(_, dtype)| DataType::pointer(dtype)),
+            Expression::Unary(expr) if expr.operator == Operator::Dereference => Err(CodegenError(format!("[C] operator {:?} is not supported", expr.operator))),
+            Expression::Struct(_) | Expression::Member(_) | Expression::MethodCall(_) => Err(CodegenError("[C] structs are not supported".into())),
+            Expression::Enum(_) => Err(CodegenError("[C] enums are not supported".into())),
+            Expression::Tuple(_) => Err(CodegenError("[C] tuples are not supported".into())),
+            Expression::FormatString(expr) => Ok(expr.dtype()),
+            Expression::Literal((literal, _)) => Ok(literal_value(literal).1),
+            Expression::Identifier(ident) => self.scopes.iter().rev()
+                .find_map(|scope| scope.get(ident).map(|(_, dtype)| *dtype))
+                .or_else(|| self.globals.get(ident).copied())
+                .ok_or_else(|| CodegenError(format!("[C] identifier `{}` not found", ident))),
+            Expression::Unary(expr) => {
+                let dtype = self.type_of(&expr.right)?;
+                unary_type(expr.operator, dtype)
+                    .ok_or_else(|| CodegenError(format!("[C] operator {:?} cannot be applied to {}", expr.operator, dtype)))
+            },
+            Expression::Infix(expr) => {
+                let (left, right) = (self.type_of(&expr.left)?, self.type_of(&expr.right)?);
+                infix_type(expr.operator, max(left, right))
+                    .filter(|_| left == right || (is_integer(left) && is_integer(right)))
+                    .ok_or_else(|| CodegenError(format!("[C] operator {:?} cannot be applied to {} and {}", expr.operator, left, right)))
+            },
+            Expression::Index(_) | Expression::Array(_) => Err(CodegenError("[C] arrays are not supported".into())),
+            Expression::FnCall(expr) => self.functions.get(&expr.ident)
+                .map(|(_, r#type)| *r#type)
+                .ok_or_else(|| CodegenError(format!("[C] unable to find function `{}`", expr.ident))),
+        }
+    }
+
+    // the C name and type of the variable `expr` names, the only thing whose address can be taken
+    fn addressed_variable(&self, expr: &Expression) -> Result<(String, DataType), CodegenError> {
+        match expr {
+            Expression::Identifier(ident) => {
+                if let Some((name, dtype)) = self.scopes.iter().rev().find_map(|scope| scope.get(ident)) {
+                    Ok((name.clone(), *dtype))
+                } else if let Some(&dtype) = self.globals.get(ident) {
+                    Ok((escape_name(ident), dtype))
+                } else {
+                    Err(CodegenError(format!("[C] identifier `{}` not found", ident)))
+                }
+            },
+            expr => Err(CodegenError(format!("[C] taking the address of {} is not supported", expr))),
+        }
+    }
+}
+
+fn generate_entry(entry: Entry, ast: &AST) -> Result<String, CodegenError> {
+    let main = find_main(ast).map_err(|err| CodegenError(err.0))?;
+
+    if entry == Entry::Freestanding {
+        return Err(CodegenError("[C] a freestanding entry point cannot be written in portable C".into()));
+    }
+
+    if main.r#type == DataType::void {
+        Ok(format!("\nint main(void) {{\n    {}();\n    return 0;\n}}\n", MAIN_FUNCTION))
+    } else {
+        Ok(format!("\nint main(void) {{\n    return {}();\n}}\n", MAIN_FUNCTION))
+    }
+}
+
+fn generate_global(stmt: &LetStatement) -> Result<String, CodegenError> {
+    let value = match &stmt.expr {
+        Expression::Literal((Literal::String(string), _)) if stmt.r#type == DataType::str => format!("\"{}\"", escape(string)),
+        Expression::Literal((Literal::Boolean(b), _)) if stmt.r#type == DataType::bool => b.to_string(),
+        Expression::Literal((literal @ (Literal::SignedInteger(_) | Literal::UnsignedInteger(_)), _)) if is_integer(stmt.r#type) => {
+            generate_literal(literal_value(literal).0, stmt.r#type)
+        },
+        _ => return Err(CodegenError(format!("[C] global `{}` must be initialized with a constant of type {}", stmt.ident, stmt.r#type))),
+    };
+
+    Ok(format!("{} = {};\n", declare(stmt.r#type, &escape_name(&stmt.ident)), value))
+}
+
+// `value` as a constant of `dtype`; `int` literals stand for themselves, the rest is cast
+fn generate_literal(value: i128, dtype: DataType) -> String {
+    if dtype == DataType::bool {
+        return (value != 0).to_string();
+    }
+
+    let literal = if i32::try_from(value).is_ok() {
+        value.to_string()
+    } else if i64::try_from(value).is_ok() && value != i64::MIN as i128 {
+        format!("INT64_C({})", value)
+    } else if u64::try_from(value).is_ok() {
+        format!("UINT64_C({})", value)
+    } else {
+        // C has no 128-bit literals
+        let bits = value as u128;
+        format!("(((unsigned __int128)UINT64_C({}) << 64) | UINT64_C({}))", bits >> 64, bits as u64)
+    };
+
+    match dtype {
+        DataType::SignedInteger(SignedInteger::i32) if i32::try_from(value).is_ok() => {
+            if value < 0 { format!("({})", literal) } else { literal }
+        },
+        _ => format!("(({}){})", c_type(dtype), literal),
+    }
+}
+
+pub fn c_type(dtype: DataType) -> &'static str {
+    match dtype {
+        DataType::void => "void",
+        DataType::bool => "bool",
+        DataType::str => "const char *",
+        DataType::SignedInteger(SignedInteger::i8) => "int8_t",
+        DataType::SignedInteger(SignedInteger::i16) => "int16_t",
+        DataType::SignedInteger(SignedInteger::i32) => "int32_t",
+        DataType::SignedInteger(SignedInteger::i64) => "int64_t",
+        DataType::SignedInteger(SignedInteger::i128) => "__int128",
+        DataType::UnsignedInteger(UnsignedInteger::u8) => "uint8_t",
+        DataType::UnsignedInteger(UnsignedInteger::u16) => "uint16_t",
+        DataType::UnsignedInteger(UnsignedInteger::u32) => "uint32_t",
+        DataType::UnsignedInteger(UnsignedInteger::u64) => "uint64_t",
+        DataType::UnsignedInteger(UnsignedInteger::u128) => "unsigned __int128",
+        DataType::FloatingPoint(FloatingPoint::f32) => "float",
+        DataType::FloatingPoint(FloatingPoint::f64) => "double",
+        DataType::FloatingPoint(FloatingPoint::f128) => "_Float128",
+        // what a pointer points to is never read or written by the generated code, only by the C library
+        DataType::Pointer(_) => "void *",
+        DataType::Array(_) | DataType::Struct(_) | DataType::Enum(_) | DataType::Tuple(_) | DataType::Param(_) => unreachable!("composite types are rejected before their C type is needed"),
+    }
+}
+
+// the unsigned type arithmetic on `dtype` wraps around in, at least as wide as `int` so it is not promoted to it
+fn unsigned_type(dtype: DataType) -> &'static str {
+    match dtype {
+        _ if is_wide(dtype) => "unsigned __int128",
+        DataType::SignedInteger(SignedInteger::i64) | DataType::UnsignedInteger(UnsignedInteger::u64) => "uint64_t",
+        _ => "uint32_t",
+    }
+}
+
+fn narrower_than_int(dtype: DataType) -> bool {
+    matches!(dtype, DataType::SignedInteger(SignedInteger::i8 | SignedInteger::i16) | DataType::UnsignedInteger(UnsignedInteger::u8 | UnsignedInteger::u16))
+}
+
+fn operator_symbol(operator: Operator) -> &'static str {
+    match operator {
+        Operator::Plus | Operator::UnaryPlus => "+",
+        Operator::Minus | Operator::UnaryMinus => "-",
+        Operator::Multiply => "*",
+        Operator::Divide => "/",
+        Operator::Modulo => "%",
+        Operator::BitwiseAnd => "&",
+        Operator::BitwiseOr => "|",
+        Operator::BitwiseXor => "^",
+        Operator::BitwiseNot => "~",
+        Operator::Equal => "==",
+        Operator::NotEqual => "!=",
+        Operator::Less => "<",
+        Operator::LessEqual => "<=",
+        Operator::Greater => ">",
+        Operator::GreaterEqual => ">=",
+        Operator::LeftShift => "<<",
+        Operator::RightShift => ">>",
+        Operator::FnCall | Operator::Index | Operator::Member | Operator::AddressOf | Operator::Dereference => "",
+    }
+}
+
+// a declaration of `name` with type `dtype`, keeping the `*` of a pointer next to the name
+fn declare(dtype: DataType, name: &str) -> String {
+    let r#type = c_type(dtype);
+
+    if r#type.ends_with('*') {
+        format!("{}{}", r#type, name)
+    } else {
+        format!("{} {}", r#type, name)
+    }
+}
+
+fn cast(code: String, from: DataType, to: DataType) -> String {
+    if from == to {
+        code
+    } else {
+        format!("(({}){})", c_type(to), code)
+    }
+}
+
+// composite types are only supported by the interpreter and the LLVM backend so far, and pointers
+// are only passed around, to the C functions that read and write what they point to
+fn check_type(dtype: DataType, name: &str) -> Result<(), CodegenError> {
+    match dtype {
+        DataType::Pointer(pointee) if *pointee == DataType::void || check_type(*pointee, name).is_ok() => Ok(()),
+        DataType::Array(_) | DataType::Pointer(_) | DataType::Struct(_) | DataType::Enum(_) | DataType::Tuple(_) => Err(CodegenError(format!("[C] {} of `{}` is not supported", dtype, name))),
+        _ => Ok(()),
+    }
+}
+
+fn check_signature(name: &str, params: &[(Identifier, DataType)], r#type: DataType) -> Result<(), CodegenError> {
+    params.iter().try_for_each(|(ident, dtype)| check_type(*dtype, ident))?;
+    check_type(r#type, name)
+}
+
+// converts `code` from `from` to `to`, which integers, pointers and `*u8` to `str` allow
+fn coerce(code: String, from: DataType, to: DataType, name: &str) -> Result<String, CodegenError> {
+    if from == to || (is_integer(from) && is_integer(to)) || is_string_buffer(from, to) {
+        Ok(cast(code, from, to))
+    } else if is_pointer_compatible(from, to) {
+        Ok(code)
+    } else {
+        Err(CodegenError(format!("[C] `{}` expects {}, found {}", name, to, from)))
+    }
+}
+
+// the functions and globals of a module are named `module.name`, which C spells `module__name`
+fn escape_name(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.replace('.', "__")
+    }
+}
+
+// the body of a string literal; `?` is escaped so no trigraph is formed
+fn escape(string: &str) -> String {
+    string.bytes().map(|byte| match byte {
+        b'"' => "\\\"".into(),
+        b'\\' => "\\\\".into(),
+        b'?' => "\\?".into(),
+        0x20..=0x7e => (byte as char).to_string(),
+        _ => format!("\\{:03o}", byte),
+    }).collect()
+}
\ No newline at end of file
@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+
+use crate::codegen::target::Target;
+use crate::lexer::Span;
+use crate::types::{DataType, EnumType, FloatingPoint};
+
+// Collects DWARF metadata nodes while the IR is generated and prints them after the last function.
+pub struct DebugInfo {
+    metadata: Vec<String>,
+    types: HashMap<DataType, u64>,
+    locations: HashMap<(Span, u64), u64>,
+    compile_unit: u64,
+    file: u64,
+    scope: Option<u64>,
+    target: Target,
+}
+
+impl DebugInfo {
+    pub fn new(filename: &str, directory: &str, target: Target) -> DebugInfo {
+        let mut debug_info = DebugInfo {
+            metadata: Vec::new(),
+            types: HashMap::new(),
+            locations: HashMap::new(),
+            compile_unit: 0,
+            file: 0,
+            scope: None,
+            target,
+        };
+
+        debug_info.file = debug_info.add(format!("!DIFile(filename: \"{}\", directory: \"{}\")", escape(filename), escape(directory)));
+        debug_info.compile_unit = debug_info.add(format!("distinct !DICompileUnit(language: DW_LANG_C99, file: !{}, producer: \"mamba\", isOptimized: false, runtimeVersion: 0, emissionKind: FullDebug)", debug_info.file));
+
+        debug_info
+    }
+
+    fn add(&mut self, node: String) -> u64 {
+        self.metadata.push(node);
+        (self.metadata.len() - 1) as u64
+    }
+
+    // starts a new `DISubprogram`, which becomes the scope of the following locations and variables
+    pub fn begin_subprogram(&mut self, name: &str, span: Span, params: &[DataType], ret: DataType) -> u64 {
+        let mut types = vec![self.get_type(ret).map_or("null".into(), |idx| format!("!{idx}"))];
+
+        for dtype in params {
+            types.push(self.get_type(*dtype).map_or("null".into(), |idx| format!("!{idx}")));
+        }
+
+        let subroutine_type = self.add(format!("!DISubroutineType(types: !{{{}}})", types.join(", ")));
+        let retained_nodes = self.add("!{}".into());
+        let subprogram = self.add(format!("distinct !DISubprogram(name: \"{}\", scope: !{}, file: !{}, line: {}, type: !{}, scopeLine: {}, spFlags: DISPFlagDefinition, unit: !{}, retainedNodes: !{})", name, self.file, self.file, span.line, subroutine_type, span.line, self.compile_unit, retained_nodes));
+
+        self.scope = Some(subprogram);
+        subprogram
+    }
+
+    pub fn end_subprogram(&mut self) {
+        self.scope = None;
+    }
+
+    pub fn location(&mut self, span: Span) -> Option<u64> {
+        let scope = self.scope?;
+
+        if let Some(idx) = self.locations.get(&(span, scope)) {
+            return Some(*idx);
+        }
+
+        let idx = self.add(format!("!DILocation(line: {}, column: {}, scope: !{})", span.line, span.column, scope));
+        self.locations.insert((span, scope), idx);
+        Some(idx)
+    }
+
+    // `arg` is the 1-based position of a parameter, `None` for a local `let`
+    pub fn local_variable(&mut self, name: &str, span: Span, dtype: DataType, arg: Option<usize>) -> Option<u64> {
+        let scope = self.scope?;
+        let dtype = self.get_type(dtype)?;
+        let arg = arg.map_or(String::new(), |arg| format!("arg: {arg}, "));
+
+        Some(self.add(format!("!DILocalVariable(name: \"{}\", {}scope: !{}, file: !{}, line: {}, type: !{})", name, arg, scope, self.file, span.line, dtype)))
+    }
+
+    // emits `llvm.dbg.declare` for a variable that lives in `ptr`
+    pub fn declare(&mut self, ptr: &str, name: &str, span: Span, dtype: DataType, arg: Option<usize>) -> String {
+        let variable = self.local_variable(name, span, dtype, arg);
+        let location = self.location(span);
+
+        if let (Some(variable), Some(location)) = (variable, location) {
+            format!("call void @llvm.dbg.declare(metadata ptr {}, metadata !{}, metadata !DIExpression()), !dbg !{}\n", ptr, variable, location)
+        } else {
+            String::new()
+        }
+    }
+
+    fn get_type(&mut self, dtype: DataType) -> Option<u64> {
+        if let Some(idx) = self.types.get(&dtype) {
+            return Some(*idx);
+        }
+
+        let node = match dtype {
+            DataType::void | DataType::Param(_) => return None,
+            DataType::bool => "!DIBasicType(name: \"bool\", size: 8, encoding: DW_ATE_boolean)".into(),
+            DataType::str => {
+                let char_type = self.add("!DIBasicType(name: \"char\", size: 8, encoding: DW_ATE_signed_char)".into());
+                format!("!DIDerivedType(tag: DW_TAG_pointer_type, name: \"str\", baseType: !{}, size: {})", char_type, self.target.pointer_width)
+            },
+            DataType::SignedInteger(dtype) => format!("!DIBasicType(name: \"{:?}\", size: {}, encoding: DW_ATE_signed)", dtype, dtype.bits()),
+            DataType::UnsignedInteger(dtype) => format!("!DIBasicType(name: \"{:?}\", size: {}, encoding: DW_ATE_unsigned)", dtype, dtype.bits()),
+            DataType::FloatingPoint(dtype) => {
+                let size = match dtype {
+                    FloatingPoint::f32 => 32,
+                    FloatingPoint::f64 => 64,
+                    FloatingPoint::f128 => 128,
+                };
+
+                format!("!DIBasicType(name: \"{:?}\", size: {}, encoding: DW_ATE_float)", dtype, size)
+            },
+            DataType::Array(array) => {
+                let element = self.get_type(array.element)?;
+                let subrange = self.add(format!("!DISubrange(count: {})", array.len));
+                let elements = self.add(format!("!{{!{}}}", subrange));
+
+                format!("!DICompositeType(tag: DW_TAG_array_type, baseType: !{}, elements: !{})", element, elements)
+            },
+            // a `*void` has no base type
+            DataType::Pointer(pointee) => {
+                let base = self.get_type(*pointee).map_or("null".into(), |idx| format!("!{idx}"));
+                format!("!DIDerivedType(tag: DW_TAG_pointer_type, baseType: {}, size: {})", base, self.target.pointer_width)
+            },
+            // sizes and offsets are in bits
+            DataType::Struct(structure) => {
+                let mut members = Vec::new();
+
+                for ((name, dtype), offset) in structure.fields.iter().zip(self.target.field_offsets(structure)) {
+                    let base = self.get_type(*dtype)?;
+                    members.push(format!("!{}", self.add(format!("!DIDerivedType(tag: DW_TAG_member, name: \"{}\", baseType: !{}, size: {}, offset: {})", name, base, self.target.size_of(*dtype) * 8, offset * 8))));
+                }
+
+                let elements = self.add(format!("!{{{}}}", members.join(", ")));
+                format!("!DICompositeType(tag: DW_TAG_structure_type, name: \"{}\", file: !{}, size: {}, elements: !{})", structure.name, self.file, self.target.size_of(dtype) * 8, elements)
+            },
+            // the elements are named like the fields of a Rust tuple, since debuggers do not accept `0` as a name
+            DataType::Tuple(tuple) => {
+                let mut members = Vec::new();
+
+                for (n, (dtype, offset)) in tuple.elements.iter().zip(self.target.element_offsets(tuple)).enumerate() {
+                    let base = self.get_type(*dtype)?;
+                    members.push(format!("!{}", self.add(format!("!DIDerivedType(tag: DW_TAG_member, name: \"__{}\", baseType: !{}, size: {}, offset: {})", n, base, self.target.size_of(*dtype) * 8, offset * 8))));
+                }
+
+                let elements = self.add(format!("!{{{}}}", members.join(", ")));
+                format!("!DICompositeType(tag: DW_TAG_structure_type, name: \"{}\", file: !{}, size: {}, elements: !{})", dtype, self.file, self.target.size_of(dtype) * 8, elements)
+            },
+            // the discriminant is described as a C enum, which becomes the `tag` member of a struct when there are payloads
+            DataType::Enum(enumeration) => {
+                let base = self.get_type(EnumType::TAG)?;
+                let enumerators = enumeration.variants.iter()
+                    .map(|variant| format!("!{}", self.add(format!("!DIEnumerator(name: \"{}\", value: {})", variant.name, variant.discriminant))))
+                    .collect::<Vec<String>>();
+
+                let elements = self.add(format!("!{{{}}}", enumerators.join(", ")));
+                let size = self.target.size_of(EnumType::TAG) * 8;
+                let tag = format!("!DICompositeType(tag: DW_TAG_enumeration_type, name: \"{}\", file: !{}, size: {}, baseType: !{}, elements: !{})", enumeration.name, self.file, size, base, elements);
+
+                if enumeration.has_payload() {
+                    let tag = self.add(tag);
+                    let member = self.add(format!("!DIDerivedType(tag: DW_TAG_member, name: \"tag\", baseType: !{}, size: {}, offset: 0)", tag, size));
+                    let elements = self.add(format!("!{{!{}}}", member));
+
+                    format!("!DICompositeType(tag: DW_TAG_structure_type, name: \"{}\", file: !{}, size: {}, elements: !{})", enumeration.name, self.file, self.target.size_of(dtype) * 8, elements)
+                } else {
+                    tag
+                }
+            },
+        };
+
+        let idx = self.add(node);
+        self.types.insert(dtype, idx);
+        Some(idx)
+    }
+
+    // attaches `!dbg` to every instruction in `code` that does not carry a location yet
+    pub fn attach(&mut self, code: &str, span: Span) -> String {
+        if code.is_empty() {
+            return String::new();
+        }
+
+        let location = if let Some(location) = self.location(span) {
+            location
+        } else {
+            return code.into();
+        };
+
+        code.lines()
+            .map(|line| {
+                if line.is_empty() || line.ends_with(':') || line.contains("!dbg") {
+                    format!("{line}\n")
+                } else {
+                    format!("{line}, !dbg !{location}\n")
+                }
+            })
+            .collect()
+    }
+
+    pub fn finish(&self) -> String {
+        let mut result = String::new();
+
+        let dwarf_version = self.metadata.len();
+        let debug_info_version = dwarf_version + 1;
+
+        result += "\ndeclare void @llvm.dbg.declare(metadata, metadata, metadata)\n\n";
+        result += &format!("!llvm.dbg.cu = !{{!{}}}\n", self.compile_unit);
+        result += &format!("!llvm.module.flags = !{{!{}, !{}}}\n", dwarf_version, debug_info_version);
+
+        for (idx, node) in self.metadata.iter().enumerate() {
+            result += &format!("!{} = {}\n", idx, node);
+        }
+
+        result += &format!("!{} = !{{i32 7, !\"Dwarf Version\", i32 4}}\n", dwarf_version);
+        result += &format!("!{} = !{{i32 2, !\"Debug Info Version\", i32 3}}\n", debug_info_version);
+
+        result
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\5C").replace('"', "\\22")
+}
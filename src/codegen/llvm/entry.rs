@@ -0,0 +1,87 @@
+use crate::codegen::target::Target;
+use crate::error::IRGenError;
+use crate::parser::{DefStatement, Statement, AST};
+use crate::types::{DataType, SignedInteger};
+
+// the user's `main` is renamed so it does not clash with the entry point itself
+pub const MAIN_SYMBOL: &str = "mamba.main";
+
+// How control reaches the program's `main`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entry {
+    // `main(i32 argc, ptr argv)` is called by the C runtime, so the program is linked through the system C compiler
+    C,
+    // `_start` calls `main` and leaves through the exit system call, for builds without libc
+    Freestanding,
+}
+
+impl Entry {
+    pub fn from_name(name: &str) -> Option<Entry> {
+        match name {
+            "c" => Some(Entry::C),
+            "freestanding" => Some(Entry::Freestanding),
+            _ => None,
+        }
+    }
+}
+
+pub fn generate_entry(entry: Entry, target: &Target, ast: &AST) -> Result<String, IRGenError> {
+    let main = find_main(ast)?;
+    let mut result = String::new();
+
+    let call = if main.r#type == DataType::void {
+        format!("    call void @{}()\n", MAIN_SYMBOL)
+    } else {
+        format!("    %exit_code = call i32 @{}()\n", MAIN_SYMBOL)
+    };
+    let exit_code = if main.r#type == DataType::void { "0" } else { "%exit_code" };
+
+    match entry {
+        Entry::C => {
+            result += "define i32 @main(i32 %argc, ptr %argv) {\n";
+            result += &call;
+            result += &format!("    ret i32 {}\n", exit_code);
+            result += "}\n\n";
+        },
+        Entry::Freestanding => {
+            let (asm, constraints, number) = exit_syscall(target)?;
+
+            // the kernel enters `_start` with a 16-byte aligned stack and no return address
+            result += "define void @_start() noreturn nounwind alignstack(16) {\n";
+            result += &call;
+            result += &format!("    %status = sext i32 {} to i64\n", exit_code);
+            result += &format!("    call void asm sideeffect \"{}\", \"{}\"(i64 {}, i64 %status)\n", asm, constraints, number);
+            result += "    unreachable\n";
+            result += "}\n\n";
+        },
+    }
+
+    Ok(result)
+}
+
+pub fn find_main(ast: &AST) -> Result<&DefStatement, IRGenError> {
+    let main = ast.iter()
+        .find_map(|stmt| match stmt {
+            Statement::Def(stmt) if stmt.name == "main" => Some(stmt),
+            _ => None,
+        })
+        .ok_or_else(|| IRGenError("[Entry] the program has no `main` function".into()))?;
+
+    let valid_type = matches!(main.r#type, DataType::void | DataType::SignedInteger(SignedInteger::i32));
+
+    if !main.params.is_empty() || !valid_type {
+        return Err(IRGenError(format!("[Entry] `main` at line {} must be declared as `def main() -> i32` or `def main() -> void`", main.span.line)));
+    }
+
+    Ok(main)
+}
+
+// instruction, register constraints and number of the `exit` system call on Linux
+pub fn exit_syscall(target: &Target) -> Result<(&'static str, &'static str, u32), IRGenError> {
+    match target.name {
+        "x86_64" => Ok(("syscall", "{rax},{rdi},~{rcx},~{r11},~{memory}", 60)),
+        "aarch64" => Ok(("svc #0", "{x8},{x0},~{memory}", 93)),
+        "riscv64" => Ok(("ecall", "{x17},{x10},~{memory}", 93)),
+        _ => Err(IRGenError(format!("[Entry] freestanding entry is not supported on {}", target.name))),
+    }
+}
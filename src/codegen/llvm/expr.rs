@@ -1,89 +1,300 @@
-use types::{cast, infix_op, unary_op};
+use types::{convert, get_cast, infix_op, unary_op};
 
-use crate::parser::Expression;
+use crate::parser::{is_place, DefStatement, Expression, FormatPiece, IndexExpression, MemberExpression, MethodCallExpression, Operator};
 use crate::error::IRGenError;
 use crate::codegen::llvm::*;
-use crate::types::DataType;
+use crate::codegen::llvm::panic::PANIC_BOUNDS;
+use crate::fold::literal_dtype;
+use crate::format;
+use crate::mono;
+use crate::types::{is_integer, pointer_infix_type, DataType, EnumType, SignedInteger, UnsignedInteger, Variant};
 
+#[allow(clippy::len_zero)]
 pub fn generate_expr(global_ctx: &mut GlobalContext, scoped_ctx: &mut Vec<ScopedContext>, expr: &Expression) -> Result<(String, String, DataType), IRGenError> {
     let mut result = String::new();
 
     let (idx, dtype) = match expr {
+        Expression::Unary(unary) if unary.operator == Operator::AddressOf => {
+            let (code, ptr, dtype) = generate_address(global_ctx, scoped_ctx, &unary.right)?;
+            result += &code;
+            (ptr, DataType::pointer(dtype))
+        },
+        Expression::Unary(unary) if unary.operator == Operator::Dereference => {
+            let (code, ptr, dtype) = generate_address(global_ctx, scoped_ctx, expr)?;
+            result += &code;
+
+            let idx = global_ctx.get_register();
+            result += &format!("{} = load {}, ptr {}, align {}\n", idx, dtype.to_mnemonic(), ptr, global_ctx.align_of(dtype));
+            (idx, dtype)
+        },
         Expression::Unary(expr) => {
             let (idx, dtype) = {
-                let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, &expr.right).unwrap();
+                let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, &expr.right)?;
                 result += &code;
                 (idx.to_string(), dtype)
             };
 
-            let (idx, code) = unary_op()[&(dtype, expr.operator)](global_ctx, &idx);
+            let op = unary_op().get(&(dtype, expr.operator))
+                .ok_or_else(|| IRGenError(format!("[IRGen] operator {:?} cannot be applied to {}", expr.operator, dtype.to_mnemonic())))?;
+            let (idx, code) = op(global_ctx, &idx);
             result += &code;
             (idx, dtype)
         },
         Expression::Infix(expr) => {
             let (left_idx, left_dtype) = {
-                let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, &expr.left).unwrap();
+                let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, &expr.left)?;
                 result += &code;
                 (idx.to_string(), dtype)
             };
             
             let (right_idx, right_dtype) = {
-                let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, &expr.right).unwrap();
+                let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, &expr.right)?;
                 result += &code;
                 (idx.to_string(), dtype)
             };
 
+            if let (DataType::Pointer(_), _) | (_, DataType::Pointer(_)) = (left_dtype, right_dtype) {
+                let (code, idx, dtype) = generate_pointer_infix(global_ctx, expr.operator, (&left_idx, left_dtype), (&right_idx, right_dtype))?;
+                return Ok((result + &code, idx, dtype));
+            }
+
             let (left_idx, right_idx, dtype) = if left_dtype < right_dtype {
-                let (casted_idx, cast_code, _) = cast()[&(left_dtype, right_dtype)](global_ctx, &left_idx);
+                let (casted_idx, cast_code, _) = get_cast(left_dtype, right_dtype)?(global_ctx, &left_idx);
                 result += &cast_code;
                 (casted_idx, right_idx, right_dtype)
             } else if left_dtype > right_dtype {
-                let (casted_idx, cast_code, _) = cast()[&(right_dtype, left_dtype)](global_ctx, &right_idx);
+                let (casted_idx, cast_code, _) = get_cast(right_dtype, left_dtype)?(global_ctx, &right_idx);
                 result += &cast_code;
                 (left_idx, casted_idx, left_dtype)
             } else {
                 (left_idx, right_idx, left_dtype)
             };
 
-            let (idx, code) = infix_op()[&(dtype, expr.operator)](global_ctx, &left_idx, &right_idx);
+            let op = infix_op().get(&(dtype, expr.operator))
+                .ok_or_else(|| IRGenError(format!("[IRGen] operator {:?} cannot be applied to {}", expr.operator, dtype.to_mnemonic())))?;
+            let (idx, code) = op(global_ctx, &left_idx, &right_idx);
             result += &code;
-            (idx, dtype)
+
+            // comparisons yield an `i1` whatever the type of their operands
+            match expr.operator {
+                Operator::Equal | Operator::NotEqual | Operator::Less | Operator::LessEqual | Operator::Greater | Operator::GreaterEqual => (idx, DataType::bool),
+                _ => (idx, dtype),
+            }
         },
         Expression::FnCall(expr) => {
-            let fn_dtype = if global_ctx.fn_decl.contains_key(&expr.ident) {
-                global_ctx.fn_decl[&expr.ident].1
-            } else {
-                panic!("Unable to find function `{}`", &expr.ident.to_string());
+            let (code, idx, dtype) = generate_call(global_ctx, scoped_ctx, &expr.ident, Vec::new(), &expr.args)?;
+            result += &code;
+            (idx, dtype)
+        },
+        Expression::MethodCall(expr) => {
+            let (code, idx, dtype) = generate_method_call(global_ctx, scoped_ctx, expr)?;
+            result += &code;
+            (idx, dtype)
+        },
+        Expression::Index(expr) => {
+            let (code, ptr, element) = generate_element_ptr(global_ctx, scoped_ctx, expr)?;
+            result += &code;
+
+            let idx = global_ctx.get_register();
+            result += &format!("{} = load {}, ptr {}, align {}\n", idx, element.to_mnemonic(), ptr, global_ctx.align_of(element));
+            (idx, element)
+        },
+        Expression::Array(elements) => {
+            let mut values = Vec::new();
+
+            for element in elements {
+                let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, element)?;
+                result += &code;
+                values.push((idx, dtype));
+            }
+
+            // the elements are promoted to a common type like the operands of an infix operator
+            let element = values.iter().map(|(_, dtype)| *dtype).max().unwrap_or(DataType::void);
+            let dtype = DataType::array(element, values.len() as u64);
+            let mut idx = String::from("undef");
+
+            for (n, (value, value_dtype)) in values.iter().enumerate() {
+                if *value_dtype != element && !(is_integer(*value_dtype) && is_integer(element)) {
+                    return Err(IRGenError(format!("[IRGen] array elements must have one type, found {} and {}", element.to_mnemonic(), value_dtype.to_mnemonic())));
+                }
+
+                let (value, cast_code) = convert(global_ctx, value, *value_dtype, element)?;
+                result += &cast_code;
+
+                let next = global_ctx.get_register();
+                result += &format!("{} = insertvalue {} {}, {} {}, {}\n", next, dtype.to_mnemonic(), idx, element.to_mnemonic(), value, n);
+                idx = next;
+            }
+
+            (idx, dtype)
+        },
+        // each value is generated once, after the text before it, and appended by the routine for its type like `format::lower` does
+        Expression::FormatString(expr) => {
+            let (code, mut text, mut text_dtype) = generate_call(global_ctx, scoped_ctx, format::START, Vec::new(), &[])?;
+            result += &code;
+
+            for piece in &expr.pieces {
+                let (code, idx, dtype) = match piece {
+                    FormatPiece::Text(string) => {
+                        let args = [Expression::Literal((Literal::String(string.clone()), DataType::str))];
+                        generate_call(global_ctx, scoped_ctx, format::TEXT, vec![format!("{} {}", text_dtype.to_mnemonic(), text)], &args)?
+                    },
+                    FormatPiece::Value(value, spec) => {
+                        let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, value)?;
+                        result += &code;
+
+                        let routine = format::routine(dtype, spec).map_err(|err| IRGenError(err.0))?;
+                        let param_dtype = global_ctx.fn_decl.get(routine).and_then(|(params, _)| params.get(1).copied())
+                            .ok_or_else(|| IRGenError(format!("[IRGen] unable to find function `{}`", routine)))?;
+
+                        let (idx, cast_code) = convert(global_ctx, &idx, dtype, param_dtype)?;
+                        result += &cast_code;
+
+                        let params = vec![format!("{} {}", text_dtype.to_mnemonic(), text), format!("{} {}", param_dtype.to_mnemonic(), idx)];
+                        generate_call(global_ctx, scoped_ctx, routine, params, &format::spec_literals(spec))?
+                    },
+                };
+
+                result += &code;
+                (text, text_dtype) = (idx, dtype);
+            }
+
+            let (code, idx, dtype) = generate_call(global_ctx, scoped_ctx, format::end(expr), vec![format!("{} {}", text_dtype.to_mnemonic(), text)], &[])?;
+            result += &code;
+            (idx, dtype)
+        },
+        Expression::Tuple(elements) => {
+            let mut values = Vec::new();
+
+            for element in elements {
+                let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, element)?;
+                result += &code;
+                values.push((idx, dtype));
+            }
+
+            let dtype = DataType::tuple(values.iter().map(|(_, dtype)| *dtype).collect());
+            let mut idx = String::from("undef");
+
+            for (n, (value, value_dtype)) in values.into_iter().enumerate() {
+                let next = global_ctx.get_register();
+                result += &format!("{} = insertvalue {} {}, {} {}, {}\n", next, dtype.to_mnemonic(), idx, value_dtype.to_mnemonic(), value, n);
+                idx = next;
+            }
+
+            (idx, dtype)
+        },
+        // the fields are evaluated in the order they are written and inserted at their declared positions
+        Expression::Struct(expr) => {
+            let structure = match expr.dtype {
+                DataType::Struct(structure) => structure,
+                dtype => return Err(IRGenError(format!("[IRGen] {} is not a struct", dtype))),
             };
-            
-            let params = expr.args.iter().map(|expr| {
-                let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, expr).unwrap();
+
+            let mut values = Vec::new();
+
+            for (field, value) in &expr.fields {
+                let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, value)?;
+                result += &code;
+                values.push((field, idx, dtype));
+            }
+
+            let mut idx = String::from("undef");
+
+            for (field, value, value_dtype) in values {
+                let (n, field_dtype) = structure.field(field)
+                    .ok_or_else(|| IRGenError(format!("[IRGen] {} has no field `{}`", expr.dtype, field)))?;
+
+                let (value, cast_code) = convert(global_ctx, &value, value_dtype, field_dtype)?;
+                result += &cast_code;
+
+                let next = global_ctx.get_register();
+                result += &format!("{} = insertvalue {} {}, {} {}, {}\n", next, expr.dtype.to_mnemonic(), idx, field_dtype.to_mnemonic(), value, n);
+                idx = next;
+            }
+
+            (idx, expr.dtype)
+        },
+        // the discriminant and the values are stored into a temporary, since the payload is only raw bytes
+        Expression::Enum(expr) => {
+            let variant = match expr.dtype {
+                DataType::Enum(enumeration) => enumeration.variant(&expr.variant),
+                _ => None,
+            }.ok_or_else(|| IRGenError(format!("[IRGen] {} has no variant `{}`", expr.dtype, expr.variant)))?;
+
+            let mut values = Vec::new();
+
+            for (value, value_dtype) in expr.values.iter().zip(&variant.payload) {
+                let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, value)?;
                 result += &code;
-                format!("{} {}", dtype.to_mnemonic(), idx)
-            }).collect::<Vec<String>>();
 
-            let idx = global_ctx.get_label();
+                let (idx, cast_code) = convert(global_ctx, &idx, dtype, *value_dtype)?;
+                result += &cast_code;
+                values.push((idx, *value_dtype));
+            }
+
+            let ptr = global_ctx.get_register();
+            let mnemonic = expr.dtype.to_mnemonic();
+            let align = global_ctx.align_of(expr.dtype);
+
+            result += &format!("{} = alloca {}, align {}\n", ptr, mnemonic, align);
+            result += &format!("store i32 {}, ptr {}, align {}\n", variant.discriminant, ptr, global_ctx.align_of(EnumType::TAG));
+
+            if !values.is_empty() {
+                let payload = global_ctx.get_register();
+                result += &format!("{} = getelementptr inbounds {}, ptr {}, i32 0, i32 1\n", payload, mnemonic, ptr);
+
+                for (n, (value, value_dtype)) in values.into_iter().enumerate() {
+                    let value_ptr = global_ctx.get_register();
+                    result += &format!("{} = getelementptr inbounds {}, ptr {}, i32 0, i32 {}\n", value_ptr, payload_mnemonic(variant), payload, n);
+                    result += &format!("store {} {}, ptr {}, align {}\n", value_dtype.to_mnemonic(), value, value_ptr, global_ctx.align_of(value_dtype));
+                }
+            }
 
-            result += &format!("%{} = call {} @{}(", idx, fn_dtype.to_mnemonic(), &expr.ident.to_string());
-            result += &params.join(", ");
-            result += ")\n";
+            let idx = global_ctx.get_register();
+            result += &format!("{} = load {}, ptr {}, align {}\n", idx, mnemonic, ptr, align);
+            (idx, expr.dtype)
+        },
+        // a field of a struct or tuple that is not in memory, such as a returned one, is extracted from the value
+        Expression::Member(member) if !is_place(&member.expr) => {
+            let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, &member.expr)?;
+            result += &code;
+
+            if let DataType::Struct(_) | DataType::Tuple(_) = dtype {
+                let (n, field) = dtype.field(&member.field)
+                    .ok_or_else(|| IRGenError(format!("[IRGen] {} has no field `{}`", dtype, member.field)))?;
+
+                let field_idx = global_ctx.get_register();
+                result += &format!("{} = extractvalue {} {}, {}\n", field_idx, dtype.to_mnemonic(), idx, n);
+                (field_idx, field)
+            } else {
+                let pointee = match dtype {
+                    DataType::Pointer(pointee) => *pointee,
+                    dtype => dtype,
+                };
 
-            let dtype = global_ctx.fn_decl[&expr.ident].1;
+                let (n, field) = pointee.field(&member.field)
+                    .ok_or_else(|| IRGenError(format!("[IRGen] {} has no field `{}`", pointee, member.field)))?;
 
-            (format!("%{idx}"), dtype)
+                let field_ptr = global_ctx.get_register();
+                let field_idx = global_ctx.get_register();
+                result += &format!("{} = getelementptr inbounds {}, ptr {}, i32 0, i32 {}\n", field_ptr, pointee.to_mnemonic(), idx, n);
+                result += &format!("{} = load {}, ptr {}, align {}\n", field_idx, field.to_mnemonic(), field_ptr, global_ctx.align_of(field));
+                (field_idx, field)
+            }
+        },
+        Expression::Member(expr) => {
+            let (code, ptr, field) = generate_field_ptr(global_ctx, scoped_ctx, expr)?;
+            result += &code;
+
+            let idx = global_ctx.get_register();
+            result += &format!("{} = load {}, ptr {}, align {}\n", idx, field.to_mnemonic(), ptr, global_ctx.align_of(field));
+            (idx, field)
         },
         Expression::Literal((literal, _)) => {
-            let (literal_code, literal_idx) = IRGen::generate_literal(global_ctx, scoped_ctx, literal).unwrap();
+            let (literal_code, literal_idx) = IRGen::generate_literal(global_ctx, scoped_ctx, literal)?;
             result += &literal_code;
 
-            let dtype = match literal {
-                Literal::SignedInteger((_, dtype)) => DataType::SignedInteger(*dtype),
-                Literal::UnsignedInteger((_, dtype)) => DataType::UnsignedInteger(*dtype),
-                Literal::String(_) => DataType::str,
-                Literal::Boolean(_) => DataType::bool,
-            };
-
-            (format!("%{literal_idx}"), dtype)
+            (literal_idx, literal_dtype(literal))
         },
         Expression::Identifier(ident) => {
             let ctx: Vec<&ScopedContext> = scoped_ctx.iter().filter(|ctx| {
@@ -99,25 +310,11 @@ pub fn generate_expr(global_ctx: &mut GlobalContext, scoped_ctx: &mut Vec<Scoped
                         (format!("%{ident}"), fn_decl[ident])
                     },
                     ScopedContext::Scope(scope) => {
-                        match scope[ident] {
-                            Literal::SignedInteger((_, dtype)) => {
-                                let new_idx = global_ctx.get_label();
-                                result += &format!("%{new_idx} = load {}, ptr %{}, align 4\n", dtype.to_mnemonic(), ident);
-                                (format!("%{new_idx}"), DataType::SignedInteger(dtype))
-                            },
-                            Literal::UnsignedInteger((_, dtype)) => {
-                                let new_idx = global_ctx.get_label();
-                                result += &format!("%{new_idx} = load {}, ptr %{}, align 4\n", dtype.to_mnemonic(), ident);
-                                (format!("%{new_idx}"), DataType::UnsignedInteger(dtype))
-                            },
-                            Literal::String(_) => (format!("%{ident}"), DataType::str),
-                            Literal::Boolean(_) => {
-                                let new_idx = global_ctx.get_label();
-                                result += &format!("%{new_idx} = load {}, ptr %{}, align 4\n", "i8", ident);
-                                (format!("%{new_idx}"), DataType::bool)
-                            }
-                        }
-                    }
+                        let (ptr, dtype) = scope[ident].clone();
+                        let new_idx = global_ctx.get_register();
+                        result += &format!("{new_idx} = load {}, ptr {}, align {}\n", dtype.to_mnemonic(), ptr, global_ctx.align_of(dtype));
+                        (new_idx, dtype)
+                    },
                 }
             } else {
                 if global_ctx.global_var.contains_key(ident) {
@@ -125,28 +322,417 @@ pub fn generate_expr(global_ctx: &mut GlobalContext, scoped_ctx: &mut Vec<Scoped
                     
                     match literal {
                         Literal::SignedInteger((_, dtype)) => {
-                            let new_idx = global_ctx.get_label();
-                            result += &format!("%{new_idx} = load {}, ptr @{}, align 4\n", dtype.to_mnemonic(), ident);
-                            (format!("%{new_idx}"), DataType::SignedInteger(dtype))
+                            let new_idx = global_ctx.get_register();
+                            result += &format!("{new_idx} = load {}, ptr @{}, align {}\n", dtype.to_mnemonic(), ident, global_ctx.align_of(DataType::SignedInteger(dtype)));
+                            (new_idx, DataType::SignedInteger(dtype))
                         },
                         Literal::UnsignedInteger((_, dtype)) => {
-                            let new_idx = global_ctx.get_label();
-                            result += &format!("%{new_idx} = load {}, ptr @{}, align 4\n", dtype.to_mnemonic(), ident);
-                            (format!("%{new_idx}"), DataType::UnsignedInteger(dtype))
+                            let new_idx = global_ctx.get_register();
+                            result += &format!("{new_idx} = load {}, ptr @{}, align {}\n", dtype.to_mnemonic(), ident, global_ctx.align_of(DataType::UnsignedInteger(dtype)));
+                            (new_idx, DataType::UnsignedInteger(dtype))
                         },
                         Literal::String(_) => (format!("@{ident}"), DataType::str),
                         Literal::Boolean(_) => {
-                            let new_idx = global_ctx.get_label();
-                            result += &format!("%{new_idx} = load {}, ptr @{}, align 4\n", "i1", ident);
-                            (format!("%{new_idx}"), DataType::bool)
-                        }
+                            let new_idx = global_ctx.get_register();
+                            result += &format!("{new_idx} = load {}, ptr @{}, align {}\n", "i1", ident, global_ctx.align_of(DataType::bool));
+                            (new_idx, DataType::bool)
+                        },
+                        Literal::Null(dtype) => {
+                            let new_idx = global_ctx.get_register();
+                            result += &format!("{new_idx} = load ptr, ptr @{}, align {}\n", ident, global_ctx.align_of(dtype));
+                            (new_idx, dtype)
+                        },
                     }
                 } else {
-                    panic!("identifier {} not found!", ident);
+                    return Err(IRGenError(format!("[IRGen] identifier `{}` not found", ident)));
                 }
             }
         },
     };
     
     Ok((result, idx, dtype))
+}
+
+// `params` are the arguments that are already lowered, they come before `args`
+fn generate_call(global_ctx: &mut GlobalContext, scoped_ctx: &mut Vec<ScopedContext>, name: &str, mut params: Vec<String>, args: &[Expression]) -> Result<(String, String, DataType), IRGenError> {
+    if let Some(function) = global_ctx.generics.get(name).cloned() {
+        return generate_generic_call(global_ctx, scoped_ctx, &function, args);
+    }
+
+    let mut result = String::new();
+
+    let (param_dtypes, fn_dtype) = global_ctx.fn_decl.get(name).cloned()
+        .ok_or_else(|| IRGenError(format!("[IRGen] unable to find function `{}`", name)))?;
+
+    if param_dtypes.len() != params.len() + args.len() {
+        return Err(IRGenError(format!("[IRGen] `{}` takes {} arguments but {} were given", name, param_dtypes.len(), params.len() + args.len())));
+    }
+
+    for (expr, param_dtype) in args.iter().zip(param_dtypes.into_iter().skip(params.len())) {
+        let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, expr)?;
+        result += &code;
+
+        let (idx, cast_code) = convert(global_ctx, &idx, dtype, param_dtype)?;
+        result += &cast_code;
+
+        params.push(format!("{} {}", param_dtype.to_mnemonic(), idx));
+    }
+
+    let (code, idx) = generate_call_instr(global_ctx, name, &params, fn_dtype);
+    result += &code;
+
+    Ok((result, idx, fn_dtype))
+}
+
+// the arguments are all generated before they are converted, their types decide which instance is called
+fn generate_generic_call(global_ctx: &mut GlobalContext, scoped_ctx: &mut Vec<ScopedContext>, function: &DefStatement, args: &[Expression]) -> Result<(String, String, DataType), IRGenError> {
+    let mut result = String::new();
+    let mut values = Vec::new();
+
+    for expr in args {
+        let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, expr)?;
+        result += &code;
+        values.push((idx, dtype));
+    }
+
+    let arg_dtypes: Vec<(DataType, bool)> = values.iter().map(|(_, dtype)| *dtype).zip(args.iter().map(mono::is_integer_literal)).collect();
+    let types = mono::infer(function, &arg_dtypes).map_err(|err| IRGenError(err.0))?;
+    let name = mono::instance_name(function, &types);
+
+    if !global_ctx.fn_decl.contains_key(&name) {
+        let instance = mono::instantiate(function, &types);
+        global_ctx.fn_decl.insert(name.clone(), (instance.params.iter().map(|(_, dtype)| *dtype).collect(), instance.r#type));
+        global_ctx.instances.push(instance);
+    }
+
+    let (param_dtypes, fn_dtype) = global_ctx.fn_decl[&name].clone();
+    let mut params = Vec::new();
+
+    for ((idx, dtype), param_dtype) in values.into_iter().zip(param_dtypes) {
+        let (idx, cast_code) = convert(global_ctx, &idx, dtype, param_dtype)?;
+        result += &cast_code;
+
+        params.push(format!("{} {}", param_dtype.to_mnemonic(), idx));
+    }
+
+    let (code, idx) = generate_call_instr(global_ctx, &name, &params, fn_dtype);
+    result += &code;
+
+    Ok((result, idx, fn_dtype))
+}
+
+// a call to a void function has no value to name
+fn generate_call_instr(global_ctx: &mut GlobalContext, name: &str, params: &[String], fn_dtype: DataType) -> (String, String) {
+    let mut result = String::new();
+
+    let idx = if fn_dtype == DataType::void {
+        result += &format!("call void @{}(", global_ctx.fn_symbol(name));
+        String::new()
+    } else {
+        let idx = global_ctx.get_register();
+        result += &format!("{} = call {} @{}(", idx, fn_dtype.to_mnemonic(), global_ctx.fn_symbol(name));
+        idx
+    };
+
+    result += &params.join(", ");
+    result += ")\n";
+
+    (result, idx)
+}
+
+// `self` is the receiver or its address, whichever the method takes; a pointer is followed like for a field
+fn generate_method_call(global_ctx: &mut GlobalContext, scoped_ctx: &mut Vec<ScopedContext>, expr: &MethodCallExpression) -> Result<(String, String, DataType), IRGenError> {
+    let mut result = String::new();
+
+    let is_value = match expr.receiver.as_ref() {
+        Expression::Identifier(ident) => find_variable(scoped_ctx, ident).is_none() && is_param(scoped_ctx, ident),
+        receiver => !is_place(receiver),
+    };
+
+    // a place is passed by address unless it holds the pointer already
+    let (receiver, dtype) = if is_value {
+        let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, &expr.receiver)?;
+        result += &code;
+        (idx, dtype)
+    } else {
+        let (code, ptr, dtype) = generate_address(global_ctx, scoped_ctx, &expr.receiver)?;
+        result += &code;
+
+        match dtype {
+            DataType::Pointer(DataType::Struct(_)) => {
+                let idx = global_ctx.get_register();
+                result += &format!("{} = load ptr, ptr {}, align {}\n", idx, ptr, global_ctx.align_of(dtype));
+                (idx, dtype)
+            },
+            dtype => (ptr, DataType::pointer(dtype)),
+        }
+    };
+
+    let structure = match dtype {
+        DataType::Struct(structure) | DataType::Pointer(&DataType::Struct(structure)) => structure,
+        DataType::Pointer(pointee) if !is_value => return Err(IRGenError(format!("[IRGen] {} has no method `{}`", pointee, expr.method))),
+        dtype => return Err(IRGenError(format!("[IRGen] {} has no method `{}`", dtype, expr.method))),
+    };
+
+    let name = structure.method(&expr.method);
+    let self_dtype = global_ctx.fn_decl.get(&name).and_then(|(params, _)| params.first().copied())
+        .ok_or_else(|| IRGenError(format!("[IRGen] {} has no method `{}`", structure.name, expr.method)))?;
+
+    let receiver = match (dtype, self_dtype) {
+        (DataType::Pointer(pointee), DataType::Struct(_)) => {
+            let idx = global_ctx.get_register();
+            result += &format!("{} = load {}, ptr {}, align {}\n", idx, pointee.to_mnemonic(), receiver, global_ctx.align_of(*pointee));
+            idx
+        },
+        (DataType::Struct(_), DataType::Pointer(_)) => return Err(IRGenError(format!("[IRGen] {} does not name a place", expr.receiver))),
+        _ => receiver,
+    };
+
+    let (code, idx, dtype) = generate_call(global_ctx, scoped_ctx, &name, vec![format!("{} {}", self_dtype.to_mnemonic(), receiver)], &expr.args)?;
+    result += &code;
+
+    Ok((result, idx, dtype))
+}
+
+// returns the code computing a pointer to the element `expr` refers to, the pointer and the element's type
+pub fn generate_element_ptr(global_ctx: &mut GlobalContext, scoped_ctx: &mut Vec<ScopedContext>, expr: &IndexExpression) -> Result<(String, String, DataType), IRGenError> {
+    let mut result = String::new();
+
+    let variable = match expr.array.as_ref() {
+        Expression::Identifier(ident) => find_variable(scoped_ctx, ident),
+        _ => None,
+    };
+
+    // variables and pointees are indexed in place, so that their elements can be assigned
+    let (ptr, dtype) = match (expr.array.as_ref(), variable) {
+        (array @ (Expression::Index(_) | Expression::Unary(_) | Expression::Member(_)), _) if is_place(array) => {
+            let (code, ptr, dtype) = generate_address(global_ctx, scoped_ctx, array)?;
+            result += &code;
+            (ptr, dtype)
+        },
+        (_, Some((ptr, dtype @ DataType::Array(_)))) => (ptr, dtype),
+        (array, _) => {
+            let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, array)?;
+            result += &code;
+
+            let ptr = global_ctx.get_register();
+            let align = global_ctx.align_of(dtype);
+
+            if let DataType::Array(_) = dtype {
+                result += &format!("{} = alloca {}, align {}\n", ptr, dtype.to_mnemonic(), align);
+                result += &format!("store {} {}, ptr {}, align {}\n", dtype.to_mnemonic(), idx, ptr, align);
+            }
+
+            (ptr, dtype)
+        },
+    };
+
+    let array = match dtype {
+        DataType::Array(array) => array,
+        dtype => return Err(IRGenError(format!("[IRGen] {} cannot be indexed", dtype.to_mnemonic()))),
+    };
+
+    let (code, idx, index_dtype) = generate_expr(global_ctx, scoped_ctx, &expr.index)?;
+    result += &code;
+
+    let bits = match index_dtype {
+        DataType::SignedInteger(dtype) => dtype.bits(),
+        DataType::UnsignedInteger(dtype) => dtype.bits(),
+        dtype => return Err(IRGenError(format!("[IRGen] index must be an integer, found {}", dtype.to_mnemonic()))),
+    };
+
+    // 128-bit indices are compared before they are truncated, all others once they are extended
+    let (checked, checked_dtype) = if bits == 128 {
+        (idx.clone(), index_dtype)
+    } else {
+        let extended_dtype = match index_dtype {
+            DataType::SignedInteger(_) => DataType::SignedInteger(SignedInteger::i64),
+            _ => DataType::UnsignedInteger(UnsignedInteger::u64),
+        };
+
+        let (idx, cast_code) = convert(global_ctx, &idx, index_dtype, extended_dtype)?;
+        result += &cast_code;
+        (idx, extended_dtype)
+    };
+
+    let (offset, cast_code) = convert(global_ctx, &checked, checked_dtype, DataType::SignedInteger(SignedInteger::i64))?;
+    result += &cast_code;
+
+    if global_ctx.bounds_checks {
+        let in_bounds = global_ctx.get_register();
+        let ok_idx = global_ctx.get_label();
+        let panic_idx = global_ctx.get_label();
+
+        // a negative index is a large unsigned one
+        result += &format!("{} = icmp ult {} {}, {}\n", in_bounds, checked_dtype.to_mnemonic(), checked, array.len);
+        result += &format!("br i1 {}, label %l.{}, label %l.{}\n", in_bounds, ok_idx, panic_idx);
+        result += &format!("l.{}:\n", panic_idx);
+        result += &format!("call void @{}(i64 {}, i1 {}, i64 {})\n", PANIC_BOUNDS, offset, matches!(index_dtype, DataType::SignedInteger(_)) as u8, array.len);
+        result += "unreachable\n";
+        result += &format!("l.{}:\n", ok_idx);
+
+        global_ctx.panics = true;
+    }
+
+    let element_ptr = global_ctx.get_register();
+    result += &format!("{} = getelementptr inbounds {}, ptr {}, i64 0, i64 {}\n", element_ptr, dtype.to_mnemonic(), ptr, offset);
+
+    Ok((result, element_ptr, array.element))
+}
+
+// returns the code computing a pointer to the field `expr` refers to, the pointer and the field's type
+pub fn generate_field_ptr(global_ctx: &mut GlobalContext, scoped_ctx: &mut Vec<ScopedContext>, expr: &MemberExpression) -> Result<(String, String, DataType), IRGenError> {
+    let mut result = String::new();
+
+    // a parameter that is neither a struct nor addressed has no stack slot, its value is used directly
+    let is_value = match expr.expr.as_ref() {
+        Expression::Identifier(ident) => find_variable(scoped_ctx, ident).is_none() && is_param(scoped_ctx, ident),
+        base => !is_place(base),
+    };
+
+    // the field of a pointer to a struct or tuple is the field of its pointee
+    let (ptr, dtype) = if is_value {
+        let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, &expr.expr)?;
+        result += &code;
+
+        match dtype {
+            DataType::Pointer(pointee @ (DataType::Struct(_) | DataType::Tuple(_))) => (idx, *pointee),
+            DataType::Struct(_) | DataType::Tuple(_) => {
+                let ptr = global_ctx.get_register();
+                let align = global_ctx.align_of(dtype);
+
+                result += &format!("{} = alloca {}, align {}\n", ptr, dtype.to_mnemonic(), align);
+                result += &format!("store {} {}, ptr {}, align {}\n", dtype.to_mnemonic(), idx, ptr, align);
+                (ptr, dtype)
+            },
+            dtype => (idx, dtype),
+        }
+    } else {
+        let (code, ptr, dtype) = generate_address(global_ctx, scoped_ctx, &expr.expr)?;
+        result += &code;
+
+        match dtype {
+            DataType::Pointer(pointee @ (DataType::Struct(_) | DataType::Tuple(_))) => {
+                let idx = global_ctx.get_register();
+                result += &format!("{} = load ptr, ptr {}, align {}\n", idx, ptr, global_ctx.align_of(dtype));
+                (idx, *pointee)
+            },
+            dtype => (ptr, dtype),
+        }
+    };
+
+    let (n, field) = dtype.field(&expr.field)
+        .ok_or_else(|| IRGenError(format!("[IRGen] {} has no field `{}`", dtype, expr.field)))?;
+
+    let field_ptr = global_ctx.get_register();
+    result += &format!("{} = getelementptr inbounds {}, ptr {}, i32 0, i32 {}\n", field_ptr, dtype.to_mnemonic(), ptr, n);
+
+    Ok((result, field_ptr, field))
+}
+
+// returns the code computing the address of the place `expr` names, the address and the type stored there
+pub fn generate_address(global_ctx: &mut GlobalContext, scoped_ctx: &mut Vec<ScopedContext>, expr: &Expression) -> Result<(String, String, DataType), IRGenError> {
+    match expr {
+        Expression::Identifier(ident) => {
+            if let Some((ptr, dtype)) = find_variable(scoped_ctx, ident) {
+                return Ok((String::new(), ptr, dtype));
+            }
+
+            let is_param = is_param(scoped_ctx, ident);
+
+            // a global string is the constant holding its characters, there is no variable to point to
+            match global_ctx.global_var.get(ident) {
+                Some(literal) if !is_param && !matches!(literal, Literal::String(_)) => Ok((String::new(), format!("@{}", ident), literal_dtype(literal))),
+                Some(_) => Err(IRGenError(format!("[IRGen] `{}` has no address", ident))),
+                None if is_param => Err(IRGenError(format!("[IRGen] `{}` has no address", ident))),
+                None => Err(IRGenError(format!("[IRGen] identifier `{}` not found", ident))),
+            }
+        },
+        Expression::Index(expr) => generate_element_ptr(global_ctx, scoped_ctx, expr),
+        Expression::Member(expr) => generate_field_ptr(global_ctx, scoped_ctx, expr),
+        Expression::Unary(unary) if unary.operator == Operator::Dereference => {
+            let (code, ptr, dtype) = generate_expr(global_ctx, scoped_ctx, &unary.right)?;
+
+            match dtype {
+                DataType::Pointer(pointee) if *pointee != DataType::void => Ok((code, ptr, *pointee)),
+                dtype => Err(IRGenError(format!("[IRGen] operator Dereference cannot be applied to {}", dtype))),
+            }
+        },
+        expr => Err(IRGenError(format!("[IRGen] {} does not name a place", expr))),
+    }
+}
+
+// pointers move by whole elements, `types::pointer_infix_type` decides which operations are allowed
+fn generate_pointer_infix(global_ctx: &mut GlobalContext, operator: Operator, left: (&str, DataType), right: (&str, DataType)) -> Result<(String, String, DataType), IRGenError> {
+    let dtype = pointer_infix_type(operator, left.1, right.1)
+        .ok_or_else(|| IRGenError(format!("[IRGen] operator {:?} cannot be applied to {} and {}", operator, left.1, right.1)))?;
+
+    let mut result = String::new();
+
+    let idx = match (left, right) {
+        ((left, DataType::Pointer(pointee)), (right, DataType::Pointer(_))) => {
+            let predicate = match operator {
+                Operator::Equal => "eq",
+                Operator::NotEqual => "ne",
+                Operator::Less => "ult",
+                Operator::LessEqual => "ule",
+                Operator::Greater => "ugt",
+                Operator::GreaterEqual => "uge",
+                // the distance in bytes, divided by the size of an element
+                _ => {
+                    let (l, r, bytes) = (global_ctx.get_register(), global_ctx.get_register(), global_ctx.get_register());
+                    result += &format!("{} = ptrtoint ptr {} to i64\n", l, left);
+                    result += &format!("{} = ptrtoint ptr {} to i64\n", r, right);
+                    result += &format!("{} = sub i64 {}, {}\n", bytes, l, r);
+
+                    let idx = global_ctx.get_register();
+                    result += &format!("{} = sdiv exact i64 {}, ptrtoint (ptr getelementptr ({}, ptr null, i64 1) to i64)\n", idx, bytes, pointee.to_mnemonic());
+
+                    return Ok((result, idx, dtype));
+                },
+            };
+
+            let idx = global_ctx.get_register();
+            result += &format!("{} = icmp {} ptr {}, {}\n", idx, predicate, left, right);
+            idx
+        },
+        ((ptr, DataType::Pointer(pointee)), (offset, offset_dtype)) | ((offset, offset_dtype), (ptr, DataType::Pointer(pointee))) => {
+            let (offset, cast_code) = convert(global_ctx, offset, offset_dtype, DataType::SignedInteger(SignedInteger::i64))?;
+            result += &cast_code;
+
+            let offset = if operator == Operator::Minus {
+                let negated = global_ctx.get_register();
+                result += &format!("{} = sub i64 0, {}\n", negated, offset);
+                negated
+            } else {
+                offset
+            };
+
+            let idx = global_ctx.get_register();
+            result += &format!("{} = getelementptr {}, ptr {}, i64 {}\n", idx, pointee.to_mnemonic(), ptr, offset);
+            idx
+        },
+        _ => unreachable!("one of the operands is a pointer"),
+    };
+
+    Ok((result, idx, dtype))
+}
+
+// the stack slot and type of the local variable `ident`, unless a parameter or nothing of that name is in scope
+fn find_variable(scoped_ctx: &[ScopedContext], ident: &str) -> Option<(String, DataType)> {
+    scoped_ctx.iter().rev()
+        .find_map(|ctx| match ctx {
+            ScopedContext::FnDecl(fn_decl, _) => fn_decl.contains_key(ident).then_some(None),
+            ScopedContext::Scope(scope) => scope.get(ident).map(|slot| Some(slot.clone())),
+        })
+        .flatten()
+}
+
+fn is_param(scoped_ctx: &[ScopedContext], ident: &str) -> bool {
+    scoped_ctx.iter().any(|ctx| matches!(ctx, ScopedContext::FnDecl(fn_decl, _) if fn_decl.contains_key(ident)))
+}
+
+// the values of a variant as a literal struct, which is how its payload is read and written
+pub fn payload_mnemonic(variant: &Variant) -> String {
+    format!("{{ {} }}", variant.payload.iter().map(|dtype| dtype.to_mnemonic()).collect::<Vec<&str>>().join(", "))
 }
\ No newline at end of file
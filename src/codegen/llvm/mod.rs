@@ -1,15 +1,25 @@
+pub mod debug;
+pub mod entry;
 pub mod expr;
+pub mod panic;
 pub mod types;
 
 use std::borrow::Borrow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::parser::{DefStatement, Expression, ExternStatement, IfBranch, IfStatement, LetStatement, ReturnStatement, Statement, WhileStatement, AST};
+use crate::parser::{addressed_variables, AssignStatement, DefStatement, DestructureStatement, EnumStatement, Expression, ExternStatement, IfBranch, IfStatement, LetStatement, MatchStatement, Pattern, ReturnStatement, Statement, StructStatement, WhileStatement, AST};
 use crate::lexer::Literal;
 use crate::error::IRGenError;
-use crate::types::{DataType, SignedInteger};
-pub use expr::generate_expr;
-use types::cast;
+use crate::fold::convert_exact;
+use crate::types::{DataType, EnumType, SignedInteger};
+use crate::codegen::target::Target;
+pub use expr::{generate_address, generate_element_ptr, generate_expr, payload_mnemonic};
+use debug::DebugInfo;
+use entry::{Entry, MAIN_SYMBOL};
+use types::convert;
+
+// a generic function whose calls keep creating new types would be instantiated forever
+const MAX_INSTANCES: usize = 1024;
 
 pub struct IRGen {
     ast: AST,
@@ -18,14 +28,27 @@ pub struct IRGen {
 
 #[derive(Default)]
 pub struct GlobalContext {
+    target: Target,
     global_var: HashMap<String, Literal>,
-    fn_decl: HashMap<String, (Vec<String>, DataType)>,
+    fn_decl: HashMap<String, (Vec<DataType>, DataType)>,
+    // generic functions, which are only generated through their instances
+    generics: HashMap<String, DefStatement>,
+    // instances that calls asked for but are not generated yet
+    instances: Vec<DefStatement>,
+    // names already taken by the function being generated
+    local_names: HashSet<String>,
     label_idx: u64,
+    debug: Option<DebugInfo>,
+    entry: Option<Entry>,
+    bounds_checks: bool,
+    // whether any check calls the panic routine, which is only emitted then
+    panics: bool,
 }
 
 pub enum ScopedContext {
     FnDecl(HashMap<String, DataType>, DataType),
-    Scope(HashMap<String, Literal>),
+    // maps each variable to its stack slot and type
+    Scope(HashMap<String, (String, DataType)>),
 }
 
 impl GlobalContext {
@@ -34,6 +57,33 @@ impl GlobalContext {
         self.label_idx += 1;
         result
     }
+
+    // temporaries are named rather than numbered, so that LLVM does not require them to be sequential.
+    // identifiers cannot contain `.`, which keeps these apart from user variables.
+    pub fn get_register(&mut self) -> String {
+        format!("%t.{}", self.get_label())
+    }
+
+    pub fn align_of(&self, dtype: DataType) -> u32 {
+        self.target.align_of(dtype)
+    }
+
+    // the first variable of a name keeps it, later ones (shadowing, sibling blocks) get a suffix
+    pub fn local_pointer(&mut self, ident: &str) -> String {
+        if self.local_names.insert(ident.into()) {
+            format!("%{}", ident)
+        } else {
+            format!("%{}.s{}", ident, self.get_label())
+        }
+    }
+
+    pub fn fn_symbol(&self, name: &str) -> String {
+        if self.entry.is_some() && name == "main" {
+            MAIN_SYMBOL.into()
+        } else {
+            name.into()
+        }
+    }
 }
 
 impl IRGen {
@@ -44,16 +94,92 @@ impl IRGen {
         }
     }
 
+    pub fn with_target(ast: AST, target: Target) -> IRGen {
+        IRGen {
+            ast,
+            context: GlobalContext {
+                target,
+                ..GlobalContext::default()
+            },
+        }
+    }
+
+    // wraps the program's `main` in an entry point, without one the module is generated as a library
+    pub fn with_entry(mut self, entry: Entry) -> IRGen {
+        self.context.entry = Some(entry);
+        self
+    }
+
+    // emits DWARF metadata describing `filename` (relative to `directory`) alongside the IR
+    pub fn with_debug_info(mut self, filename: &str, directory: &str) -> IRGen {
+        self.context.debug = Some(DebugInfo::new(filename, directory, self.context.target));
+        self
+    }
+
+    // checks every array index at run time, an index out of bounds panics with exit code 101
+    pub fn with_bounds_checks(mut self) -> IRGen {
+        self.context.bounds_checks = true;
+        self
+    }
+
     pub fn generate_ir(&mut self) -> Result<String, IRGenError> {
         let mut result: String = String::new();
         let mut scoped_ctx = Vec::new();
 
-        result += include_str!("stub.ll");
+        result += &format!("target datalayout = \"{}\"\n", self.context.target.data_layout);
+        result += &format!("target triple = \"{}\"\n\n", self.context.target.triple);
+
+        // functions and globals can be used before the statement that defines them
+        for stmt in &self.ast {
+            match stmt {
+                Statement::Def(stmt) if !stmt.type_params.is_empty() => {
+                    self.context.generics.insert(stmt.name.clone(), stmt.clone());
+                },
+                Statement::Def(stmt) => {
+                    self.context.fn_decl.insert(stmt.name.clone(), (stmt.params.iter().map(|(_, dtype)| *dtype).collect(), stmt.r#type));
+                },
+                Statement::Extern(stmt) => {
+                    self.context.fn_decl.insert(stmt.name.clone(), (stmt.params.iter().map(|(_, dtype)| *dtype).collect(), stmt.r#type));
+                },
+                Statement::Let(LetStatement { ident, expr: Expression::Literal((literal, _)), .. }) => {
+                    self.context.global_var.insert(ident.clone(), literal.clone());
+                },
+                _ => {},
+            }
+        }
+
+        if let Some(entry) = self.context.entry {
+            result += &entry::generate_entry(entry, &self.context.target, &self.ast)?;
+        }
+
         result += &self.ast.iter()
-            .map(|stmt| IRGen::generate_global_stmt(&mut self.context, &mut scoped_ctx, stmt).unwrap())
-            .collect::<Vec<String>>()
+            .map(|stmt| IRGen::generate_global_stmt(&mut self.context, &mut scoped_ctx, stmt))
+            .collect::<Result<Vec<String>, IRGenError>>()?
             .join("");
 
+        // an instance can call for more instances, which are appended until none are left
+        let mut generated = 0;
+
+        while !self.context.instances.is_empty() {
+            let instance = self.context.instances.remove(0);
+
+            generated += 1;
+            if generated > MAX_INSTANCES {
+                let name = instance.name.split('$').next().unwrap_or_default();
+                return Err(IRGenError(format!("[IRGen] more than {} instances of generic functions, `{}` keeps calling for new ones", MAX_INSTANCES, name)));
+            }
+
+            result += &IRGen::generate_def(&mut self.context, &mut scoped_ctx, &instance)?;
+        }
+
+        if self.context.panics {
+            result += &panic::generate_panic(&self.context)?;
+        }
+
+        if let Some(debug) = &self.context.debug {
+            result += &debug.finish();
+        }
+
         Ok(result)
     }
 
@@ -61,10 +187,13 @@ impl IRGen {
         let mut result = String::new();
 
         match stmt {
-            Statement::Let(stmt) => result += &IRGen::generate_global_variable(global_ctx, scoped_ctx, stmt).unwrap(),
-            Statement::Def(stmt) => result += &IRGen::generate_def(global_ctx, scoped_ctx, stmt).unwrap(),
-            Statement::Extern(stmt) => result += &IRGen::generate_extern(global_ctx, scoped_ctx, stmt).unwrap(),
-            _ => panic!("{} cannot be global", stmt),
+            Statement::Let(stmt) => result += &IRGen::generate_global_variable(global_ctx, scoped_ctx, stmt)?,
+            Statement::Def(stmt) if !stmt.type_params.is_empty() => {},
+            Statement::Def(stmt) => result += &IRGen::generate_def(global_ctx, scoped_ctx, stmt)?,
+            Statement::Extern(stmt) => result += &IRGen::generate_extern(global_ctx, scoped_ctx, stmt)?,
+            Statement::Struct(stmt) => result += &IRGen::generate_struct(stmt)?,
+            Statement::Enum(stmt) => result += &IRGen::generate_enum(global_ctx, stmt)?,
+            _ => return Err(IRGenError(format!("[IRGen] {} cannot be global", stmt))),
         }
 
         Ok(result)
@@ -74,12 +203,25 @@ impl IRGen {
         let mut result = String::new();
 
         match stmt {
-            Statement::Let(stmt) => result += &IRGen::generate_local_variable(global_ctx, scoped_ctx, stmt).unwrap(),
-            Statement::If(stmt) => result += &IRGen::generate_if(global_ctx, scoped_ctx, stmt).unwrap(),
-            Statement::Return(stmt) => result += &IRGen::generate_ret(global_ctx, scoped_ctx, stmt).unwrap(),
-            Statement::Expression(stmt) => result += &generate_expr(global_ctx, scoped_ctx, &stmt.expr).unwrap().0,
-            Statement::While(stmt) => result += &IRGen::generate_while(global_ctx, scoped_ctx, stmt).unwrap(),
-            _ => panic!("{} cannot be local", stmt),
+            Statement::Let(stmt) => result += &IRGen::generate_local_variable(global_ctx, scoped_ctx, stmt)?,
+            Statement::Destructure(stmt) => result += &IRGen::generate_destructure(global_ctx, scoped_ctx, stmt)?,
+            Statement::If(stmt) => result += &IRGen::generate_if(global_ctx, scoped_ctx, stmt)?,
+            Statement::Return(stmt) => result += &IRGen::generate_ret(global_ctx, scoped_ctx, stmt)?,
+            Statement::Expression(stmt) => result += &generate_expr(global_ctx, scoped_ctx, &stmt.expr)?.0,
+            Statement::While(stmt) => result += &IRGen::generate_while(global_ctx, scoped_ctx, stmt)?,
+            Statement::Assign(stmt) => result += &IRGen::generate_assign(global_ctx, scoped_ctx, stmt)?,
+            Statement::Match(stmt) => result += &IRGen::generate_match(global_ctx, scoped_ctx, stmt)?,
+            _ => return Err(IRGenError(format!("[IRGen] {} cannot be local", stmt))),
+        }
+
+        if let Some(debug) = global_ctx.debug.as_mut() {
+            if let (Statement::Let(stmt), Some(ScopedContext::Scope(scope))) = (stmt, scoped_ctx.last()) {
+                if let Some((ptr, DataType::SignedInteger(_) | DataType::UnsignedInteger(_) | DataType::bool | DataType::Array(_) | DataType::Pointer(_) | DataType::Struct(_) | DataType::Enum(_) | DataType::Tuple(_))) = scope.get(&stmt.ident) {
+                    result += &debug.declare(ptr, &stmt.ident, stmt.span, stmt.r#type, None);
+                }
+            }
+
+            result = debug.attach(&result, stmt.span());
         }
 
         Ok(result)
@@ -92,11 +234,11 @@ impl IRGen {
             match literal {
                 Literal::SignedInteger((n, dtype)) => {
                     global_ctx.global_var.insert(stmt.ident.clone(), literal.clone());
-                    result += &format!("@{} = global {} {}\n", stmt.ident.clone(), dtype.to_mnemonic(), n);
+                    result += &format!("@{} = global {} {}, align {}\n", stmt.ident.clone(), dtype.to_mnemonic(), n, global_ctx.align_of(DataType::SignedInteger(*dtype)));
                 },
                 Literal::UnsignedInteger((n, dtype)) => {
                     global_ctx.global_var.insert(stmt.ident.clone(), literal.clone());
-                    result += &format!("@{} = global {} {}\n", stmt.ident.clone(), dtype.to_mnemonic(), n);
+                    result += &format!("@{} = global {} {}, align {}\n", stmt.ident.clone(), dtype.to_mnemonic(), n, global_ctx.align_of(DataType::UnsignedInteger(*dtype)));
                 },
                 Literal::String(s) => {
                     global_ctx.global_var.insert(stmt.ident.clone(), literal.clone());
@@ -104,55 +246,111 @@ impl IRGen {
                 },
                 Literal::Boolean(b) => {
                     global_ctx.global_var.insert(stmt.ident.clone(), literal.clone());
-                    result += &format!("@{} = global {} {}\n", stmt.ident, "i1", if *b { "1" } else { "0" });
-                }
+                    result += &format!("@{} = global {} {}, align {}\n", stmt.ident, "i1", if *b { "1" } else { "0" }, global_ctx.align_of(DataType::bool));
+                },
+                Literal::Null(dtype) => {
+                    global_ctx.global_var.insert(stmt.ident.clone(), literal.clone());
+                    result += &format!("@{} = global ptr null, align {}\n", stmt.ident, global_ctx.align_of(*dtype));
+                },
             }
         } else {
-            eprintln!("{:?} in let expression is not implemented.", stmt.expr);
+            return Err(IRGenError(format!("[IRGen] global `{}` must be initialized with a constant expression", stmt.ident)));
         }
 
         Ok(result)
     }
 
-    fn generate_local_variable(_global_ctx: &mut GlobalContext, scoped_ctx: &mut Vec<ScopedContext>, stmt: &LetStatement) -> Result<String, IRGenError> {
+    fn generate_local_variable(global_ctx: &mut GlobalContext, scoped_ctx: &mut Vec<ScopedContext>, stmt: &LetStatement) -> Result<String, IRGenError> {
         let mut result = String::new();
-        
-        if let Expression::Literal((literal, _)) = &stmt.expr {
-            if let ScopedContext::Scope(scope) = scoped_ctx.last_mut().unwrap() {
-                scope.insert(stmt.ident.clone(), literal.clone());
-            } else {
-                panic!("expected scope, something else found :(");
-            }
 
-            match literal {
-                Literal::SignedInteger((n, dtype)) => {
-                    result += &format!("%{} = alloca {}, align 4\n", stmt.ident.clone(), dtype.to_mnemonic());
-                    result += &format!("store {} {}, ptr %{}, align 4\n", dtype.to_mnemonic(), n, stmt.ident.clone());
-                },
-                Literal::UnsignedInteger((n, dtype)) => {
-                    result += &format!("%{} = alloca {}, align 4\n", stmt.ident.clone(), dtype.to_mnemonic());
-                    result += &format!("store {} {}, ptr %{}, align 4\n", dtype.to_mnemonic(), n, stmt.ident.clone());
-                },
-                Literal::String(s) => {
-                    result += &format!("%{} = private unnamed_addr constant [{} x i8] c\"{}\\00\"\n", stmt.ident, s.len() + 1, s);
-                },
-                Literal::Boolean(b) => {
-                    result += &format!("%{} = alloca {}, align 4\n", stmt.ident.clone(), "i1");
-                    result += &format!("store {} {}, ptr %{}, align 4\n", "i1", if *b { "1" } else { "0" }, stmt.ident.clone());
-                }
+        // an integer or bool literal of the right type is stored as is, anything else is evaluated first
+        let value = match &stmt.expr {
+            Expression::Literal((Literal::SignedInteger((n, dtype)), _)) if DataType::SignedInteger(*dtype) == stmt.r#type => n.to_string(),
+            Expression::Literal((Literal::UnsignedInteger((n, dtype)), _)) if DataType::UnsignedInteger(*dtype) == stmt.r#type => n.to_string(),
+            Expression::Literal((Literal::Boolean(b), _)) if stmt.r#type == DataType::bool => (if *b { "1" } else { "0" }).into(),
+            expr => {
+                let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, expr)?;
+                result += &code;
+
+                let (idx, cast_code) = convert(global_ctx, &idx, dtype, stmt.r#type)?;
+                result += &cast_code;
+                idx
+            },
+        };
+
+        let ptr = global_ctx.local_pointer(&stmt.ident);
+        let align = global_ctx.align_of(stmt.r#type);
+
+        result += &format!("{} = alloca {}, align {}\n", ptr, stmt.r#type.to_mnemonic(), align);
+        result += &format!("store {} {}, ptr {}, align {}\n", stmt.r#type.to_mnemonic(), value, ptr, align);
+
+        match scoped_ctx.last_mut() {
+            Some(ScopedContext::Scope(scope)) => scope.insert(stmt.ident.clone(), (ptr, stmt.r#type)),
+            _ => return Err(IRGenError(format!("[IRGen] `{}` must be declared inside a scope", stmt.ident))),
+        };
+
+        Ok(result)
+    }
+
+    // every named element is extracted into a stack slot of its own, like a `let` of the element's type
+    fn generate_destructure(global_ctx: &mut GlobalContext, scoped_ctx: &mut Vec<ScopedContext>, stmt: &DestructureStatement) -> Result<String, IRGenError> {
+        let mut result = String::new();
+
+        let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, &stmt.expr)?;
+        result += &code;
+
+        let (idx, dtype) = match stmt.r#type {
+            Some(r#type) => {
+                let (idx, cast_code) = convert(global_ctx, &idx, dtype, r#type)?;
+                result += &cast_code;
+                (idx, r#type)
+            },
+            None => (idx, dtype),
+        };
+
+        let tuple = match dtype {
+            DataType::Tuple(tuple) if tuple.elements.len() == stmt.names.len() => tuple,
+            dtype => return Err(IRGenError(format!("[IRGen] {} cannot be destructured into {} names", dtype, stmt.names.len()))),
+        };
+
+        let mut bindings = Vec::new();
+
+        for (n, (name, element)) in stmt.names.iter().zip(&tuple.elements).enumerate() {
+            let name = match name {
+                Some(name) => name,
+                None => continue,
+            };
+
+            let value = global_ctx.get_register();
+            let ptr = global_ctx.local_pointer(name);
+            let align = global_ctx.align_of(*element);
+
+            result += &format!("{} = extractvalue {} {}, {}\n", value, dtype.to_mnemonic(), idx, n);
+            result += &format!("{} = alloca {}, align {}\n", ptr, element.to_mnemonic(), align);
+            result += &format!("store {} {}, ptr {}, align {}\n", element.to_mnemonic(), value, ptr, align);
+
+            if let Some(debug) = global_ctx.debug.as_mut() {
+                result += &debug.declare(&ptr, name, stmt.span, *element, None);
             }
-        } else {
-            eprintln!("{:?} in let expression is not implemented.", stmt.expr);
+
+            bindings.push((name.clone(), (ptr, *element)));
         }
 
+        match scoped_ctx.last_mut() {
+            Some(ScopedContext::Scope(scope)) => scope.extend(bindings),
+            _ => return Err(IRGenError(format!("[IRGen] {} must be declared inside a scope", stmt))),
+        };
+
         Ok(result)
     }
 
     fn generate_def(global_ctx: &mut GlobalContext, scoped_ctx: &mut Vec<ScopedContext>, stmt: &DefStatement) -> Result<String, IRGenError> {
         let mut result = String::new();
 
-        result += &format!("define {} @{}(", stmt.r#type.to_mnemonic(), stmt.name);
-        
+        result += &format!("define {} @{}(", stmt.r#type.to_mnemonic(), global_ctx.fn_symbol(&stmt.name));
+
+        let addressed = addressed_variables(&stmt.stmts);
+
         let mut params: HashMap<String, DataType> = HashMap::new();
 
         result += &stmt.params.iter()
@@ -163,23 +361,87 @@ impl IRGen {
             .collect::<Vec<String>>()
             .join(", ");
 
-        result += ") {\n";
+        if let Some(debug) = global_ctx.debug.as_mut() {
+            let param_dtypes: Vec<DataType> = stmt.params.iter().map(|(_, dtype)| *dtype).collect();
+            let subprogram = debug.begin_subprogram(&stmt.name, stmt.span, &param_dtypes, stmt.r#type);
+            result += &format!(") !dbg !{} {{\n", subprogram);
+
+            // parameters are plain SSA values, so give the debugger a stack slot to look at
+            let mut code = String::new();
+
+            for (idx, (ident, dtype)) in stmt.params.iter().enumerate() {
+                // some parameters get a stack slot anyway, which is declared below
+                if needs_slot(ident, *dtype, &addressed) {
+                    continue;
+                }
+
+                let align = global_ctx.target.align_of(*dtype);
+                code += &format!("%{ident}.addr = alloca {}, align {}\n", dtype.to_mnemonic(), align);
+                code += &format!("store {} %{ident}, ptr %{ident}.addr, align {}\n", dtype.to_mnemonic(), align);
+                code += &debug.declare(&format!("%{ident}.addr"), ident, stmt.span, *dtype, Some(idx + 1));
+            }
+
+            result += &debug.attach(&code, stmt.span);
+        } else {
+            result += ") {\n";
+        }
 
-        global_ctx.fn_decl.insert(stmt.name.to_string(), (stmt.params.iter().map(|(_, dtype)| dtype.to_mnemonic().into()).collect::<Vec<String>>(), stmt.r#type));
+        global_ctx.fn_decl.insert(stmt.name.to_string(), (stmt.params.iter().map(|(_, dtype)| *dtype).collect(), stmt.r#type));
+        global_ctx.local_names = stmt.params.iter().map(|(ident, _)| ident.clone()).collect();
         scoped_ctx.push(ScopedContext::FnDecl(params, stmt.r#type));
 
+        // array and struct parameters and those whose address is taken are copied to the stack,
+        // where they can be indexed, assigned and pointed to
+        let mut slots = HashMap::new();
+        let mut copies = String::new();
+
+        for (idx, (ident, dtype)) in stmt.params.iter().enumerate() {
+            if needs_slot(ident, *dtype, &addressed) {
+                let ptr = global_ctx.local_pointer(ident);
+                let align = global_ctx.align_of(*dtype);
+
+                copies += &format!("{} = alloca {}, align {}\n", ptr, dtype.to_mnemonic(), align);
+                copies += &format!("store {} %{}, ptr {}, align {}\n", dtype.to_mnemonic(), ident, ptr, align);
+
+                if let Some(debug) = global_ctx.debug.as_mut() {
+                    copies += &debug.declare(&ptr, ident, stmt.span, *dtype, Some(idx + 1));
+                }
+
+                slots.insert(ident.clone(), (ptr, *dtype));
+            }
+        }
+
+        if let Some(debug) = global_ctx.debug.as_mut() {
+            result += &debug.attach(&copies, stmt.span);
+        } else {
+            result += &copies;
+        }
+
         // add statements
-        scoped_ctx.push(ScopedContext::Scope(HashMap::new()));
+        scoped_ctx.push(ScopedContext::Scope(slots));
         result += &stmt.stmts.iter()
-            .map(|stmt| IRGen::generate_local_stmt(global_ctx, scoped_ctx, stmt).unwrap())
-            .collect::<Vec<String>>()
+            .map(|stmt| IRGen::generate_local_stmt(global_ctx, scoped_ctx, stmt))
+            .collect::<Result<Vec<String>, IRGenError>>()?
             .join("\n");
 
+        // every block needs a terminator, including the one after the last statement
+        let terminator = if stmt.r#type == DataType::void { "ret void\n" } else { "unreachable\n" };
+
+        if let Some(debug) = global_ctx.debug.as_mut() {
+            result += &debug.attach(terminator, stmt.span);
+        } else {
+            result += terminator;
+        }
+
         result += "}\n";
 
         scoped_ctx.pop(); // pop scope
         scoped_ctx.pop(); // pop fn_decl
 
+        if let Some(debug) = global_ctx.debug.as_mut() {
+            debug.end_subprogram();
+        }
+
         Ok(result)
     }
 
@@ -188,43 +450,33 @@ impl IRGen {
         let mut result = String::new();
         let then_idx = global_ctx.get_label();
         let else_idx = global_ctx.get_label();
+        let end_idx = global_ctx.get_label();
 
         // process condition
-        let (expr_code, expr_idx, _expr_dtype) = generate_expr(global_ctx, scoped_ctx, &stmt.condition).unwrap();
+        let (expr_code, expr_idx, _expr_dtype) = generate_expr(global_ctx, scoped_ctx, &stmt.condition)?;
         result += &expr_code;
-        result += &format!("br i1 {}, label %l{}, label %l{}\n", expr_idx, then_idx, else_idx);
-
-        if let IfBranch::None = *stmt.r#else {
-            result += &format!("l{}:\n", then_idx);
-            for then_stmt in &stmt.then {
-                result += &IRGen::generate_local_stmt(global_ctx, scoped_ctx, then_stmt).unwrap();
-            }
-
-            result += &format!("l{}:\n", else_idx);
-        } else {
-            // process then
-            result += &format!("l{}:\n", then_idx);
-            for then_stmt in &stmt.then {
-                result += &IRGen::generate_local_stmt(global_ctx, scoped_ctx, then_stmt).unwrap();
-            }
-            // process else
-            match stmt.r#else.borrow() {
-                IfBranch::Elif(stmt) => {
-                    result += &format!("l{}:\n", else_idx);
-                    result += &Self::generate_if(global_ctx, scoped_ctx, &stmt).unwrap();
-                },
-                IfBranch::Else(stmt) => {
-                    result += &format!("l{}:\n", else_idx);
-                    for else_stmt in stmt {
-                        result += &IRGen::generate_local_stmt(global_ctx, scoped_ctx, &else_stmt).unwrap();
-                    }
-                },
-                IfBranch::None => { },
-            }
+        result += &format!("br i1 {}, label %l.{}, label %l.{}\n", expr_idx, then_idx, else_idx);
+
+        // process then
+        result += &format!("l.{}:\n", then_idx);
+        result += &IRGen::generate_block(global_ctx, scoped_ctx, &stmt.then)?;
+        result += &format!("br label %l.{}\n", end_idx);
+
+        // process else
+        result += &format!("l.{}:\n", else_idx);
+        match stmt.r#else.borrow() {
+            IfBranch::Elif(stmt) => {
+                result += &Self::generate_if(global_ctx, scoped_ctx, stmt)?;
+            },
+            IfBranch::Else(stmts) => {
+                result += &IRGen::generate_block(global_ctx, scoped_ctx, stmts)?;
+            },
+            IfBranch::None => { },
         }
+        result += &format!("br label %l.{}\n", end_idx);
 
-
-        result += "\n";
+        // a `br` right after a `ret` opens an unreachable block, which LLVM accepts
+        result += &format!("l.{}:\n", end_idx);
         Ok(result)
     }
 
@@ -235,29 +487,157 @@ impl IRGen {
         let loop_idx = global_ctx.get_label();
         let break_idx = global_ctx.get_label();
 
-        let (expr_code, expr_idx, _expr_dtype) = generate_expr(global_ctx, scoped_ctx, &stmt.condition).unwrap();
-        result += &format!("br label %l{}\n", check_idx);
-        result += &format!("l{}:\n", check_idx);
-        result += &expr_code;
-        result += &format!("br i1 {}, label %l{}, label %l{}\n", expr_idx, loop_idx, break_idx);
-         
-        result += &format!("l{}:\n", loop_idx);
-        for stmt in &stmt.blocks {
-            result += &IRGen::generate_local_stmt(global_ctx, scoped_ctx, stmt).unwrap();
-        }
+        let (expr_code, expr_idx, _expr_dtype) = generate_expr(global_ctx, scoped_ctx, &stmt.condition)?;
+        let mut code = format!("l.{}:\n", check_idx);
+        code += &expr_code;
+        code += &format!("br i1 {}, label %l.{}, label %l.{}\n", expr_idx, loop_idx, break_idx);
+
+        code += &format!("l.{}:\n", loop_idx);
+        code += &IRGen::generate_block(global_ctx, scoped_ctx, &stmt.blocks)?;
 
-        result += &format!("br label %l{}\n", check_idx);
+        // the stack slots of the loop are allocated once before it, an `alloca` in it would take more stack on every pass
+        let (allocas, code): (Vec<&str>, Vec<&str>) = code.lines().partition(|line| line.starts_with('%') && line.contains(" = alloca "));
+        result += &allocas.iter().map(|line| format!("{}\n", line)).collect::<String>();
 
-        result += &format!("l{}:\n", break_idx);
+        result += &format!("br label %l.{}\n", check_idx);
+        result += &code.iter().map(|line| format!("{}\n", line)).collect::<String>();
+        result += &format!("br label %l.{}\n", check_idx);
+
+        result += &format!("l.{}:\n", break_idx);
         //result += "\n";
 
         Ok(result)
     }
 
+    // a `switch` on the discriminant of an enum, or on the value of an integer; a case without `_` is never left out,
+    // so the default destination is `unreachable` unless there is a `case _`
+    fn generate_match(global_ctx: &mut GlobalContext, scoped_ctx: &mut Vec<ScopedContext>, stmt: &MatchStatement) -> Result<String, IRGenError> {
+        let mut result = String::new();
+
+        let (code, value, dtype) = generate_expr(global_ctx, scoped_ctx, &stmt.expr)?;
+        result += &code;
+
+        // an enum is matched through a copy on the stack, where its discriminant and payload can be read
+        let (value, ptr) = if let DataType::Enum(_) = dtype {
+            let ptr = global_ctx.get_register();
+            let align = global_ctx.align_of(dtype);
+
+            result += &format!("{} = alloca {}, align {}\n", ptr, dtype.to_mnemonic(), align);
+            result += &format!("store {} {}, ptr {}, align {}\n", dtype.to_mnemonic(), value, ptr, align);
+
+            let tag = global_ctx.get_register();
+            result += &format!("{} = load i32, ptr {}, align {}\n", tag, ptr, global_ctx.align_of(EnumType::TAG));
+            (tag, ptr)
+        } else {
+            (value, String::new())
+        };
+
+        let labels = stmt.cases.iter().map(|_| global_ctx.get_label()).collect::<Vec<u64>>();
+        let end_idx = global_ctx.get_label();
+
+        let mut default = None;
+        let mut destinations = Vec::new();
+
+        for (case, label) in stmt.cases.iter().zip(&labels) {
+            match (&case.pattern, dtype) {
+                (Pattern::Wildcard, _) => default = Some(*label),
+                (Pattern::Integer(n), DataType::SignedInteger(_) | DataType::UnsignedInteger(_)) => {
+                    let literal = Literal::SignedInteger((*n, SignedInteger::i128));
+
+                    match convert_exact(&literal, dtype) {
+                        Ok(Some(_)) => destinations.push(format!("{} {}, label %l.{}", dtype.to_mnemonic(), n, label)),
+                        _ => return Err(IRGenError(format!("[IRGen] case {} does not fit in {}", n, dtype))),
+                    }
+                },
+                (Pattern::Variant { dtype: pattern_dtype, variant, .. }, DataType::Enum(enumeration)) if *pattern_dtype == dtype => {
+                    let variant = enumeration.variant(variant)
+                        .ok_or_else(|| IRGenError(format!("[IRGen] {} has no variant `{}`", dtype, variant)))?;
+
+                    destinations.push(format!("i32 {}, label %l.{}", variant.discriminant, label));
+                },
+                (pattern, dtype) => return Err(IRGenError(format!("[IRGen] {} cannot be matched against `{}`", dtype, pattern))),
+            }
+        }
+
+        let mnemonic = if let DataType::Enum(_) = dtype { "i32" } else { dtype.to_mnemonic() };
+
+        match default {
+            Some(default) => result += &format!("switch {} {}, label %l.{} [ {} ]\n", mnemonic, value, default, destinations.join(" ")),
+            None => {
+                let default = global_ctx.get_label();
+                result += &format!("switch {} {}, label %l.{} [ {} ]\n", mnemonic, value, default, destinations.join(" "));
+                result += &format!("l.{}:\nunreachable\n", default);
+            },
+        }
+
+        for (case, label) in stmt.cases.iter().zip(labels) {
+            result += &format!("l.{}:\n", label);
+
+            // the values a case binds are copied out of the payload into variables of their own
+            let mut bindings = HashMap::new();
+
+            if let (Pattern::Variant { variant, bindings: names, .. }, DataType::Enum(enumeration)) = (&case.pattern, dtype) {
+                let variant = enumeration.variant(variant)
+                    .ok_or_else(|| IRGenError(format!("[IRGen] {} has no variant `{}`", dtype, variant)))?;
+
+                if names.iter().any(Option::is_some) {
+                    let payload = global_ctx.get_register();
+                    result += &format!("{} = getelementptr inbounds {}, ptr {}, i32 0, i32 1\n", payload, dtype.to_mnemonic(), ptr);
+
+                    for (n, (name, value_dtype)) in names.iter().zip(&variant.payload).enumerate() {
+                        let name = match name {
+                            Some(name) => name,
+                            None => continue,
+                        };
+
+                        let align = global_ctx.align_of(*value_dtype);
+                        let value_ptr = global_ctx.get_register();
+                        let value = global_ctx.get_register();
+                        let binding = global_ctx.local_pointer(name);
+
+                        result += &format!("{} = getelementptr inbounds {}, ptr {}, i32 0, i32 {}\n", value_ptr, payload_mnemonic(variant), payload, n);
+                        result += &format!("{} = load {}, ptr {}, align {}\n", value, value_dtype.to_mnemonic(), value_ptr, align);
+                        result += &format!("{} = alloca {}, align {}\n", binding, value_dtype.to_mnemonic(), align);
+                        result += &format!("store {} {}, ptr {}, align {}\n", value_dtype.to_mnemonic(), value, binding, align);
+
+                        if let Some(debug) = global_ctx.debug.as_mut() {
+                            result += &debug.declare(&binding, name, stmt.span, *value_dtype, None);
+                        }
+
+                        bindings.insert(name.clone(), (binding, *value_dtype));
+                    }
+                }
+            }
+
+            scoped_ctx.push(ScopedContext::Scope(bindings));
+            let block = IRGen::generate_block(global_ctx, scoped_ctx, &case.stmts);
+            scoped_ctx.pop();
+
+            result += &block?;
+            result += &format!("br label %l.{}\n", end_idx);
+        }
+
+        result += &format!("l.{}:\n", end_idx);
+        Ok(result)
+    }
+
+    // variables declared in `stmts` are only visible until the end of the block
+    fn generate_block(global_ctx: &mut GlobalContext, scoped_ctx: &mut Vec<ScopedContext>, stmts: &[Statement]) -> Result<String, IRGenError> {
+        scoped_ctx.push(ScopedContext::Scope(HashMap::new()));
+
+        let result = stmts.iter()
+            .map(|stmt| IRGen::generate_local_stmt(global_ctx, scoped_ctx, stmt))
+            .collect::<Result<String, IRGenError>>();
+
+        scoped_ctx.pop();
+        result
+    }
+
+    #[allow(clippy::clone_on_copy)]
     fn generate_ret(global_ctx: &mut GlobalContext, scoped_ctx: &mut Vec<ScopedContext>, stmt: &ReturnStatement) -> Result<String, IRGenError> {
         let mut result = String::new();
 
-        let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, &stmt.expr).unwrap();
+        let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, &stmt.expr)?;
         
         let ret_dtype: Vec<DataType> = scoped_ctx.iter().filter_map(|ctx| {
             if let ScopedContext::FnDecl(_, dtype) = ctx {
@@ -269,16 +649,15 @@ impl IRGen {
 
         let ret_dtype = ret_dtype.last().unwrap().clone(); // shitty code
 
-        let (idx, cast_code) = if ret_dtype != dtype {
-            let (casted_idx, cast_code, _) = cast()[&(dtype, ret_dtype)](global_ctx, &idx);
-            (casted_idx, cast_code)
+        let (idx, cast_code) = if ret_dtype != dtype && ret_dtype != DataType::void {
+            convert(global_ctx, &idx, dtype, ret_dtype)?
         } else {
             (idx, String::new())
         };
 
-        if idx.is_empty() { // what???
+        if idx.is_empty() || ret_dtype == DataType::void {
             result += &code;
-            result += "ret\n";
+            result += "ret void\n";
         } else {
             result += &code;
             result += &cast_code;
@@ -288,58 +667,113 @@ impl IRGen {
         Ok(result)
     }
 
-    fn generate_literal(global_ctx: &mut GlobalContext, _scoped_ctx: &mut Vec<ScopedContext>, literal: &Literal) -> Result<(String, u64), IRGenError> {
+    // the value is generated before the indices of the target
+    fn generate_assign(global_ctx: &mut GlobalContext, scoped_ctx: &mut Vec<ScopedContext>, stmt: &AssignStatement) -> Result<String, IRGenError> {
+        let mut result = String::new();
+
+        let (code, idx, dtype) = generate_expr(global_ctx, scoped_ctx, &stmt.expr)?;
+        result += &code;
+
+        let (code, ptr, element) = match &stmt.target {
+            target @ (Expression::Index(_) | Expression::Unary(_) | Expression::Member(_)) => generate_address(global_ctx, scoped_ctx, target)?,
+            target => return Err(IRGenError(format!("[IRGen] cannot assign to {}", target))),
+        };
+        result += &code;
+
+        let (idx, cast_code) = convert(global_ctx, &idx, dtype, element)?;
+        result += &cast_code;
+        result += &format!("store {} {}, ptr {}, align {}\n", element.to_mnemonic(), idx, ptr, global_ctx.align_of(element));
+
+        Ok(result)
+    }
+
+    fn generate_literal(global_ctx: &mut GlobalContext, _scoped_ctx: &mut Vec<ScopedContext>, literal: &Literal) -> Result<(String, String), IRGenError> {
         let mut result = String::new();
 
         let (idx, _dtype) = match literal {
-            Literal::SignedInteger((n, _dtype)) => {
-                let ptr_idx = global_ctx.get_label();
-                let ret_idx = global_ctx.get_label();
+            Literal::SignedInteger((n, dtype)) => {
+                let ptr_idx = global_ctx.get_register();
+                let ret_idx = global_ctx.get_register();
+                let align = global_ctx.align_of(DataType::SignedInteger(*dtype));
     
-                result += &format!("%{} = alloca i32, align 4\n", ptr_idx);
-                result += &format!("store i32 {}, ptr %{}, align 4\n", n, ptr_idx);
-                result += &format!("%{} = load i32, ptr %{}, align 4\n", ret_idx, ptr_idx);
+                result += &format!("{} = alloca {}, align {}\n", ptr_idx, dtype.to_mnemonic(), align);
+                result += &format!("store {} {}, ptr {}, align {}\n", dtype.to_mnemonic(), n, ptr_idx, align);
+                result += &format!("{} = load {}, ptr {}, align {}\n", ret_idx, dtype.to_mnemonic(), ptr_idx, align);
                 
-                (ret_idx, DataType::SignedInteger(SignedInteger::i32))
+                (ret_idx, DataType::SignedInteger(*dtype))
             },
-            Literal::UnsignedInteger((n, _dtype)) => {
-                let ptr_idx = global_ctx.get_label();
-                let ret_idx = global_ctx.get_label();
+            Literal::UnsignedInteger((n, dtype)) => {
+                let ptr_idx = global_ctx.get_register();
+                let ret_idx = global_ctx.get_register();
+                let align = global_ctx.align_of(DataType::UnsignedInteger(*dtype));
     
-                result += &format!("%{} = alloca u32, align 4\n", ptr_idx);
-                result += &format!("store u32 {}, ptr %{}, align 4\n", n, ptr_idx);
-                result += &format!("%{} = load u32, ptr %{}, align 4\n", ret_idx, ptr_idx);
+                result += &format!("{} = alloca {}, align {}\n", ptr_idx, dtype.to_mnemonic(), align);
+                result += &format!("store {} {}, ptr {}, align {}\n", dtype.to_mnemonic(), n, ptr_idx, align);
+                result += &format!("{} = load {}, ptr {}, align {}\n", ret_idx, dtype.to_mnemonic(), ptr_idx, align);
                 
-                (ret_idx, DataType::SignedInteger(SignedInteger::i32))
+                (ret_idx, DataType::UnsignedInteger(*dtype))
             },
             Literal::String(s) => {
-                let ptr_idx = global_ctx.get_label();
-                result += &format!("%{} = alloca [{} x i8], align 4\n", ptr_idx, s.len() + 1);
-                result += &format!("store [{} x i8] c\"{}\\00\", ptr %{}, align 4\n", s.len() + 1, s, ptr_idx);
+                let ptr_idx = global_ctx.get_register();
+                result += &format!("{} = alloca [{} x i8], align 1\n", ptr_idx, s.len() + 1);
+                result += &format!("store [{} x i8] c\"{}\\00\", ptr {}, align 1\n", s.len() + 1, s, ptr_idx);
 
                 (ptr_idx, DataType::str)
             },
             Literal::Boolean(b) => {
-                let ptr_idx = global_ctx.get_label();
-                let ret_idx = global_ctx.get_label();
+                let ptr_idx = global_ctx.get_register();
+                let ret_idx = global_ctx.get_register();
+                let align = global_ctx.align_of(DataType::bool);
 
-                result += &format!("%{} = alloca i1, align 4\n", ptr_idx);
-                result += &format!("store i1 {}, ptr %{}, align 4\n", if *b { "1" } else { "0" }, ptr_idx);
-                result += &format!("%{} = load i1, ptr %{}, align 4\n", ret_idx, ptr_idx);
+                result += &format!("{} = alloca i1, align {}\n", ptr_idx, align);
+                result += &format!("store i1 {}, ptr {}, align {}\n", if *b { "1" } else { "0" }, ptr_idx, align);
+                result += &format!("{} = load i1, ptr {}, align {}\n", ret_idx, ptr_idx, align);
 
                 (ret_idx, DataType::bool)
-            }
+            },
+            Literal::Null(dtype) => ("null".into(), *dtype),
         };
 
         Ok((result, idx))
     }
 
+    // a named type, LLVM lays out its fields according to the data layout like a C struct
+    fn generate_struct(stmt: &StructStatement) -> Result<String, IRGenError> {
+        let fields = match stmt.dtype {
+            DataType::Struct(structure) => structure.fields.iter().map(|(_, dtype)| dtype.to_mnemonic()).collect::<Vec<&str>>(),
+            dtype => return Err(IRGenError(format!("[IRGen] {} is not a struct", dtype))),
+        };
+
+        Ok(format!("{} = type {{ {} }}\n", stmt.dtype.to_mnemonic(), fields.join(", ")))
+    }
+
+    // the discriminant, then room for the values of the largest variant, as integers aligned like the most aligned value
+    fn generate_enum(global_ctx: &mut GlobalContext, stmt: &EnumStatement) -> Result<String, IRGenError> {
+        let enumeration = match stmt.dtype {
+            DataType::Enum(enumeration) => enumeration,
+            dtype => return Err(IRGenError(format!("[IRGen] {} is not an enum", dtype))),
+        };
+
+        if !enumeration.has_payload() {
+            return Ok(format!("{} = type {{ i32 }}\n", stmt.dtype.to_mnemonic()));
+        }
+
+        let align = global_ctx.target.payload_align(enumeration);
+        let size = global_ctx.target.payload_size(enumeration);
+
+        Ok(format!("{} = type {{ i32, [{} x i{}] }}\n", stmt.dtype.to_mnemonic(), size / align as u64, align * 8))
+    }
+
     fn generate_extern(global_ctx: &mut GlobalContext, _scoped_ctx: &mut Vec<ScopedContext>, stmt: &ExternStatement) -> Result<String, IRGenError> {
         let mut result = String::new();
 
-        global_ctx.fn_decl.insert(stmt.name.to_string(), (stmt.params.iter().map(|(_, dtype)| dtype.to_mnemonic().into()).collect::<Vec<String>>(), stmt.r#type));
+        global_ctx.fn_decl.insert(stmt.name.to_string(), (stmt.params.iter().map(|(_, dtype)| *dtype).collect(), stmt.r#type));
         result += &format!("declare {} @{}({}) nounwind\n", stmt.r#type.to_mnemonic(), stmt.name, stmt.params.iter().map(|(_, dtype)| dtype.to_mnemonic().into()).collect::<Vec<String>>().join(", "));
 
         Ok(result)
     }
+}
+
+fn needs_slot(ident: &str, dtype: DataType, addressed: &HashSet<String>) -> bool {
+    matches!(dtype, DataType::Array(_) | DataType::Struct(_) | DataType::Tuple(_)) || addressed.contains(ident)
 }
\ No newline at end of file
@@ -0,0 +1,97 @@
+use crate::codegen::target::Target;
+use crate::error::IRGenError;
+use super::entry::{exit_syscall, Entry};
+use super::GlobalContext;
+
+// the status a panicking program exits with, the same as Rust's
+pub const PANIC_EXIT_CODE: u32 = 101;
+
+pub const PANIC_BOUNDS: &str = "mamba.panic_bounds";
+
+// `@mamba.panic_bounds(i64 index, i1 signed, i64 len)` reports an index out of bounds on stderr and
+// exits. It only talks to the kernel, so it works the same with and without libc; with libc, what
+// the C library still buffers is flushed first, since the exit system call would drop it.
+pub fn generate_panic(global_ctx: &GlobalContext) -> Result<String, IRGenError> {
+    let target = &global_ctx.target;
+    let (write, constraints, number) = write_syscall(target)?;
+    let (exit, exit_constraints, exit_number) = exit_syscall(target)?;
+    let flush = global_ctx.entry == Some(Entry::C);
+
+    let mut result = String::new();
+
+    // a program that declares `fflush` itself, like `std` does, already has it
+    if flush && !global_ctx.fn_decl.contains_key("fflush") {
+        result += "\ndeclare i32 @fflush(ptr) nounwind\n";
+    }
+
+    result += "\n@mamba.panic.0 = private unnamed_addr constant [13 x i8] c\"panic: index \"\n";
+    result += "@mamba.panic.1 = private unnamed_addr constant [26 x i8] c\" out of bounds for length \"\n";
+    result += "@mamba.panic.2 = private unnamed_addr constant [1 x i8] c\"-\"\n";
+    result += "@mamba.panic.3 = private unnamed_addr constant [1 x i8] c\"\\0A\"\n\n";
+
+    result += "define private void @mamba.write(ptr %buf, i64 %len) nounwind {\n";
+    result += &format!("    %written = call i64 asm sideeffect \"{}\", \"{}\"(i64 {}, i64 2, ptr %buf, i64 %len)\n", write, constraints, number);
+    result += "    ret void\n";
+    result += "}\n\n";
+
+    // the digits are written backwards from the end of the buffer
+    result += "define private void @mamba.write_int(i64 %n, i1 %signed) nounwind {\n";
+    result += "entry:\n";
+    result += "    %buf = alloca [20 x i8], align 1\n";
+    result += "    %below_zero = icmp slt i64 %n, 0\n";
+    result += "    %negative = and i1 %signed, %below_zero\n";
+    result += "    br i1 %negative, label %minus, label %digits\n";
+    result += "minus:\n";
+    result += "    call void @mamba.write(ptr @mamba.panic.2, i64 1)\n";
+    result += "    br label %digits\n";
+    result += "digits:\n";
+    result += "    %negated = sub i64 0, %n\n";
+    result += "    %abs = select i1 %negative, i64 %negated, i64 %n\n";
+    result += "    %end = getelementptr inbounds [20 x i8], ptr %buf, i64 0, i64 20\n";
+    result += "    br label %loop\n";
+    result += "loop:\n";
+    result += "    %value = phi i64 [ %abs, %digits ], [ %next, %loop ]\n";
+    result += "    %pos = phi ptr [ %end, %digits ], [ %start, %loop ]\n";
+    result += "    %next = udiv i64 %value, 10\n";
+    result += "    %rem = urem i64 %value, 10\n";
+    result += "    %rem.8 = trunc i64 %rem to i8\n";
+    result += "    %digit = add i8 %rem.8, 48\n";
+    result += "    %start = getelementptr inbounds i8, ptr %pos, i64 -1\n";
+    result += "    store i8 %digit, ptr %start, align 1\n";
+    result += "    %done = icmp eq i64 %next, 0\n";
+    result += "    br i1 %done, label %write, label %loop\n";
+    result += "write:\n";
+    result += "    %from = ptrtoint ptr %start to i64\n";
+    result += "    %to = ptrtoint ptr %end to i64\n";
+    result += "    %len = sub i64 %to, %from\n";
+    result += "    call void @mamba.write(ptr %start, i64 %len)\n";
+    result += "    ret void\n";
+    result += "}\n\n";
+
+    result += &format!("define private void @{}(i64 %index, i1 %signed, i64 %len) noreturn nounwind cold {{\n", PANIC_BOUNDS);
+
+    if flush {
+        result += "    %flushed = call i32 @fflush(ptr null)\n";
+    }
+
+    result += "    call void @mamba.write(ptr @mamba.panic.0, i64 13)\n";
+    result += "    call void @mamba.write_int(i64 %index, i1 %signed)\n";
+    result += "    call void @mamba.write(ptr @mamba.panic.1, i64 26)\n";
+    result += "    call void @mamba.write_int(i64 %len, i1 0)\n";
+    result += "    call void @mamba.write(ptr @mamba.panic.3, i64 1)\n";
+    result += &format!("    call void asm sideeffect \"{}\", \"{}\"(i64 {}, i64 {})\n", exit, exit_constraints, exit_number, PANIC_EXIT_CODE);
+    result += "    unreachable\n";
+    result += "}\n";
+
+    Ok(result)
+}
+
+// instruction, register constraints and number of the `write` system call on Linux
+fn write_syscall(target: &Target) -> Result<(&'static str, &'static str, u32), IRGenError> {
+    match target.name {
+        "x86_64" => Ok(("syscall", "={rax},{rax},{rdi},{rsi},{rdx},~{rcx},~{r11},~{memory}", 1)),
+        "aarch64" => Ok(("svc #0", "={x0},{x8},{x0},{x1},{x2},~{memory}", 64)),
+        "riscv64" => Ok(("ecall", "={x10},{x17},{x10},{x11},{x12},~{memory}", 64)),
+        _ => Err(IRGenError(format!("[Panic] bounds checks are not supported on {}", target.name))),
+    }
+}
\ No newline at end of file
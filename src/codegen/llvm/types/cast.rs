@@ -1,5 +1,5 @@
-use std::{cmp::max, collections::HashMap, sync::OnceLock};
-use crate::{codegen::llvm::GlobalContext, types::{DataType, SignedInteger, UnsignedInteger}};
+use std::{collections::HashMap, sync::OnceLock};
+use crate::{codegen::llvm::GlobalContext, error::IRGenError, types::{is_pointer_compatible, is_string_buffer, DataType}};
 use super::{SIGNED_INTEGERS, UNSIGNED_INTEGERS};
 
 type FnTypeCast = dyn Fn(&mut GlobalContext, &str) -> (String, String, DataType) + Send + Sync + 'static;
@@ -16,77 +16,98 @@ pub fn cast() -> &'static HashMap<(DataType, DataType), Box<FnTypeCast>> {
     CAST.get_or_init(|| {
         let mut cast: HashMap<(DataType, DataType), Box<FnTypeCast>> = HashMap::new();
 
-        for a in &SIGNED_INTEGERS {
-            for b in &SIGNED_INTEGERS {
-                if a == b {
-                    continue;
-                } else if a < b {
-                    add_cast!(cast, DataType::SignedInteger(*a), DataType::SignedInteger(*b), |ctx, src| {
-                        let idx = &format!("%{}", ctx.get_label());
-                        (idx.into(), format!("{} = zext {} {} to {}\n", idx, a.to_mnemonic(), src, b.to_mnemonic()), max(DataType::SignedInteger(*a), DataType::SignedInteger(*b)))
-                    });
-                } else if a > b {
-                    add_cast!(cast, DataType::SignedInteger(*a), DataType::SignedInteger(*b), |ctx, src| {
-                        let idx = &format!("%{}", ctx.get_label());
-                        (idx.into(), format!("{} = trunc {} {} to {}\n", idx, a.to_mnemonic(), src, b.to_mnemonic()), max(DataType::SignedInteger(*a), DataType::SignedInteger(*b)))
-                    });
-                }
-            }
+        let integers = SIGNED_INTEGERS.iter().map(|ty| DataType::SignedInteger(*ty))
+            .chain(UNSIGNED_INTEGERS.iter().map(|ty| DataType::UnsignedInteger(*ty)))
+            .collect::<Vec<DataType>>();
 
-            for b in &UNSIGNED_INTEGERS {
-                let c: &SignedInteger = b.into();
+        // like `as` in Rust: extend according to the signedness of the source, then truncate
+        for from in &integers {
+            for to in &integers {
+                let (from, to) = (*from, *to);
 
-                if a == c {
+                if from == to {
                     continue;
-                } else if a < c {
-                    add_cast!(cast, DataType::SignedInteger(*a), DataType::UnsignedInteger(*b), |ctx, src| {
-                        let idx = &format!("%{}", ctx.get_label());
-                        (idx.into(), format!("{} = zext {} {} to {}\n", idx, a.to_mnemonic(), src, b.to_mnemonic()), max(DataType::SignedInteger(*a), DataType::SignedInteger(*c))) });
-                } else if a > c {
-                    add_cast!(cast, DataType::SignedInteger(*a), DataType::UnsignedInteger(*b), |ctx, src| {
-                        let idx = &format!("%{}", ctx.get_label());
-                        (idx.into(), format!("{} = trunc {} {} to {}\n", idx, a.to_mnemonic(), src, b.to_mnemonic()), max(DataType::SignedInteger(*a), DataType::SignedInteger(*c)))
+                } else if bits(from) == bits(to) {
+                    add_cast!(cast, from, to, move |_, src| (src.into(), String::new(), to));
+                } else {
+                    let instr = match from {
+                        _ if bits(from) > bits(to) => "trunc",
+                        DataType::SignedInteger(_) => "sext",
+                        _ => "zext",
+                    };
+
+                    add_cast!(cast, from, to, move |ctx, src| {
+                        let idx = &ctx.get_register();
+                        (idx.into(), format!("{} = {} {} {} to {}\n", idx, instr, from.to_mnemonic(), src, to.to_mnemonic()), to)
                     });
                 }
             }
         }
 
-        for a in &UNSIGNED_INTEGERS {
-            for b in &SIGNED_INTEGERS {
-                let c: &UnsignedInteger = b.into();
+        cast
+    })
+}
+
+fn bits(dtype: DataType) -> u32 {
+    match dtype {
+        DataType::SignedInteger(ty) => ty.bits(),
+        DataType::UnsignedInteger(ty) => ty.bits(),
+        _ => 0,
+    }
+}
 
-                if a == c {
-                    continue;
-                } else if a < c {
-                    add_cast!(cast, DataType::UnsignedInteger(*a), DataType::SignedInteger(*b), |ctx, src| {
-                        let idx = &format!("%{}", ctx.get_label());
-                        (idx.into(), format!("{} = zext {} {} to {}\n", idx, a.to_mnemonic(), src, b.to_mnemonic()), max(DataType::UnsignedInteger(*a), DataType::UnsignedInteger(*c)))
-                    });
-                } else if a > c {
-                    add_cast!(cast, DataType::UnsignedInteger(*a), DataType::SignedInteger(*b), |ctx, src| {
-                        let idx = &format!("%{}", ctx.get_label());
-                        (idx.into(), format!("{} = trunc {} {} to {}\n", idx, a.to_mnemonic(), src, b.to_mnemonic()), max(DataType::UnsignedInteger(*a), DataType::UnsignedInteger(*c)))
-                    });
-                }
+pub fn get_cast(from: DataType, to: DataType) -> Result<&'static FnTypeCast, IRGenError> {
+    cast().get(&(from, to))
+        .map(|cast| cast.as_ref())
+        .ok_or_else(|| IRGenError(format!("[IRGen] {} cannot be converted to {}", from.to_mnemonic(), to.to_mnemonic())))
+}
+
+// like `get_cast`, but arrays of the same length and tuples of the same arity are converted element by element and pointers as they are
+pub fn convert(ctx: &mut GlobalContext, src: &str, from: DataType, to: DataType) -> Result<(String, String), IRGenError> {
+    match (from, to) {
+        _ if from == to => Ok((src.into(), String::new())),
+        (DataType::Array(from_array), DataType::Array(to_array)) if from_array.len == to_array.len => {
+            let mut code = String::new();
+            let mut result = String::from("undef");
+
+            for n in 0..from_array.len {
+                let element = ctx.get_register();
+                code += &format!("{} = extractvalue {} {}, {}\n", element, from.to_mnemonic(), src, n);
+
+                let (element, cast_code) = convert(ctx, &element, from_array.element, to_array.element)?;
+                code += &cast_code;
+
+                let idx = ctx.get_register();
+                code += &format!("{} = insertvalue {} {}, {} {}, {}\n", idx, to.to_mnemonic(), result, to_array.element.to_mnemonic(), element, n);
+                result = idx;
             }
 
-            for b in &UNSIGNED_INTEGERS {
-                if a == b {
-                    continue;
-                } else if a < b {
-                    add_cast!(cast, DataType::UnsignedInteger(*a), DataType::UnsignedInteger(*b), |ctx, src| {
-                        let idx = &format!("%{}", ctx.get_label());
-                        (idx.into(), format!("{} = zext {} {} to {}\n", idx, a.to_mnemonic(), src, b.to_mnemonic()), max(DataType::UnsignedInteger(*a), DataType::UnsignedInteger(*b)))
-                    });
-                } else if a > b {
-                    add_cast!(cast, DataType::UnsignedInteger(*a), DataType::UnsignedInteger(*b), |ctx, src| {
-                        let idx = &format!("%{}", ctx.get_label());
-                        (idx.into(), format!("{} = trunc {} {} to {}\n", idx, a.to_mnemonic(), src, b.to_mnemonic()), max(DataType::UnsignedInteger(*a), DataType::UnsignedInteger(*b)))
-                    });
-                }
+            Ok((result, code))
+        },
+        (DataType::Tuple(from_tuple), DataType::Tuple(to_tuple)) if from_tuple.elements.len() == to_tuple.elements.len() => {
+            let mut code = String::new();
+            let mut result = String::from("undef");
+
+            for (n, (from_element, to_element)) in from_tuple.elements.iter().zip(&to_tuple.elements).enumerate() {
+                let element = ctx.get_register();
+                code += &format!("{} = extractvalue {} {}, {}\n", element, from.to_mnemonic(), src, n);
+
+                let (element, cast_code) = convert(ctx, &element, *from_element, *to_element)?;
+                code += &cast_code;
+
+                let idx = ctx.get_register();
+                code += &format!("{} = insertvalue {} {}, {} {}, {}\n", idx, to.to_mnemonic(), result, to_element.to_mnemonic(), element, n);
+                result = idx;
             }
-        }
 
-        cast
-    })
+            Ok((result, code))
+        },
+        // all pointers are a `ptr`, only their pointee types tell them apart, and so is a string
+        _ if is_pointer_compatible(from, to) || is_string_buffer(from, to) => Ok((src.into(), String::new())),
+        (DataType::Pointer(_) | DataType::Struct(_) | DataType::Enum(_) | DataType::Tuple(_), _) | (_, DataType::Pointer(_) | DataType::Struct(_) | DataType::Enum(_) | DataType::Tuple(_)) => Err(IRGenError(format!("[IRGen] {} cannot be converted to {}", from, to))),
+        _ => {
+            let (idx, code, _) = get_cast(from, to)?(ctx, src);
+            Ok((idx, code))
+        },
+    }
 }
\ No newline at end of file
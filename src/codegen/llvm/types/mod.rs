@@ -1,9 +1,12 @@
 mod cast;
 mod op;
 
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
 use crate::types::{DataType, SignedInteger, UnsignedInteger, FloatingPoint};
 
-pub use cast::cast;
+pub use cast::{cast, convert, get_cast};
 pub use op::{unary_op, infix_op};
 
 const SIGNED_INTEGERS: [SignedInteger; 5] = [SignedInteger::i8, SignedInteger::i16, SignedInteger::i32, SignedInteger::i64, SignedInteger::i128];
@@ -14,10 +17,43 @@ impl DataType {
         match self{
             DataType::void => "void",
             DataType::bool  => "i1",
-            DataType::str => "i8*",
+            DataType::str | DataType::Pointer(_) => "ptr",
             DataType::SignedInteger(dtype) => dtype.to_mnemonic(),
             DataType::UnsignedInteger(dtype) => dtype.to_mnemonic(),
             DataType::FloatingPoint(dtype) => dtype.to_mnemonic(),
+            DataType::Array(array) => {
+                // array types are interned, so are their mnemonics
+                static MNEMONICS: OnceLock<Mutex<HashMap<DataType, &'static str>>> = OnceLock::new();
+
+                // the element's mnemonic is looked up before the lock is taken, it may be an array too
+                let element = array.element.to_mnemonic();
+
+                let mut mnemonics = MNEMONICS.get_or_init(Default::default).lock().unwrap_or_else(|err| err.into_inner());
+                mnemonics.entry(*self).or_insert_with(|| Box::leak(format!("[{} x {}]", array.len, element).into_boxed_str()))
+            },
+            // a literal struct type, interned like arrays
+            DataType::Tuple(tuple) => {
+                static MNEMONICS: OnceLock<Mutex<HashMap<DataType, &'static str>>> = OnceLock::new();
+
+                let elements = tuple.elements.iter().map(|dtype| dtype.to_mnemonic()).collect::<Vec<&str>>();
+
+                let mut mnemonics = MNEMONICS.get_or_init(Default::default).lock().unwrap_or_else(|err| err.into_inner());
+                mnemonics.entry(*self).or_insert_with(|| Box::leak(format!("{{ {} }}", elements.join(", ")).into_boxed_str()))
+            },
+            // structs and enums are referred to by the name of their `type` definition
+            DataType::Struct(_) | DataType::Enum(_) => {
+                static MNEMONICS: OnceLock<Mutex<HashMap<DataType, &'static str>>> = OnceLock::new();
+
+                let mut mnemonics = MNEMONICS.get_or_init(Default::default).lock().unwrap_or_else(|err| err.into_inner());
+                mnemonics.entry(*self).or_insert_with(|| Box::leak(format!("%{}", self).into_boxed_str()))
+            },
+            // only instances are generated, the name shows up in the AST of a generic `def`
+            DataType::Param(param) => {
+                static MNEMONICS: OnceLock<Mutex<HashMap<DataType, &'static str>>> = OnceLock::new();
+
+                let mut mnemonics = MNEMONICS.get_or_init(Default::default).lock().unwrap_or_else(|err| err.into_inner());
+                mnemonics.entry(*self).or_insert_with(|| Box::leak(param.name.clone().into_boxed_str()))
+            },
         }
     }
 }
@@ -30,19 +30,23 @@ pub fn unary_op() -> &'static HashMap<(DataType, Operator), Box<FnUnaryOperation
             });
 
             add_unary_operation!(op, DataType::SignedInteger(*ty), Operator::UnaryMinus, |ctx, src| {
-                let idx = &format!("%{}", ctx.get_label());
+                let idx = &ctx.get_register();
                 (idx.into(),  format!("{} = sub nsw {} 0, {}\n", idx, ty.to_mnemonic(), src))
             });
 
             add_unary_operation!(op, DataType::SignedInteger(*ty), Operator::BitwiseNot, |ctx, src| {
-                let idx = &format!("%{}", ctx.get_label());
-                (idx.into(), format!("{} = sub nsw {} 0, {}\n", idx, ty.to_mnemonic(), src))
+                let idx = &ctx.get_register();
+                (idx.into(), format!("{} = xor {} {}, -1\n", idx, ty.to_mnemonic(), src))
             });
         }
 
-        for ty in &UNSIGNED_INTEGERS {            
+        for ty in &UNSIGNED_INTEGERS {
+            add_unary_operation!(op, DataType::UnsignedInteger(*ty), Operator::UnaryPlus, |_, src| {
+                (src.into(), String::new())
+            });
+
             add_unary_operation!(op, DataType::UnsignedInteger(*ty), Operator::BitwiseNot, |ctx, src| {
-                let idx = &format!("%{}", ctx.get_label());
+                let idx = &ctx.get_register();
                 (idx.into(), format!("{} = xor {} {}, -1\n", idx, ty.to_mnemonic(), src))
             });
         }
@@ -51,101 +55,99 @@ pub fn unary_op() -> &'static HashMap<(DataType, Operator), Box<FnUnaryOperation
     })
 }
 
+// signed overflow is undefined (the constant folder reports it), unsigned arithmetic wraps around
+const SIGNED_INSTRUCTIONS: [(Operator, &str); 10] = [
+    (Operator::Plus, "add nsw"),
+    (Operator::Minus, "sub nsw"),
+    (Operator::Multiply, "mul nsw"),
+    (Operator::Divide, "sdiv"),
+    (Operator::Modulo, "srem"),
+    (Operator::BitwiseAnd, "and"),
+    (Operator::BitwiseOr, "or"),
+    (Operator::BitwiseXor, "xor"),
+    (Operator::LeftShift, "shl"),
+    (Operator::RightShift, "ashr"),
+];
+
+const UNSIGNED_INSTRUCTIONS: [(Operator, &str); 10] = [
+    (Operator::Plus, "add"),
+    (Operator::Minus, "sub"),
+    (Operator::Multiply, "mul"),
+    (Operator::Divide, "udiv"),
+    (Operator::Modulo, "urem"),
+    (Operator::BitwiseAnd, "and"),
+    (Operator::BitwiseOr, "or"),
+    (Operator::BitwiseXor, "xor"),
+    (Operator::LeftShift, "shl"),
+    (Operator::RightShift, "lshr"),
+];
+
+const BOOL_INSTRUCTIONS: [(Operator, &str); 5] = [
+    (Operator::Equal, "icmp eq"),
+    (Operator::NotEqual, "icmp ne"),
+    (Operator::BitwiseAnd, "and"),
+    (Operator::BitwiseOr, "or"),
+    (Operator::BitwiseXor, "xor"),
+];
+
+const COMPARISONS: [Operator; 6] = [Operator::Equal, Operator::NotEqual, Operator::Less, Operator::LessEqual, Operator::Greater, Operator::GreaterEqual];
+
 pub fn infix_op() -> &'static HashMap<(DataType, Operator), Box<FnInfixOperation>> {
     INFIX_OPERATION.get_or_init(|| {
         let mut op: HashMap<(DataType, Operator), Box<FnInfixOperation>> = HashMap::new();
 
-
-        for cmp in &[Operator::Equal, Operator::NotEqual, Operator::Less, Operator::LessEqual, Operator::Greater, Operator::GreaterEqual] {
+        for cmp in &COMPARISONS {
             for ty in &SIGNED_INTEGERS {
                 add_infix_operation!(op, DataType::SignedInteger(*ty), *cmp, |ctx, left, right| {
-                    let idx = &format!("%{}", ctx.get_label());
+                    let idx = &ctx.get_register();
                     (idx.into(), format!("{} = icmp {} {} {}, {}\n", idx, cmp.to_mnemonic(), ty.to_mnemonic(), left, right))
                 });
             }
 
             for ty in &UNSIGNED_INTEGERS {
                 add_infix_operation!(op, DataType::UnsignedInteger(*ty), *cmp, |ctx, left, right| {
-                    let idx = &format!("%{}", ctx.get_label());
-                    (idx.into(), format!("{} = icmp {} {} {}, {}\n", idx, cmp.to_mnemonic(), ty.to_mnemonic(), left, right))
+                    let idx = &ctx.get_register();
+                    (idx.into(), format!("{} = icmp {} {} {}, {}\n", idx, unsigned_predicate(*cmp), ty.to_mnemonic(), left, right))
                 });
             }
         }
 
-        // TODO: clean duplicated code in an elegant way
-        for ty in &SIGNED_INTEGERS {
-            add_infix_operation!(op, DataType::SignedInteger(*ty), Operator::Plus, |ctx, left, right| {
-                let idx = &format!("%{}", ctx.get_label());
-                (idx.into(), format!("{} = add nsw {} {}, {}\n", idx, ty.to_mnemonic(), left, right))
-            });
-
-            add_infix_operation!(op, DataType::SignedInteger(*ty), Operator::Minus, |ctx, left, right| {
-                let idx = &format!("%{}", ctx.get_label());
-                (idx.into(), format!("{} = sub nsw {} {}, {}\n", idx, ty.to_mnemonic(), left, right))
-            });
-
-            add_infix_operation!(op, DataType::SignedInteger(*ty), Operator::Multiply, |ctx, left, right| {
-                let idx = &format!("%{}", ctx.get_label());
-                (idx.into(), format!("{} = mul nsw {} {}, {}\n", idx, ty.to_mnemonic(), left, right))
-            });
-
-            add_infix_operation!(op, DataType::SignedInteger(*ty), Operator::Divide, |ctx, left, right| {
-                let idx = &format!("%{}", ctx.get_label());
-                (idx.into(), format!("{} = sdiv {} {}, {}\n", idx, ty.to_mnemonic(), left, right))
-            });
-
-            add_infix_operation!(op, DataType::SignedInteger(*ty), Operator::Modulo, |ctx, left, right| {
-                let idx = &format!("%{}", ctx.get_label());
-                (idx.into(), format!("{} = srem {} {}, {}\n", idx, ty.to_mnemonic(), left, right))
-            });
-
-            add_infix_operation!(op, DataType::SignedInteger(*ty), Operator::LeftShift, |ctx, left, right| {
-                let idx = &format!("%{}", ctx.get_label());
-                (idx.into(), format!("{} = shl {} {}, {}\n", idx, ty.to_mnemonic(), left, right))
-            });
-
-            add_infix_operation!(op, DataType::SignedInteger(*ty), Operator::RightShift, |ctx, left, right| {
-                let idx = &format!("%{}", ctx.get_label());
-                (idx.into(), format!("{} = ashr {} {}, {}\n", idx, ty.to_mnemonic(), left, right))
-            });
+        for (operator, instr) in SIGNED_INSTRUCTIONS {
+            for ty in &SIGNED_INTEGERS {
+                add_infix_operation!(op, DataType::SignedInteger(*ty), operator, move |ctx, left, right| {
+                    let idx = &ctx.get_register();
+                    (idx.into(), format!("{} = {} {} {}, {}\n", idx, instr, ty.to_mnemonic(), left, right))
+                });
+            }
         }
 
-        for ty in &UNSIGNED_INTEGERS {
-            add_infix_operation!(op, DataType::UnsignedInteger(*ty), Operator::Plus, |ctx, left, right| {
-                let idx = &format!("%{}", ctx.get_label());
-                (idx.into(), format!("{} = add nsw {} {}, {}\n", idx, ty.to_mnemonic(), left, right))
-            });
-
-            add_infix_operation!(op, DataType::UnsignedInteger(*ty), Operator::Minus, |ctx, left, right| {
-                let idx = &format!("%{}", ctx.get_label());
-                (idx.into(), format!("{} = sub nsw {} {}, {}\n", idx, ty.to_mnemonic(), left, right))
-            });
-
-            add_infix_operation!(op, DataType::UnsignedInteger(*ty), Operator::Multiply, |ctx, left, right| {
-                let idx = &format!("%{}", ctx.get_label());
-                (idx.into(), format!("{} = mul nsw {} {}, {}\n", idx, ty.to_mnemonic(), left, right))
-            });
-
-            add_infix_operation!(op, DataType::UnsignedInteger(*ty), Operator::Divide, |ctx, left, right| {
-                let idx = &format!("%{}", ctx.get_label());
-                (idx.into(), format!("{} = sdiv {} {}, {}\n", idx, ty.to_mnemonic(), left, right))
-            });
-
-            add_infix_operation!(op, DataType::UnsignedInteger(*ty), Operator::Modulo, |ctx, left, right| {
-                let idx = &format!("%{}", ctx.get_label());
-                (idx.into(), format!("{} = srem {} {}, {}\n", idx, ty.to_mnemonic(), left, right))
-            });
-
-            add_infix_operation!(op, DataType::UnsignedInteger(*ty), Operator::LeftShift, |ctx, left, right| {
-                let idx = &format!("%{}", ctx.get_label());
-                (idx.into(), format!("{} = shl {} {}, {}\n", idx, ty.to_mnemonic(), left, right))
-            });
+        for (operator, instr) in UNSIGNED_INSTRUCTIONS {
+            for ty in &UNSIGNED_INTEGERS {
+                add_infix_operation!(op, DataType::UnsignedInteger(*ty), operator, move |ctx, left, right| {
+                    let idx = &ctx.get_register();
+                    (idx.into(), format!("{} = {} {} {}, {}\n", idx, instr, ty.to_mnemonic(), left, right))
+                });
+            }
+        }
 
-            add_infix_operation!(op, DataType::UnsignedInteger(*ty), Operator::RightShift, |ctx, left, right| {
-                let idx = &format!("%{}", ctx.get_label());
-                (idx.into(), format!("{} = ashr {} {}, {}\n", idx, ty.to_mnemonic(), left, right))
+        for (operator, instr) in BOOL_INSTRUCTIONS {
+            add_infix_operation!(op, DataType::bool, operator, move |ctx, left, right| {
+                let idx = &ctx.get_register();
+                (idx.into(), format!("{} = {} i1 {}, {}\n", idx, instr, left, right))
             });
         }
+
         op
     })
+}
+
+// `Operator::to_mnemonic` gives the signed predicates
+fn unsigned_predicate(operator: Operator) -> &'static str {
+    match operator {
+        Operator::Less => "ult",
+        Operator::LessEqual => "ule",
+        Operator::Greater => "ugt",
+        Operator::GreaterEqual => "uge",
+        _ => operator.to_mnemonic(),
+    }
 }
\ No newline at end of file
@@ -1 +1,9 @@
-pub mod llvm;
\ No newline at end of file
+pub mod asm;
+pub mod c;
+pub mod llvm;
+pub mod native;
+pub mod regalloc;
+pub mod riscv;
+pub mod target;
+pub mod wasm;
+pub mod x86_64;
\ No newline at end of file
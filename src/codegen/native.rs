@@ -0,0 +1,698 @@
+use std::cmp::max;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use crate::codegen::asm::{emit, escape, is_wide, literal_value};
+use crate::codegen::llvm::entry::{find_main, Entry, MAIN_SYMBOL};
+use crate::codegen::regalloc::record::{resolve, Access, Recorder};
+use crate::codegen::regalloc::{allocate, Location, TargetRegisters, VirtReg};
+use crate::error::CodegenError;
+use crate::format;
+use crate::lexer::Literal;
+use crate::parser::{DefStatement, Expression, Identifier, IfBranch, IfStatement, LetStatement, Operator, Statement, AST};
+use crate::types::{infix_type, is_integer, is_pointer_compatible, is_string_buffer, unary_type, DataType, SignedInteger};
+
+// The instructions of a target the native lowering is written in. Values are computed in
+// `RESULT`, 128-bit ones taking both registers, and offsets into the frame are relative to
+// the frame pointer the prologue sets up.
+pub trait Machine {
+    // the name errors are reported under
+    const NAME: &'static str;
+    const REGISTERS: TargetRegisters;
+    // integer argument registers in the order the calling convention assigns them
+    const ARG_REGISTERS: &'static [&'static str];
+    // where values are computed and where `infix` takes its right operand, low half first
+    const RESULT: (&'static str, &'static str);
+    const OPERAND: (&'static str, &'static str);
+    // a register left to the lowering, which stack arguments are copied through
+    const SCRATCH: &'static str;
+    // bytes below the frame pointer the prologue takes for itself
+    const FRAME_HEADER: i64;
+    // offset of the stack arguments from the frame pointer
+    const STACK_ARGS: i64;
+    // functions are aligned to `1 << FUNCTION_ALIGN` bytes
+    const FUNCTION_ALIGN: u32;
+    const JUMP: &'static str;
+    const TRAP: &'static str;
+    // appended to the module, after its data
+    const TRAILER: &'static str;
+
+    // assigns every parameter a location and returns them with the size of the stack area the call needs
+    fn assign(params: &[DataType]) -> (Vec<ArgLoc>, i64);
+    fn move_reg(code: &mut String, to: &str, from: &str);
+    // loads or stores `reg` at `offset` from the frame pointer
+    fn load(code: &mut String, reg: &str, offset: i64);
+    fn store(code: &mut String, reg: &str, offset: i64);
+    // stores `reg` at `offset` from the stack pointer, where a call finds its stack arguments
+    fn store_arg(code: &mut String, reg: &str, offset: i64);
+    fn adjust_sp(code: &mut String, amount: i64);
+    fn load_immediate(code: &mut String, reg: &str, value: i64);
+    fn load_address(code: &mut String, reg: &str, symbol: &str);
+    // loads the global `symbol` of `dtype` into `RESULT`, in the form its registers hold it in
+    fn load_global(code: &mut String, symbol: &str, dtype: DataType);
+    // emits the test of `RESULT` and returns the jump taken when it is zero
+    fn branch_if_zero(code: &mut String) -> &'static str;
+    fn call(code: &mut String, symbol: &str);
+    // sets up the frame pointer and takes `locals` more bytes of stack
+    fn prologue(code: &mut String, locals: i64);
+    fn epilogue(code: &mut String);
+    // the body of the entry point calling the program's `main`, which returns `r#type`
+    fn entry(code: &mut String, entry: Entry, r#type: DataType);
+    // the directive of an integer of `bytes` in the data
+    fn directive(bytes: u32) -> &'static str;
+    // whether a value of `dtype` only defines its low bits when it comes from outside the function
+    fn needs_extension(dtype: DataType) -> bool;
+    // brings `RESULT` back to the form of `dtype`
+    fn normalize(code: &mut String, dtype: DataType);
+    // bit pattern of the registers holding `value` as a `dtype`, high half last
+    fn registers(value: i128, dtype: DataType) -> (i64, i64);
+    // truncates, sign- or zero-extends the integer in `RESULT` from `from` to `to`
+    fn cast(code: &mut String, from: DataType, to: DataType);
+    fn unary(code: &mut String, operator: Operator, dtype: DataType);
+    fn infix(code: &mut String, operator: Operator, dtype: DataType);
+    // whether `infix` leaves `operator` to a function of the compiler runtime
+    fn calls_runtime(operator: Operator, dtype: DataType) -> bool;
+}
+
+// Where a calling convention puts an argument: registers are indices into
+// `Machine::ARG_REGISTERS` and offsets are relative to the stack pointer at the call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgLoc {
+    Reg(usize),
+    // a 128-bit value in two consecutive registers, low half first
+    Pair(usize),
+    // a 128-bit value whose low half goes into the last argument register and high half onto the stack
+    Split(i64),
+    Stack(i64),
+}
+
+// Where a variable, an intermediate result or an argument is kept.
+#[derive(Debug, Clone, Copy)]
+enum Storage {
+    Virt(VirtReg),
+    // a 128-bit value, at this offset from the frame pointer
+    Slot(i64),
+}
+
+// Lowers a folded AST to assembly for the GNU assembler in the instructions of `M`.
+// Variables, intermediate results and arguments are kept in virtual registers, which the
+// register allocator places in the registers `M::REGISTERS` leaves to them or in the frame
+// once the function is generated; 128-bit ones get a slot in the frame.
+pub struct NativeGen<M: Machine> {
+    ast: AST,
+    entry: Option<Entry>,
+    functions: HashMap<Identifier, (Vec<DataType>, DataType)>,
+    globals: HashMap<Identifier, DataType>,
+    // string literals, placed in `.rodata` as `.Lstr.N`
+    strings: Vec<String>,
+    label_idx: u64,
+    // where the variables of the function being generated are kept, innermost scope last
+    scopes: Vec<HashMap<Identifier, (Storage, DataType)>>,
+    recorder: Recorder,
+    // bytes below the frame pointer taken so far
+    frame_size: i64,
+    // name, return type and return label of the function being generated
+    current: (Identifier, DataType, String),
+    machine: PhantomData<M>,
+}
+
+impl<M: Machine> NativeGen<M> {
+    pub fn new(ast: AST) -> NativeGen<M> {
+        NativeGen {
+            ast,
+            entry: None,
+            functions: HashMap::new(),
+            globals: HashMap::new(),
+            strings: Vec::new(),
+            label_idx: 0,
+            scopes: Vec::new(),
+            recorder: Recorder::new(),
+            frame_size: 0,
+            current: (String::new(), DataType::void, String::new()),
+            machine: PhantomData,
+        }
+    }
+
+    // wraps the program's `main` in an entry point, without one the module is generated as a library
+    pub fn with_entry(mut self, entry: Entry) -> NativeGen<M> {
+        self.entry = Some(entry);
+        self
+    }
+
+    pub fn generate_asm(mut self) -> Result<String, CodegenError> {
+        let ast = std::mem::take(&mut self.ast);
+
+        // functions and globals can be used before the statement that defines them
+        for stmt in &ast {
+            match stmt {
+                Statement::Def(stmt) if !stmt.type_params.is_empty() => return Err(CodegenError(format!("[{}] generic function `{}` is not supported, use `--backend llvm`", M::NAME, stmt.name))),
+                Statement::Def(stmt) => {
+                    check_signature::<M>(&stmt.name, &stmt.params, stmt.r#type)?;
+                    self.functions.insert(stmt.name.clone(), (stmt.params.iter().map(|(_, dtype)| *dtype).collect(), stmt.r#type));
+                },
+                Statement::Extern(stmt) => {
+                    check_signature::<M>(&stmt.name, &stmt.params, stmt.r#type)?;
+                    self.functions.insert(stmt.name.clone(), (stmt.params.iter().map(|(_, dtype)| *dtype).collect(), stmt.r#type));
+                },
+                Statement::Let(stmt) => {
+                    check_type::<M>(stmt.r#type, &stmt.ident)?;
+                    self.globals.insert(stmt.ident.clone(), stmt.r#type);
+                },
+                // a declaration only introduces a type, which the values using it are checked for
+                Statement::Struct(_) | Statement::Enum(_) => {},
+                _ => return Err(CodegenError(format!("[{}] {} cannot be global", M::NAME, stmt))),
+            }
+        }
+
+        let mut result = String::from("    .text\n");
+
+        if let Some(entry) = self.entry {
+            result += &generate_entry::<M>(entry, &ast)?;
+        }
+
+        for stmt in &ast {
+            if let Statement::Def(stmt) = stmt {
+                result += &self.generate_def(stmt)?;
+            }
+        }
+
+        for stmt in &ast {
+            if let Statement::Let(stmt) = stmt {
+                result += &generate_global::<M>(stmt)?;
+            }
+        }
+
+        if !self.strings.is_empty() {
+            result += "\n    .section .rodata\n";
+
+            for (n, string) in self.strings.iter().enumerate() {
+                result += &format!(".Lstr.{}:\n    .asciz \"{}\"\n", n, escape(string));
+            }
+        }
+
+        result += M::TRAILER;
+        Ok(result)
+    }
+
+    fn get_label(&mut self) -> String {
+        self.label_idx += 1;
+        format!(".L{}", self.label_idx - 1)
+    }
+
+    fn symbol(&self, name: &str) -> String {
+        if self.entry.is_some() && name == "main" {
+            MAIN_SYMBOL.into()
+        } else {
+            name.into()
+        }
+    }
+
+    // reserves a frame slot for a `dtype` and returns its offset from the frame pointer
+    fn allocate(&mut self, dtype: DataType) -> i64 {
+        self.frame_size += if is_wide(dtype) { 16 } else { 8 };
+        -self.frame_size
+    }
+
+    fn generate_def(&mut self, stmt: &DefStatement) -> Result<String, CodegenError> {
+        let symbol = self.symbol(&stmt.name);
+        let ret = self.get_label();
+
+        self.current = (stmt.name.clone(), stmt.r#type, ret.clone());
+        self.frame_size = M::FRAME_HEADER;
+        self.scopes = vec![HashMap::new()];
+        self.recorder = Recorder::new();
+
+        // parameters are copied out of the argument registers like any other variable
+        let mut body = String::new();
+        let (locs, _) = M::assign(&stmt.params.iter().map(|(_, dtype)| *dtype).collect::<Vec<DataType>>());
+
+        for ((ident, dtype), loc) in stmt.params.iter().zip(locs) {
+            let storage = match loc {
+                ArgLoc::Reg(n) if M::needs_extension(*dtype) => {
+                    M::move_reg(&mut body, M::RESULT.0, M::ARG_REGISTERS[n]);
+                    M::normalize(&mut body, *dtype);
+                    self.store(&mut body, *dtype)
+                },
+                ArgLoc::Reg(n) => {
+                    let vreg = self.recorder.vreg();
+                    self.recorder.def(&mut body, vreg, M::ARG_REGISTERS[n]);
+                    Storage::Virt(vreg)
+                },
+                ArgLoc::Pair(n) => {
+                    let slot = self.allocate(*dtype);
+                    M::store(&mut body, M::ARG_REGISTERS[n], slot);
+                    M::store(&mut body, M::ARG_REGISTERS[n + 1], slot + 8);
+                    Storage::Slot(slot)
+                },
+                ArgLoc::Split(offset) => {
+                    let slot = self.allocate(*dtype);
+                    M::store(&mut body, M::ARG_REGISTERS[M::ARG_REGISTERS.len() - 1], slot);
+                    M::load(&mut body, M::SCRATCH, offset + M::STACK_ARGS);
+                    M::store(&mut body, M::SCRATCH, slot + 8);
+                    Storage::Slot(slot)
+                },
+                ArgLoc::Stack(offset) => {
+                    M::load(&mut body, M::RESULT.0, offset + M::STACK_ARGS);
+
+                    if M::needs_extension(*dtype) {
+                        M::normalize(&mut body, *dtype);
+                    }
+
+                    if is_wide(*dtype) {
+                        M::load(&mut body, M::RESULT.1, offset + M::STACK_ARGS + 8);
+                    }
+
+                    self.store(&mut body, *dtype)
+                },
+            };
+
+            self.scopes[0].insert(ident.clone(), (storage, *dtype));
+        }
+
+        self.generate_block(&stmt.stmts, &mut body)?;
+
+        // like LLVM's `unreachable`, falling off the end of a function with a value traps
+        if stmt.r#type != DataType::void {
+            emit(&mut body, M::TRAP);
+        }
+
+        let allocation = allocate(&std::mem::take(&mut self.recorder).finish(), &M::REGISTERS);
+        let spill_area = self.frame_size;
+        self.frame_size += 8 * allocation.slots as i64;
+
+        let saved = allocation.callee_saved.iter()
+            .map(|reg| (M::REGISTERS.name(*reg), self.allocate(DataType::SignedInteger(SignedInteger::i64))))
+            .collect::<Vec<(&str, i64)>>();
+
+        // a spilled value lives in its slot of the frame, the others are moved between registers
+        let body = resolve(&body, |code, access, vreg, reg| {
+            match (allocation.locations[&vreg], access) {
+                (Location::Reg(preg), Access::Def) => M::move_reg(code, M::REGISTERS.name(preg), reg),
+                (Location::Reg(preg), Access::Use) => M::move_reg(code, reg, M::REGISTERS.name(preg)),
+                (Location::Stack(slot), Access::Def) => M::store(code, reg, -spill_area - 8 * (slot as i64 + 1)),
+                (Location::Stack(slot), Access::Use) => M::load(code, reg, -spill_area - 8 * (slot as i64 + 1)),
+            }
+        })?;
+
+        let locals = (self.frame_size + 15) / 16 * 16 - M::FRAME_HEADER;
+        let mut result = header::<M>(&symbol);
+
+        M::prologue(&mut result, locals);
+
+        for (reg, slot) in &saved {
+            M::store(&mut result, reg, *slot);
+        }
+
+        result += &body;
+        result += &format!("{ret}:\n");
+
+        for (reg, slot) in &saved {
+            M::load(&mut result, reg, *slot);
+        }
+
+        M::epilogue(&mut result);
+        result += &format!("    .size {symbol}, .-{symbol}\n");
+
+        Ok(result)
+    }
+
+    // moves the value in `RESULT` into a new variable: a virtual register, or a frame slot for a 128-bit one
+    fn store(&mut self, code: &mut String, dtype: DataType) -> Storage {
+        if is_wide(dtype) {
+            let slot = self.allocate(dtype);
+            M::store(code, M::RESULT.0, slot);
+            M::store(code, M::RESULT.1, slot + 8);
+            Storage::Slot(slot)
+        } else {
+            let vreg = self.recorder.vreg();
+            self.recorder.def(code, vreg, M::RESULT.0);
+            Storage::Virt(vreg)
+        }
+    }
+
+    fn load(&mut self, code: &mut String, storage: Storage) {
+        match storage {
+            Storage::Virt(vreg) => self.recorder.read(code, vreg, M::RESULT.0),
+            Storage::Slot(slot) => {
+                M::load(code, M::RESULT.0, slot);
+                M::load(code, M::RESULT.1, slot + 8);
+            },
+        }
+    }
+
+    // variables declared in `stmts` are only visible until the end of the block
+    fn generate_block(&mut self, stmts: &[Statement], code: &mut String) -> Result<(), CodegenError> {
+        self.scopes.push(HashMap::new());
+
+        for stmt in stmts {
+            self.generate_stmt(stmt, code)?;
+        }
+
+        self.scopes.pop();
+        Ok(())
+    }
+
+    fn generate_stmt(&mut self, stmt: &Statement, code: &mut String) -> Result<(), CodegenError> {
+        match stmt {
+            Statement::Let(stmt) => {
+                check_type::<M>(stmt.r#type, &stmt.ident)?;
+                let dtype = self.generate_expr(&stmt.expr, code)?;
+                coerce::<M>(code, dtype, stmt.r#type, &stmt.ident)?;
+
+                // every `let` gets a variable of its own, shadowed variables keep theirs
+                let storage = self.store(code, stmt.r#type);
+
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.insert(stmt.ident.clone(), (storage, stmt.r#type));
+                }
+            },
+            Statement::If(stmt) => self.generate_if(stmt, code)?,
+            Statement::While(stmt) => {
+                let check = self.get_label();
+                let end = self.get_label();
+
+                self.recorder.label(code, &check);
+                self.generate_condition(&stmt.condition, code)?;
+                let branch = M::branch_if_zero(code);
+                self.recorder.jump(code, branch, &end, true);
+
+                self.generate_block(&stmt.blocks, code)?;
+                self.recorder.jump(code, M::JUMP, &check, false);
+                self.recorder.label(code, &end);
+            },
+            Statement::Return(stmt) => {
+                let (name, r#type, ret) = self.current.clone();
+                let dtype = self.generate_expr(&stmt.expr, code)?;
+
+                // a void function drops whatever it is asked to return
+                if r#type != DataType::void {
+                    coerce::<M>(code, dtype, r#type, &name)?;
+                }
+
+                self.recorder.jump(code, M::JUMP, &ret, false);
+            },
+            Statement::Expression(stmt) => {
+                self.generate_expr(&stmt.expr, code)?;
+            },
+            Statement::Match(_) => return Err(CodegenError(format!("[{}] `match` is not supported, use `--backend llvm`", M::NAME))),
+            Statement::Destructure(_) => return Err(CodegenError(format!("[{}] tuples are not supported, use `--backend llvm`", M::NAME))),
+            Statement::Def(_) | Statement::Extern(_) | Statement::Struct(_) | Statement::Enum(_) => return Err(CodegenError(format!("[{}] {} cannot be local", M::NAME, stmt))),
+            Statement::Assign(_) => return Err(CodegenError(format!("[{}] arrays, pointers and structs are not supported, use `--backend llvm`", M::NAME))),
+        }
+
+        Ok(())
+    }
+
+    fn generate_if(&mut self, stmt: &IfStatement, code: &mut String) -> Result<(), CodegenError> {
+        let otherwise = self.get_label();
+        let end = self.get_label();
+
+        self.generate_condition(&stmt.condition, code)?;
+        let branch = M::branch_if_zero(code);
+        self.recorder.jump(code, branch, &otherwise, true);
+        self.generate_block(&stmt.then, code)?;
+        self.recorder.jump(code, M::JUMP, &end, false);
+
+        self.recorder.label(code, &otherwise);
+
+        match stmt.r#else.as_ref() {
+            IfBranch::Elif(stmt) => self.generate_if(stmt, code)?,
+            IfBranch::Else(stmts) => self.generate_block(stmts, code)?,
+            IfBranch::None => {},
+        }
+
+        self.recorder.label(code, &end);
+        Ok(())
+    }
+
+    fn generate_condition(&mut self, expr: &Expression, code: &mut String) -> Result<(), CodegenError> {
+        match self.generate_expr(expr, code)? {
+            DataType::bool => Ok(()),
+            dtype => Err(CodegenError(format!("[{}] expected a bool condition, found {}", M::NAME, dtype))),
+        }
+    }
+
+    // emits the code leaving the value of `expr` in `RESULT` and returns its type
+    fn generate_expr(&mut self, expr: &Expression, code: &mut String) -> Result<DataType, CodegenError> {
+        match expr {
+            Expression::Unary(expr) if matches!(expr.operator, Operator::AddressOf | Operator::Dereference) => Err(CodegenError(format!("[{}] operator {:?} is not supported, use `--backend llvm`", M::NAME, expr.operator))),
+            Expression::Struct(_) | Expression::Member(_) | Expression::MethodCall(_) => Err(CodegenError(format!("[{}] structs are not supported, use `--backend llvm`", M::NAME))),
+            Expression::Enum(_) => Err(CodegenError(format!("[{}] enums are not supported, use `--backend llvm`", M::NAME))),
+            Expression::Tuple(_) => Err(CodegenError(format!("[{}] tuples are not supported, use `--backend llvm`", M::NAME))),
+            Expression::Literal((Literal::String(string), _)) => {
+                M::load_address(code, M::RESULT.0, &format!(".Lstr.{}", self.strings.len()));
+                self.strings.push(string.clone());
+                Ok(DataType::str)
+            },
+            Expression::Literal((literal, _)) => {
+                let (value, dtype) = literal_value(literal);
+                let (low, high) = M::registers(value, dtype);
+
+                M::load_immediate(code, M::RESULT.0, low);
+
+                if is_wide(dtype) {
+                    M::load_immediate(code, M::RESULT.1, high);
+                }
+
+                Ok(dtype)
+            },
+            Expression::Identifier(ident) => {
+                if let Some(&(storage, dtype)) = self.scopes.iter().rev().find_map(|scope| scope.get(ident)) {
+                    self.load(code, storage);
+                    Ok(dtype)
+                } else if let Some(&dtype) = self.globals.get(ident) {
+                    if dtype == DataType::str {
+                        M::load_address(code, M::RESULT.0, ident);
+                    } else {
+                        M::load_global(code, ident, dtype);
+                    }
+
+                    Ok(dtype)
+                } else {
+                    Err(CodegenError(format!("[{}] identifier `{}` not found", M::NAME, ident)))
+                }
+            },
+            Expression::Unary(expr) => {
+                let dtype = self.generate_expr(&expr.right, code)?;
+                let result = unary_type(expr.operator, dtype)
+                    .ok_or_else(|| CodegenError(format!("[{}] operator {:?} cannot be applied to {}", M::NAME, expr.operator, dtype)))?;
+
+                M::unary(code, expr.operator, dtype);
+                Ok(result)
+            },
+            Expression::Infix(expr) => {
+                let left = self.generate_expr(&expr.left, code)?;
+                let temporary = self.store(code, left);
+                let right = self.generate_expr(&expr.right, code)?;
+
+                let dtype = max(left, right);
+                let result = infix_type(expr.operator, dtype)
+                    .filter(|_| left == right || (is_integer(left) && is_integer(right)))
+                    .ok_or_else(|| CodegenError(format!("[{}] operator {:?} cannot be applied to {} and {}", M::NAME, expr.operator, left, right)))?;
+
+                M::cast(code, right, dtype);
+                M::move_reg(code, M::OPERAND.0, M::RESULT.0);
+
+                if is_wide(dtype) {
+                    M::move_reg(code, M::OPERAND.1, M::RESULT.1);
+                }
+
+                self.load(code, temporary);
+                M::cast(code, left, dtype);
+
+                M::infix(code, expr.operator, dtype);
+
+                if M::calls_runtime(expr.operator, dtype) {
+                    self.recorder.call("runtime");
+                }
+
+                Ok(result)
+            },
+            Expression::Index(_) | Expression::Array(_) => Err(CodegenError(format!("[{}] arrays are not supported, use `--backend llvm`", M::NAME))),
+            Expression::FormatString(expr) => {
+                let types = expr.values()
+                    .map(|value| self.type_of(value))
+                    .collect::<Result<Vec<DataType>, CodegenError>>()?;
+
+                self.generate_expr(&format::lower(expr, &types).map_err(|err| CodegenError(err.0))?, code)
+            },
+            Expression::FnCall(expr) => {
+                let (params, r#type) = self.functions.get(&expr.ident).cloned()
+                    .ok_or_else(|| CodegenError(format!("[{}] unable to find function `{}`", M::NAME, expr.ident)))?;
+
+                if params.len() != expr.args.len() {
+                    return Err(CodegenError(format!("[{}] `{}` takes {} arguments but {} were given", M::NAME, expr.ident, params.len(), expr.args.len())));
+                }
+
+                // every argument is evaluated before any register is loaded
+                let mut args = Vec::new();
+
+                for (arg, param) in expr.args.iter().zip(&params) {
+                    let dtype = self.generate_expr(arg, code)?;
+                    coerce::<M>(code, dtype, *param, &expr.ident)?;
+                    args.push(self.store(code, *param));
+                }
+
+                let (locs, stack_size) = M::assign(&params);
+
+                if stack_size > 0 {
+                    M::adjust_sp(code, -stack_size);
+                }
+
+                // the stack arguments go through `SCRATCH`, before any argument register is loaded
+                for (loc, arg) in locs.iter().zip(&args) {
+                    match (loc, arg) {
+                        (ArgLoc::Stack(offset), Storage::Virt(vreg)) => {
+                            self.recorder.read(code, *vreg, M::SCRATCH);
+                            M::store_arg(code, M::SCRATCH, *offset);
+                        },
+                        (ArgLoc::Stack(offset), Storage::Slot(slot)) => {
+                            M::load(code, M::SCRATCH, *slot);
+                            M::store_arg(code, M::SCRATCH, *offset);
+                            M::load(code, M::SCRATCH, slot + 8);
+                            M::store_arg(code, M::SCRATCH, offset + 8);
+                        },
+                        (ArgLoc::Split(offset), Storage::Slot(slot)) => {
+                            M::load(code, M::SCRATCH, slot + 8);
+                            M::store_arg(code, M::SCRATCH, *offset);
+                        },
+                        _ => {},
+                    }
+                }
+
+                for (loc, arg) in locs.iter().zip(&args) {
+                    match (loc, arg) {
+                        (ArgLoc::Reg(reg), Storage::Virt(vreg)) => self.recorder.read(code, *vreg, M::ARG_REGISTERS[*reg]),
+                        (ArgLoc::Pair(reg), Storage::Slot(slot)) => {
+                            M::load(code, M::ARG_REGISTERS[*reg], *slot);
+                            M::load(code, M::ARG_REGISTERS[reg + 1], slot + 8);
+                        },
+                        (ArgLoc::Split(_), Storage::Slot(slot)) => M::load(code, M::ARG_REGISTERS[M::ARG_REGISTERS.len() - 1], *slot),
+                        _ => {},
+                    }
+                }
+
+                M::call(code, &self.symbol(&expr.ident));
+                self.recorder.call(&expr.ident);
+
+                if stack_size > 0 {
+                    M::adjust_sp(code, stack_size);
+                }
+
+                // only the bits of the returned type are defined
+                if M::needs_extension(r#type) {
+                    M::normalize(code, r#type);
+                }
+
+                Ok(r#type)
+            },
+        }
+    }
+
+    // the type of `expr` without generating it, for the values of an f-string
+    fn type_of(&self, expr: &Expression) -> Result<DataType, CodegenError> {
+        match expr {
+            Expression::Unary(expr) if matches!(expr.operator, Operator::AddressOf | Operator::Dereference) => Err(CodegenError(format!("[{}] operator {:?} is not supported, use `--backend llvm`", M::NAME, expr.operator))),
+            Expression::Struct(_) | Expression::Member(_) | Expression::MethodCall(_) => Err(CodegenError(format!("[{}] structs are not supported, use `--backend llvm`", M::NAME))),
+            Expression::Enum(_) => Err(CodegenError(format!("[{}] enums are not supported, use `--backend llvm`", M::NAME))),
+            Expression::Tuple(_) => Err(CodegenError(format!("[{}] tuples are not supported, use `--backend llvm`", M::NAME))),
+            Expression::FormatString(expr) => Ok(expr.dtype()),
+            Expression::Literal((literal, _)) => Ok(literal_value(literal).1),
+            Expression::Identifier(ident) => self.scopes.iter().rev()
+                .find_map(|scope| scope.get(ident).map(|(_, dtype)| *dtype))
+                .or_else(|| self.globals.get(ident).copied())
+                .ok_or_else(|| CodegenError(format!("[{}] identifier `{}` not found", M::NAME, ident))),
+            Expression::Unary(expr) => {
+                let dtype = self.type_of(&expr.right)?;
+                unary_type(expr.operator, dtype)
+                    .ok_or_else(|| CodegenError(format!("[{}] operator {:?} cannot be applied to {}", M::NAME, expr.operator, dtype)))
+            },
+            Expression::Infix(expr) => {
+                let (left, right) = (self.type_of(&expr.left)?, self.type_of(&expr.right)?);
+                infix_type(expr.operator, max(left, right))
+                    .filter(|_| left == right || (is_integer(left) && is_integer(right)))
+                    .ok_or_else(|| CodegenError(format!("[{}] operator {:?} cannot be applied to {} and {}", M::NAME, expr.operator, left, right)))
+            },
+            Expression::Index(_) | Expression::Array(_) => Err(CodegenError(format!("[{}] arrays are not supported, use `--backend llvm`", M::NAME))),
+            Expression::FnCall(expr) => self.functions.get(&expr.ident)
+                .map(|(_, r#type)| *r#type)
+                .ok_or_else(|| CodegenError(format!("[{}] unable to find function `{}`", M::NAME, expr.ident))),
+        }
+    }
+}
+
+fn header<M: Machine>(symbol: &str) -> String {
+    format!("\n    .globl {symbol}\n    .p2align {}\n    .type {symbol}, @function\n{symbol}:\n", M::FUNCTION_ALIGN)
+}
+
+fn generate_entry<M: Machine>(entry: Entry, ast: &AST) -> Result<String, CodegenError> {
+    let main = find_main(ast).map_err(|err| CodegenError(err.0))?;
+    let symbol = match entry {
+        Entry::C => "main",
+        Entry::Freestanding => "_start",
+    };
+
+    let mut result = header::<M>(symbol);
+    M::entry(&mut result, entry, main.r#type);
+    result += &format!("    .size {symbol}, .-{symbol}\n");
+
+    Ok(result)
+}
+
+fn generate_global<M: Machine>(stmt: &LetStatement) -> Result<String, CodegenError> {
+    let ident = &stmt.ident;
+
+    let value = match &stmt.expr {
+        Expression::Literal((Literal::String(string), _)) if stmt.r#type == DataType::str => {
+            return Ok(format!("\n    .section .rodata\n    .globl {ident}\n{ident}:\n    .asciz \"{}\"\n", escape(string)));
+        },
+        Expression::Literal((Literal::Boolean(b), _)) if stmt.r#type == DataType::bool => *b as i128,
+        Expression::Literal((literal @ (Literal::SignedInteger(_) | Literal::UnsignedInteger(_)), _)) if is_integer(stmt.r#type) => literal_value(literal).0,
+        _ => return Err(CodegenError(format!("[{}] global `{}` must be initialized with a constant of type {}", M::NAME, ident, stmt.r#type))),
+    };
+
+    let size = match stmt.r#type {
+        DataType::bool => 1,
+        DataType::SignedInteger(dtype) => dtype.bits() / 8,
+        DataType::UnsignedInteger(dtype) => dtype.bits() / 8,
+        _ => unreachable!(),
+    };
+
+    let mut result = format!("\n    .data\n    .globl {ident}\n    .p2align {}\n{ident}:\n", size.trailing_zeros());
+
+    if is_wide(stmt.r#type) {
+        result += &format!("    {directive} {}\n    {directive} {}\n", value as u64, (value >> 64) as u64, directive = M::directive(8));
+    } else {
+        let (bits, _) = M::registers(value, stmt.r#type);
+        result += &format!("    {} {}\n", M::directive(size), bits as u64 & (u64::MAX >> (64 - size * 8)));
+    }
+
+    Ok(result)
+}
+
+// composite types are only supported by the interpreter and the LLVM backend so far, and pointers
+// are only passed around, to the C functions that read and write what they point to
+fn check_type<M: Machine>(dtype: DataType, name: &str) -> Result<(), CodegenError> {
+    match dtype {
+        DataType::Pointer(pointee) if *pointee == DataType::void || check_type::<M>(*pointee, name).is_ok() => Ok(()),
+        DataType::Array(_) | DataType::Pointer(_) | DataType::Struct(_) | DataType::Enum(_) | DataType::Tuple(_) => Err(CodegenError(format!("[{}] {} of `{}` is not supported, use `--backend llvm`", M::NAME, dtype, name))),
+        _ => Ok(()),
+    }
+}
+
+fn check_signature<M: Machine>(name: &str, params: &[(Identifier, DataType)], r#type: DataType) -> Result<(), CodegenError> {
+    params.iter().try_for_each(|(ident, dtype)| check_type::<M>(*dtype, ident))?;
+    check_type::<M>(r#type, name)
+}
+
+// converts `RESULT` from `from` to `to`, which integers, pointers and `*u8` to `str` allow
+fn coerce<M: Machine>(code: &mut String, from: DataType, to: DataType, name: &str) -> Result<(), CodegenError> {
+    if from == to || is_pointer_compatible(from, to) || is_string_buffer(from, to) {
+        Ok(())
+    } else if is_integer(from) && is_integer(to) {
+        M::cast(code, from, to);
+        Ok(())
+    } else {
+        Err(CodegenError(format!("[{}] `{}` expects {}, found {}", M::NAME, name, to, from)))
+    }
+}
\ No newline at end of file
@@ -0,0 +1,139 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::codegen::regalloc::{Function, PhysReg, Reg, VirtReg};
+
+// Instructions are numbered in layout order and every one gets four positions: its uses are
+// read at 4n + 1, a call overwrites the caller-saved registers at 4n + 2 and its defs are
+// written at 4n + 3, so a value may be used by a call and another one defined by it in the
+// same register, while one living across the call may not.
+pub fn use_pos(n: u32) -> u32 {
+    4 * n + 1
+}
+
+pub fn clobber_pos(n: u32) -> u32 {
+    4 * n + 2
+}
+
+pub fn def_pos(n: u32) -> u32 {
+    4 * n + 3
+}
+
+// the first and last position a register is live at, both included
+pub type Range = (u32, u32);
+
+// The registers live on entry to and on exit from each block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Liveness {
+    pub live_in: Vec<BTreeSet<Reg>>,
+    pub live_out: Vec<BTreeSet<Reg>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Intervals {
+    // the range from the first to the last position every virtual register is live at, holes included
+    pub virtuals: BTreeMap<VirtReg, Range>,
+    // the exact ranges the instructions occupy each physical register in
+    pub fixed: BTreeMap<PhysReg, Vec<Range>>,
+    // the clobber positions of the calls
+    pub calls: Vec<u32>,
+}
+
+// solves the backward dataflow equations until no set changes, which loops need more than one pass for
+pub fn analyze(function: &Function) -> Liveness {
+    let blocks = &function.blocks;
+
+    // the registers each block reads before writing them, and the ones it writes
+    let (gen, kill): (Vec<BTreeSet<Reg>>, Vec<BTreeSet<Reg>>) = blocks.iter().map(|block| {
+        let (mut gen, mut kill) = (BTreeSet::new(), BTreeSet::new());
+
+        for inst in &block.insts {
+            gen.extend(inst.uses.iter().filter(|reg| !kill.contains(*reg)));
+            kill.extend(inst.defs.iter());
+        }
+
+        (gen, kill)
+    }).unzip();
+
+    let mut live_in = vec![BTreeSet::new(); blocks.len()];
+    let mut live_out = vec![BTreeSet::new(); blocks.len()];
+    let mut changed = true;
+
+    while changed {
+        changed = false;
+
+        for (n, block) in blocks.iter().enumerate().rev() {
+            let out = block.succs.iter().flat_map(|succ| live_in[*succ].iter().copied()).collect::<BTreeSet<Reg>>();
+            let r#in = gen[n].iter().chain(out.difference(&kill[n])).copied().collect::<BTreeSet<Reg>>();
+
+            if out != live_out[n] || r#in != live_in[n] {
+                live_out[n] = out;
+                live_in[n] = r#in;
+                changed = true;
+            }
+        }
+    }
+
+    Liveness { live_in, live_out }
+}
+
+pub fn intervals(function: &Function) -> Intervals {
+    let liveness = analyze(function);
+    let mut segments: BTreeMap<Reg, Vec<Range>> = BTreeMap::new();
+    let mut calls = Vec::new();
+    let mut first = 0;
+
+    for (n, block) in function.blocks.iter().enumerate() {
+        let last = first + block.insts.len() as u32;
+
+        if block.insts.is_empty() {
+            continue;
+        }
+
+        // walks the block backwards, keeping the end of the segment every live register is in
+        let mut open = liveness.live_out[n].iter().map(|reg| (*reg, def_pos(last - 1))).collect::<BTreeMap<Reg, u32>>();
+
+        for (i, inst) in block.insts.iter().enumerate().rev() {
+            let i = first + i as u32;
+
+            if inst.is_call() {
+                calls.push(clobber_pos(i));
+            }
+
+            // a def nothing reads still occupies its register while it is written
+            for reg in &inst.defs {
+                let end = open.remove(reg).unwrap_or(def_pos(i));
+                segments.entry(*reg).or_default().push((def_pos(i), end));
+            }
+
+            for reg in &inst.uses {
+                open.entry(*reg).or_insert(use_pos(i));
+            }
+        }
+
+        for (reg, end) in open {
+            segments.entry(reg).or_default().push((4 * first, end));
+        }
+
+        first = last;
+    }
+
+    calls.sort();
+    let mut intervals = Intervals { calls, ..Intervals::default() };
+
+    for (reg, mut ranges) in segments {
+        ranges.sort();
+
+        match reg {
+            Reg::Virt(vreg) => {
+                let start = ranges.iter().map(|range| range.0).min().unwrap_or(0);
+                let end = ranges.iter().map(|range| range.1).max().unwrap_or(0);
+                intervals.virtuals.insert(vreg, (start, end));
+            },
+            Reg::Phys(preg) => {
+                intervals.fixed.insert(preg, ranges);
+            },
+        }
+    }
+
+    intervals
+}
\ No newline at end of file
@@ -0,0 +1,202 @@
+pub mod liveness;
+pub mod record;
+pub mod scan;
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::error::CodegenError;
+
+pub use scan::allocate;
+
+// A machine register, numbered the way the target's `names` lists them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PhysReg(pub u8);
+
+// A value of unbounded supply that the allocator maps to a machine register or a stack slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VirtReg(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Reg {
+    Virt(VirtReg),
+    // fixed by the instruction, like an argument register around a call
+    Phys(PhysReg),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstKind {
+    // any instruction the backend understands, the allocator only looks at its operands
+    Op(String),
+    // overwrites every caller-saved register
+    Call(String),
+    // stores its use into a spill slot
+    Spill(u32),
+    // loads its def from a spill slot
+    Reload(u32),
+}
+
+// An instruction of the machine IR: it reads all of its uses before it writes any of its defs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inst {
+    pub kind: InstKind,
+    pub defs: Vec<Reg>,
+    pub uses: Vec<Reg>,
+}
+
+impl Inst {
+    pub fn op(name: &str, defs: &[Reg], uses: &[Reg]) -> Inst {
+        Inst { kind: InstKind::Op(name.into()), defs: defs.to_vec(), uses: uses.to_vec() }
+    }
+
+    pub fn call(name: &str, defs: &[Reg], uses: &[Reg]) -> Inst {
+        Inst { kind: InstKind::Call(name.into()), defs: defs.to_vec(), uses: uses.to_vec() }
+    }
+
+    pub fn is_call(&self) -> bool {
+        matches!(self.kind, InstKind::Call(_))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub insts: Vec<Inst>,
+    // indices of the blocks control may continue with
+    pub succs: Vec<usize>,
+}
+
+// A function in the machine IR; its blocks are laid out in order and the first one is the entry.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Function {
+    pub blocks: Vec<Block>,
+}
+
+// What the allocator needs to know about the registers of a target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetRegisters {
+    pub names: &'static [&'static str],
+    // the registers the allocator may hand out, in order of preference
+    pub allocatable: &'static [PhysReg],
+    // the registers a call may overwrite; every other allocatable one must be preserved by the callee
+    pub caller_saved: &'static [PhysReg],
+}
+
+impl TargetRegisters {
+    pub fn is_caller_saved(&self, reg: PhysReg) -> bool {
+        self.caller_saved.contains(&reg)
+    }
+
+    pub fn name(&self, reg: PhysReg) -> &'static str {
+        self.names[reg.0 as usize]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Location {
+    Reg(PhysReg),
+    // an index into the spill area of the frame
+    Stack(u32),
+}
+
+// Where every virtual register of a function lives for its whole lifetime.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Allocation {
+    pub locations: HashMap<VirtReg, Location>,
+    // the number of spill slots the frame needs
+    pub slots: u32,
+    // the callee-saved registers handed out, which the prologue must save
+    pub callee_saved: Vec<PhysReg>,
+}
+
+impl Allocation {
+    // replaces every virtual register with its machine register; spilled operands are reloaded
+    // into `scratch` registers right before the instruction and stored right after it
+    pub fn apply(&self, function: &Function, scratch: &[PhysReg]) -> Result<Function, CodegenError> {
+        let mut blocks = Vec::new();
+
+        for block in &function.blocks {
+            let mut insts = Vec::new();
+
+            for inst in &block.insts {
+                let mut spilled = HashMap::new();
+                let mut place = |reg: Reg| -> Result<(Reg, Option<u32>), CodegenError> {
+                    match reg {
+                        Reg::Phys(_) => Ok((reg, None)),
+                        Reg::Virt(vreg) => match self.locations.get(&vreg) {
+                            Some(Location::Reg(preg)) => Ok((Reg::Phys(*preg), None)),
+                            Some(Location::Stack(slot)) => {
+                                // an operand both read and written keeps its scratch register
+                                let next = spilled.len();
+                                let preg = *spilled.entry(vreg).or_insert_with(|| scratch.get(next).copied());
+                                let preg = preg.ok_or_else(|| CodegenError(format!("[Regalloc] {} needs more than {} scratch registers", inst, scratch.len())))?;
+
+                                Ok((Reg::Phys(preg), Some(*slot)))
+                            },
+                            None => Err(CodegenError(format!("[Regalloc] {} has no location", Reg::Virt(vreg)))),
+                        },
+                    }
+                };
+
+                let uses = inst.uses.iter().map(|reg| place(*reg)).collect::<Result<Vec<_>, CodegenError>>()?;
+                let defs = inst.defs.iter().map(|reg| place(*reg)).collect::<Result<Vec<_>, CodegenError>>()?;
+
+                let mut reloaded = Vec::new();
+
+                for (reg, slot) in &uses {
+                    if let Some(slot) = slot.filter(|_| !reloaded.contains(reg)) {
+                        insts.push(Inst { kind: InstKind::Reload(slot), defs: vec![*reg], uses: Vec::new() });
+                        reloaded.push(*reg);
+                    }
+                }
+
+                insts.push(Inst {
+                    kind: inst.kind.clone(),
+                    defs: defs.iter().map(|(reg, _)| *reg).collect(),
+                    uses: uses.iter().map(|(reg, _)| *reg).collect(),
+                });
+
+                for (reg, slot) in &defs {
+                    if let Some(slot) = slot {
+                        insts.push(Inst { kind: InstKind::Spill(*slot), defs: Vec::new(), uses: vec![*reg] });
+                    }
+                }
+            }
+
+            blocks.push(Block { insts, succs: block.succs.clone() });
+        }
+
+        Ok(Function { blocks })
+    }
+}
+
+impl fmt::Display for Reg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reg::Virt(vreg) => write!(f, "%{}", vreg.0),
+            Reg::Phys(preg) => write!(f, "${}", preg.0),
+        }
+    }
+}
+
+impl fmt::Display for Inst {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operands = |regs: &[Reg]| regs.iter().map(|reg| reg.to_string()).collect::<Vec<String>>().join(", ");
+
+        if !self.defs.is_empty() {
+            write!(f, "{} = ", operands(&self.defs))?;
+        }
+
+        match &self.kind {
+            InstKind::Op(name) => write!(f, "{}", name)?,
+            InstKind::Call(name) => write!(f, "call {}", name)?,
+            InstKind::Spill(slot) => write!(f, "spill [{}]", slot)?,
+            InstKind::Reload(slot) => write!(f, "reload [{}]", slot)?,
+        }
+
+        if !self.uses.is_empty() {
+            write!(f, " {}", operands(&self.uses))?;
+        }
+
+        Ok(())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use crate::codegen::asm::emit;
+use crate::error::CodegenError;
+use crate::codegen::regalloc::{Block, Function, Inst, Reg, VirtReg};
+
+// Whether a marker writes a register into a virtual one or reads the virtual one into it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Def,
+    Use,
+}
+
+// Builds the machine IR of a function while a backend writes its code. The values the code
+// keeps in virtual registers are moved in and out of them through marker lines, which
+// `resolve` replaces with the moves, loads and stores of their locations once the whole
+// function is known and allocated; labels and jumps delimit the blocks.
+#[derive(Debug, Clone, Default)]
+pub struct Recorder {
+    blocks: Vec<Block>,
+    // the block every label starts and the labels every block jumps to
+    labels: HashMap<String, usize>,
+    targets: Vec<Vec<String>>,
+    // whether control reaches the end of the current block, and so the next one
+    falls_through: bool,
+    vregs: u32,
+}
+
+impl Recorder {
+    pub fn new() -> Recorder {
+        Recorder {
+            blocks: vec![Block { insts: Vec::new(), succs: Vec::new() }],
+            targets: vec![Vec::new()],
+            falls_through: true,
+            ..Recorder::default()
+        }
+    }
+
+    pub fn vreg(&mut self) -> VirtReg {
+        self.vregs += 1;
+        VirtReg(self.vregs - 1)
+    }
+
+    fn push(&mut self, inst: Inst) {
+        if let Some(block) = self.blocks.last_mut() {
+            block.insts.push(inst);
+        }
+    }
+
+    // moves the machine register `reg` into `vreg`
+    pub fn def(&mut self, code: &mut String, vreg: VirtReg, reg: &str) {
+        self.push(Inst::op("def", &[Reg::Virt(vreg)], &[]));
+        emit(code, format!("@def {} {}", vreg.0, reg));
+    }
+
+    // moves `vreg` into the machine register `reg`
+    pub fn read(&mut self, code: &mut String, vreg: VirtReg, reg: &str) {
+        self.push(Inst::op("use", &[], &[Reg::Virt(vreg)]));
+        emit(code, format!("@use {} {}", vreg.0, reg));
+    }
+
+    // a call overwrites the caller-saved registers, which the values living across it must stay out of
+    pub fn call(&mut self, name: &str) {
+        self.push(Inst::call(name, &[], &[]));
+    }
+
+    // starts a new block, which the current one continues with unless it ended in a jump
+    fn start(&mut self) {
+        let n = self.blocks.len();
+
+        if self.falls_through {
+            self.blocks[n - 1].succs.push(n);
+        }
+
+        self.blocks.push(Block { insts: Vec::new(), succs: Vec::new() });
+        self.targets.push(Vec::new());
+        self.falls_through = true;
+    }
+
+    pub fn label(&mut self, code: &mut String, label: &str) {
+        code.push_str(&format!("{label}:\n"));
+        self.start();
+        self.labels.insert(label.into(), self.blocks.len() - 1);
+    }
+
+    // `instr` jumps to `label`, only on some condition when `conditional`
+    pub fn jump(&mut self, code: &mut String, instr: &str, label: &str, conditional: bool) {
+        emit(code, format!("{instr} {label}"));
+
+        if let Some(targets) = self.targets.last_mut() {
+            targets.push(label.into());
+        }
+
+        self.falls_through = conditional;
+        self.start();
+    }
+
+    pub fn finish(mut self) -> Function {
+        for (block, targets) in self.blocks.iter_mut().zip(&self.targets) {
+            // the label of the function's epilogue is not one of its blocks
+            block.succs.extend(targets.iter().filter_map(|label| self.labels.get(label).copied()));
+        }
+
+        Function { blocks: self.blocks }
+    }
+}
+
+// replaces every marker line of `code` with what `place` emits for it, a line starting with `@` that
+// is not a `@def` or `@use` of a virtual register is an error
+pub fn resolve(code: &str, mut place: impl FnMut(&mut String, Access, VirtReg, &str)) -> Result<String, CodegenError> {
+    let mut result = String::new();
+
+    for line in code.lines() {
+        let Some(marker) = line.trim_start().strip_prefix('@') else {
+            result.push_str(line);
+            result.push('\n');
+            continue;
+        };
+
+        match marker.split(' ').collect::<Vec<&str>>().as_slice() {
+            [access, vreg, reg] => {
+                let access = match *access {
+                    "def" => Access::Def,
+                    "use" => Access::Use,
+                    access => return Err(CodegenError(format!("[Regalloc] unknown marker `@{}`", access))),
+                };
+
+                let vreg = vreg.parse().map_err(|_| CodegenError(format!("[Regalloc] expected virtual register, found `{}`", vreg)))?;
+                place(&mut result, access, VirtReg(vreg), reg);
+            },
+            _ => return Err(CodegenError(format!("[Regalloc] malformed marker `@{}`", marker))),
+        }
+    }
+
+    Ok(result)
+}
\ No newline at end of file
@@ -0,0 +1,90 @@
+use crate::codegen::regalloc::liveness::{intervals, Range};
+use crate::codegen::regalloc::{Allocation, Function, Location, PhysReg, TargetRegisters, VirtReg};
+
+fn overlaps(a: Range, b: Range) -> bool {
+    a.0 <= b.1 && b.0 <= a.1
+}
+
+// Linear scan over the live intervals of the virtual registers, in the order they start. Every
+// interval keeps one location for its whole lifetime: when no register is free, the interval
+// that ends last, among the active ones and the new one, goes to a stack slot.
+pub fn allocate(function: &Function, target: &TargetRegisters) -> Allocation {
+    let intervals = intervals(function);
+
+    // whether `reg` can hold a value over `range`: the instructions must not need it in between,
+    // and a call must not overwrite it
+    let fits = |reg: PhysReg, range: Range| {
+        let fixed = intervals.fixed.get(&reg).is_some_and(|ranges| ranges.iter().any(|fixed| overlaps(*fixed, range)));
+        let clobbered = target.is_caller_saved(reg) && intervals.calls.iter().any(|call| range.0 < *call && *call < range.1);
+
+        !fixed && !clobbered
+    };
+
+    let mut order = intervals.virtuals.iter().map(|(vreg, range)| (*vreg, *range)).collect::<Vec<(VirtReg, Range)>>();
+    order.sort_by_key(|(vreg, range)| (range.0, *vreg));
+
+    let mut allocation = Allocation::default();
+    let mut active: Vec<(VirtReg, Range, PhysReg)> = Vec::new();
+    // the ranges stored in every slot so far, which a slot is shared between as long as they do not overlap
+    let mut slots: Vec<Vec<Range>> = Vec::new();
+
+    let mut spill = |vreg: VirtReg, range: Range, allocation: &mut Allocation| {
+        let slot = match slots.iter().position(|ranges| ranges.iter().all(|other| !overlaps(*other, range))) {
+            Some(slot) => slot,
+            None => {
+                slots.push(Vec::new());
+                slots.len() - 1
+            },
+        };
+
+        slots[slot].push(range);
+        allocation.locations.insert(vreg, Location::Stack(slot as u32));
+    };
+
+    for (vreg, range) in order {
+        active.retain(|(_, other, _)| other.1 >= range.0);
+
+        let free = target.allocatable.iter()
+            .copied()
+            .find(|reg| active.iter().all(|(_, _, other)| other != reg) && fits(*reg, range));
+
+        if let Some(reg) = free {
+            active.push((vreg, range, reg));
+            allocation.locations.insert(vreg, Location::Reg(reg));
+            continue;
+        }
+
+        // the victim's register must be one this interval could have taken
+        let victim = active.iter()
+            .enumerate()
+            .filter(|(_, (_, other, reg))| other.1 > range.1 && fits(*reg, range))
+            .max_by_key(|(_, (_, other, _))| other.1)
+            .map(|(n, _)| n);
+
+        match victim {
+            Some(n) => {
+                let (spilled, other, reg) = active[n];
+                spill(spilled, other, &mut allocation);
+
+                active[n] = (vreg, range, reg);
+                allocation.locations.insert(vreg, Location::Reg(reg));
+            },
+            None => spill(vreg, range, &mut allocation),
+        }
+    }
+
+    allocation.slots = slots.len() as u32;
+
+    let mut callee_saved = allocation.locations.values()
+        .filter_map(|location| match location {
+            Location::Reg(reg) if !target.is_caller_saved(*reg) => Some(*reg),
+            _ => None,
+        })
+        .collect::<Vec<PhysReg>>();
+
+    callee_saved.sort();
+    callee_saved.dedup();
+    allocation.callee_saved = callee_saved;
+
+    allocation
+}
\ No newline at end of file
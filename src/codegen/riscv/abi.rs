@@ -0,0 +1,57 @@
+use crate::codegen::asm::is_wide;
+use crate::codegen::regalloc::{PhysReg, TargetRegisters};
+use crate::types::DataType;
+
+pub use crate::codegen::native::ArgLoc;
+
+// The code generator works in `t0` to `t2`, `t6` and the argument registers, which leaves
+// `t3` to `t5` and the callee-saved registers to the values the allocator places.
+pub const REGISTERS: TargetRegisters = TargetRegisters {
+    names: &[
+        "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4", "a5",
+        "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4", "t5", "t6",
+    ],
+    allocatable: &[
+        PhysReg(28), PhysReg(29), PhysReg(30),
+        PhysReg(9), PhysReg(18), PhysReg(19), PhysReg(20), PhysReg(21), PhysReg(22), PhysReg(23), PhysReg(24), PhysReg(25), PhysReg(26), PhysReg(27),
+    ],
+    caller_saved: &[
+        PhysReg(1), PhysReg(5), PhysReg(6), PhysReg(7), PhysReg(28), PhysReg(29), PhysReg(30), PhysReg(31),
+        PhysReg(10), PhysReg(11), PhysReg(12), PhysReg(13), PhysReg(14), PhysReg(15), PhysReg(16), PhysReg(17),
+    ],
+};
+
+// integer argument registers in the order the LP64 ABI assigns them
+pub const ARG_REGISTERS: [&str; 8] = ["a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7"];
+
+// assigns every parameter a location under the LP64 calling convention and returns them with
+// the size of the stack area the call needs
+pub fn assign(params: &[DataType]) -> (Vec<ArgLoc>, i64) {
+    let mut next = 0;
+    let mut stack = 0;
+
+    let locs = params.iter().map(|dtype| {
+        if is_wide(*dtype) {
+            if next + 1 < ARG_REGISTERS.len() {
+                next += 2;
+                ArgLoc::Pair(next - 2)
+            } else if next + 1 == ARG_REGISTERS.len() {
+                next += 1;
+                stack += 8;
+                ArgLoc::Split(stack - 8)
+            } else {
+                // stack arguments are aligned to their own size
+                stack = (stack + 15) / 16 * 16 + 16;
+                ArgLoc::Stack(stack - 16)
+            }
+        } else if next < ARG_REGISTERS.len() {
+            next += 1;
+            ArgLoc::Reg(next - 1)
+        } else {
+            stack += 8;
+            ArgLoc::Stack(stack - 8)
+        }
+    }).collect();
+
+    (locs, (stack + 15) / 16 * 16)
+}
\ No newline at end of file
@@ -0,0 +1,199 @@
+pub mod abi;
+pub mod ops;
+
+use crate::codegen::asm::{emit, is_wide};
+use crate::codegen::llvm::entry::{Entry, MAIN_SYMBOL};
+use crate::codegen::native::{ArgLoc, Machine, NativeGen};
+use crate::codegen::regalloc::TargetRegisters;
+use crate::parser::Operator;
+use crate::types::DataType;
+
+// Lowers a folded AST to RV64IM assembly for the GNU assembler, following the LP64 ABI.
+pub type RiscvGen = NativeGen<Riscv64>;
+
+// Values are computed in `a0`/`a1` and frame slots are addressed from `s0`, below the saved
+// `ra` and `s0`.
+pub struct Riscv64;
+
+impl Machine for Riscv64 {
+    const NAME: &'static str = "RISC-V";
+    const REGISTERS: TargetRegisters = abi::REGISTERS;
+    const ARG_REGISTERS: &'static [&'static str] = &abi::ARG_REGISTERS;
+    const RESULT: (&'static str, &'static str) = ("a0", "a1");
+    const OPERAND: (&'static str, &'static str) = ("a2", "a3");
+    const SCRATCH: &'static str = "t0";
+    const FRAME_HEADER: i64 = 16;
+    // the stack pointer at the call becomes the frame pointer
+    const STACK_ARGS: i64 = 0;
+    const FUNCTION_ALIGN: u32 = 2;
+    const JUMP: &'static str = "j";
+    const TRAP: &'static str = "unimp";
+    const TRAILER: &'static str = "";
+
+    fn assign(params: &[DataType]) -> (Vec<ArgLoc>, i64) {
+        abi::assign(params)
+    }
+
+    fn move_reg(code: &mut String, to: &str, from: &str) {
+        emit(code, format!("mv {to}, {from}"));
+    }
+
+    fn load(code: &mut String, reg: &str, offset: i64) {
+        access(code, "ld", reg, offset);
+    }
+
+    fn store(code: &mut String, reg: &str, offset: i64) {
+        access(code, "sd", reg, offset);
+    }
+
+    fn store_arg(code: &mut String, reg: &str, offset: i64) {
+        emit(code, format!("sd {reg}, {offset}(sp)"));
+    }
+
+    fn adjust_sp(code: &mut String, amount: i64) {
+        if (-2048..2048).contains(&amount) {
+            emit(code, format!("addi sp, sp, {amount}"));
+        } else {
+            emit(code, format!("li t0, {amount}"));
+            emit(code, "add sp, sp, t0");
+        }
+    }
+
+    fn load_immediate(code: &mut String, reg: &str, value: i64) {
+        emit(code, format!("li {reg}, {value}"));
+    }
+
+    fn load_address(code: &mut String, reg: &str, symbol: &str) {
+        emit(code, format!("lla {reg}, {symbol}"));
+    }
+
+    fn load_global(code: &mut String, symbol: &str, dtype: DataType) {
+        emit(code, format!("lla t0, {}", symbol));
+        emit(code, format!("{} a0, 0(t0)", load_instr(dtype)));
+
+        if is_wide(dtype) {
+            emit(code, "ld a1, 8(t0)");
+        }
+    }
+
+    fn branch_if_zero(_: &mut String) -> &'static str {
+        "beqz a0,"
+    }
+
+    fn call(code: &mut String, symbol: &str) {
+        emit(code, format!("call {symbol}"));
+    }
+
+    fn prologue(code: &mut String, locals: i64) {
+        emit(code, "addi sp, sp, -16");
+        emit(code, "sd ra, 8(sp)");
+        emit(code, "sd s0, 0(sp)");
+        emit(code, "addi s0, sp, 16");
+
+        if locals > 0 {
+            Self::adjust_sp(code, -locals);
+        }
+    }
+
+    fn epilogue(code: &mut String) {
+        emit(code, "ld ra, -8(s0)");
+        emit(code, "mv t0, s0");
+        emit(code, "ld s0, -16(s0)");
+        emit(code, "mv sp, t0");
+        emit(code, "ret");
+    }
+
+    fn entry(code: &mut String, entry: Entry, r#type: DataType) {
+        match entry {
+            Entry::C => {
+                emit(code, "addi sp, sp, -16");
+                emit(code, "sd ra, 8(sp)");
+                emit(code, format!("call {}", MAIN_SYMBOL));
+
+                if r#type == DataType::void {
+                    emit(code, "li a0, 0");
+                }
+
+                emit(code, "ld ra, 8(sp)");
+                emit(code, "addi sp, sp, 16");
+                emit(code, "ret");
+            },
+            Entry::Freestanding => {
+                // without the C runtime nobody else sets up `gp` for linker relaxation
+                *code += "    .option push\n    .option norelax\n";
+                emit(code, "lla gp, __global_pointer$");
+                *code += "    .option pop\n";
+
+                emit(code, format!("call {}", MAIN_SYMBOL));
+
+                if r#type == DataType::void {
+                    emit(code, "li a0, 0");
+                }
+
+                // the exit system call
+                emit(code, "li a7, 93");
+                emit(code, "ecall");
+            },
+        }
+    }
+
+    fn directive(bytes: u32) -> &'static str {
+        match bytes {
+            1 => ".byte",
+            2 => ".half",
+            4 => ".word",
+            _ => ".dword",
+        }
+    }
+
+    // the ABI passes and returns narrow values already extended to the form `ops` keeps them in
+    fn needs_extension(_: DataType) -> bool {
+        false
+    }
+
+    fn normalize(code: &mut String, dtype: DataType) {
+        ops::normalize(code, "a0", dtype);
+    }
+
+    fn registers(value: i128, dtype: DataType) -> (i64, i64) {
+        ops::registers(value, dtype)
+    }
+
+    fn cast(code: &mut String, from: DataType, to: DataType) {
+        ops::cast(code, from, to);
+    }
+
+    fn unary(code: &mut String, operator: Operator, dtype: DataType) {
+        ops::unary(code, operator, dtype);
+    }
+
+    fn infix(code: &mut String, operator: Operator, dtype: DataType) {
+        ops::infix(code, operator, dtype);
+    }
+
+    fn calls_runtime(operator: Operator, dtype: DataType) -> bool {
+        ops::calls_runtime(operator, dtype)
+    }
+}
+
+// the instruction loading a global of `dtype` into the form its register holds it in
+fn load_instr(dtype: DataType) -> &'static str {
+    match dtype {
+        DataType::SignedInteger(dtype) => ["lb", "lh", "lw", "ld"][dtype.bits().trailing_zeros() as usize - 3],
+        // a `u32` is kept sign-extended like every 32-bit value
+        DataType::UnsignedInteger(dtype) => ["lbu", "lhu", "lw", "ld"][dtype.bits().trailing_zeros() as usize - 3],
+        DataType::bool => "lbu",
+        _ => "ld",
+    }
+}
+
+// loads or stores `reg` at `offset(s0)`, going through `t6` when the offset does not fit in 12 bits
+fn access(code: &mut String, instr: &str, reg: &str, offset: i64) {
+    if offset >= -2048 {
+        emit(code, format!("{instr} {reg}, {offset}(s0)"));
+    } else {
+        emit(code, format!("li t6, {offset}"));
+        emit(code, "add t6, s0, t6");
+        emit(code, format!("{instr} {reg}, 0(t6)"));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,237 @@
+use crate::codegen::asm::{emit, is_wide};
+use crate::parser::Operator;
+use crate::types::DataType;
+
+// Integers live in registers in the form the LP64 ABI passes them: types narrower than
+// 64 bits are sign- or zero-extended by their signedness, except `u32`, which like every
+// 32-bit value is sign-extended. 128-bit values take `a0` (low) and `a1` (high). Operations
+// take their left operand in `a0`/`a1` and their right one in `a2`/`a3`, leave the result in
+// `a0`/`a1` and only clobber temporaries.
+
+fn layout(dtype: DataType) -> (u32, bool) {
+    match dtype {
+        DataType::SignedInteger(dtype) => (dtype.bits(), true),
+        DataType::UnsignedInteger(dtype) => (dtype.bits(), false),
+        DataType::bool => (8, false),
+        _ => (64, false),
+    }
+}
+
+pub fn is_signed(dtype: DataType) -> bool {
+    layout(dtype).1
+}
+
+// brings `reg` back to the form of `dtype` after a 64-bit operation
+pub fn normalize(code: &mut String, reg: &str, dtype: DataType) {
+    match layout(dtype) {
+        (8, true) | (16, true) => {
+            let shift = 64 - layout(dtype).0;
+            emit(code, format!("slli {reg}, {reg}, {shift}"));
+            emit(code, format!("srai {reg}, {reg}, {shift}"));
+        },
+        (32, _) => emit(code, format!("sext.w {reg}, {reg}")),
+        (8, false) => emit(code, format!("andi {reg}, {reg}, 255")),
+        (16, false) => {
+            emit(code, format!("slli {reg}, {reg}, 48"));
+            emit(code, format!("srli {reg}, {reg}, 48"));
+        },
+        _ => {},
+    }
+}
+
+// bit pattern of the registers holding `value` as a `dtype`, high half last
+pub fn registers(value: i128, dtype: DataType) -> (i64, i64) {
+    match layout(dtype) {
+        (128, _) => (value as i64, (value >> 64) as i64),
+        (32, _) => (value as i32 as i64, 0),
+        (8, true) => (value as i8 as i64, 0),
+        (16, true) => (value as i16 as i64, 0),
+        (8, false) => (value as u8 as i64, 0),
+        (16, false) => (value as u16 as i64, 0),
+        _ => (value as i64, 0),
+    }
+}
+
+// truncates, sign- or zero-extends the integer in `a0`/`a1` from `from` to `to`
+pub fn cast(code: &mut String, from: DataType, to: DataType) {
+    let (from_bits, from_signed) = layout(from);
+    let (to_bits, _) = layout(to);
+
+    if from == to {
+        return;
+    }
+
+    // a `u32` widens with zeros, while its register holds it sign-extended
+    if from_bits == 32 && !from_signed && to_bits > 32 {
+        emit(code, "slli a0, a0, 32");
+        emit(code, "srli a0, a0, 32");
+    }
+
+    if to_bits == 128 && from_bits < 128 {
+        if from_signed {
+            emit(code, "srai a1, a0, 63");
+        } else {
+            emit(code, "li a1, 0");
+        }
+    }
+
+    if to_bits < 64 {
+        normalize(code, "a0", to);
+    }
+}
+
+pub fn unary(code: &mut String, operator: Operator, dtype: DataType) {
+    let (bits, _) = layout(dtype);
+
+    match operator {
+        Operator::UnaryMinus if is_wide(dtype) => {
+            emit(code, "snez t0, a0");
+            emit(code, "neg a0, a0");
+            emit(code, "neg a1, a1");
+            emit(code, "sub a1, a1, t0");
+        },
+        Operator::UnaryMinus if bits == 32 => emit(code, "negw a0, a0"),
+        Operator::UnaryMinus => {
+            emit(code, "neg a0, a0");
+            normalize(code, "a0", dtype);
+        },
+        Operator::BitwiseNot => {
+            emit(code, "not a0, a0");
+
+            if is_wide(dtype) {
+                emit(code, "not a1, a1");
+            } else {
+                normalize(code, "a0", dtype);
+            }
+        },
+        _ => {},
+    }
+}
+
+pub fn infix(code: &mut String, operator: Operator, dtype: DataType) {
+    if is_wide(dtype) {
+        return infix_wide(code, operator, is_signed(dtype));
+    }
+
+    let (bits, signed) = layout(dtype);
+    // 32-bit operations have `w` forms that sign-extend their result
+    let suffix = if bits == 32 { "w" } else { "" };
+
+    let instr = match operator {
+        Operator::Plus => format!("add{suffix}"),
+        Operator::Minus => format!("sub{suffix}"),
+        Operator::Multiply => format!("mul{suffix}"),
+        Operator::Divide => format!("div{}{suffix}", if signed { "" } else { "u" }),
+        Operator::Modulo => format!("rem{}{suffix}", if signed { "" } else { "u" }),
+        Operator::LeftShift => format!("sll{suffix}"),
+        Operator::RightShift => format!("sr{}{suffix}", if signed { "a" } else { "l" }),
+        Operator::BitwiseAnd => "and".into(),
+        Operator::BitwiseOr => "or".into(),
+        Operator::BitwiseXor => "xor".into(),
+        _ => return compare(code, operator, signed),
+    };
+
+    emit(code, format!("{instr} a0, a0, a2"));
+
+    if bits < 32 {
+        normalize(code, "a0", dtype);
+    }
+}
+
+fn compare(code: &mut String, operator: Operator, signed: bool) {
+    let slt = if signed { "slt" } else { "sltu" };
+
+    match operator {
+        Operator::Equal => {
+            emit(code, "xor a0, a0, a2");
+            emit(code, "seqz a0, a0");
+        },
+        Operator::NotEqual => {
+            emit(code, "xor a0, a0, a2");
+            emit(code, "snez a0, a0");
+        },
+        Operator::Less => emit(code, format!("{slt} a0, a0, a2")),
+        Operator::Greater => emit(code, format!("{slt} a0, a2, a0")),
+        Operator::LessEqual => {
+            emit(code, format!("{slt} a0, a2, a0"));
+            emit(code, "xori a0, a0, 1");
+        },
+        Operator::GreaterEqual => {
+            emit(code, format!("{slt} a0, a0, a2"));
+            emit(code, "xori a0, a0, 1");
+        },
+        _ => {},
+    }
+}
+
+// whether `infix` leaves `operator` to a function of the compiler runtime
+pub fn calls_runtime(operator: Operator, dtype: DataType) -> bool {
+    is_wide(dtype) && matches!(operator, Operator::Divide | Operator::Modulo | Operator::LeftShift | Operator::RightShift)
+}
+
+fn infix_wide(code: &mut String, operator: Operator, signed: bool) {
+    match operator {
+        Operator::Plus => {
+            emit(code, "add a0, a0, a2");
+            emit(code, "sltu t0, a0, a2");
+            emit(code, "add a1, a1, a3");
+            emit(code, "add a1, a1, t0");
+        },
+        Operator::Minus => {
+            emit(code, "sltu t0, a0, a2");
+            emit(code, "sub a0, a0, a2");
+            emit(code, "sub a1, a1, a3");
+            emit(code, "sub a1, a1, t0");
+        },
+        Operator::Multiply => {
+            emit(code, "mul t0, a0, a3");
+            emit(code, "mul t1, a1, a2");
+            emit(code, "mulhu t2, a0, a2");
+            emit(code, "mul a0, a0, a2");
+            emit(code, "add a1, t0, t1");
+            emit(code, "add a1, a1, t2");
+        },
+        // the rest is left to the compiler runtime, which takes the shift amount as an `int` in `a2`
+        Operator::Divide => emit(code, if signed { "call __divti3" } else { "call __udivti3" }),
+        Operator::Modulo => emit(code, if signed { "call __modti3" } else { "call __umodti3" }),
+        Operator::LeftShift => emit(code, "call __ashlti3"),
+        Operator::RightShift => emit(code, if signed { "call __ashrti3" } else { "call __lshrti3" }),
+        Operator::BitwiseAnd | Operator::BitwiseOr | Operator::BitwiseXor => {
+            let instr = match operator {
+                Operator::BitwiseAnd => "and",
+                Operator::BitwiseOr => "or",
+                _ => "xor",
+            };
+
+            emit(code, format!("{instr} a0, a0, a2"));
+            emit(code, format!("{instr} a1, a1, a3"));
+        },
+        Operator::Equal | Operator::NotEqual => {
+            emit(code, "xor a0, a0, a2");
+            emit(code, "xor a1, a1, a3");
+            emit(code, "or a0, a0, a1");
+            emit(code, if operator == Operator::Equal { "seqz a0, a0" } else { "snez a0, a0" });
+        },
+        Operator::Less => less_wide(code, signed, ("a0", "a1"), ("a2", "a3")),
+        Operator::Greater => less_wide(code, signed, ("a2", "a3"), ("a0", "a1")),
+        Operator::LessEqual => {
+            less_wide(code, signed, ("a2", "a3"), ("a0", "a1"));
+            emit(code, "xori a0, a0, 1");
+        },
+        Operator::GreaterEqual => {
+            less_wide(code, signed, ("a0", "a1"), ("a2", "a3"));
+            emit(code, "xori a0, a0, 1");
+        },
+        _ => {},
+    }
+}
+
+// `a0 = left < right`: the high halves decide unless they are equal
+fn less_wide(code: &mut String, signed: bool, (left_low, left_high): (&str, &str), (right_low, right_high): (&str, &str)) {
+    emit(code, format!("{} t0, {left_high}, {right_high}", if signed { "slt" } else { "sltu" }));
+    emit(code, format!("xor t1, {left_high}, {right_high}"));
+    emit(code, "seqz t1, t1");
+    emit(code, format!("sltu t2, {left_low}, {right_low}"));
+    emit(code, "and t1, t1, t2");
+    emit(code, "or a0, t0, t1");
+}
\ No newline at end of file
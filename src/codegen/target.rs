@@ -0,0 +1,133 @@
+use crate::types::{DataType, EnumType, FloatingPoint, StructType, TupleType, Variant};
+
+// Describes the machine a module is generated for. The data layout strings follow
+// the ones clang emits, except that `i128` is always 16-byte aligned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target {
+    pub name: &'static str,
+    pub triple: &'static str,
+    pub pointer_width: u32,
+    pub data_layout: &'static str,
+}
+
+pub const TARGETS: [Target; 3] = [
+    Target {
+        name: "x86_64",
+        triple: "x86_64-pc-linux-gnu",
+        pointer_width: 64,
+        data_layout: "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:128-n8:16:32:64-S128",
+    },
+    Target {
+        name: "aarch64",
+        triple: "aarch64-unknown-linux-gnu",
+        pointer_width: 64,
+        data_layout: "e-m:e-i8:8:32-i16:16:32-i64:64-i128:128-n32:64-S128",
+    },
+    Target {
+        name: "riscv64",
+        triple: "riscv64-unknown-linux-gnu",
+        pointer_width: 64,
+        data_layout: "e-m:e-p:64:64-i64:64-i128:128-n32:64-S128",
+    },
+];
+
+impl Target {
+    pub fn from_name(name: &str) -> Option<Target> {
+        TARGETS.iter().find(|target| target.name == name || target.triple == name).copied()
+    }
+
+    pub fn host() -> Target {
+        Target::from_name(std::env::consts::ARCH).unwrap_or(TARGETS[0])
+    }
+
+    // ABI alignment in bytes
+    pub fn align_of(&self, dtype: DataType) -> u32 {
+        match dtype {
+            DataType::void | DataType::bool => 1,
+            DataType::str | DataType::Pointer(_) => self.pointer_width / 8,
+            DataType::SignedInteger(dtype) => dtype.bits() / 8,
+            DataType::UnsignedInteger(dtype) => dtype.bits() / 8,
+            DataType::FloatingPoint(FloatingPoint::f32) => 4,
+            DataType::FloatingPoint(FloatingPoint::f64) => 8,
+            DataType::FloatingPoint(FloatingPoint::f128) => 16,
+            DataType::Array(array) => self.align_of(array.element),
+            DataType::Struct(structure) => structure.fields.iter().map(|(_, dtype)| self.align_of(*dtype)).max().unwrap_or(1),
+            DataType::Enum(enumeration) => self.align_of(EnumType::TAG).max(self.payload_align(enumeration)),
+            DataType::Tuple(tuple) => tuple.elements.iter().map(|dtype| self.align_of(*dtype)).max().unwrap_or(1),
+            DataType::Param(param) => unreachable!("type parameter `{}` has no layout, only the instances of its function do", param.name),
+        }
+    }
+
+    // size in bytes, including the padding at the end of a struct, the same as LLVM's alloc size
+    pub fn size_of(&self, dtype: DataType) -> u64 {
+        match dtype {
+            DataType::Array(array) => array.len * self.size_of(array.element),
+            DataType::Struct(structure) => {
+                let end = self.field_offsets(structure).last().zip(structure.fields.last())
+                    .map_or(0, |(offset, (_, dtype))| offset + self.size_of(*dtype));
+
+                end.next_multiple_of(self.align_of(dtype) as u64)
+            },
+            DataType::Tuple(tuple) => {
+                let end = self.element_offsets(tuple).last().zip(tuple.elements.last())
+                    .map_or(0, |(offset, dtype)| offset + self.size_of(*dtype));
+
+                end.next_multiple_of(self.align_of(dtype) as u64)
+            },
+            DataType::Enum(enumeration) => (self.payload_offset(enumeration) + self.payload_size(enumeration)).next_multiple_of(self.align_of(dtype) as u64),
+            DataType::void => 0,
+            dtype => self.align_of(dtype) as u64,
+        }
+    }
+
+    // the byte offset of every field, each one aligned for its type like in C
+    pub fn field_offsets(&self, structure: &StructType) -> Vec<u64> {
+        self.offsets(structure.fields.iter().map(|(_, dtype)| *dtype))
+    }
+
+    pub fn element_offsets(&self, tuple: &TupleType) -> Vec<u64> {
+        self.offsets(tuple.elements.iter().copied())
+    }
+
+    // the values of a variant are laid out like the fields of a struct, starting at `payload_offset`
+    pub fn payload_offsets(&self, variant: &Variant) -> Vec<u64> {
+        self.offsets(variant.payload.iter().copied())
+    }
+
+    // the values of every variant share the memory after the discriminant
+    pub fn payload_offset(&self, enumeration: &EnumType) -> u64 {
+        self.size_of(EnumType::TAG).next_multiple_of(self.payload_align(enumeration) as u64)
+    }
+
+    // the room for the largest variant, a multiple of `payload_align`
+    pub fn payload_size(&self, enumeration: &EnumType) -> u64 {
+        let end = enumeration.variants.iter()
+            .map(|variant| self.payload_offsets(variant).last().zip(variant.payload.last()).map_or(0, |(offset, dtype)| offset + self.size_of(*dtype)))
+            .max()
+            .unwrap_or(0);
+
+        end.next_multiple_of(self.payload_align(enumeration) as u64)
+    }
+
+    pub fn payload_align(&self, enumeration: &EnumType) -> u32 {
+        enumeration.variants.iter().flat_map(|variant| &variant.payload).map(|dtype| self.align_of(*dtype)).max().unwrap_or(1)
+    }
+
+    fn offsets(&self, dtypes: impl Iterator<Item = DataType>) -> Vec<u64> {
+        let mut offset: u64 = 0;
+
+        dtypes
+            .map(|dtype| {
+                let start = offset.next_multiple_of(self.align_of(dtype) as u64);
+                offset = start + self.size_of(dtype);
+                start
+            })
+            .collect()
+    }
+}
+
+impl Default for Target {
+    fn default() -> Target {
+        Target::host()
+    }
+}
@@ -0,0 +1,762 @@
+use std::cmp::max;
+use std::collections::{HashMap, HashSet};
+
+use crate::codegen::asm::{is_wide, literal_value};
+use crate::codegen::llvm::entry::{find_main, Entry};
+use crate::error::CodegenError;
+use crate::format;
+use crate::lexer::Literal;
+use crate::parser::{addressed_variables, DefStatement, Expression, Identifier, IfBranch, IfStatement, LetStatement, Operator, Statement, AST};
+use crate::types::{infix_type, is_integer, is_pointer_compatible, is_string_buffer, unary_type, DataType};
+
+// string literals are laid out from here, so none of them sits at the null address
+pub const DATA_START: u32 = 1024;
+const PAGE_SIZE: u32 = 65536;
+// the stack the frames of the variables whose address is taken grow down in, after the data
+const STACK_SIZE: u32 = 65536;
+// every such variable takes a cell of this size, which fits and aligns any value type
+const CELL_SIZE: u32 = 8;
+
+// Lowers a folded AST to a WebAssembly module in the text format. Integers up to 32 bits
+// live in an `i32`, sign- or zero-extended from their own width, and 64-bit ones in an `i64`;
+// strings are `i32` addresses into the module's memory. `extern` functions are imported from
+// `env`, and every function the program defines is exported under its own name. A variable whose
+// address is taken lives in a cell of its function's frame in memory instead of a local, and
+// `$sp` points to the last frame; the host allocates whatever it needs beyond the initial memory.
+pub struct WasmGen {
+    ast: AST,
+    entry: Option<Entry>,
+    functions: HashMap<Identifier, (Vec<DataType>, DataType)>,
+    globals: HashMap<Identifier, DataType>,
+    // the data segment and the address of every string literal in it
+    data: Vec<u8>,
+    strings: HashMap<String, u32>,
+    label_idx: u64,
+    // local names and types of the variables of the function being generated, innermost scope last
+    scopes: Vec<HashMap<Identifier, (String, DataType)>>,
+    // locals declared by `let`, which wasm wants at the top of the function
+    locals: Vec<(String, DataType)>,
+    taken: HashSet<String>,
+    // the variables of the function whose address is taken, and the offset below `$.frame` of the
+    // cell each of them got
+    addressed: HashSet<Identifier>,
+    cells: HashMap<String, u32>,
+    // whether any function has a frame, which needs `$sp` and the stack
+    stack: bool,
+    // name and return type of the function being generated
+    current: (Identifier, DataType),
+}
+
+impl WasmGen {
+    pub fn new(ast: AST) -> WasmGen {
+        WasmGen {
+            ast,
+            entry: None,
+            functions: HashMap::new(),
+            globals: HashMap::new(),
+            data: Vec::new(),
+            strings: HashMap::new(),
+            label_idx: 0,
+            scopes: Vec::new(),
+            locals: Vec::new(),
+            taken: HashSet::new(),
+            addressed: HashSet::new(),
+            cells: HashMap::new(),
+            stack: false,
+            current: (String::new(), DataType::void),
+        }
+    }
+
+    // checks the program's `main`; a freestanding entry adds a WASI `_start` that exits with its result
+    pub fn with_entry(mut self, entry: Entry) -> WasmGen {
+        self.entry = Some(entry);
+        self
+    }
+
+    pub fn generate_wat(mut self) -> Result<String, CodegenError> {
+        let ast = std::mem::take(&mut self.ast);
+
+        for stmt in &ast {
+            match stmt {
+                Statement::Def(stmt) if !stmt.type_params.is_empty() => return Err(CodegenError(format!("[Wasm] generic function `{}` is not supported", stmt.name))),
+                Statement::Def(stmt) => {
+                    self.functions.insert(stmt.name.clone(), (stmt.params.iter().map(|(_, dtype)| *dtype).collect(), stmt.r#type));
+                },
+                Statement::Extern(stmt) => {
+                    self.functions.insert(stmt.name.clone(), (stmt.params.iter().map(|(_, dtype)| *dtype).collect(), stmt.r#type));
+                },
+                Statement::Let(stmt) => {
+                    self.globals.insert(stmt.ident.clone(), stmt.r#type);
+                },
+                // a declaration only introduces a type, which the values using it are checked for
+                Statement::Struct(_) | Statement::Enum(_) => {},
+                _ => return Err(CodegenError(format!("[Wasm] {} cannot be global", stmt))),
+            }
+        }
+
+        // imports must come before everything else the module defines
+        let mut imports = String::new();
+
+        for stmt in &ast {
+            if let Statement::Extern(stmt) = stmt {
+                let params = stmt.params.iter().map(|(_, dtype)| *dtype).collect::<Vec<DataType>>();
+                imports += &format!("  (import \"env\" \"{}\" (func ${}{}))\n", stmt.name, stmt.name, signature(&params, stmt.r#type, &stmt.name)?);
+            }
+        }
+
+        let main = match self.entry {
+            Some(_) => Some(find_main(&ast).map_err(|err| CodegenError(err.0))?),
+            None => None,
+        };
+
+        if self.entry == Some(Entry::Freestanding) {
+            imports += "  (import \"wasi_snapshot_preview1\" \"proc_exit\" (func $proc_exit (param i32)))\n";
+        }
+
+        let mut body = String::new();
+
+        for stmt in &ast {
+            match stmt {
+                Statement::Let(stmt) => body += &self.generate_global(stmt)?,
+                Statement::Def(stmt) => body += &self.generate_def(stmt)?,
+                _ => {},
+            }
+        }
+
+        if let (Some(Entry::Freestanding), Some(main)) = (self.entry, main) {
+            body += "  (func $_start (export \"_start\")\n";
+            body += if main.r#type == DataType::void { "    call $main\n    i32.const 0\n" } else { "    call $main\n" };
+            body += "    call $proc_exit\n  )\n";
+        }
+
+        let end = DATA_START + self.data.len() as u32;
+        let top = end.next_multiple_of(CELL_SIZE) + STACK_SIZE;
+
+        let pages = if self.stack { top } else { end }.div_ceil(PAGE_SIZE);
+        let mut result = format!("(module\n{imports}  (memory (export \"memory\") {pages})\n");
+
+        if !self.data.is_empty() {
+            result += &format!("  (data (i32.const {}) \"{}\")\n", DATA_START, escape(&self.data));
+        }
+
+        if self.stack {
+            result += &format!("  (global $sp (mut i32) (i32.const {}))\n", top);
+        }
+
+        result += &body;
+        result += ")\n";
+
+        Ok(result)
+    }
+
+    fn get_label(&mut self) -> u64 {
+        self.label_idx += 1;
+        self.label_idx - 1
+    }
+
+    // the address of `string` in the data segment, where it is stored null-terminated once
+    fn intern(&mut self, string: &str) -> u32 {
+        if let Some(address) = self.strings.get(string) {
+            return *address;
+        }
+
+        let address = DATA_START + self.data.len() as u32;
+        self.data.extend(string.bytes());
+        self.data.push(0);
+        self.strings.insert(string.into(), address);
+
+        address
+    }
+
+    // picks a local for a new variable `ident`, shadowed variables keep theirs
+    fn bind(&mut self, ident: &Identifier, dtype: DataType) -> String {
+        let mut name = ident.clone();
+        let mut n = 0;
+
+        while self.taken.contains(&name) {
+            n += 1;
+            name = format!("{}.{}", ident, n);
+        }
+
+        self.taken.insert(name.clone());
+
+        if self.addressed.contains(ident) {
+            self.cells.insert(name.clone(), (self.cells.len() as u32 + 1) * CELL_SIZE);
+        } else {
+            self.locals.push((name.clone(), dtype));
+        }
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(ident.clone(), (name.clone(), dtype));
+        }
+
+        name
+    }
+
+    fn generate_global(&mut self, stmt: &LetStatement) -> Result<String, CodegenError> {
+        let ident = &stmt.ident;
+        let r#type = value_type(stmt.r#type, ident)?;
+
+        let value = match &stmt.expr {
+            Expression::Literal((Literal::String(string), _)) if stmt.r#type == DataType::str => self.intern(string) as i64,
+            Expression::Literal((Literal::Boolean(b), _)) if stmt.r#type == DataType::bool => *b as i64,
+            Expression::Literal((literal @ (Literal::SignedInteger(_) | Literal::UnsignedInteger(_)), _)) if is_integer(stmt.r#type) => {
+                representation(literal_value(literal).0, stmt.r#type)
+            },
+            _ => return Err(CodegenError(format!("[Wasm] global `{}` must be initialized with a constant of type {}", ident, stmt.r#type))),
+        };
+
+        Ok(format!("  (global ${ident} {type} ({type}.const {value}))\n", r#type = r#type))
+    }
+
+    fn generate_def(&mut self, stmt: &DefStatement) -> Result<String, CodegenError> {
+        self.current = (stmt.name.clone(), stmt.r#type);
+        self.scopes = vec![HashMap::new()];
+        self.locals = Vec::new();
+        self.taken = stmt.params.iter().map(|(ident, _)| ident.clone()).collect();
+        self.addressed = addressed_variables(&stmt.stmts);
+        self.cells = HashMap::new();
+
+        let mut code = String::new();
+
+        for (ident, dtype) in &stmt.params {
+            value_type(*dtype, ident)?;
+            self.scopes[0].insert(ident.clone(), (ident.clone(), *dtype));
+
+            // the host may pass any bits above a narrow width
+            if needs_extension(*dtype) {
+                emit(&mut code, 2, format!("local.get ${}", ident));
+                normalize(&mut code, 2, *dtype);
+                emit(&mut code, 2, format!("local.set ${}", ident));
+            }
+
+            // a parameter whose address is taken is copied to a cell, which it is read from afterwards
+            if self.addressed.contains(ident) {
+                let name = self.bind(ident, *dtype);
+
+                self.address(&name, 2, &mut code);
+                emit(&mut code, 2, format!("local.get ${}", ident));
+                emit(&mut code, 2, store(*dtype));
+            }
+        }
+
+        self.generate_block(&stmt.stmts, 2, &mut code)?;
+
+        // like LLVM's `unreachable`, falling off the end of a function with a value traps
+        if stmt.r#type != DataType::void {
+            emit(&mut code, 2, "unreachable");
+        } else if !self.addressed.is_empty() {
+            emit(&mut code, 2, "local.get $.frame");
+            emit(&mut code, 2, "global.set $sp");
+        }
+
+        // the frame is only allocated once its size is known
+        if !self.addressed.is_empty() {
+            let mut prologue = String::new();
+
+            emit(&mut prologue, 2, "global.get $sp");
+            emit(&mut prologue, 2, "local.tee $.frame");
+            emit(&mut prologue, 2, format!("i32.const {}", self.cells.len() as u32 * CELL_SIZE));
+            emit(&mut prologue, 2, "i32.sub");
+            emit(&mut prologue, 2, "global.set $sp");
+
+            code = prologue + &code;
+            self.locals.push((".frame".into(), DataType::pointer(DataType::void)));
+            self.stack = true;
+        }
+
+        let params = stmt.params.iter()
+            .map(|(ident, dtype)| Ok(format!(" (param ${} {})", ident, value_type(*dtype, ident)?)))
+            .collect::<Result<String, CodegenError>>()?;
+        let result = match stmt.r#type {
+            DataType::void => String::new(),
+            dtype => format!(" (result {})", value_type(dtype, &stmt.name)?),
+        };
+
+        let mut function = format!("  (func ${name} (export \"{name}\"){params}{result}\n", name = stmt.name);
+
+        for (name, dtype) in &self.locals {
+            function += &format!("    (local ${} {})\n", name, value_type(*dtype, name)?);
+        }
+
+        function += &code;
+        function += "  )\n";
+
+        Ok(function)
+    }
+
+    // variables declared in `stmts` are only visible until the end of the block
+    fn generate_block(&mut self, stmts: &[Statement], depth: usize, code: &mut String) -> Result<(), CodegenError> {
+        self.scopes.push(HashMap::new());
+
+        for stmt in stmts {
+            self.generate_stmt(stmt, depth, code)?;
+        }
+
+        self.scopes.pop();
+        Ok(())
+    }
+
+    fn generate_stmt(&mut self, stmt: &Statement, depth: usize, code: &mut String) -> Result<(), CodegenError> {
+        match stmt {
+            // a cell takes the address below the value it stores
+            Statement::Let(stmt) if self.addressed.contains(&stmt.ident) => {
+                value_type(stmt.r#type, &stmt.ident)?;
+                emit(code, depth, "local.get $.frame");
+                emit(code, depth, format!("i32.const {}", (self.cells.len() as u32 + 1) * CELL_SIZE));
+                emit(code, depth, "i32.sub");
+
+                let dtype = self.generate_expr(&stmt.expr, depth, code)?;
+                coerce(code, depth, dtype, stmt.r#type, &stmt.ident)?;
+
+                self.bind(&stmt.ident, stmt.r#type);
+                emit(code, depth, store(stmt.r#type));
+            },
+            Statement::Let(stmt) => {
+                let dtype = self.generate_expr(&stmt.expr, depth, code)?;
+                coerce(code, depth, dtype, stmt.r#type, &stmt.ident)?;
+
+                // the initializer is generated first, so it still sees the variable it shadows
+                let name = self.bind(&stmt.ident, stmt.r#type);
+                value_type(stmt.r#type, &stmt.ident)?;
+                emit(code, depth, format!("local.set ${}", name));
+            },
+            Statement::If(stmt) => {
+                let end = self.get_label();
+
+                emit(code, depth, format!("block $end{}", end));
+                self.generate_if(stmt, end, depth + 1, code)?;
+                emit(code, depth, "end");
+            },
+            // the loop is left through the block around it as soon as the condition is false
+            Statement::While(stmt) => {
+                let n = self.get_label();
+
+                emit(code, depth, format!("block $break{}", n));
+                emit(code, depth + 1, format!("loop $continue{}", n));
+                self.generate_condition(&stmt.condition, depth + 2, code)?;
+                emit(code, depth + 2, "i32.eqz");
+                emit(code, depth + 2, format!("br_if $break{}", n));
+
+                self.generate_block(&stmt.blocks, depth + 2, code)?;
+                emit(code, depth + 2, format!("br $continue{}", n));
+                emit(code, depth + 1, "end");
+                emit(code, depth, "end");
+            },
+            Statement::Return(stmt) => {
+                let (name, r#type) = self.current.clone();
+                let dtype = self.generate_expr(&stmt.expr, depth, code)?;
+
+                // a void function drops whatever it is asked to return
+                if r#type == DataType::void {
+                    if dtype != DataType::void {
+                        emit(code, depth, "drop");
+                    }
+                } else {
+                    coerce(code, depth, dtype, r#type, &name)?;
+                }
+
+                if !self.addressed.is_empty() {
+                    emit(code, depth, "local.get $.frame");
+                    emit(code, depth, "global.set $sp");
+                }
+
+                emit(code, depth, "return");
+            },
+            Statement::Expression(stmt) => {
+                if self.generate_expr(&stmt.expr, depth, code)? != DataType::void {
+                    emit(code, depth, "drop");
+                }
+            },
+            Statement::Match(_) => return Err(CodegenError("[Wasm] `match` is not supported".into())),
+            Statement::Destructure(_) => return Err(CodegenError("[Wasm] tuples are not supported".into())),
+            Statement::Def(_) | Statement::Extern(_) | Statement::Struct(_) | Statement::Enum(_) => return Err(CodegenError(format!("[Wasm] {} cannot be local", stmt))),
+            Statement::Assign(_) => return Err(CodegenError("[Wasm] arrays, pointers and structs are not supported".into())),
+        }
+
+        Ok(())
+    }
+
+    // every branch sits in a block of its own and skips to the end of the whole chain once it ran
+    fn generate_if(&mut self, stmt: &IfStatement, end: u64, depth: usize, code: &mut String) -> Result<(), CodegenError> {
+        let otherwise = self.get_label();
+
+        emit(code, depth, format!("block $else{}", otherwise));
+        self.generate_condition(&stmt.condition, depth + 1, code)?;
+        emit(code, depth + 1, "i32.eqz");
+        emit(code, depth + 1, format!("br_if $else{}", otherwise));
+        self.generate_block(&stmt.then, depth + 1, code)?;
+        emit(code, depth + 1, format!("br $end{}", end));
+        emit(code, depth, "end");
+
+        match stmt.r#else.as_ref() {
+            IfBranch::Elif(stmt) => self.generate_if(stmt, end, depth, code)?,
+            IfBranch::Else(stmts) => self.generate_block(stmts, depth, code)?,
+            IfBranch::None => {},
+        }
+
+        Ok(())
+    }
+
+    fn generate_condition(&mut self, expr: &Expression, depth: usize, code: &mut String) -> Result<(), CodegenError> {
+        match self.generate_expr(expr, depth, code)? {
+            DataType::bool => Ok(()),
+            dtype => Err(CodegenError(format!("[Wasm] expected a bool condition, found {}", dtype))),
+        }
+    }
+
+    // emits the code leaving the value of `expr` on the operand stack and returns its type
+    fn generate_expr(&mut self, expr: &Expression, depth: usize, code: &mut String) -> Result<DataType, CodegenError> {
+        match expr {
+            Expression::Unary(expr) if expr.operator == Operator::AddressOf => {
+                let (name, dtype) = self.addressed_variable(&expr.right)?;
+                self.address(&name, depth, code);
+                Ok(DataType::pointer(dtype))
+            },
+            Expression::Unary(expr) if expr.operator == Operator::Dereference => Err(CodegenError(format!("[Wasm] operator {:?} is not supported", expr.operator))),
+            Expression::Struct(_) | Expression::Member(_) | Expression::MethodCall(_) => Err(CodegenError("[Wasm] structs are not supported".into())),
+            Expression::Enum(_) => Err(CodegenError("[Wasm] enums are not supported".into())),
+            Expression::Tuple(_) => Err(CodegenError("[Wasm] tuples are not supported".into())),
+            Expression::FormatString(expr) => {
+                let types = expr.values()
+                    .map(|value| self.type_of(value))
+                    .collect::<Result<Vec<DataType>, CodegenError>>()?;
+
+                self.generate_expr(&format::lower(expr, &types).map_err(|err| CodegenError(err.0))?, depth, code)
+            },
+            Expression::Literal((Literal::String(string), _)) => {
+                let address = self.intern(string);
+                emit(code, depth, format!("i32.const {}", address));
+                Ok(DataType::str)
+            },
+            Expression::Literal((literal, _)) => {
+                let (value, dtype) = literal_value(literal);
+                emit(code, depth, format!("{}.const {}", value_type(dtype, "a literal")?, representation(value, dtype)));
+                Ok(dtype)
+            },
+            Expression::Identifier(ident) => {
+                if let Some((name, dtype)) = self.scopes.iter().rev().find_map(|scope| scope.get(ident)).cloned() {
+                    if self.cells.contains_key(&name) {
+                        self.address(&name, depth, code);
+                        emit(code, depth, load(dtype));
+                    } else {
+                        emit(code, depth, format!("local.get ${}", name));
+                    }
+
+                    Ok(dtype)
+                } else if let Some(&dtype) = self.globals.get(ident) {
+                    emit(code, depth, format!("global.get ${}", ident));
+                    Ok(dtype)
+                } else {
+                    Err(CodegenError(format!("[Wasm] identifier `{}` not found", ident)))
+                }
+            },
+            Expression::Unary(expr) => {
+                // `0 - x` and `x ^ -1` need their constant below the operand
+                let r#type = if expr.operator == Operator::UnaryMinus { Some(self.prefix_zero(&expr.right, depth, code)?) } else { None };
+                let dtype = self.generate_expr(&expr.right, depth, code)?;
+                let result = unary_type(expr.operator, dtype)
+                    .ok_or_else(|| CodegenError(format!("[Wasm] operator {:?} cannot be applied to {}", expr.operator, dtype)))?;
+                let r#type = r#type.unwrap_or(value_type(dtype, "an operand")?);
+
+                match expr.operator {
+                    Operator::UnaryMinus => emit(code, depth, format!("{}.sub", r#type)),
+                    Operator::BitwiseNot => {
+                        emit(code, depth, format!("{}.const -1", r#type));
+                        emit(code, depth, format!("{}.xor", r#type));
+                    },
+                    _ => {},
+                }
+
+                normalize(code, depth, dtype);
+                Ok(result)
+            },
+            Expression::Infix(expr) => {
+                // the operands are converted to their common type right after each is computed
+                let (left, right) = (self.type_of(&expr.left)?, self.type_of(&expr.right)?);
+                let dtype = max(left, right);
+                let result = infix_type(expr.operator, dtype)
+                    .filter(|_| left == right || (is_integer(left) && is_integer(right)))
+                    .ok_or_else(|| CodegenError(format!("[Wasm] operator {:?} cannot be applied to {} and {}", expr.operator, left, right)))?;
+
+                self.generate_expr(&expr.left, depth, code)?;
+                cast(code, depth, left, dtype);
+                self.generate_expr(&expr.right, depth, code)?;
+                cast(code, depth, right, dtype);
+
+                infix(code, depth, expr.operator, dtype)?;
+                Ok(result)
+            },
+            Expression::Index(_) | Expression::Array(_) => Err(CodegenError("[Wasm] arrays are not supported".into())),
+            Expression::FnCall(expr) => {
+                let (params, r#type) = self.functions.get(&expr.ident).cloned()
+                    .ok_or_else(|| CodegenError(format!("[Wasm] unable to find function `{}`", expr.ident)))?;
+
+                if params.len() != expr.args.len() {
+                    return Err(CodegenError(format!("[Wasm] `{}` takes {} arguments but {} were given", expr.ident, params.len(), expr.args.len())));
+                }
+
+                for (arg, param) in expr.args.iter().zip(&params) {
+                    let dtype = self.generate_expr(arg, depth, code)?;
+                    coerce(code, depth, dtype, *param, &expr.ident)?;
+                }
+
+                emit(code, depth, format!("call ${}", expr.ident));
+
+                // only the bits of the returned type are defined when the host implements the function
+                if needs_extension(r#type) {
+                    normalize(code, depth, r#type);
+                }
+
+                Ok(r#type)
+            },
+        }
+    }
+
+    // pushes the zero that `expr` is subtracted from and returns the value type of both
+    fn prefix_zero(&mut self, expr: &Expression, depth: usize, code: &mut String) -> Result<&'static str, CodegenError> {
+        let r#type = match self.type_of(expr)? {
+            DataType::void | DataType::str | DataType::FloatingPoint(_) => "i32",
+            dtype => value_type(dtype, "an operand")?,
+        };
+
+        emit(code, depth, format!("{}.const 0", r#type));
+        Ok(r#type)
+    }
+
+    // the type of `expr` without generating it, for the operands that must be converted in place
+    fn type_of(&self, expr: &Expression) -> Result<DataType, CodegenError> {
+        match expr {
+            Expression::Unary(expr) if expr.operator == Operator::AddressOf => self.addressed_variable(&expr.right).map(|(_, dtype)| DataType::pointer(dtype)),
+            Expression::Unary(expr) if expr.operator == Operator::Dereference => Err(CodegenError(format!("[Wasm] operator {:?} is not supported", expr.operator))),
+            Expression::Struct(_) | Expression::Member(_) | Expression::MethodCall(_) => Err(CodegenError("[Wasm] structs are not supported".into())),
+            Expression::Enum(_) => Err(CodegenError("[Wasm] enums are not supported".into())),
+            Expression::Tuple(_) => Err(CodegenError("[Wasm] tuples are not supported".into())),
+            Expression::FormatString(expr) => Ok(expr.dtype()),
+            Expression::Literal((literal, _)) => Ok(literal_value(literal).1),
+            Expression::Identifier(ident) => self.scopes.iter().rev()
+                .find_map(|scope| scope.get(ident).map(|(_, dtype)| *dtype))
+                .or_else(|| self.globals.get(ident).copied())
+                .ok_or_else(|| CodegenError(format!("[Wasm] identifier `{}` not found", ident))),
+            Expression::Unary(expr) => {
+                let dtype = self.type_of(&expr.right)?;
+                unary_type(expr.operator, dtype)
+                    .ok_or_else(|| CodegenError(format!("[Wasm] operator {:?} cannot be applied to {}", expr.operator, dtype)))
+            },
+            Expression::Infix(expr) => {
+                let (left, right) = (self.type_of(&expr.left)?, self.type_of(&expr.right)?);
+                infix_type(expr.operator, max(left, right))
+                    .filter(|_| left == right || (is_integer(left) && is_integer(right)))
+                    .ok_or_else(|| CodegenError(format!("[Wasm] operator {:?} cannot be applied to {} and {}", expr.operator, left, right)))
+            },
+            Expression::Index(_) | Expression::Array(_) => Err(CodegenError("[Wasm] arrays are not supported".into())),
+            Expression::FnCall(expr) => self.functions.get(&expr.ident)
+                .map(|(_, r#type)| *r#type)
+                .ok_or_else(|| CodegenError(format!("[Wasm] unable to find function `{}`", expr.ident))),
+        }
+    }
+
+    // the cell and type of the local `expr` names; a global is not in memory, so its address cannot be taken
+    fn addressed_variable(&self, expr: &Expression) -> Result<(String, DataType), CodegenError> {
+        let ident = match expr {
+            Expression::Identifier(ident) => ident,
+            expr => return Err(CodegenError(format!("[Wasm] taking the address of {} is not supported", expr))),
+        };
+
+        match self.scopes.iter().rev().find_map(|scope| scope.get(ident)) {
+            Some((name, dtype)) if self.cells.contains_key(name) => Ok((name.clone(), *dtype)),
+            Some(_) => unreachable!("`addressed_variables` finds every local whose address is taken"),
+            None if self.globals.contains_key(ident) => Err(CodegenError(format!("[Wasm] taking the address of global `{}` is not supported", ident))),
+            None => Err(CodegenError(format!("[Wasm] identifier `{}` not found", ident))),
+        }
+    }
+
+    // pushes the address of the cell of the local `name`
+    fn address(&self, name: &str, depth: usize, code: &mut String) {
+        emit(code, depth, "local.get $.frame");
+        emit(code, depth, format!("i32.const {}", self.cells[name]));
+        emit(code, depth, "i32.sub");
+    }
+}
+
+// the wasm value type holding a `dtype`; 128-bit integers and floating point are not supported,
+// and pointers are addresses in the memory only the host functions read and write through them
+fn value_type(dtype: DataType, name: &str) -> Result<&'static str, CodegenError> {
+    match dtype {
+        DataType::bool | DataType::str => Ok("i32"),
+        DataType::Pointer(pointee) if *pointee == DataType::void || value_type(*pointee, name).is_ok() => Ok("i32"),
+        _ if is_wide(dtype) => Err(CodegenError(format!("[Wasm] {} of `{}` is not supported", dtype, name))),
+        DataType::SignedInteger(dtype) if dtype.bits() == 64 => Ok("i64"),
+        DataType::UnsignedInteger(dtype) if dtype.bits() == 64 => Ok("i64"),
+        DataType::SignedInteger(_) | DataType::UnsignedInteger(_) => Ok("i32"),
+        _ => Err(CodegenError(format!("[Wasm] {} of `{}` is not supported", dtype, name))),
+    }
+}
+
+// the instruction storing a `dtype` from its value type to the address below it
+fn store(dtype: DataType) -> &'static str {
+    match bits(dtype).0 {
+        8 => "i32.store8",
+        16 => "i32.store16",
+        64 => "i64.store",
+        _ => "i32.store",
+    }
+}
+
+// the instruction loading a `dtype` into its value type, extended from its own width
+fn load(dtype: DataType) -> &'static str {
+    match bits(dtype) {
+        (8, true) => "i32.load8_s",
+        (8, false) => "i32.load8_u",
+        (16, true) => "i32.load16_s",
+        (16, false) => "i32.load16_u",
+        (64, _) => "i64.load",
+        _ => "i32.load",
+    }
+}
+
+fn signature(params: &[DataType], r#type: DataType, name: &str) -> Result<String, CodegenError> {
+    let mut result = String::new();
+
+    if !params.is_empty() {
+        result += " (param";
+
+        for dtype in params {
+            result += " ";
+            result += value_type(*dtype, name)?;
+        }
+
+        result += ")";
+    }
+
+    if r#type != DataType::void {
+        result += &format!(" (result {})", value_type(r#type, name)?);
+    }
+
+    Ok(result)
+}
+
+fn bits(dtype: DataType) -> (u32, bool) {
+    match dtype {
+        DataType::SignedInteger(dtype) => (dtype.bits(), true),
+        DataType::UnsignedInteger(dtype) => (dtype.bits(), false),
+        DataType::bool => (8, false),
+        _ => (32, false),
+    }
+}
+
+// whether the value type holds bits of a `dtype` that are not part of its value
+fn needs_extension(dtype: DataType) -> bool {
+    bits(dtype).0 < 32
+}
+
+// the constant holding `value` as a `dtype` in its value type
+fn representation(value: i128, dtype: DataType) -> i64 {
+    match bits(dtype) {
+        (8, true) => value as i8 as i64,
+        (16, true) => value as i16 as i64,
+        (8, false) => value as u8 as i64,
+        (16, false) => value as u16 as i64,
+        (32, _) => value as i32 as i64,
+        _ => value as i64,
+    }
+}
+
+// brings the value on top of the stack back to the form of `dtype` after a full-width operation
+fn normalize(code: &mut String, depth: usize, dtype: DataType) {
+    match bits(dtype) {
+        (8, true) => emit(code, depth, "i32.extend8_s"),
+        (16, true) => emit(code, depth, "i32.extend16_s"),
+        (8, false) => {
+            emit(code, depth, "i32.const 255");
+            emit(code, depth, "i32.and");
+        },
+        (16, false) => {
+            emit(code, depth, "i32.const 65535");
+            emit(code, depth, "i32.and");
+        },
+        _ => {},
+    }
+}
+
+// truncates, sign- or zero-extends the integer on top of the stack from `from` to `to`
+fn cast(code: &mut String, depth: usize, from: DataType, to: DataType) {
+    if from == to {
+        return;
+    }
+
+    let ((from_bits, from_signed), (to_bits, _)) = (bits(from), bits(to));
+
+    if from_bits < 64 && to_bits == 64 {
+        emit(code, depth, if from_signed { "i64.extend_i32_s" } else { "i64.extend_i32_u" });
+    } else if from_bits == 64 && to_bits < 64 {
+        emit(code, depth, "i32.wrap_i64");
+    }
+
+    if to_bits < 32 {
+        normalize(code, depth, to);
+    }
+}
+
+// converts the value on top of the stack from `from` to `to`, which integers, pointers and `*u8` to `str` allow
+fn coerce(code: &mut String, depth: usize, from: DataType, to: DataType, name: &str) -> Result<(), CodegenError> {
+    if from == to || is_pointer_compatible(from, to) || is_string_buffer(from, to) {
+        Ok(())
+    } else if is_integer(from) && is_integer(to) {
+        cast(code, depth, from, to);
+        Ok(())
+    } else {
+        Err(CodegenError(format!("[Wasm] `{}` expects {}, found {}", name, to, from)))
+    }
+}
+
+fn infix(code: &mut String, depth: usize, operator: Operator, dtype: DataType) -> Result<(), CodegenError> {
+    let r#type = value_type(dtype, "an operand")?;
+    let (_, signed) = bits(dtype);
+    let sign = if signed { "s" } else { "u" };
+
+    let instr = match operator {
+        Operator::Plus => "add".into(),
+        Operator::Minus => "sub".into(),
+        Operator::Multiply => "mul".into(),
+        // both operands are extended to the full width, so its division gives the narrow result
+        Operator::Divide => format!("div_{sign}"),
+        Operator::Modulo => format!("rem_{sign}"),
+        Operator::BitwiseAnd => "and".into(),
+        Operator::BitwiseOr => "or".into(),
+        Operator::BitwiseXor => "xor".into(),
+        Operator::LeftShift => "shl".into(),
+        Operator::RightShift => format!("shr_{sign}"),
+        Operator::Equal => "eq".into(),
+        Operator::NotEqual => "ne".into(),
+        Operator::Less => format!("lt_{sign}"),
+        Operator::LessEqual => format!("le_{sign}"),
+        Operator::Greater => format!("gt_{sign}"),
+        Operator::GreaterEqual => format!("ge_{sign}"),
+        _ => return Ok(()),
+    };
+
+    emit(code, depth, format!("{}.{}", r#type, instr));
+
+    if !matches!(operator, Operator::Equal | Operator::NotEqual | Operator::Less | Operator::LessEqual | Operator::Greater | Operator::GreaterEqual) {
+        normalize(code, depth, dtype);
+    }
+
+    Ok(())
+}
+
+// the body of a string in the text format, with everything outside printable ASCII as hex escapes
+fn escape(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| match byte {
+        b'"' => "\\\"".into(),
+        b'\\' => "\\\\".into(),
+        0x20..=0x7e => (*byte as char).to_string(),
+        _ => format!("\\{:02x}", byte),
+    }).collect()
+}
+
+fn emit(code: &mut String, depth: usize, instr: impl AsRef<str>) {
+    code.push_str(&"  ".repeat(depth));
+    code.push_str(instr.as_ref());
+    code.push('\n');
+}
\ No newline at end of file
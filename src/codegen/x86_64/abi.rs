@@ -0,0 +1,44 @@
+use crate::codegen::asm::is_wide;
+use crate::codegen::regalloc::{PhysReg, TargetRegisters};
+use crate::types::DataType;
+
+pub use crate::codegen::native::ArgLoc;
+
+// The code generator works in `rax`, `rcx`, `rdx`, `r8` and the argument registers, which
+// leaves `r10`, `r11` and the callee-saved registers to the values the allocator places.
+pub const REGISTERS: TargetRegisters = TargetRegisters {
+    names: &["rax", "rcx", "rdx", "rbx", "rsp", "rbp", "rsi", "rdi", "r8", "r9", "r10", "r11", "r12", "r13", "r14", "r15"],
+    allocatable: &[PhysReg(10), PhysReg(11), PhysReg(3), PhysReg(12), PhysReg(13), PhysReg(14), PhysReg(15)],
+    caller_saved: &[PhysReg(0), PhysReg(1), PhysReg(2), PhysReg(6), PhysReg(7), PhysReg(8), PhysReg(9), PhysReg(10), PhysReg(11)],
+};
+
+// integer argument registers in the order the System V ABI assigns them
+pub const ARG_REGISTERS: [&str; 6] = ["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
+
+// assigns every parameter a location under the System V calling convention and returns them
+// with the size of the stack area the call needs
+pub fn assign(params: &[DataType]) -> (Vec<ArgLoc>, i64) {
+    let mut next = 0;
+    let mut stack = 0;
+
+    let locs = params.iter().map(|dtype| {
+        if is_wide(*dtype) {
+            // a 128-bit value is never split, it goes onto the stack when two registers are not left
+            if next + 2 <= ARG_REGISTERS.len() {
+                next += 2;
+                ArgLoc::Pair(next - 2)
+            } else {
+                stack = (stack + 15) / 16 * 16 + 16;
+                ArgLoc::Stack(stack - 16)
+            }
+        } else if next < ARG_REGISTERS.len() {
+            next += 1;
+            ArgLoc::Reg(next - 1)
+        } else {
+            stack += 8;
+            ArgLoc::Stack(stack - 8)
+        }
+    }).collect();
+
+    (locs, (stack + 15) / 16 * 16)
+}
\ No newline at end of file
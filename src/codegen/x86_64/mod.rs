@@ -0,0 +1,186 @@
+pub mod abi;
+pub mod ops;
+
+use crate::codegen::asm::{emit, is_wide};
+use crate::codegen::llvm::entry::{Entry, MAIN_SYMBOL};
+use crate::codegen::native::{ArgLoc, Machine, NativeGen};
+use crate::codegen::regalloc::TargetRegisters;
+use crate::parser::Operator;
+use crate::types::DataType;
+
+// Lowers a folded AST to x86-64 assembly in AT&T syntax for the GNU assembler, following the
+// System V ABI.
+pub type X86Gen = NativeGen<X86_64>;
+
+// Values are computed in `rax`/`rdx` and frame slots are addressed from `rbp`.
+pub struct X86_64;
+
+impl Machine for X86_64 {
+    const NAME: &'static str = "x86-64";
+    const REGISTERS: TargetRegisters = abi::REGISTERS;
+    const ARG_REGISTERS: &'static [&'static str] = &abi::ARG_REGISTERS;
+    const RESULT: (&'static str, &'static str) = ("rax", "rdx");
+    const OPERAND: (&'static str, &'static str) = ("rcx", "r8");
+    const SCRATCH: &'static str = "rax";
+    const FRAME_HEADER: i64 = 0;
+    // stack arguments start above the saved `rbp` and the return address
+    const STACK_ARGS: i64 = 16;
+    const FUNCTION_ALIGN: u32 = 4;
+    const JUMP: &'static str = "jmp";
+    const TRAP: &'static str = "ud2";
+    // the stack stays non-executable when the object is linked
+    const TRAILER: &'static str = "\n    .section .note.GNU-stack,\"\",@progbits\n";
+
+    fn assign(params: &[DataType]) -> (Vec<ArgLoc>, i64) {
+        abi::assign(params)
+    }
+
+    fn move_reg(code: &mut String, to: &str, from: &str) {
+        emit(code, format!("movq %{from}, %{to}"));
+    }
+
+    fn load(code: &mut String, reg: &str, offset: i64) {
+        emit(code, format!("movq {offset}(%rbp), %{reg}"));
+    }
+
+    fn store(code: &mut String, reg: &str, offset: i64) {
+        emit(code, format!("movq %{reg}, {offset}(%rbp)"));
+    }
+
+    fn store_arg(code: &mut String, reg: &str, offset: i64) {
+        emit(code, format!("movq %{reg}, {offset}(%rsp)"));
+    }
+
+    fn adjust_sp(code: &mut String, amount: i64) {
+        if amount < 0 {
+            emit(code, format!("subq ${}, %rsp", -amount));
+        } else {
+            emit(code, format!("addq ${amount}, %rsp"));
+        }
+    }
+
+    fn load_immediate(code: &mut String, reg: &str, value: i64) {
+        ops::load_immediate(code, reg, value);
+    }
+
+    fn load_address(code: &mut String, reg: &str, symbol: &str) {
+        emit(code, format!("leaq {symbol}(%rip), %{reg}"));
+    }
+
+    fn load_global(code: &mut String, symbol: &str, dtype: DataType) {
+        emit(code, format!("{} {}(%rip), {}", load_instr(dtype), symbol, if Self::needs_extension(dtype) && !ops::is_signed(dtype) { "%eax" } else { "%rax" }));
+
+        if is_wide(dtype) {
+            emit(code, format!("movq {}+8(%rip), %rdx", symbol));
+        }
+    }
+
+    fn branch_if_zero(code: &mut String) -> &'static str {
+        emit(code, "testq %rax, %rax");
+        "je"
+    }
+
+    // external functions are reached through the PLT when linked into a position-independent executable
+    fn call(code: &mut String, symbol: &str) {
+        emit(code, format!("call {symbol}@PLT"));
+    }
+
+    fn prologue(code: &mut String, locals: i64) {
+        emit(code, "pushq %rbp");
+        emit(code, "movq %rsp, %rbp");
+
+        if locals > 0 {
+            emit(code, format!("subq ${locals}, %rsp"));
+        }
+    }
+
+    fn epilogue(code: &mut String) {
+        emit(code, "leave");
+        emit(code, "ret");
+    }
+
+    fn entry(code: &mut String, entry: Entry, r#type: DataType) {
+        match entry {
+            Entry::C => {
+                // the return address leaves the stack 8 bytes off the alignment calls need
+                emit(code, "subq $8, %rsp");
+                emit(code, format!("call {}", MAIN_SYMBOL));
+
+                if r#type == DataType::void {
+                    emit(code, "xorl %eax, %eax");
+                }
+
+                emit(code, "addq $8, %rsp");
+                emit(code, "ret");
+            },
+            // the kernel enters `_start` with a 16-byte aligned stack and no return address
+            Entry::Freestanding => {
+                emit(code, format!("call {}", MAIN_SYMBOL));
+
+                if r#type == DataType::void {
+                    emit(code, "xorl %edi, %edi");
+                } else {
+                    emit(code, "movq %rax, %rdi");
+                }
+
+                // the exit system call
+                emit(code, "movl $60, %eax");
+                emit(code, "syscall");
+            },
+        }
+    }
+
+    fn directive(bytes: u32) -> &'static str {
+        match bytes {
+            1 => ".byte",
+            2 => ".short",
+            4 => ".long",
+            _ => ".quad",
+        }
+    }
+
+    // the ABI leaves the upper bits of narrow arguments and return values undefined
+    fn needs_extension(dtype: DataType) -> bool {
+        match dtype {
+            DataType::SignedInteger(dtype) => dtype.bits() < 64,
+            DataType::UnsignedInteger(dtype) => dtype.bits() < 64,
+            DataType::bool => true,
+            _ => false,
+        }
+    }
+
+    fn normalize(code: &mut String, dtype: DataType) {
+        ops::normalize(code, dtype);
+    }
+
+    fn registers(value: i128, dtype: DataType) -> (i64, i64) {
+        ops::registers(value, dtype)
+    }
+
+    fn cast(code: &mut String, from: DataType, to: DataType) {
+        ops::cast(code, from, to);
+    }
+
+    fn unary(code: &mut String, operator: Operator, dtype: DataType) {
+        ops::unary(code, operator, dtype);
+    }
+
+    fn infix(code: &mut String, operator: Operator, dtype: DataType) {
+        ops::infix(code, operator, dtype);
+    }
+
+    fn calls_runtime(operator: Operator, dtype: DataType) -> bool {
+        ops::calls_runtime(operator, dtype)
+    }
+}
+
+// the instruction loading a global of `dtype` and extending it to the form its register holds it in
+fn load_instr(dtype: DataType) -> &'static str {
+    match dtype {
+        DataType::SignedInteger(dtype) => ["movsbq", "movswq", "movslq", "movq"][dtype.bits().trailing_zeros() as usize - 3],
+        // writing the 32-bit register zero-extends into the whole of `rax`
+        DataType::UnsignedInteger(dtype) => ["movzbl", "movzwl", "movl", "movq"][dtype.bits().trailing_zeros() as usize - 3],
+        DataType::bool => "movzbl",
+        _ => "movq",
+    }
+}
\ No newline at end of file
@@ -0,0 +1,253 @@
+use crate::codegen::asm::{emit, is_wide};
+use crate::parser::Operator;
+use crate::types::DataType;
+
+// Integers narrower than 64 bits live in `rax` sign- or zero-extended by their signedness,
+// which is also what callers may rely on after a call. 128-bit values take `rax` (low) and
+// `rdx` (high). Operations take their left operand in `rax`/`rdx` and their right one in
+// `rcx`/`r8`, leave the result in `rax`/`rdx` and only clobber caller-saved registers.
+
+fn layout(dtype: DataType) -> (u32, bool) {
+    match dtype {
+        DataType::SignedInteger(dtype) => (dtype.bits(), true),
+        DataType::UnsignedInteger(dtype) => (dtype.bits(), false),
+        DataType::bool => (8, false),
+        _ => (64, false),
+    }
+}
+
+pub fn is_signed(dtype: DataType) -> bool {
+    layout(dtype).1
+}
+
+// brings `rax` back to the form of `dtype` after a 64-bit operation
+pub fn normalize(code: &mut String, dtype: DataType) {
+    match layout(dtype) {
+        (8, true) => emit(code, "movsbq %al, %rax"),
+        (16, true) => emit(code, "movswq %ax, %rax"),
+        (32, true) => emit(code, "movslq %eax, %rax"),
+        (8, false) => emit(code, "movzbl %al, %eax"),
+        (16, false) => emit(code, "movzwl %ax, %eax"),
+        // writing a 32-bit register clears the upper half
+        (32, false) => emit(code, "movl %eax, %eax"),
+        _ => {},
+    }
+}
+
+// bit pattern of the registers holding `value` as a `dtype`, high half last
+pub fn registers(value: i128, dtype: DataType) -> (i64, i64) {
+    match layout(dtype) {
+        (128, _) => (value as i64, (value >> 64) as i64),
+        (8, true) => (value as i8 as i64, 0),
+        (16, true) => (value as i16 as i64, 0),
+        (32, true) => (value as i32 as i64, 0),
+        (8, false) => (value as u8 as i64, 0),
+        (16, false) => (value as u16 as i64, 0),
+        (32, false) => (value as u32 as i64, 0),
+        _ => (value as i64, 0),
+    }
+}
+
+// `mov` only takes a 64-bit immediate in its `movabs` form
+pub fn load_immediate(code: &mut String, reg: &str, value: i64) {
+    if i32::try_from(value).is_ok() {
+        emit(code, format!("movq ${value}, %{reg}"));
+    } else {
+        emit(code, format!("movabsq ${value}, %{reg}"));
+    }
+}
+
+// truncates, sign- or zero-extends the integer in `rax`/`rdx` from `from` to `to`
+pub fn cast(code: &mut String, from: DataType, to: DataType) {
+    let (from_bits, from_signed) = layout(from);
+    let (to_bits, _) = layout(to);
+
+    if from == to {
+        return;
+    }
+
+    if to_bits == 128 && from_bits < 128 {
+        if from_signed {
+            emit(code, "cqto");
+        } else {
+            emit(code, "xorl %edx, %edx");
+        }
+    }
+
+    if to_bits < 64 {
+        normalize(code, to);
+    }
+}
+
+pub fn unary(code: &mut String, operator: Operator, dtype: DataType) {
+    match operator {
+        Operator::UnaryMinus if is_wide(dtype) => {
+            emit(code, "negq %rax");
+            emit(code, "adcq $0, %rdx");
+            emit(code, "negq %rdx");
+        },
+        Operator::UnaryMinus => {
+            emit(code, "negq %rax");
+            normalize(code, dtype);
+        },
+        Operator::BitwiseNot => {
+            emit(code, "notq %rax");
+
+            if is_wide(dtype) {
+                emit(code, "notq %rdx");
+            } else {
+                normalize(code, dtype);
+            }
+        },
+        _ => {},
+    }
+}
+
+pub fn infix(code: &mut String, operator: Operator, dtype: DataType) {
+    if is_wide(dtype) {
+        return infix_wide(code, operator, is_signed(dtype));
+    }
+
+    let (bits, signed) = layout(dtype);
+
+    match operator {
+        Operator::Plus => emit(code, "addq %rcx, %rax"),
+        Operator::Minus => emit(code, "subq %rcx, %rax"),
+        Operator::Multiply => emit(code, "imulq %rcx, %rax"),
+        Operator::BitwiseAnd => emit(code, "andq %rcx, %rax"),
+        Operator::BitwiseOr => emit(code, "orq %rcx, %rax"),
+        Operator::BitwiseXor => emit(code, "xorq %rcx, %rax"),
+        // both operands are extended to 64 bits, so a 64-bit division gives the narrow result
+        Operator::Divide | Operator::Modulo => {
+            if signed {
+                emit(code, "cqto");
+                emit(code, "idivq %rcx");
+            } else {
+                emit(code, "xorl %edx, %edx");
+                emit(code, "divq %rcx");
+            }
+
+            if operator == Operator::Modulo {
+                emit(code, "movq %rdx, %rax");
+            }
+        },
+        // 32-bit shifts mask their amount to 5 bits, the others to 6
+        Operator::LeftShift | Operator::RightShift => {
+            let instr = match (operator, signed) {
+                (Operator::LeftShift, _) => "shl",
+                (_, true) => "sar",
+                _ => "shr",
+            };
+
+            if bits == 32 {
+                emit(code, format!("{instr}l %cl, %eax"));
+            } else {
+                emit(code, format!("{instr}q %cl, %rax"));
+            }
+        },
+        _ => return compare(code, operator, signed),
+    }
+
+    if bits < 64 {
+        normalize(code, dtype);
+    }
+}
+
+// the condition code of `setcc` for a comparison of `left` against `right`
+fn condition(operator: Operator, signed: bool) -> &'static str {
+    match (operator, signed) {
+        (Operator::Equal, _) => "e",
+        (Operator::NotEqual, _) => "ne",
+        (Operator::Less, true) => "l",
+        (Operator::Less, false) => "b",
+        (Operator::Greater, true) => "g",
+        (Operator::Greater, false) => "a",
+        (Operator::LessEqual, true) => "le",
+        (Operator::LessEqual, false) => "be",
+        (Operator::GreaterEqual, true) => "ge",
+        (_, _) => "ae",
+    }
+}
+
+fn compare(code: &mut String, operator: Operator, signed: bool) {
+    if !matches!(operator, Operator::Equal | Operator::NotEqual | Operator::Less | Operator::Greater | Operator::LessEqual | Operator::GreaterEqual) {
+        return;
+    }
+
+    emit(code, "cmpq %rcx, %rax");
+    emit(code, format!("set{} %al", condition(operator, signed)));
+    emit(code, "movzbl %al, %eax");
+}
+
+fn infix_wide(code: &mut String, operator: Operator, signed: bool) {
+    match operator {
+        Operator::Plus => {
+            emit(code, "addq %rcx, %rax");
+            emit(code, "adcq %r8, %rdx");
+        },
+        Operator::Minus => {
+            emit(code, "subq %rcx, %rax");
+            emit(code, "sbbq %r8, %rdx");
+        },
+        // the low halves multiply into 128 bits, the cross products only reach the high half
+        Operator::Multiply => {
+            emit(code, "imulq %rcx, %rdx");
+            emit(code, "imulq %rax, %r8");
+            emit(code, "addq %rdx, %r8");
+            emit(code, "mulq %rcx");
+            emit(code, "addq %r8, %rdx");
+        },
+        // the rest is left to the compiler runtime, which takes the shift amount as an `int`
+        Operator::Divide => libcall(code, if signed { "__divti3" } else { "__udivti3" }),
+        Operator::Modulo => libcall(code, if signed { "__modti3" } else { "__umodti3" }),
+        Operator::LeftShift => libcall(code, "__ashlti3"),
+        Operator::RightShift => libcall(code, if signed { "__ashrti3" } else { "__lshrti3" }),
+        Operator::BitwiseAnd | Operator::BitwiseOr | Operator::BitwiseXor => {
+            let instr = match operator {
+                Operator::BitwiseAnd => "and",
+                Operator::BitwiseOr => "or",
+                _ => "xor",
+            };
+
+            emit(code, format!("{instr}q %rcx, %rax"));
+            emit(code, format!("{instr}q %r8, %rdx"));
+        },
+        Operator::Equal | Operator::NotEqual => {
+            emit(code, "xorq %rcx, %rax");
+            emit(code, "xorq %r8, %rdx");
+            emit(code, "orq %rdx, %rax");
+            emit(code, format!("set{} %al", condition(operator, signed)));
+            emit(code, "movzbl %al, %eax");
+        },
+        // a 128-bit subtraction sets the flags of the comparison, `a > b` is computed as `b < a`
+        Operator::Less | Operator::GreaterEqual => {
+            emit(code, "cmpq %rcx, %rax");
+            emit(code, "sbbq %r8, %rdx");
+            emit(code, format!("set{} %al", condition(operator, signed)));
+            emit(code, "movzbl %al, %eax");
+        },
+        Operator::Greater | Operator::LessEqual => {
+            let swapped = if operator == Operator::Greater { Operator::Less } else { Operator::GreaterEqual };
+
+            emit(code, "cmpq %rax, %rcx");
+            emit(code, "sbbq %rdx, %r8");
+            emit(code, format!("set{} %al", condition(swapped, signed)));
+            emit(code, "movzbl %al, %eax");
+        },
+        _ => {},
+    }
+}
+
+// whether `infix` leaves `operator` to a function of the compiler runtime
+pub fn calls_runtime(operator: Operator, dtype: DataType) -> bool {
+    is_wide(dtype) && matches!(operator, Operator::Divide | Operator::Modulo | Operator::LeftShift | Operator::RightShift)
+}
+
+// calls a runtime function taking both operands, the left one in `rdi`/`rsi` and the right one in `rdx`/`rcx`
+fn libcall(code: &mut String, function: &str) {
+    emit(code, "movq %rax, %rdi");
+    emit(code, "movq %rdx, %rsi");
+    emit(code, "movq %rcx, %rdx");
+    emit(code, "movq %r8, %rcx");
+    emit(code, format!("call {function}@PLT"));
+}
\ No newline at end of file
@@ -0,0 +1,473 @@
+pub mod repl;
+pub mod toolchain;
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::codegen::c::CGen;
+use crate::codegen::llvm::entry::Entry;
+use crate::codegen::llvm::IRGen;
+use crate::codegen::riscv::RiscvGen;
+use crate::codegen::target::{Target, TARGETS};
+use crate::codegen::wasm::WasmGen;
+use crate::codegen::x86_64::X86Gen;
+use crate::error::DriverError;
+use crate::fold::ConstFolder;
+use crate::interp::Interpreter;
+use crate::module::Loader;
+use crate::lexer::{Lexer, Span, Token};
+use crate::parser::Parser;
+use crate::vm::{disassemble, Compiler, Program, Vm};
+
+pub const USAGE: &str = "\
+usage: mamba [options] [file] [-- program arguments]
+       mamba run [--vm] <file>
+
+Starts the REPL when no file is given. `run` interprets the program
+without invoking `llc` or a linker; with `--vm` it compiles it to
+bytecode first, and also accepts a `.mbc` file.
+
+x86_64 and riscv64 have native backends that generate assembly without
+`llc`; other targets, `--emit ir` and `-g` need the LLVM one. Unless
+`--backend` says otherwise, LLVM also builds what the native backends do
+not support, and any program with `-g` or `-O1` to `-O3`. The C
+backend translates the program to C99 and builds it with the C compiler.
+`--emit wat` writes a WebAssembly module in the text format instead.
+Programs for another machine are assembled and linked by its
+`<arch>-linux-gnu-` toolchain and run through `qemu-<arch>`.
+
+options:
+    -o <path>             write the output to <path>, `-` for stdout
+    --emit <stage>        tokens, ast, bytecode, mbc, ir, c, wat, asm, obj or exe
+                          (default: exe)
+    --run                 run the executable after building it
+    -O<level>             optimization level from 0 to 3 (default: 0)
+    --target <name>       x86_64, aarch64 or riscv64 (default: host)
+    --backend <name>      native, llvm or c (default: native when the target has one)
+    --entry <kind>        c or freestanding (default: c)
+    --linker <command>    linker to invoke (default: cc, ld for freestanding)
+    -I <dir>              look for imported modules in <dir> when the directory
+                          of the input file does not have them
+    -g                    emit debug information
+    --no-bounds-checks    do not check array indices at run time
+    -h, --help            print this message";
+
+// The last stage a compilation runs through; its result is what gets written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emit {
+    Tokens,
+    Ast,
+    // disassembled bytecode
+    Bytecode,
+    // bytecode serialized for `mamba run --vm`
+    Mbc,
+    Ir,
+    // C99 source for any C compiler
+    C,
+    // a WebAssembly module in the text format
+    Wat,
+    Asm,
+    Obj,
+    Exe,
+}
+
+impl Emit {
+    pub fn from_name(name: &str) -> Option<Emit> {
+        match name {
+            "tokens" => Some(Emit::Tokens),
+            "ast" => Some(Emit::Ast),
+            "bytecode" => Some(Emit::Bytecode),
+            "mbc" => Some(Emit::Mbc),
+            "ir" => Some(Emit::Ir),
+            "c" => Some(Emit::C),
+            "wat" => Some(Emit::Wat),
+            "asm" => Some(Emit::Asm),
+            "obj" => Some(Emit::Obj),
+            "exe" => Some(Emit::Exe),
+            _ => None,
+        }
+    }
+
+    // extension of the default output file, `None` for stages printed to stdout
+    fn extension(&self) -> Option<&'static str> {
+        match self {
+            Emit::Tokens | Emit::Ast | Emit::Bytecode => None,
+            Emit::Mbc => Some("mbc"),
+            Emit::Ir => Some("ll"),
+            Emit::C => Some("c"),
+            Emit::Wat => Some("wat"),
+            Emit::Asm => Some("s"),
+            Emit::Obj => Some("o"),
+            Emit::Exe => Some(""),
+        }
+    }
+}
+
+// What turns the program into assembly for `--emit asm`, `obj` and `exe`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Native,
+    Llvm,
+    // goes through C and the system C compiler
+    C,
+}
+
+impl Backend {
+    pub fn from_name(name: &str) -> Option<Backend> {
+        match name {
+            "native" => Some(Backend::Native),
+            "llvm" => Some(Backend::Llvm),
+            "c" => Some(Backend::C),
+            _ => None,
+        }
+    }
+
+    // the backend used when none is asked for
+    pub fn default_for(target: &Target) -> Backend {
+        if has_native_backend(target) {
+            Backend::Native
+        } else {
+            Backend::Llvm
+        }
+    }
+}
+
+fn has_native_backend(target: &Target) -> bool {
+    matches!(target.name, "x86_64" | "riscv64")
+}
+
+// How `mamba run` executes a program without building it first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Executor {
+    Interp,
+    Vm,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub input: Option<String>,
+    pub output: Option<String>,
+    pub emit: Emit,
+    pub run: bool,
+    pub opt_level: u32,
+    pub target: Target,
+    // `None` picks the default of the target
+    pub backend: Option<Backend>,
+    pub entry: Entry,
+    pub linker: Option<String>,
+    pub debug_info: bool,
+    // an index out of bounds panics instead of reading or writing past the array
+    pub bounds_checks: bool,
+    pub help: bool,
+    pub executor: Option<Executor>,
+    // everything after `--`, handed to the program by `--run`
+    pub program_args: Vec<String>,
+    // directories given by `-I`, in order
+    pub search_path: Vec<String>,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            input: None,
+            output: None,
+            emit: Emit::Exe,
+            run: false,
+            opt_level: 0,
+            target: Target::host(),
+            backend: None,
+            entry: Entry::C,
+            linker: None,
+            debug_info: false,
+            bounds_checks: true,
+            help: false,
+            executor: None,
+            program_args: Vec::new(),
+            search_path: Vec::new(),
+        }
+    }
+}
+
+impl Options {
+    // parses the command line without the program name; `--opt value` and `--opt=value` are both accepted
+    pub fn parse(args: &[String]) -> Result<Options, DriverError> {
+        let mut options = Options::default();
+        let mut args = args.iter().peekable();
+
+        if args.peek().map(|arg| arg.as_str()) == Some("run") {
+            args.next();
+            options.executor = Some(Executor::Interp);
+        }
+
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+
+            let mut value = |name: &str| -> Result<String, DriverError> {
+                inline_value.clone()
+                    .or_else(|| args.next().cloned())
+                    .ok_or_else(|| DriverError::Usage(format!("`{}` expects a value", name)))
+            };
+
+            match flag {
+                "--" => {
+                    options.program_args = args.by_ref().cloned().collect();
+                },
+                "-h" | "--help" => options.help = true,
+                "-g" => options.debug_info = true,
+                "--no-bounds-checks" => options.bounds_checks = false,
+                "--run" => options.run = true,
+                "--vm" => {
+                    if options.executor.is_none() {
+                        return Err(DriverError::Usage("`--vm` can only be used with `mamba run`".into()));
+                    }
+
+                    options.executor = Some(Executor::Vm);
+                },
+                "-o" => options.output = Some(value("-o")?),
+                "-I" => options.search_path.push(value("-I")?),
+                "--emit" => {
+                    let name = value("--emit")?;
+                    options.emit = Emit::from_name(&name)
+                        .ok_or_else(|| DriverError::Usage(format!("unknown stage `{}`, expected one of: tokens, ast, bytecode, mbc, ir, c, wat, asm, obj, exe", name)))?;
+                },
+                "--target" => {
+                    let name = value("--target")?;
+                    options.target = Target::from_name(&name)
+                        .ok_or_else(|| DriverError::Usage(format!("unknown target `{}`, expected one of: {}", name, TARGETS.iter().map(|target| target.name).collect::<Vec<&str>>().join(", "))))?;
+                },
+                "--backend" => {
+                    let name = value("--backend")?;
+                    options.backend = Some(Backend::from_name(&name)
+                        .ok_or_else(|| DriverError::Usage(format!("unknown backend `{}`, expected `native`, `llvm` or `c`", name)))?);
+                },
+                "--entry" => {
+                    let name = value("--entry")?;
+                    options.entry = Entry::from_name(&name)
+                        .ok_or_else(|| DriverError::Usage(format!("unknown entry `{}`, expected `c` or `freestanding`", name)))?;
+                },
+                "--linker" => options.linker = Some(value("--linker")?),
+                _ if flag.starts_with("-O") => {
+                    options.opt_level = match &flag[2..] {
+                        "0" => 0,
+                        "1" => 1,
+                        "2" => 2,
+                        "3" => 3,
+                        level => return Err(DriverError::Usage(format!("unknown optimization level `{}`, expected 0 to 3", level))),
+                    };
+                },
+                _ if flag.starts_with("-o") => options.output = Some(flag[2..].into()),
+                _ if flag.starts_with("-I") => options.search_path.push(flag[2..].into()),
+                _ if flag.starts_with('-') && flag != "-" => return Err(DriverError::Usage(format!("unknown option `{}`", arg))),
+                _ => {
+                    if options.input.is_some() {
+                        return Err(DriverError::Usage(format!("unexpected argument `{}`, only one input file is supported", arg)));
+                    }
+
+                    options.input = Some(arg.clone());
+                },
+            }
+        }
+
+        if options.run && options.emit != Emit::Exe {
+            return Err(DriverError::Usage("`--run` can only be combined with `--emit exe`".into()));
+        }
+
+        if options.backend == Some(Backend::Native) && !has_native_backend(&options.target) {
+            return Err(DriverError::Usage(format!("{} has no native backend, use `--backend llvm`", options.target.name)));
+        }
+
+        Ok(options)
+    }
+
+    // where the result goes: `None` means stdout
+    pub fn output_path(&self) -> Option<PathBuf> {
+        match self.output.as_deref() {
+            Some("-") => None,
+            Some(output) => Some(PathBuf::from(output)),
+            None => {
+                let extension = self.emit.extension()?;
+                let stem = self.input.as_deref()
+                    .and_then(|input| Path::new(input).file_stem())
+                    .map_or("out".into(), |stem| stem.to_string_lossy().to_string());
+
+                Some(PathBuf::from(stem).with_extension(extension))
+            },
+        }
+    }
+}
+
+// compiles `options.input` down to `options.emit` and returns the exit code the process should end with
+pub fn compile(options: &Options) -> Result<i32, DriverError> {
+    let input = options.input.as_deref()
+        .ok_or_else(|| DriverError::Usage("no input file".into()))?;
+    let output = options.output_path();
+
+    if options.executor == Some(Executor::Vm) && input.ends_with(".mbc") {
+        let bytes = fs::read(input)
+            .map_err(|err| DriverError::Io(format!("cannot read `{}`: {}", input, err)))?;
+
+        return Ok(Vm::new().run(&Program::decode(&bytes)?)?);
+    }
+
+    let source = fs::read_to_string(input)
+        .map_err(|err| DriverError::Io(format!("cannot read `{}`: {}", input, err)))?;
+
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.get_tokens()?;
+
+    if options.emit == Emit::Tokens {
+        let text = format_tokens(&tokens, &lexer.get_spans());
+
+        write_output(output.as_deref(), text.as_bytes())?;
+        return Ok(0);
+    }
+
+    let mut parser = Parser::with_spans(tokens, lexer.get_spans());
+    let mut loader = Loader::new(Path::new(input)).with_search_path(&options.search_path);
+
+    let imports = parser.parse_imports()?;
+    loader.load(imports, &mut parser)?;
+
+    let mut ast = loader.link(parser.parse_program()?)?;
+
+    if options.emit == Emit::Ast {
+        let text = ast.iter().map(|stmt| format!("{}\n", stmt)).collect::<String>();
+
+        write_output(output.as_deref(), text.as_bytes())?;
+        return Ok(0);
+    }
+
+    ConstFolder::new().fold(&mut ast)?;
+
+    if let Some(Executor::Interp) = options.executor {
+        return Ok(Interpreter::new().run(&ast)?);
+    }
+
+    if matches!(options.emit, Emit::Bytecode | Emit::Mbc) || options.executor == Some(Executor::Vm) {
+        let program = Compiler::new().compile(&ast)?;
+
+        return match (options.executor, options.emit) {
+            (Some(Executor::Vm), _) => Ok(Vm::new().run(&program)?),
+            (_, Emit::Bytecode) => write_output(output.as_deref(), disassemble(&program).as_bytes()).map(|_| 0),
+            _ => write_output(output.as_deref(), &program.encode()).map(|_| 0),
+        };
+    }
+
+    if options.emit == Emit::Wat {
+        let wat = WasmGen::new(ast).with_entry(options.entry).generate_wat()?;
+
+        return write_output(output.as_deref(), wat.as_bytes()).map(|_| 0);
+    }
+
+    // `-g` and `-O1` to `-O3` only reach LLVM
+    let backend = match options.backend {
+        Some(backend) => backend,
+        None if options.debug_info || options.opt_level > 0 => Backend::Llvm,
+        None => Backend::default_for(&options.target),
+    };
+
+    if options.emit == Emit::C || backend == Backend::C && options.emit != Emit::Ir {
+        let c = CGen::new(ast).with_entry(options.entry).generate_c()?;
+
+        return match options.emit {
+            Emit::C => write_output(output.as_deref(), c.as_bytes()).map(|_| 0),
+            Emit::Asm => write_output(output.as_deref(), &toolchain::cc(&c, false, options)?).map(|_| 0),
+            Emit::Obj => write_output(output.as_deref(), &toolchain::cc(&c, true, options)?).map(|_| 0),
+            _ => build(options, output, &toolchain::cc(&c, true, options)?),
+        };
+    }
+
+    // IR only comes out of LLVM, whichever backend would build the program
+    if backend == Backend::Native && options.emit != Emit::Ir {
+        if options.debug_info {
+            return Err(DriverError::Usage("`-g` is not supported by the native backends, use `--backend llvm`".into()));
+        }
+
+        if options.opt_level > 0 {
+            return Err(DriverError::Usage(format!("`-O{}` is not supported by the native backends, use `--backend llvm`", options.opt_level)));
+        }
+
+        let asm = if options.target.name == "riscv64" {
+            RiscvGen::new(ast.clone()).with_entry(options.entry).generate_asm()
+        } else {
+            X86Gen::new(ast.clone()).with_entry(options.entry).generate_asm()
+        };
+
+        // the native backends only know part of the language, LLVM builds the rest unless asked not to
+        match asm {
+            Ok(asm) => return match options.emit {
+                Emit::Asm => write_output(output.as_deref(), asm.as_bytes()).map(|_| 0),
+                Emit::Obj => write_output(output.as_deref(), &toolchain::assemble(&asm, &options.target)?).map(|_| 0),
+                _ => build(options, output, &toolchain::assemble(&asm, &options.target)?),
+            },
+            Err(err) if options.backend.is_some() => return Err(err.into()),
+            Err(_) => {},
+        }
+    }
+
+    let mut irgen = IRGen::with_target(ast, options.target).with_entry(options.entry);
+
+    if options.debug_info {
+        let path = fs::canonicalize(input)
+            .map_err(|err| DriverError::Io(format!("cannot resolve `{}`: {}", input, err)))?;
+        let filename = path.file_name().map_or("".into(), |name| name.to_string_lossy());
+        let directory = path.parent().map_or("".into(), |parent| parent.to_string_lossy());
+
+        irgen = irgen.with_debug_info(&filename, &directory);
+    }
+
+    if options.bounds_checks {
+        irgen = irgen.with_bounds_checks();
+    }
+
+    let ir = irgen.generate_ir()?;
+
+    match options.emit {
+        Emit::Tokens | Emit::Ast | Emit::Bytecode | Emit::Mbc | Emit::C | Emit::Wat => unreachable!(),
+        Emit::Ir => {
+            write_output(output.as_deref(), ir.as_bytes())?;
+            Ok(0)
+        },
+        Emit::Asm | Emit::Obj => {
+            let code = toolchain::llc(&ir, options.emit == Emit::Obj, options.opt_level)?;
+
+            write_output(output.as_deref(), &code)?;
+            Ok(0)
+        },
+        Emit::Exe => build(options, output, &toolchain::llc(&ir, true, options.opt_level)?),
+    }
+}
+
+// links `object` into the executable at `output` and runs it when asked to
+fn build(options: &Options, output: Option<PathBuf>, object: &[u8]) -> Result<i32, DriverError> {
+    let output = output.ok_or_else(|| DriverError::Usage("an executable cannot be written to stdout".into()))?;
+
+    toolchain::link(object, &output, options.entry, options.linker.as_deref(), &options.target)?;
+
+    if options.run {
+        toolchain::run(&output, &options.program_args, &options.target)
+    } else {
+        Ok(0)
+    }
+}
+
+// one `line:column token` line per token
+pub fn format_tokens(tokens: &[Token], spans: &[Span]) -> String {
+    tokens.iter()
+        .zip(spans)
+        .map(|(token, span)| format!("{}:{} {:?}\n", span.line, span.column, token))
+        .collect()
+}
+
+fn write_output(path: Option<&Path>, content: &[u8]) -> Result<(), DriverError> {
+    match path {
+        Some(path) => fs::write(path, content)
+            .map_err(|err| DriverError::Io(format!("cannot write `{}`: {}", path.display(), err))),
+        None => io::stdout().write_all(content)
+            .map_err(|err| DriverError::Io(format!("cannot write to stdout: {}", err))),
+    }
+}
\ No newline at end of file
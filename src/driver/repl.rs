@@ -0,0 +1,264 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use crate::codegen::llvm::IRGen;
+use crate::codegen::target::Target;
+use crate::driver::format_tokens;
+use crate::error::DriverError;
+use crate::fold::ConstFolder;
+use crate::interp::{Interpreter, Value};
+use crate::lexer::{Lexer, Token};
+use crate::module::Loader;
+use crate::parser::{ImportStatement, Parser, Statement, AST};
+
+pub const HELP: &str = "\
+Enter `def`, `extern` and `let` declarations and `import`s; they are kept until `:reset`.
+Expressions are evaluated and their value is printed. A block ends with an empty line.
+The prelude is available as in a program, modules are looked for in the current directory.
+
+commands:
+    :ir                 print the IR of every declaration so far
+    :ast [code]         print the AST of `code`, or of every declaration so far
+    :tokens <code>      print the tokens of `code`
+    :load <file>        enter the declarations of `file`
+    :reset              forget every declaration
+    :help               print this message
+    :quit               leave the REPL";
+
+// Keeps the declarations entered so far and compiles each new input together with them,
+// linked with the prelude and the modules imported so far like a program.
+pub struct Repl {
+    target: Target,
+    // the prelude, loaded once, and the modules loaded with it
+    loader: Loader,
+    imports: Vec<ImportStatement>,
+    declarations: AST,
+    // the declarations linked with the parts of the modules they use
+    program: AST,
+}
+
+impl Repl {
+    pub fn new(target: Target) -> Repl {
+        let mut loader = Loader::new(Path::new("<repl>"));
+
+        // without imports, only the prelude is loaded and the parser is not needed
+        loader.load(Vec::new(), &mut Parser::new(Vec::new()))
+            .unwrap_or_else(|err| panic!("the prelude does not load: {}", err));
+
+        Repl {
+            target,
+            loader,
+            imports: Vec::new(),
+            declarations: AST::new(),
+            program: AST::new(),
+        }
+    }
+
+    // whether `source` can be evaluated, or the REPL should keep reading continuation lines
+    pub fn is_complete(source: &str) -> bool {
+        let source = source.strip_suffix('\n').unwrap_or(source);
+
+        // an empty line closes every open block
+        if source.trim().is_empty() || source.ends_with('\n') {
+            return true;
+        }
+
+        let tokens = match Lexer::new(source.into()).get_tokens() {
+            Ok(tokens) => tokens,
+            Err(_) => return true,
+        };
+
+        // the lexer closes the blocks that are still open at the end of the input,
+        // so only the `Dedent`s before that trailing run are part of the balance
+        let end = tokens.iter().rposition(|token| !matches!(token, Token::Dedent | Token::EOF)).map_or(0, |idx| idx + 1);
+        let balance = tokens[..end].iter().fold(0, |balance, token| match token {
+            Token::Indent => balance + 1,
+            Token::Dedent => balance - 1,
+            _ => balance,
+        });
+
+        balance == 0 && tokens[..end].last() != Some(&Token::Colon)
+    }
+
+    // evaluates a complete input, either a command or declarations, and returns what to print
+    pub fn eval(&mut self, input: &str) -> Result<String, DriverError> {
+        let input = input.trim_end();
+
+        if let Some(command) = input.strip_prefix(':') {
+            let (command, argument) = command.split_once(' ').unwrap_or((command, ""));
+            let argument = argument.trim();
+
+            return match command {
+                "ir" => self.ir(&self.program),
+                "ast" if argument.is_empty() => Ok(self.declarations.iter().map(|stmt| format!("{}\n", stmt)).collect()),
+                "ast" => Ok(parse(argument, &self.declarations)?.iter().map(|stmt| format!("{}\n", stmt)).collect()),
+                "tokens" => {
+                    let mut lexer = Lexer::new(argument.into());
+                    let tokens = lexer.get_tokens()?;
+
+                    Ok(format_tokens(&tokens, &lexer.get_spans()))
+                },
+                "load" => {
+                    let source = fs::read_to_string(argument)
+                        .map_err(|err| DriverError::Io(format!("cannot read `{}`: {}", argument, err)))?;
+
+                    self.declare(&source)
+                },
+                "reset" => {
+                    self.imports.clear();
+                    self.declarations.clear();
+                    self.program.clear();
+                    Ok("forgot every declaration\n".into())
+                },
+                "help" => Ok(format!("{}\n", HELP)),
+                _ => Err(DriverError::Usage(format!("unknown command `:{}`, see `:help`", command))),
+            };
+        }
+
+        if input.is_empty() {
+            return Ok(String::new());
+        }
+
+        self.declare(input)
+    }
+
+    fn declare(&mut self, source: &str) -> Result<String, DriverError> {
+        let mut lexer = Lexer::new(source.into());
+        let tokens = lexer.get_tokens()?;
+        let mut parser = Parser::with_spans(tokens, lexer.get_spans()).with_types(&self.declarations);
+
+        // the modules imported before are imported again, so that the parser knows their types,
+        // and entering an import again changes nothing
+        let mut imports = self.imports.clone();
+
+        for import in parser.parse_imports()? {
+            if !imports.iter().any(|other| other.module == import.module && other.names == import.names) {
+                imports.push(import);
+            }
+        }
+
+        let mut loader = self.loader.clone();
+        loader.load(imports.clone(), &mut parser)?;
+
+        let mut declarations = self.declarations.clone();
+        let mut inputs = Vec::new();
+        let mut result = String::new();
+
+        for stmt in parser.parse_program()? {
+            if let Statement::Expression(_) = stmt {
+                inputs.push(stmt);
+                continue;
+            }
+
+            let name = declared_name(&stmt).cloned()
+                .ok_or_else(|| DriverError::Compile(format!("only declarations and expressions can be entered at the top level, found {}", stmt)))?;
+
+            // entering a declaration again replaces the earlier one
+            if let Some(idx) = declarations.iter().position(|decl| declared_name(decl) == Some(&name)) {
+                declarations[idx] = stmt;
+                result += &format!("redefined `{}`\n", name);
+            } else {
+                declarations.push(stmt);
+                result += &format!("defined `{}`\n", name);
+            }
+        }
+
+        // the expressions are linked along, so the names they use are resolved and kept in the program
+        let mut program = AST::new();
+        let mut expressions = Vec::new();
+
+        for stmt in loader.link(declarations.iter().cloned().chain(inputs).collect())? {
+            match stmt {
+                Statement::Expression(stmt) => expressions.push(stmt.expr),
+                stmt => program.push(stmt),
+            }
+        }
+
+        // nothing is kept unless the whole module still compiles
+        self.ir(&program)?;
+        self.imports = imports;
+        self.declarations = declarations;
+        self.program = program;
+
+        if !expressions.is_empty() {
+            let mut interp = Interpreter::new().capture_output();
+            interp.load(&self.program)?;
+
+            for expr in &expressions {
+                let value = interp.eval_expr(expr);
+                result += &interp.take_output();
+
+                match value? {
+                    Value::Void => { },
+                    value => result += &format!("{}: {}\n", value, value.dtype()),
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn ir(&self, declarations: &AST) -> Result<String, DriverError> {
+        let mut ast = declarations.clone();
+        ConstFolder::new().fold(&mut ast)?;
+
+        Ok(IRGen::with_target(ast, self.target).generate_ir()?)
+    }
+}
+
+// the structs and enums declared in earlier inputs can be used as types
+fn parse(source: &str, declarations: &AST) -> Result<AST, DriverError> {
+    let mut lexer = Lexer::new(source.into());
+    let tokens = lexer.get_tokens()?;
+
+    let mut parser = Parser::with_spans(tokens, lexer.get_spans()).with_types(declarations);
+    Ok(parser.parse_program()?)
+}
+
+fn declared_name(stmt: &Statement) -> Option<&String> {
+    match stmt {
+        Statement::Def(stmt) => Some(&stmt.name),
+        Statement::Extern(stmt) => Some(&stmt.name),
+        Statement::Let(stmt) => Some(&stmt.ident),
+        Statement::Struct(stmt) => Some(&stmt.name),
+        Statement::Enum(stmt) => Some(&stmt.name),
+        _ => None,
+    }
+}
+
+// reads inputs from stdin until `:quit` or the end of the input
+pub fn run(target: Target) {
+    let mut repl = Repl::new(target);
+    let mut buffer = String::new();
+
+    println!("Mamba REPL, `:help` for help");
+
+    loop {
+        print!("{}", if buffer.is_empty() { "> " } else { "... " });
+        io::stdout().flush().ok();
+
+        let mut line = String::new();
+
+        match io::stdin().lock().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => buffer += &line,
+        }
+
+        // commands always fit on one line
+        if !buffer.starts_with(':') && !Repl::is_complete(&buffer) {
+            continue;
+        }
+
+        if buffer.trim_end() == ":quit" {
+            break;
+        }
+
+        match repl.eval(&buffer) {
+            Ok(output) => print!("{}", output),
+            Err(err) => eprintln!("{}", err),
+        }
+
+        buffer.clear();
+    }
+}
\ No newline at end of file
@@ -0,0 +1,214 @@
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use crate::codegen::llvm::entry::Entry;
+use crate::codegen::target::Target;
+use crate::driver::Options;
+use crate::error::DriverError;
+
+// compiles textual IR with `llc` and returns the object file or assembly it printed
+pub fn llc(ir: &str, object: bool, opt_level: u32) -> Result<Vec<u8>, DriverError> {
+    let mut args = vec![
+        format!("-filetype={}", if object { "obj" } else { "asm" }),
+        format!("-O{}", opt_level),
+        "-relocation-model=pic".into(),
+        "-o".into(),
+        "-".into(),
+        "-".into(),
+    ];
+
+    // LLVM 14 to 16 only read `ptr` when asked to
+    if needs_opaque_pointers()? {
+        args.push("-opaque-pointers".into());
+    }
+
+    let mut child = Command::new("llc")
+        .args(&args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| not_found("llc", err))?;
+
+    // `llc` reads the whole module before it writes anything, so stdout cannot fill up while we write
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(ir.as_bytes())
+            .map_err(|err| DriverError::Io(format!("cannot pass the IR to `llc`: {}", err)))?;
+    }
+
+    let output = child.wait_with_output()
+        .map_err(|err| DriverError::Io(format!("cannot wait for `llc`: {}", err)))?;
+
+    check("llc", output).map(|output| output.stdout)
+}
+
+fn needs_opaque_pointers() -> Result<bool, DriverError> {
+    let output = Command::new("llc")
+        .arg("--version")
+        .output()
+        .map_err(|err| not_found("llc", err))?;
+    let version = String::from_utf8_lossy(&output.stdout);
+
+    Ok(["LLVM version 14.", "LLVM version 15.", "LLVM version 16."].iter().any(|prefix| version.contains(prefix)))
+}
+
+// name of `tool` for `target`: a cross toolchain is expected under the usual `<arch>-linux-gnu-` prefix
+pub fn tool(target: &Target, tool: &str) -> String {
+    if *target == Target::host() {
+        tool.into()
+    } else {
+        format!("{}-linux-gnu-{}", target.name, tool)
+    }
+}
+
+// assembles GNU assembler source for `target` and returns the object file
+pub fn assemble(asm: &str, target: &Target) -> Result<Vec<u8>, DriverError> {
+    let base = env::temp_dir().join(format!("mamba-{}", std::process::id()));
+    let (source_path, object_path) = (base.with_extension("s"), base.with_extension("o"));
+
+    fs::write(&source_path, asm)
+        .map_err(|err| DriverError::Io(format!("cannot write `{}`: {}", source_path.display(), err)))?;
+
+    let assembler = tool(target, "as");
+    let result = Command::new(&assembler)
+        .arg(&source_path)
+        .arg("-o")
+        .arg(&object_path)
+        .output();
+
+    let _ = fs::remove_file(&source_path);
+    let output = result.map_err(|err| not_found(&assembler, err))?;
+    check(&assembler, output)?;
+
+    let object = fs::read(&object_path)
+        .map_err(|err| DriverError::Io(format!("cannot read `{}`: {}", object_path.display(), err)));
+    let _ = fs::remove_file(&object_path);
+
+    object
+}
+
+// compiles C source with the C compiler of `options.target` and returns the object file or assembly
+pub fn cc(source: &str, object: bool, options: &Options) -> Result<Vec<u8>, DriverError> {
+    let base = env::temp_dir().join(format!("mamba-{}", std::process::id()));
+    let (source_path, output_path) = (base.with_extension("c"), base.with_extension(if object { "o" } else { "s" }));
+
+    fs::write(&source_path, source)
+        .map_err(|err| DriverError::Io(format!("cannot write `{}`: {}", source_path.display(), err)))?;
+
+    let compiler = tool(&options.target, if options.target == Target::host() { "cc" } else { "gcc" });
+    let mut command = Command::new(&compiler);
+    // externs are declared with mamba's types, e.g. `free(const char *)`, which the compiler would
+    // otherwise compare against its built-in prototypes
+    command.args([if object { "-c" } else { "-S" }, &format!("-O{}", options.opt_level), "-fno-builtin"]);
+
+    if options.debug_info {
+        command.arg("-g");
+    }
+
+    let result = command
+        .arg(&source_path)
+        .arg("-o")
+        .arg(&output_path)
+        .output();
+
+    let _ = fs::remove_file(&source_path);
+    let output = result.map_err(|err| not_found(&compiler, err))?;
+    check(&compiler, output)?;
+
+    let code = fs::read(&output_path)
+        .map_err(|err| DriverError::Io(format!("cannot read `{}`: {}", output_path.display(), err)));
+    let _ = fs::remove_file(&output_path);
+
+    code
+}
+
+// links `object` into an executable at `output`
+pub fn link(object: &[u8], output: &Path, entry: Entry, linker: Option<&str>, target: &Target) -> Result<(), DriverError> {
+    let object_path = env::temp_dir().join(format!("mamba-{}.o", std::process::id()));
+    let cross = *target != Target::host();
+
+    fs::write(&object_path, object)
+        .map_err(|err| DriverError::Io(format!("cannot write `{}`: {}", object_path.display(), err)))?;
+
+    let mut command = match entry {
+        Entry::C => {
+            let mut command = Command::new(linker.map_or_else(|| tool(target, if cross { "gcc" } else { "cc" }), String::from));
+
+            // an emulator runs a static executable without the target's shared libraries
+            if cross {
+                command.arg("-static");
+            }
+
+            command
+        },
+        Entry::Freestanding => {
+            let mut command = Command::new(linker.map_or_else(|| tool(target, "ld"), String::from));
+            command.args(["-nostdlib", "-static", "-e", "_start"]);
+            command
+        },
+    };
+
+    let result = command
+        .arg(&object_path)
+        .arg("-o")
+        .arg(output)
+        .output();
+
+    let _ = fs::remove_file(&object_path);
+
+    let program = command.get_program().to_string_lossy().to_string();
+    let output = result.map_err(|err| not_found(&program, err))?;
+
+    check(&program, output).map(|_| ())
+}
+
+// runs the executable, through `qemu-<arch>` when it was built for another machine, and returns its exit code
+pub fn run(path: &Path, args: &[String], target: &Target) -> Result<i32, DriverError> {
+    // a bare file name would be looked up in `PATH`
+    let path = if path.components().count() == 1 {
+        PathBuf::from(".").join(path)
+    } else {
+        path.to_path_buf()
+    };
+
+    let mut command = if *target == Target::host() {
+        Command::new(&path)
+    } else {
+        let mut command = Command::new(format!("qemu-{}", target.name));
+        command.arg(&path);
+        command
+    };
+
+    let status = command
+        .args(args)
+        .status()
+        .map_err(|err| DriverError::Io(format!("cannot run `{}`: {}", path.display(), err)))?;
+
+    // a program killed by a signal has no exit code of its own
+    Ok(status.code().unwrap_or(1))
+}
+
+fn check(tool: &str, output: Output) -> Result<Output, DriverError> {
+    if output.status.success() {
+        // warnings still deserve to be seen
+        io::stderr().write_all(&output.stderr).ok();
+        Ok(output)
+    } else {
+        Err(DriverError::Tool {
+            tool: tool.into(),
+            code: output.status.code(),
+            stderr: String::from_utf8_lossy(&output.stderr).into(),
+        })
+    }
+}
+
+fn not_found(tool: &str, err: io::Error) -> DriverError {
+    DriverError::Tool {
+        tool: tool.into(),
+        code: None,
+        stderr: format!("cannot start `{}`: {}", tool, err),
+    }
+}
\ No newline at end of file
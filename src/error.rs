@@ -4,6 +4,39 @@ pub struct LexerError(pub String);
 pub struct ParseError(pub String);
 pub struct CompileError(pub String);
 pub struct IRGenError(pub String);
+pub struct FoldError(pub String);
+pub struct MonoError(pub String);
+pub struct FormatError(pub String);
+pub struct ModuleError(pub String);
+pub struct RuntimeError(pub String);
+pub struct BytecodeError(pub String);
+pub struct CodegenError(pub String);
+
+// Everything that stops the command-line driver, grouped by the exit code it ends the process with.
+pub enum DriverError {
+    // the source program is invalid
+    Compile(String),
+    // the command line is invalid
+    Usage(String),
+    // a file could not be read or written
+    Io(String),
+    // an external tool such as `llc` or the linker failed
+    Tool { tool: String, code: Option<i32>, stderr: String },
+    // the interpreted program failed
+    Runtime(String),
+}
+
+impl DriverError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            DriverError::Compile(_) => 1,
+            DriverError::Usage(_) => 2,
+            DriverError::Io(_) => 3,
+            DriverError::Tool { code, .. } => code.filter(|code| *code != 0).unwrap_or(4),
+            DriverError::Runtime(_) => 5,
+        }
+    }
+}
 
 impl fmt::Display for LexerError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -29,6 +62,60 @@ impl fmt::Display for IRGenError {
     }
 }
 
+impl fmt::Display for FoldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "constant folding error: {}", self.0)
+    }
+}
+
+impl fmt::Display for MonoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "monomorphization error: {}", self.0)
+    }
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "format error: {}", self.0)
+    }
+}
+
+impl fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "module error: {}", self.0)
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "runtime error: {}", self.0)
+    }
+}
+
+impl fmt::Display for BytecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "bytecode error: {}", self.0)
+    }
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "codegen error: {}", self.0)
+    }
+}
+
+impl fmt::Display for DriverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DriverError::Compile(message) | DriverError::Runtime(message) => write!(f, "{}", message),
+            DriverError::Usage(message) => write!(f, "usage error: {}", message),
+            DriverError::Io(message) => write!(f, "io error: {}", message),
+            DriverError::Tool { tool, code: Some(code), stderr } => write!(f, "`{}` failed with exit code {}\n{}", tool, code, stderr.trim_end()),
+            DriverError::Tool { tool, code: None, stderr } => write!(f, "`{}` failed\n{}", tool, stderr.trim_end()),
+        }
+    }
+}
+
 impl fmt::Debug for LexerError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.0)
@@ -51,4 +138,100 @@ impl fmt::Debug for IRGenError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.0)
     }
+}
+
+impl fmt::Debug for FoldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Debug for MonoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Debug for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Debug for ModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Debug for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Debug for BytecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Debug for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Debug for DriverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl From<LexerError> for DriverError {
+    fn from(err: LexerError) -> DriverError {
+        DriverError::Compile(err.to_string())
+    }
+}
+
+impl From<ParseError> for DriverError {
+    fn from(err: ParseError) -> DriverError {
+        DriverError::Compile(err.to_string())
+    }
+}
+
+impl From<FoldError> for DriverError {
+    fn from(err: FoldError) -> DriverError {
+        DriverError::Compile(err.to_string())
+    }
+}
+
+impl From<ModuleError> for DriverError {
+    fn from(err: ModuleError) -> DriverError {
+        DriverError::Compile(err.to_string())
+    }
+}
+
+impl From<IRGenError> for DriverError {
+    fn from(err: IRGenError) -> DriverError {
+        DriverError::Compile(err.to_string())
+    }
+}
+
+impl From<RuntimeError> for DriverError {
+    fn from(err: RuntimeError) -> DriverError {
+        DriverError::Runtime(err.to_string())
+    }
+}
+
+impl From<BytecodeError> for DriverError {
+    fn from(err: BytecodeError) -> DriverError {
+        DriverError::Compile(err.to_string())
+    }
+}
+
+impl From<CodegenError> for DriverError {
+    fn from(err: CodegenError) -> DriverError {
+        DriverError::Compile(err.to_string())
+    }
 }
\ No newline at end of file
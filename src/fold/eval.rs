@@ -0,0 +1,242 @@
+use std::cmp::max;
+use crate::error::FoldError;
+use crate::lexer::Literal;
+use crate::parser::Operator;
+use crate::types::{DataType, SignedInteger, UnsignedInteger};
+
+pub fn literal_dtype(literal: &Literal) -> DataType {
+    match literal {
+        Literal::SignedInteger((_, dtype)) => DataType::SignedInteger(*dtype),
+        Literal::UnsignedInteger((_, dtype)) => DataType::UnsignedInteger(*dtype),
+        Literal::String(_) => DataType::str,
+        Literal::Boolean(_) => DataType::bool,
+        Literal::Null(dtype) => *dtype,
+    }
+}
+
+// two's complement truncation of `n` to the width of `dtype`
+pub fn wrap_signed(n: i128, dtype: SignedInteger) -> i128 {
+    let shift = 128 - dtype.bits();
+    (n << shift) >> shift
+}
+
+pub fn wrap_unsigned(n: u128, dtype: UnsignedInteger) -> u128 {
+    if dtype.bits() == 128 {
+        n
+    } else {
+        n & ((1u128 << dtype.bits()) - 1)
+    }
+}
+
+// converts an integer literal to `dtype` the same way `as` does in Rust:
+// sign-extend or zero-extend according to the source, then truncate.
+pub fn convert(literal: &Literal, dtype: DataType) -> Option<Literal> {
+    match (literal, dtype) {
+        (Literal::SignedInteger((n, _)), DataType::SignedInteger(to)) => Some(Literal::SignedInteger((wrap_signed(*n, to), to))),
+        (Literal::SignedInteger((n, _)), DataType::UnsignedInteger(to)) => Some(Literal::UnsignedInteger((wrap_unsigned(*n as u128, to), to))),
+        (Literal::UnsignedInteger((n, _)), DataType::SignedInteger(to)) => Some(Literal::SignedInteger((wrap_signed(*n as i128, to), to))),
+        (Literal::UnsignedInteger((n, _)), DataType::UnsignedInteger(to)) => Some(Literal::UnsignedInteger((wrap_unsigned(*n, to), to))),
+        (Literal::Boolean(b), DataType::bool) => Some(Literal::Boolean(*b)),
+        _ => None,
+    }
+}
+
+// like `convert`, but fails instead of wrapping when the value does not fit
+pub fn convert_exact(literal: &Literal, dtype: DataType) -> Result<Option<Literal>, FoldError> {
+    let converted = if let Some(converted) = convert(literal, dtype) {
+        converted
+    } else {
+        return Ok(None);
+    };
+
+    let preserved = match (literal, &converted) {
+        (Literal::SignedInteger((a, _)), Literal::SignedInteger((b, _))) => a == b,
+        (Literal::SignedInteger((a, _)), Literal::UnsignedInteger((b, _))) => *a >= 0 && *a as u128 == *b,
+        (Literal::UnsignedInteger((a, _)), Literal::SignedInteger((b, _))) => *b >= 0 && *a == *b as u128,
+        (Literal::UnsignedInteger((a, _)), Literal::UnsignedInteger((b, _))) => a == b,
+        _ => true,
+    };
+
+    if preserved {
+        Ok(Some(converted))
+    } else {
+        Err(FoldError(format!("[ConstFolder] `{}` does not fit in {}", literal_value(literal), dtype)))
+    }
+}
+
+// the parser tags an integer literal with the type it is expected to have, which its value must fit
+pub fn check_literal(literal: &Literal, line: usize) -> Result<(), FoldError> {
+    match convert_exact(literal, literal_dtype(literal)) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(FoldError(format!("[ConstFolder] `{}` overflows {} at line {}", literal_value(literal), literal_dtype(literal), line))),
+    }
+}
+
+// brings both operands to a common type, mirroring the promotion done by `codegen::llvm::generate_expr`
+pub fn promote(left: &Literal, right: &Literal) -> Option<(Literal, Literal)> {
+    let (left_dtype, right_dtype) = (literal_dtype(left), literal_dtype(right));
+
+    match (left_dtype, right_dtype) {
+        (DataType::bool, DataType::bool) => Some((left.clone(), right.clone())),
+        (DataType::SignedInteger(_) | DataType::UnsignedInteger(_), DataType::SignedInteger(_) | DataType::UnsignedInteger(_)) => {
+            let dtype = max(left_dtype, right_dtype);
+            Some((convert(left, dtype)?, convert(right, dtype)?))
+        },
+        _ => None,
+    }
+}
+
+pub fn eval_unary(operator: Operator, right: &Literal) -> Result<Option<Literal>, FoldError> {
+    Ok(match (right, operator) {
+        (Literal::SignedInteger(_), Operator::UnaryPlus) => Some(right.clone()),
+        (Literal::SignedInteger((n, dtype)), Operator::UnaryMinus) => {
+            let result = n.checked_neg().filter(|result| wrap_signed(*result, *dtype) == *result);
+
+            if let Some(result) = result {
+                Some(Literal::SignedInteger((result, *dtype)))
+            } else {
+                return Err(FoldError(format!("[ConstFolder] `-{}` overflows {}", n, DataType::SignedInteger(*dtype))));
+            }
+        },
+        (Literal::SignedInteger((n, dtype)), Operator::BitwiseNot) => Some(Literal::SignedInteger((!n, *dtype))),
+        (Literal::UnsignedInteger((n, dtype)), Operator::BitwiseNot) => Some(Literal::UnsignedInteger((wrap_unsigned(!n, *dtype), *dtype))),
+        _ => None,
+    })
+}
+
+// both operands must already share a type, see `promote`
+pub fn eval_infix(operator: Operator, left: &Literal, right: &Literal) -> Result<Option<Literal>, FoldError> {
+    match (left, right) {
+        (Literal::SignedInteger((l, dtype)), Literal::SignedInteger((r, _))) => eval_signed(operator, *l, *r, *dtype),
+        (Literal::UnsignedInteger((l, dtype)), Literal::UnsignedInteger((r, _))) => eval_unsigned(operator, *l, *r, *dtype),
+        (Literal::Boolean(l), Literal::Boolean(r)) => Ok(match operator {
+            Operator::Equal => Some(Literal::Boolean(l == r)),
+            Operator::NotEqual => Some(Literal::Boolean(l != r)),
+            Operator::BitwiseAnd => Some(Literal::Boolean(l & r)),
+            Operator::BitwiseOr => Some(Literal::Boolean(l | r)),
+            Operator::BitwiseXor => Some(Literal::Boolean(l ^ r)),
+            _ => None,
+        }),
+        _ => Ok(None),
+    }
+}
+
+// signed arithmetic is lowered with `nsw`, so overflow is reported instead of wrapped
+fn eval_signed(operator: Operator, l: i128, r: i128, dtype: SignedInteger) -> Result<Option<Literal>, FoldError> {
+    let overflow = || FoldError(format!("[ConstFolder] `{} {} {}` overflows {}", l, to_symbol(operator), r, DataType::SignedInteger(dtype)));
+    let checked = |result: Option<i128>| result.filter(|result| wrap_signed(*result, dtype) == *result).ok_or_else(overflow);
+
+    let result = match operator {
+        Operator::Plus => checked(l.checked_add(r))?,
+        Operator::Minus => checked(l.checked_sub(r))?,
+        Operator::Multiply => checked(l.checked_mul(r))?,
+        Operator::Divide | Operator::Modulo => {
+            if r == 0 {
+                return Err(FoldError(format!("[ConstFolder] attempt to compute `{} {} 0`, which is a division by zero", l, to_symbol(operator))));
+            }
+
+            // MIN / -1 does not fit, and LLVM leaves MIN % -1 undefined as well
+            checked(l.checked_div(r))?;
+
+            if let Operator::Divide = operator {
+                l / r
+            } else {
+                l % r
+            }
+        },
+        Operator::BitwiseAnd => l & r,
+        Operator::BitwiseOr => l | r,
+        Operator::BitwiseXor => l ^ r,
+        Operator::LeftShift => wrap_signed(l << shift_amount(operator, r, DataType::SignedInteger(dtype), dtype.bits())?, dtype),
+        Operator::RightShift => l >> shift_amount(operator, r, DataType::SignedInteger(dtype), dtype.bits())?,
+        Operator::Equal => return Ok(Some(Literal::Boolean(l == r))),
+        Operator::NotEqual => return Ok(Some(Literal::Boolean(l != r))),
+        Operator::Less => return Ok(Some(Literal::Boolean(l < r))),
+        Operator::LessEqual => return Ok(Some(Literal::Boolean(l <= r))),
+        Operator::Greater => return Ok(Some(Literal::Boolean(l > r))),
+        Operator::GreaterEqual => return Ok(Some(Literal::Boolean(l >= r))),
+        _ => return Ok(None),
+    };
+
+    Ok(Some(Literal::SignedInteger((result, dtype))))
+}
+
+// unsigned arithmetic wraps around modulo 2^bits
+fn eval_unsigned(operator: Operator, l: u128, r: u128, dtype: UnsignedInteger) -> Result<Option<Literal>, FoldError> {
+    let result = match operator {
+        Operator::Plus => l.wrapping_add(r),
+        Operator::Minus => l.wrapping_sub(r),
+        Operator::Multiply => l.wrapping_mul(r),
+        Operator::Divide | Operator::Modulo => {
+            if r == 0 {
+                return Err(FoldError(format!("[ConstFolder] attempt to compute `{} {} 0`, which is a division by zero", l, to_symbol(operator))));
+            }
+
+            if let Operator::Divide = operator {
+                l / r
+            } else {
+                l % r
+            }
+        },
+        Operator::BitwiseAnd => l & r,
+        Operator::BitwiseOr => l | r,
+        Operator::BitwiseXor => l ^ r,
+        Operator::LeftShift => l << shift_amount(operator, r as i128, DataType::UnsignedInteger(dtype), dtype.bits())?,
+        Operator::RightShift => l >> shift_amount(operator, r as i128, DataType::UnsignedInteger(dtype), dtype.bits())?,
+        Operator::Equal => return Ok(Some(Literal::Boolean(l == r))),
+        Operator::NotEqual => return Ok(Some(Literal::Boolean(l != r))),
+        Operator::Less => return Ok(Some(Literal::Boolean(l < r))),
+        Operator::LessEqual => return Ok(Some(Literal::Boolean(l <= r))),
+        Operator::Greater => return Ok(Some(Literal::Boolean(l > r))),
+        Operator::GreaterEqual => return Ok(Some(Literal::Boolean(l >= r))),
+        _ => return Ok(None),
+    };
+
+    Ok(Some(Literal::UnsignedInteger((wrap_unsigned(result, dtype), dtype))))
+}
+
+// shifting by the bit width or more is poison in LLVM
+fn shift_amount(operator: Operator, amount: i128, dtype: DataType, bits: u32) -> Result<u32, FoldError> {
+    if (0..bits as i128).contains(&amount) {
+        Ok(amount as u32)
+    } else {
+        Err(FoldError(format!("[ConstFolder] shift amount {} of `{}` is out of range for {}", amount, to_symbol(operator), dtype)))
+    }
+}
+
+fn to_symbol(operator: Operator) -> &'static str {
+    match operator {
+        Operator::UnaryPlus | Operator::Plus => "+",
+        Operator::UnaryMinus | Operator::Minus => "-",
+        Operator::BitwiseAnd => "&",
+        Operator::BitwiseOr => "|",
+        Operator::BitwiseXor => "^",
+        Operator::BitwiseNot => "~",
+        Operator::Multiply => "*",
+        Operator::Divide => "/",
+        Operator::Modulo => "%",
+        Operator::Equal => "==",
+        Operator::NotEqual => "!=",
+        Operator::Less => "<",
+        Operator::LessEqual => "<=",
+        Operator::Greater => ">",
+        Operator::GreaterEqual => ">=",
+        Operator::LeftShift => "<<",
+        Operator::RightShift => ">>",
+        Operator::FnCall => "()",
+        Operator::Index => "[]",
+        Operator::Member => ".",
+        Operator::AddressOf => "&",
+        Operator::Dereference => "*",
+    }
+}
+
+fn literal_value(literal: &Literal) -> String {
+    match literal {
+        Literal::SignedInteger((n, _)) => n.to_string(),
+        Literal::UnsignedInteger((n, _)) => n.to_string(),
+        Literal::String(s) => format!("{s:?}"),
+        Literal::Boolean(b) => if *b { "True".into() } else { "False".into() },
+        Literal::Null(_) => "null".into(),
+    }
+}
\ No newline at end of file
@@ -0,0 +1,260 @@
+mod eval;
+
+pub use eval::{check_literal, convert, convert_exact, eval_infix, eval_unary, literal_dtype, promote, wrap_signed, wrap_unsigned};
+
+use std::collections::{HashMap, HashSet};
+
+use crate::error::FoldError;
+use crate::lexer::Literal;
+use crate::parser::{addressed_variables, Expression, IfBranch, IfStatement, Identifier, LetStatement, Operator, Pattern, Statement, AST};
+
+// Evaluates integer and boolean expressions whose operands are known at compile time,
+// and propagates the values of global `let`s into the expressions that use them.
+#[derive(Default)]
+pub struct ConstFolder {
+    constants: HashMap<Identifier, Literal>,
+    addressed: HashSet<Identifier>,
+    scopes: Vec<HashSet<Identifier>>,
+    // the line of the statement being folded, where a literal that overflows is reported
+    line: usize,
+}
+
+impl ConstFolder {
+    pub fn new() -> ConstFolder {
+        ConstFolder::default()
+    }
+
+    pub fn fold(&mut self, ast: &mut AST) -> Result<(), FoldError> {
+        // a global whose address is taken can be written through the pointer, so its value is not known
+        self.addressed.extend(addressed_variables(ast));
+
+        for stmt in ast.iter_mut() {
+            self.fold_stmt(stmt)?;
+        }
+
+        Ok(())
+    }
+
+    fn fold_stmt(&mut self, stmt: &mut Statement) -> Result<(), FoldError> {
+        self.line = stmt.span().line;
+
+        match stmt {
+            Statement::Let(stmt) => self.fold_let(stmt)?,
+            Statement::Destructure(stmt) => {
+                self.fold_expr(&mut stmt.expr)?;
+
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.extend(stmt.names.iter().flatten().cloned());
+                }
+            },
+            Statement::Def(stmt) => {
+                self.scopes.push(stmt.params.iter().map(|(ident, _)| ident.clone()).collect());
+                self.fold_block(&mut stmt.stmts)?;
+                self.scopes.pop();
+            },
+            Statement::If(stmt) => self.fold_if(stmt)?,
+            Statement::While(stmt) => {
+                self.fold_expr(&mut stmt.condition)?;
+                self.fold_block(&mut stmt.blocks)?;
+            },
+            Statement::Return(stmt) => {
+                self.fold_expr(&mut stmt.expr)?;
+            },
+            Statement::Expression(stmt) => {
+                self.fold_expr(&mut stmt.expr)?;
+            },
+            Statement::Assign(stmt) => {
+                self.fold_place(&mut stmt.target)?;
+                self.fold_expr(&mut stmt.expr)?;
+            },
+            // the values a case binds shadow the globals of the same name in its block
+            Statement::Match(stmt) => {
+                self.fold_expr(&mut stmt.expr)?;
+
+                for case in stmt.cases.iter_mut() {
+                    if let Pattern::Variant { bindings, .. } = &case.pattern {
+                        self.scopes.push(bindings.iter().flatten().cloned().collect());
+                    } else {
+                        self.scopes.push(HashSet::new());
+                    }
+
+                    let result = self.fold_block(&mut case.stmts);
+                    self.scopes.pop();
+                    result?;
+                }
+            },
+            Statement::Extern(_) | Statement::Struct(_) | Statement::Enum(_) => { },
+        }
+
+        Ok(())
+    }
+
+    fn fold_if(&mut self, stmt: &mut IfStatement) -> Result<(), FoldError> {
+        self.fold_expr(&mut stmt.condition)?;
+        self.fold_block(&mut stmt.then)?;
+
+        match stmt.r#else.as_mut() {
+            IfBranch::Elif(stmt) => self.fold_if(stmt),
+            IfBranch::Else(stmts) => self.fold_block(stmts),
+            IfBranch::None => Ok(()),
+        }
+    }
+
+    fn fold_block(&mut self, stmts: &mut [Statement]) -> Result<(), FoldError> {
+        self.scopes.push(HashSet::new());
+
+        let result = stmts.iter_mut().try_for_each(|stmt| self.fold_stmt(stmt));
+
+        self.scopes.pop();
+        result
+    }
+
+    fn fold_let(&mut self, stmt: &mut LetStatement) -> Result<(), FoldError> {
+        if let Some(literal) = self.fold_expr(&mut stmt.expr)? {
+            if let Some(literal) = convert_exact(&literal, stmt.r#type)? {
+                stmt.expr = Expression::Literal((literal.clone(), stmt.r#type));
+
+                if self.scopes.is_empty() && !self.addressed.contains(&stmt.ident) {
+                    self.constants.insert(stmt.ident.clone(), literal);
+                }
+            }
+        }
+
+        // a local shadows any global of the same name from here on
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(stmt.ident.clone());
+        }
+
+        Ok(())
+    }
+
+    // folds `expr` in place and returns its value if the whole expression turned out to be constant
+    pub fn fold_expr(&self, expr: &mut Expression) -> Result<Option<Literal>, FoldError> {
+        let literal = match expr {
+            Expression::Literal((literal, _)) => {
+                check_literal(literal, self.line)?;
+
+                return Ok(match literal {
+                    Literal::SignedInteger(_) | Literal::UnsignedInteger(_) | Literal::Boolean(_) => Some(literal.clone()),
+                    Literal::String(_) | Literal::Null(_) => None,
+                });
+            },
+            Expression::Identifier(ident) => {
+                if self.scopes.iter().any(|scope| scope.contains(ident)) {
+                    return Ok(None);
+                }
+
+                self.constants.get(ident).cloned()
+            },
+            Expression::Unary(expr) if expr.operator == Operator::AddressOf => {
+                self.fold_place(&mut expr.right)?;
+                None
+            },
+            Expression::Unary(expr) => {
+                // the operand of a unary minus may be one more than the largest value, its negation is checked instead
+                let right = match (expr.operator, expr.right.as_mut()) {
+                    (Operator::UnaryMinus, Expression::Literal((right @ Literal::SignedInteger(_), _))) => Some(right.clone()),
+                    (_, right) => self.fold_expr(right)?,
+                };
+
+                if let Some(right) = right {
+                    eval_unary(expr.operator, &right)?
+                } else {
+                    None
+                }
+            },
+            Expression::Infix(expr) => {
+                let left = self.fold_expr(&mut expr.left)?;
+                let right = self.fold_expr(&mut expr.right)?;
+
+                if let (Some(left), Some(right)) = (left, right) {
+                    if let Some((left, right)) = promote(&left, &right) {
+                        eval_infix(expr.operator, &left, &right)?
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                }
+            },
+            Expression::FnCall(expr) => {
+                for arg in expr.args.iter_mut() {
+                    self.fold_expr(arg)?;
+                }
+
+                None
+            },
+            // the receiver may be passed by address
+            Expression::MethodCall(expr) => {
+                self.fold_place(&mut expr.receiver)?;
+
+                for arg in expr.args.iter_mut() {
+                    self.fold_expr(arg)?;
+                }
+
+                None
+            },
+            // arrays are never constant, only their elements and indices are folded
+            Expression::Index(expr) => {
+                self.fold_expr(&mut expr.array)?;
+                self.fold_expr(&mut expr.index)?;
+
+                None
+            },
+            Expression::Array(elements) | Expression::Tuple(elements) => {
+                for element in elements.iter_mut() {
+                    self.fold_expr(element)?;
+                }
+
+                None
+            },
+            // like arrays, structs are never constant
+            Expression::Struct(expr) => {
+                for (_, field) in expr.fields.iter_mut() {
+                    self.fold_expr(field)?;
+                }
+
+                None
+            },
+            Expression::Member(expr) => {
+                self.fold_place(&mut expr.expr)?;
+                None
+            },
+            Expression::Enum(expr) => {
+                for value in expr.values.iter_mut() {
+                    self.fold_expr(value)?;
+                }
+
+                None
+            },
+            // the values are folded, the string is only made at run time
+            Expression::FormatString(expr) => {
+                for value in expr.values_mut() {
+                    self.fold_expr(value)?;
+                }
+
+                None
+            },
+        };
+
+        if let Some(literal) = &literal {
+            *expr = Expression::Literal((literal.clone(), literal_dtype(literal)));
+        }
+
+        Ok(literal)
+    }
+
+    // a place keeps naming its variable, even a constant one, only the indices in it are folded
+    fn fold_place(&self, expr: &mut Expression) -> Result<(), FoldError> {
+        match expr {
+            Expression::Identifier(_) => Ok(()),
+            Expression::Index(expr) => {
+                self.fold_place(&mut expr.array)?;
+                self.fold_expr(&mut expr.index)?;
+                Ok(())
+            },
+            Expression::Member(expr) => self.fold_place(&mut expr.expr),
+            expr => self.fold_expr(expr).map(|_| ()),
+        }
+    }
+}
\ No newline at end of file
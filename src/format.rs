@@ -0,0 +1,95 @@
+use crate::error::FormatError;
+use crate::lexer::{FormatSpec, Literal, Radix};
+use crate::parser::{Expression, FnCallExpression, FormatPiece, FormatStringExpression};
+use crate::types::{DataType, SignedInteger};
+
+// F-strings are lowered to calls of routines of `std`: `_format` starts an empty buffer, each piece
+// is appended to what came before it, a value by the routine for its type with the width, precision and
+// flags of its spec as the arguments after it, and `_format_end` makes the buffer the string's value,
+// which is never freed. An f-string handed straight to `print` or `println` ends with `_format_print` or
+// `_format_println` instead, which free the buffer once it is printed.
+// Only the backends know the types of the values.
+
+// by their names in the combined program
+pub const START: &str = "std._format";
+pub const TEXT: &str = "std._format_text";
+pub const END: &str = "std._format_end";
+pub const PRINT: &str = "std._format_print";
+pub const PRINTLN: &str = "std._format_println";
+pub const ROUTINES: [&str; 9] = [START, TEXT, END, PRINT, PRINTLN, "std._format_int", "std._format_uint", "std._format_bool", "std._format_str"];
+
+// the bits of the `flags` argument
+const LEFT: i128 = 1;
+const ZERO: i128 = 2;
+const HEX: i128 = 4;
+const UPPER: i128 = 8;
+
+// the routine that appends a value of type `dtype` formatted by `spec`
+pub fn routine(dtype: DataType, spec: &FormatSpec) -> Result<&'static str, FormatError> {
+    match dtype {
+        DataType::SignedInteger(dtype) if dtype.bits() <= 64 => Ok("std._format_int"),
+        DataType::UnsignedInteger(dtype) if dtype.bits() <= 64 => Ok("std._format_uint"),
+        DataType::bool | DataType::str if spec.zero || spec.radix != Radix::Decimal => Err(FormatError(format!("[Format] `0`, `x` and `X` only apply to integers, found {}", dtype))),
+        DataType::bool => Ok("std._format_bool"),
+        DataType::str => Ok("std._format_str"),
+        DataType::SignedInteger(_) | DataType::UnsignedInteger(_) => Err(FormatError(format!("[Format] integers wider than 64 bits cannot be formatted, found {}", dtype))),
+        dtype => Err(FormatError(format!("[Format] a value of type {} cannot be formatted", dtype))),
+    }
+}
+
+// the width, precision and flags the routine takes after the value, a precision of -1 is none
+pub fn spec_args(spec: &FormatSpec) -> [i128; 3] {
+    let flags = [(spec.left, LEFT), (spec.zero, ZERO), (spec.radix != Radix::Decimal, HEX), (spec.radix == Radix::UpperHex, UPPER)]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |flags, (_, bit)| flags | bit);
+
+    [spec.width as i128, spec.precision.map_or(-1, |precision| precision as i128), flags]
+}
+
+// the arguments of `spec_args` as `i64` literals
+pub fn spec_literals(spec: &FormatSpec) -> [Expression; 3] {
+    spec_args(spec).map(|n| Expression::Literal((Literal::SignedInteger((n, SignedInteger::i64)), DataType::SignedInteger(SignedInteger::i64))))
+}
+
+// the calls `expr` stands for, given the types of its values in their order
+pub fn lower(expr: &FormatStringExpression, types: &[DataType]) -> Result<Expression, FormatError> {
+    let mut types = types.iter();
+
+    let text = expr.pieces.iter().try_fold(call(START, Vec::new()), |text, piece| match piece {
+        FormatPiece::Text(string) => Ok(call(TEXT, vec![text, Expression::Literal((Literal::String(string.clone()), DataType::str))])),
+        FormatPiece::Value(value, spec) => {
+            let dtype = *types.next().ok_or_else(|| FormatError("[Format] the type of every value is needed".into()))?;
+
+            let mut args = vec![text, value.clone()];
+            args.extend(spec_literals(spec));
+
+            Ok(call(routine(dtype, spec)?, args))
+        },
+    })?;
+
+    Ok(call(end(expr), vec![text]))
+}
+
+// the routine the buffer of `expr` is handed to once every piece is appended
+pub fn end(expr: &FormatStringExpression) -> &str {
+    expr.printer.as_deref().unwrap_or(END)
+}
+
+// `print(f"...")` or `println(f"...")`, by the names `std` gives them, as the f-string that prints itself
+pub fn printed(call: &FnCallExpression) -> Option<FormatStringExpression> {
+    let printer = match call.ident.as_str() {
+        "std.print" => PRINT,
+        "std.println" => PRINTLN,
+        _ => return None,
+    };
+
+    match call.args.as_slice() {
+        [Expression::FormatString(expr)] if expr.printer.is_none() => Some(FormatStringExpression { printer: Some(printer.into()), ..expr.clone() }),
+        _ => None,
+    }
+}
+
+fn call(ident: &str, args: Vec<Expression>) -> Expression {
+    Expression::FnCall(FnCallExpression { ident: ident.into(), args })
+}
\ No newline at end of file
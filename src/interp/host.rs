@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+use crate::error::RuntimeError;
+use crate::types::{DataType, SignedInteger, UnsignedInteger};
+use super::value::{Address, Step, Value, Variable};
+use super::{index_of, Interpreter};
+
+// Rust stand-in for a C function that a program declares with `extern`.
+pub type HostFn = fn(&mut Interpreter, &[Value]) -> Result<Value, RuntimeError>;
+
+pub fn host_functions() -> HashMap<String, HostFn> {
+    let mut host: HashMap<String, HostFn> = HashMap::new();
+
+    host.insert("puts".into(), puts);
+    host.insert("putchar".into(), putchar);
+    host.insert("fflush".into(), fflush);
+    host.insert("clock".into(), clock);
+    // what the `std` prelude is built on
+    host.insert("getchar".into(), getchar);
+    host.insert("write".into(), write);
+    host.insert("strlen".into(), strlen);
+    host.insert("calloc".into(), calloc);
+    host.insert("realloc".into(), realloc);
+    host.insert("memset".into(), memset);
+    host.insert("strcat".into(), strcat);
+    host.insert("strncat".into(), strncat);
+    host.insert("atol".into(), atol);
+    host.insert("free".into(), free);
+    host.insert("_exit".into(), exit);
+    host.insert("raise".into(), raise);
+    host.insert("open_memstream".into(), open_memstream);
+    host.insert("fputc".into(), fputc);
+    host.insert("fclose".into(), fclose);
+
+    host
+}
+
+fn puts(interp: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    if let [Value::Str(s)] = args {
+        interp.write(format!("{}\n", s).as_bytes())?;
+
+        // glibc returns the number of bytes written
+        Ok(Value::Signed(s.len() as i128 + 1, SignedInteger::i32))
+    } else {
+        Err(mismatch("puts", args))
+    }
+}
+
+fn putchar(interp: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    if let [Value::Signed(c, _)] = args {
+        let c = (*c as u8) as i128;
+        interp.write(&[c as u8])?;
+
+        Ok(Value::Signed(c, SignedInteger::i32))
+    } else {
+        Err(mismatch("putchar", args))
+    }
+}
+
+fn fflush(interp: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    if let [Value::Signed(..) | Value::Unsigned(..) | Value::Pointer(..)] = args {
+        interp.flush()?;
+        Ok(Value::Signed(0, SignedInteger::i32))
+    } else {
+        Err(mismatch("fflush", args))
+    }
+}
+
+// processor time in microseconds, like `clock()` with `CLOCKS_PER_SEC` of 1000000
+fn clock(interp: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    if args.is_empty() {
+        Ok(Value::Signed(interp.elapsed().as_micros() as i32 as i128, SignedInteger::i32))
+    } else {
+        Err(mismatch("clock", args))
+    }
+}
+
+// the next byte of stdin, or -1 at its end; what was printed so far is shown first, as it may be a prompt
+fn getchar(interp: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    if !args.is_empty() {
+        return Err(mismatch("getchar", args));
+    }
+
+    interp.flush()?;
+
+    let mut byte = [0u8];
+    let c = match io::stdin().read(&mut byte) {
+        Ok(1) => byte[0] as i128,
+        Ok(_) => -1,
+        Err(err) => return Err(RuntimeError(format!("[Interpreter] cannot read from stdin: {}", err))),
+    };
+
+    Ok(Value::Signed(c, SignedInteger::i32))
+}
+
+// only stdout and stderr can be written to
+fn write(interp: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    let (fd, bytes) = match args {
+        [Value::Signed(fd, _), Value::Str(s), Value::Unsigned(n, _)] => (*fd, &s.as_bytes()[..(*n as usize).min(s.len())]),
+        _ => return Err(mismatch("write", args)),
+    };
+
+    match fd {
+        1 => interp.write(bytes)?,
+        2 => {
+            interp.flush()?;
+            io::stderr().write_all(bytes).map_err(|err| RuntimeError(format!("[Interpreter] cannot write to stderr: {}", err)))?;
+        },
+        _ => return Ok(Value::Signed(-1, SignedInteger::i64)),
+    }
+
+    Ok(Value::Signed(bytes.len() as i128, SignedInteger::i64))
+}
+
+fn strlen(_: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    if let [Value::Str(s)] = args {
+        Ok(Value::Unsigned(s.len() as u128, UnsignedInteger::u64))
+    } else {
+        Err(mismatch("strlen", args))
+    }
+}
+
+// the heap is a list of byte arrays, which a pointer from `calloc` indexes like any other array;
+// larger requests fail as if memory had run out
+const MAX_ALLOCATION: u128 = 1 << 32;
+
+fn calloc(interp: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Unsigned(count, _), Value::Unsigned(size, _)] => Ok(allocate(interp, count.saturating_mul(*size))),
+        _ => Err(mismatch("calloc", args)),
+    }
+}
+
+// the block keeps its place, so the pointer returned is the one given; the bytes it gains are zero
+fn realloc(interp: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    let (pointer, size) = match args {
+        [Value::Pointer(None, _), Value::Unsigned(size, _)] => return Ok(allocate(interp, *size)),
+        [pointer @ Value::Pointer(..), Value::Unsigned(size, _)] => (pointer, *size),
+        _ => return Err(mismatch("realloc", args)),
+    };
+
+    if size > MAX_ALLOCATION {
+        return Ok(Value::Pointer(None, DataType::void));
+    }
+
+    match block(interp, pointer, "realloc")? {
+        Value::Array(bytes, _) => bytes.resize(size as usize, byte(0)),
+        value => return Err(RuntimeError(format!("[Interpreter] {} cannot be reallocated", value.dtype()))),
+    }
+
+    Ok(pointer.clone())
+}
+
+fn memset(interp: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    let (pointer, c, n) = match args {
+        [pointer @ Value::Pointer(..), Value::Signed(c, _), Value::Unsigned(n, _)] => (pointer, *c as u8, *n),
+        _ => return Err(mismatch("memset", args)),
+    };
+
+    let (bytes, start) = interp.buffer(pointer)?;
+
+    // the last byte set must be inside the array as well
+    if n > 0 {
+        index_of(start as i128 + n as i128 - 1, bytes.len())?;
+    }
+
+    bytes[start..start + n as usize].fill(byte(c));
+    Ok(pointer.clone())
+}
+
+fn strcat(interp: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    match args {
+        [pointer @ Value::Pointer(..), Value::Str(suffix)] => append(interp, pointer, suffix.as_bytes()),
+        _ => Err(mismatch("strcat", args)),
+    }
+}
+
+fn strncat(interp: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    match args {
+        [pointer @ Value::Pointer(..), Value::Str(suffix), Value::Unsigned(n, _)] => append(interp, pointer, &suffix.as_bytes()[..(*n as usize).min(suffix.len())]),
+        _ => Err(mismatch("strncat", args)),
+    }
+}
+
+// the integer at the start of the string after any whitespace, 0 if there is none
+fn atol(_: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    let s = match args {
+        [Value::Str(s)] => s.trim_start(),
+        _ => return Err(mismatch("atol", args)),
+    };
+
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+
+    let n = digits.bytes()
+        .take_while(u8::is_ascii_digit)
+        .fold(0i64, |n, digit| n.wrapping_mul(10).wrapping_add((digit - b'0') as i64));
+
+    Ok(Value::Signed(if negative { n.wrapping_neg() } else { n } as i128, SignedInteger::i64))
+}
+
+// freeing null does nothing, freeing a block twice fails like any other use of it
+fn free(interp: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Pointer(None, _)] => {},
+        [pointer @ Value::Pointer(Some(address), _)] => {
+            block(interp, pointer, "free")?;
+
+            if let Variable::Heap(n) = address.variable {
+                interp.heap[n] = None;
+            }
+        },
+        _ => return Err(mismatch("free", args)),
+    }
+
+    Ok(Value::Void)
+}
+
+fn exit(interp: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    if let [Value::Signed(code, _)] = args {
+        Err(interp.exit(*code as i32))
+    } else {
+        Err(mismatch("_exit", args))
+    }
+}
+
+fn raise(_: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    if let [Value::Signed(signal, _)] = args {
+        Err(RuntimeError(format!("[Interpreter] the program raised signal {}", signal)))
+    } else {
+        Err(mismatch("raise", args))
+    }
+}
+
+// a stream is a block holding what was written to it and a NUL, which `fclose` hands out through the
+// two pointers; only such streams can be written to and closed
+fn open_memstream(interp: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    let (buffer, size) = match args {
+        [Value::Pointer(Some(buffer), _), Value::Pointer(Some(size), _)] => (buffer.clone(), size.clone()),
+        _ => return Err(mismatch("open_memstream", args)),
+    };
+
+    let stream = allocate(interp, 1);
+
+    if let Value::Pointer(Some(Address { variable: Variable::Heap(n), .. }), _) = stream {
+        interp.streams.insert(n, (buffer, size));
+    }
+
+    Ok(stream)
+}
+
+fn fputc(interp: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    let (c, stream) = match args {
+        [Value::Signed(c, _), stream @ Value::Pointer(..)] => (*c as u8, stream),
+        _ => return Err(mismatch("fputc", args)),
+    };
+
+    stream_of(interp, stream, "fputc")?;
+
+    match block(interp, stream, "fputc")? {
+        Value::Array(bytes, _) => bytes.insert(bytes.len() - 1, byte(c)),
+        value => return Err(RuntimeError(format!("[Interpreter] {} is not a stream", value.dtype()))),
+    }
+
+    Ok(Value::Signed(c as i128, SignedInteger::i32))
+}
+
+fn fclose(interp: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    let stream = match args {
+        [stream @ Value::Pointer(..)] => stream,
+        _ => return Err(mismatch("fclose", args)),
+    };
+
+    let n = stream_of(interp, stream, "fclose")?;
+    let (buffer, size) = interp.streams.remove(&n).unwrap_or_else(|| unreachable!("`stream_of` found the stream"));
+
+    let length = match block(interp, stream, "fclose")? {
+        Value::Array(bytes, _) => bytes.len() - 1,
+        value => return Err(RuntimeError(format!("[Interpreter] {} is not a stream", value.dtype()))),
+    };
+
+    let block = Address { variable: Variable::Heap(n), path: vec![Step::Index(0)] };
+    *interp.place(&buffer)? = Value::Pointer(Some(block), DataType::UnsignedInteger(UnsignedInteger::u8));
+    *interp.place(&size)? = Value::Unsigned(length as u128, UnsignedInteger::u64);
+
+    Ok(Value::Signed(0, SignedInteger::i32))
+}
+
+// the block of the stream `open_memstream` returned as `pointer`
+fn stream_of(interp: &Interpreter, pointer: &Value, name: &str) -> Result<usize, RuntimeError> {
+    match pointer {
+        Value::Pointer(Some(Address { variable: Variable::Heap(n), path }), _) if path == &[Step::Index(0)] && interp.streams.contains_key(n) => Ok(*n),
+        _ => Err(RuntimeError(format!("[Interpreter] `{}` was given {}, which `open_memstream` did not return", name, pointer))),
+    }
+}
+
+// a new block of `size` zero bytes and a pointer to its first one, null when it is too large
+fn allocate(interp: &mut Interpreter, size: u128) -> Value {
+    if size > MAX_ALLOCATION {
+        return Value::Pointer(None, DataType::void);
+    }
+
+    interp.heap.push(Some(Value::Array(vec![byte(0); size as usize], DataType::UnsignedInteger(UnsignedInteger::u8))));
+
+    let address = Address { variable: Variable::Heap(interp.heap.len() - 1), path: vec![Step::Index(0)] };
+    Value::Pointer(Some(address), DataType::void)
+}
+
+// the block `pointer` points to the start of, which `name` only takes from `calloc`
+fn block<'a>(interp: &'a mut Interpreter, pointer: &Value, name: &str) -> Result<&'a mut Value, RuntimeError> {
+    match pointer {
+        Value::Pointer(Some(address @ Address { variable: Variable::Heap(_), .. }), _) if address.path == [Step::Index(0)] => {
+            let block = Address { variable: address.variable.clone(), path: Vec::new() };
+            interp.place(&block)
+        },
+        _ => Err(RuntimeError(format!("[Interpreter] `{}` was given {}, which `calloc` did not return", name, pointer))),
+    }
+}
+
+// writes `suffix` and a NUL over the NUL that ends the string at `pointer`
+fn append(interp: &mut Interpreter, pointer: &Value, suffix: &[u8]) -> Result<Value, RuntimeError> {
+    let (bytes, start) = interp.buffer(pointer)?;
+
+    let end = bytes[start..].iter().position(|byte| byte.to_i128() == Some(0))
+        .ok_or_else(|| RuntimeError(format!("[Interpreter] the string at {} has no terminating NUL", pointer)))?;
+
+    for (n, c) in suffix.iter().chain([&0]).enumerate() {
+        let n = index_of((start + end + n) as i128, bytes.len())?;
+        bytes[n] = byte(*c);
+    }
+
+    Ok(pointer.clone())
+}
+
+fn byte(c: u8) -> Value {
+    Value::Unsigned(c as u128, UnsignedInteger::u8)
+}
+
+fn mismatch(name: &str, args: &[Value]) -> RuntimeError {
+    let types = args.iter().map(|arg| arg.dtype().to_string()).collect::<Vec<String>>().join(", ");
+    RuntimeError(format!("[Interpreter] host function `{}` cannot be called with ({})", name, types))
+}
\ No newline at end of file
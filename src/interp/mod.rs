@@ -0,0 +1,764 @@
+pub mod host;
+pub mod ops;
+pub mod value;
+
+pub use host::HostFn;
+pub use value::{Address, Step, Value, Variable};
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::codegen::llvm::entry::find_main;
+use crate::error::RuntimeError;
+use crate::format;
+use crate::mono;
+use crate::parser::{is_place, AssignStatement, DefStatement, Expression, ExternStatement, FormatPiece, Identifier, IfBranch, IfStatement, MatchStatement, MethodCallExpression, Operator, Pattern, Statement, AST};
+use crate::types::{is_integer, is_string_buffer, DataType, SignedInteger};
+use ops::{eval_infix, eval_unary};
+
+// every mamba call nests a few Rust calls, so programs run on a thread whose stack
+// is large enough for `MAX_CALL_DEPTH` calls even in a debug build
+const MAX_CALL_DEPTH: usize = 10000;
+const STACK_SIZE: usize = 256 << 20;
+
+// What a statement asks its enclosing block to do next.
+enum Flow {
+    Next,
+    Return(Value),
+}
+
+// Evaluates the AST directly, without going through LLVM.
+pub struct Interpreter {
+    globals: HashMap<Identifier, Value>,
+    functions: HashMap<Identifier, Arc<DefStatement>>,
+    externs: HashMap<Identifier, ExternStatement>,
+    host: HashMap<String, HostFn>,
+    // one list of nested scopes per active call
+    frames: Vec<Vec<HashMap<Identifier, Value>>>,
+    // `None` writes straight to stdout
+    output: Option<Vec<u8>>,
+    start: Instant,
+    // the code the program called `_exit` with, which unwinds it like an error
+    exit_code: Option<i32>,
+    // the blocks of bytes `calloc` allocated and the variables the VM keeps here, `None` once they are freed
+    heap: Vec<Option<Value>>,
+    // the blocks `open_memstream` collects into, with where `fclose` stores the block and its length
+    streams: HashMap<usize, (Address, Address)>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Interpreter {
+        Interpreter {
+            globals: HashMap::new(),
+            functions: HashMap::new(),
+            externs: HashMap::new(),
+            host: host::host_functions(),
+            frames: Vec::new(),
+            output: None,
+            start: Instant::now(),
+            exit_code: None,
+            heap: Vec::new(),
+            streams: HashMap::new(),
+        }
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        Interpreter::default()
+    }
+
+    // keeps whatever the program prints, to be collected with `take_output`
+    pub fn capture_output(mut self) -> Interpreter {
+        self.output = Some(Vec::new());
+        self
+    }
+
+    pub fn with_host_function(mut self, name: &str, function: HostFn) -> Interpreter {
+        self.host.insert(name.into(), function);
+        self
+    }
+
+    pub fn host_function(&self, name: &str) -> Option<HostFn> {
+        self.host.get(name).copied()
+    }
+
+    pub fn take_output(&mut self) -> String {
+        self.output.as_mut().map_or(String::new(), |output| String::from_utf8_lossy(&std::mem::take(output)).into())
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) -> Result<(), RuntimeError> {
+        match self.output.as_mut() {
+            Some(output) => output.extend_from_slice(bytes),
+            None => io::stdout().write_all(bytes).map_err(|err| RuntimeError(format!("[Interpreter] cannot write to stdout: {}", err)))?,
+        }
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), RuntimeError> {
+        io::stdout().flush().map_err(|err| RuntimeError(format!("[Interpreter] cannot flush stdout: {}", err)))
+    }
+
+    // stops the program as if `main` returned `code`
+    pub fn exit(&mut self, code: i32) -> RuntimeError {
+        self.exit_code = Some(code);
+        RuntimeError(format!("[Interpreter] the program exited with code {}", code))
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    // declares the functions and evaluates the globals of `ast`
+    pub fn load(&mut self, ast: &AST) -> Result<(), RuntimeError> {
+        for stmt in ast {
+            match stmt {
+                Statement::Def(stmt) => {
+                    self.functions.insert(stmt.name.clone(), Arc::new(stmt.clone()));
+                },
+                Statement::Extern(stmt) => {
+                    self.externs.insert(stmt.name.clone(), stmt.clone());
+                },
+                Statement::Let(stmt) => {
+                    let value = self.on_large_stack(|interp| interp.eval(&stmt.expr))?;
+                    let value = self.convert(value, stmt.r#type, &stmt.ident)?;
+                    self.globals.insert(stmt.ident.clone(), value);
+                },
+                // the parser already resolved the type, values carry it themselves
+                Statement::Struct(_) | Statement::Enum(_) => {},
+                _ => return Err(RuntimeError(format!("[Interpreter] {} cannot be global", stmt))),
+            }
+        }
+
+        Ok(())
+    }
+
+    // runs the program's `main` and returns its exit code
+    pub fn run(&mut self, ast: &AST) -> Result<i32, RuntimeError> {
+        find_main(ast).map_err(|err| RuntimeError(err.0))?;
+        self.load(ast)?;
+
+        let code = match self.call("main", Vec::new()) {
+            Ok(Value::Signed(code, _)) => code as i32,
+            Ok(_) => 0,
+            Err(err) => self.exit_code.ok_or(err)?,
+        };
+
+        self.flush()?;
+        Ok(code)
+    }
+
+    pub fn call(&mut self, name: &str, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        self.on_large_stack(|interp| interp.call_fn(name, args))
+    }
+
+    pub fn eval_expr(&mut self, expr: &Expression) -> Result<Value, RuntimeError> {
+        self.on_large_stack(|interp| interp.eval(expr))
+    }
+
+    fn on_large_stack<T: Send>(&mut self, f: impl FnOnce(&mut Interpreter) -> T + Send) -> T {
+        thread::scope(|scope| {
+            thread::Builder::new()
+                .stack_size(STACK_SIZE)
+                .spawn_scoped(scope, || f(self))
+                .expect("cannot spawn the interpreter thread")
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        })
+    }
+
+    fn call_fn(&mut self, name: &str, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        if let Some(function) = self.functions.get(name).cloned() {
+            let literals = vec![false; args.len()];
+            let function = self.instance(function, &args, &literals)?;
+            return self.call_def(&function, args);
+        }
+
+        let stmt = self.externs.get(name)
+            .ok_or_else(|| RuntimeError(format!("[Interpreter] unable to find function `{}`", name)))?;
+        let host = *self.host.get(name)
+            .ok_or_else(|| RuntimeError(format!("[Interpreter] extern `{}` has no host implementation", name)))?;
+
+        let (params, dtype) = (stmt.params.clone(), stmt.r#type);
+        let args = self.convert_args(name, &params, args)?;
+
+        let value = host(self, &args)?;
+        self.convert(value, dtype, name)
+    }
+
+    // a generic function is instantiated for the types of its arguments once, on its first call with them
+    fn instance(&mut self, function: Arc<DefStatement>, args: &[Value], literals: &[bool]) -> Result<Arc<DefStatement>, RuntimeError> {
+        if function.type_params.is_empty() {
+            return Ok(function);
+        }
+
+        let args: Vec<(DataType, bool)> = args.iter().map(Value::dtype).zip(literals.iter().copied()).collect();
+        let types = mono::infer(&function, &args).map_err(|err| RuntimeError(err.0))?;
+
+        let instance = self.functions.entry(mono::instance_name(&function, &types))
+            .or_insert_with(|| Arc::new(mono::instantiate(&function, &types)));
+
+        Ok(instance.clone())
+    }
+
+    fn call_def(&mut self, function: &DefStatement, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(RuntimeError(format!("[Interpreter] stack overflow while calling `{}`", function.name)));
+        }
+
+        let args = self.convert_args(&function.name, &function.params, args)?;
+        let params = function.params.iter().map(|(ident, _)| ident.clone()).zip(args).collect();
+
+        self.frames.push(vec![params]);
+        let flow = self.exec_block(&function.stmts);
+        self.frames.pop();
+
+        match flow? {
+            Flow::Return(_) if function.r#type == DataType::void => Ok(Value::Void),
+            Flow::Return(value) => self.convert(value, function.r#type, &function.name),
+            Flow::Next if function.r#type == DataType::void => Ok(Value::Void),
+            Flow::Next => Err(RuntimeError(format!("[Interpreter] `{}` reached its end without returning a value", function.name))),
+        }
+    }
+
+    // `self` is the receiver or its address, whichever the method takes; a pointer is followed like for a field
+    fn call_method(&mut self, expr: &MethodCallExpression) -> Result<Value, RuntimeError> {
+        // a place is passed by address unless it holds the pointer already
+        let receiver = if is_place(&expr.receiver) {
+            let address = self.address_of(&expr.receiver)?;
+
+            match self.place(&address)? {
+                pointer @ Value::Pointer(_, DataType::Struct(_)) => pointer.clone(),
+                value => Value::Pointer(Some(address), value.dtype()),
+            }
+        } else {
+            self.eval(&expr.receiver)?
+        };
+
+        let structure = match &receiver {
+            Value::Pointer(_, DataType::Struct(structure)) | Value::Struct(_, DataType::Struct(structure)) => *structure,
+            Value::Pointer(_, dtype) if is_place(&expr.receiver) => return Err(RuntimeError(format!("[Interpreter] {} has no method `{}`", dtype, expr.method))),
+            value => return Err(RuntimeError(format!("[Interpreter] {} has no method `{}`", value.dtype(), expr.method))),
+        };
+
+        let function = self.functions.get(&structure.method(&expr.method)).cloned()
+            .ok_or_else(|| RuntimeError(format!("[Interpreter] {} has no method `{}`", structure.name, expr.method)))?;
+
+        let receiver = match (receiver, function.params.first()) {
+            (Value::Pointer(Some(address), _), Some((_, DataType::Struct(_)))) => self.place(&address)?.clone(),
+            (Value::Pointer(None, _), Some((_, DataType::Struct(_)))) => return Err(RuntimeError("[Interpreter] null pointer dereference".into())),
+            (Value::Struct(..), Some((_, DataType::Pointer(_)))) => return Err(RuntimeError(format!("[Interpreter] {} does not name a place", expr.receiver))),
+            (receiver, _) => receiver,
+        };
+
+        let mut args = vec![receiver];
+
+        for arg in &expr.args {
+            args.push(self.eval(arg)?);
+        }
+
+        self.call_def(&function, args)
+    }
+
+    fn exec_block(&mut self, stmts: &[Statement]) -> Result<Flow, RuntimeError> {
+        self.scopes()?.push(HashMap::new());
+
+        let mut result = Ok(Flow::Next);
+
+        for stmt in stmts {
+            result = self.exec_stmt(stmt);
+
+            if !matches!(result, Ok(Flow::Next)) {
+                break;
+            }
+        }
+
+        self.scopes()?.pop();
+        result
+    }
+
+    fn exec_stmt(&mut self, stmt: &Statement) -> Result<Flow, RuntimeError> {
+        match stmt {
+            Statement::Let(stmt) => {
+                let value = self.eval(&stmt.expr)?;
+                let value = self.convert(value, stmt.r#type, &stmt.ident)?;
+
+                if let Some(scope) = self.scopes()?.last_mut() {
+                    scope.insert(stmt.ident.clone(), value);
+                }
+            },
+            Statement::Destructure(stmt) => {
+                let value = self.eval(&stmt.expr)?;
+                let value = match stmt.r#type {
+                    Some(dtype) => self.convert(value, dtype, &format!("({})", stmt.names.iter().map(|name| name.as_deref().unwrap_or("_")).collect::<Vec<&str>>().join(", ")))?,
+                    None => value,
+                };
+
+                let elements = match value {
+                    Value::Struct(elements, DataType::Tuple(_)) if elements.len() == stmt.names.len() => elements,
+                    value => return Err(RuntimeError(format!("[Interpreter] {} cannot be destructured into {} names", value.dtype(), stmt.names.len()))),
+                };
+
+                let bindings = stmt.names.iter().zip(elements)
+                    .filter_map(|(name, element)| name.clone().map(|name| (name, element)))
+                    .collect::<Vec<(Identifier, Value)>>();
+
+                if let Some(scope) = self.scopes()?.last_mut() {
+                    scope.extend(bindings);
+                }
+            },
+            Statement::If(stmt) => return self.exec_if(stmt),
+            Statement::While(stmt) => {
+                while self.eval_condition(&stmt.condition)? {
+                    if let Flow::Return(value) = self.exec_block(&stmt.blocks)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+            },
+            Statement::Return(stmt) => return Ok(Flow::Return(self.eval(&stmt.expr)?)),
+            Statement::Expression(stmt) => {
+                self.eval(&stmt.expr)?;
+            },
+            Statement::Assign(stmt) => self.exec_assign(stmt)?,
+            Statement::Match(stmt) => return self.exec_match(stmt),
+            Statement::Def(_) | Statement::Extern(_) | Statement::Struct(_) | Statement::Enum(_) => return Err(RuntimeError(format!("[Interpreter] {} cannot be local", stmt))),
+        }
+
+        Ok(Flow::Next)
+    }
+
+    // the value is evaluated before the indices of the target
+    fn exec_assign(&mut self, stmt: &AssignStatement) -> Result<(), RuntimeError> {
+        let value = self.eval(&stmt.expr)?;
+        let address = self.address_of(&stmt.target)?;
+
+        let dtype = self.place(&address)?.dtype();
+        let value = self.convert(value, dtype, &address.variable.to_string())?;
+
+        *self.place(&address)? = value;
+        Ok(())
+    }
+
+    fn exec_if(&mut self, stmt: &IfStatement) -> Result<Flow, RuntimeError> {
+        if self.eval_condition(&stmt.condition)? {
+            return self.exec_block(&stmt.then);
+        }
+
+        match stmt.r#else.as_ref() {
+            IfBranch::Elif(stmt) => self.exec_if(stmt),
+            IfBranch::Else(stmts) => self.exec_block(stmts),
+            IfBranch::None => Ok(Flow::Next),
+        }
+    }
+
+    // runs the first case matching the value, with the values it binds in a scope around its block
+    fn exec_match(&mut self, stmt: &MatchStatement) -> Result<Flow, RuntimeError> {
+        let value = self.eval(&stmt.expr)?;
+
+        for case in &stmt.cases {
+            let bindings = match (&case.pattern, &value) {
+                (Pattern::Wildcard, _) => HashMap::new(),
+                (Pattern::Integer(n), value) if is_integer(value.dtype()) => {
+                    let pattern = Value::Signed(*n, SignedInteger::i128).convert(value.dtype())
+                        .filter(|pattern| pattern.to_i128() == Some(*n))
+                        .ok_or_else(|| RuntimeError(format!("[Interpreter] case {} does not fit in {}", n, value.dtype())))?;
+
+                    if pattern != *value {
+                        continue;
+                    }
+
+                    HashMap::new()
+                },
+                (Pattern::Variant { dtype, variant, bindings }, Value::Enum(name, values, found)) if dtype == found => {
+                    if variant != name {
+                        continue;
+                    }
+
+                    bindings.iter().zip(values)
+                        .filter_map(|(binding, value)| binding.clone().map(|binding| (binding, value.clone())))
+                        .collect()
+                },
+                (pattern, value) => return Err(RuntimeError(format!("[Interpreter] {} cannot be matched against `{}`", value.dtype(), pattern))),
+            };
+
+            self.scopes()?.push(bindings);
+            let flow = self.exec_block(&case.stmts);
+            self.scopes()?.pop();
+
+            return flow;
+        }
+
+        Ok(Flow::Next)
+    }
+
+    fn eval_condition(&mut self, expr: &Expression) -> Result<bool, RuntimeError> {
+        match self.eval(expr)? {
+            Value::Bool(b) => Ok(b),
+            value => Err(RuntimeError(format!("[Interpreter] expected a bool condition, found {}", value.dtype()))),
+        }
+    }
+
+    fn eval(&mut self, expr: &Expression) -> Result<Value, RuntimeError> {
+        match expr {
+            Expression::Literal((literal, _)) => Ok(Value::from(literal)),
+            Expression::Identifier(ident) => self.lookup(ident),
+            Expression::Unary(expr) if expr.operator == Operator::AddressOf => {
+                let address = self.address_of(&expr.right)?;
+                let dtype = self.place(&address)?.dtype();
+                Ok(Value::Pointer(Some(address), dtype))
+            },
+            Expression::Unary(expr) => {
+                let right = self.eval(&expr.right)?;
+
+                match (expr.operator, right) {
+                    (Operator::Dereference, Value::Pointer(Some(address), pointee)) if pointee != DataType::void => {
+                        let value = self.place(&address)?.clone();
+                        self.convert(value, pointee, &format!("*{}", expr.right))
+                    },
+                    (Operator::Dereference, Value::Pointer(None, _)) => Err(RuntimeError("[Interpreter] null pointer dereference".into())),
+                    (operator, right) => eval_unary(operator, right),
+                }
+            },
+            Expression::Infix(expr) => {
+                let left = self.eval(&expr.left)?;
+                let right = self.eval(&expr.right)?;
+                eval_infix(expr.operator, left, right)
+            },
+            Expression::FnCall(expr) => {
+                let args = expr.args.iter()
+                    .map(|arg| self.eval(arg))
+                    .collect::<Result<Vec<Value>, RuntimeError>>()?;
+
+                match self.functions.get(&expr.ident).cloned() {
+                    Some(function) => {
+                        let literals: Vec<bool> = expr.args.iter().map(mono::is_integer_literal).collect();
+                        let function = self.instance(function, &args, &literals)?;
+                        self.call_def(&function, args)
+                    },
+                    None => self.call_fn(&expr.ident, args),
+                }
+            },
+            Expression::MethodCall(expr) => self.call_method(expr),
+            // a value is appended by the routine for the type it turns out to have, like `format::lower` does
+            Expression::FormatString(expr) => {
+                let text = expr.pieces.iter().try_fold(self.call_fn(format::START, Vec::new())?, |text, piece| match piece {
+                    FormatPiece::Text(string) => self.call_fn(format::TEXT, vec![text, Value::Str(string.clone())]),
+                    FormatPiece::Value(value, spec) => {
+                        let value = self.eval(value)?;
+                        let routine = format::routine(value.dtype(), spec).map_err(|err| RuntimeError(err.0))?;
+
+                        let mut args = vec![text, value];
+                        args.extend(format::spec_args(spec).map(|n| Value::Signed(n, SignedInteger::i64)));
+
+                        self.call_fn(routine, args)
+                    },
+                })?;
+
+                self.call_fn(format::end(expr), vec![text])
+            },
+            Expression::Index(expr) => {
+                let array = self.eval(&expr.array)?;
+                let index = self.eval(&expr.index)?;
+
+                match array {
+                    Value::Array(mut elements, _) => {
+                        let n = index_of(integer(&index)?, elements.len())?;
+                        Ok(elements.swap_remove(n))
+                    },
+                    value => Err(RuntimeError(format!("[Interpreter] {} cannot be indexed", value.dtype()))),
+                }
+            },
+            Expression::Array(elements) => {
+                let elements = elements.iter()
+                    .map(|element| self.eval(element))
+                    .collect::<Result<Vec<Value>, RuntimeError>>()?;
+
+                // the elements are promoted to a common type like the operands of an infix operator
+                let dtype = elements.iter().map(|element| element.dtype()).max().unwrap_or(DataType::void);
+
+                if let Some(element) = elements.iter().find(|element| element.dtype() != dtype && !(is_integer(element.dtype()) && is_integer(dtype))) {
+                    return Err(RuntimeError(format!("[Interpreter] array elements must have one type, found {} and {}", dtype, element.dtype())));
+                }
+
+                let elements = elements.into_iter()
+                    .map(|element| self.convert(element, dtype, "[]"))
+                    .collect::<Result<Vec<Value>, RuntimeError>>()?;
+
+                Ok(Value::Array(elements, dtype))
+            },
+            Expression::Tuple(elements) => {
+                let elements = elements.iter()
+                    .map(|element| self.eval(element))
+                    .collect::<Result<Vec<Value>, RuntimeError>>()?;
+
+                let dtype = DataType::tuple(elements.iter().map(|element| element.dtype()).collect());
+                Ok(Value::Struct(elements, dtype))
+            },
+            Expression::Struct(expr) => {
+                let structure = match expr.dtype {
+                    DataType::Struct(structure) => structure,
+                    dtype => return Err(RuntimeError(format!("[Interpreter] {} is not a struct", dtype))),
+                };
+
+                // the fields are evaluated in the order they are written, then put in declaration order
+                let mut fields = vec![Value::Void; structure.fields.len()];
+
+                for (ident, field) in &expr.fields {
+                    let (n, dtype) = structure.field(ident)
+                        .ok_or_else(|| RuntimeError(format!("[Interpreter] {} has no field `{}`", expr.dtype, ident)))?;
+
+                    let value = self.eval(field)?;
+                    fields[n] = self.convert(value, dtype, ident)?;
+                }
+
+                Ok(Value::Struct(fields, expr.dtype))
+            },
+            Expression::Enum(expr) => {
+                let variant = match expr.dtype {
+                    DataType::Enum(enumeration) => enumeration.variant(&expr.variant),
+                    _ => None,
+                };
+
+                let variant = variant.ok_or_else(|| RuntimeError(format!("[Interpreter] {} has no variant `{}`", expr.dtype, expr.variant)))?;
+
+                if variant.payload.len() != expr.values.len() {
+                    return Err(RuntimeError(format!("[Interpreter] `{}.{}` takes {} values but {} were given", expr.dtype, expr.variant, variant.payload.len(), expr.values.len())));
+                }
+
+                let values = variant.payload.iter().zip(&expr.values)
+                    .map(|(dtype, value)| {
+                        let value = self.eval(value)?;
+                        self.convert(value, *dtype, &expr.variant)
+                    })
+                    .collect::<Result<Vec<Value>, RuntimeError>>()?;
+
+                Ok(Value::Enum(expr.variant.clone(), values, expr.dtype))
+            },
+            Expression::Member(member) if is_place(&member.expr) => {
+                let address = self.address_of(expr)?;
+                Ok(self.place(&address)?.clone())
+            },
+            Expression::Member(member) => match self.eval(&member.expr)? {
+                Value::Pointer(Some(address), DataType::Struct(_) | DataType::Tuple(_)) => {
+                    let address = Address { variable: address.variable, path: [address.path, vec![Step::Field(member.field.clone())]].concat() };
+                    Ok(self.place(&address)?.clone())
+                },
+                Value::Pointer(None, DataType::Struct(_) | DataType::Tuple(_)) => Err(RuntimeError("[Interpreter] null pointer dereference".into())),
+                value => field(value, &member.field),
+            },
+        }
+    }
+
+    fn lookup(&self, ident: &Identifier) -> Result<Value, RuntimeError> {
+        self.frames.last()
+            .and_then(|scopes| scopes.iter().rev().find_map(|scope| scope.get(ident)))
+            .or_else(|| self.globals.get(ident))
+            .cloned()
+            .ok_or_else(|| RuntimeError(format!("[Interpreter] identifier `{}` not found", ident)))
+    }
+
+    // where the place `expr` names is, its indices are evaluated innermost first
+    fn address_of(&mut self, expr: &Expression) -> Result<Address, RuntimeError> {
+        match expr {
+            Expression::Identifier(ident) => {
+                let frame = self.frames.len().wrapping_sub(1);
+                let scope = self.frames.last().and_then(|scopes| scopes.iter().rposition(|scope| scope.contains_key(ident)));
+
+                let variable = match scope {
+                    Some(scope) => Variable::Local(frame, scope, ident.clone()),
+                    None if self.globals.contains_key(ident) => Variable::Global(ident.clone()),
+                    None => return Err(RuntimeError(format!("[Interpreter] identifier `{}` not found", ident))),
+                };
+
+                Ok(Address { variable, path: Vec::new() })
+            },
+            Expression::Index(expr) => {
+                let mut address = self.address_of(&expr.array)?;
+                let index = self.eval(&expr.index)?;
+
+                address.path.push(Step::Index(integer(&index)?));
+                Ok(address)
+            },
+            // a pointer to a struct or tuple is followed to it, like `->` in C
+            Expression::Member(expr) => {
+                let mut address = if is_place(&expr.expr) {
+                    let address = self.address_of(&expr.expr)?;
+
+                    match self.place(&address)? {
+                        Value::Pointer(Some(pointee), DataType::Struct(_) | DataType::Tuple(_)) => pointee.clone(),
+                        Value::Pointer(None, DataType::Struct(_) | DataType::Tuple(_)) => return Err(RuntimeError("[Interpreter] null pointer dereference".into())),
+                        _ => address,
+                    }
+                } else {
+                    match self.eval(&expr.expr)? {
+                        Value::Pointer(Some(pointee), DataType::Struct(_) | DataType::Tuple(_)) => pointee,
+                        Value::Pointer(None, DataType::Struct(_) | DataType::Tuple(_)) => return Err(RuntimeError("[Interpreter] null pointer dereference".into())),
+                        _ => return Err(RuntimeError(format!("[Interpreter] {} does not name a place", expr))),
+                    }
+                };
+
+                address.path.push(Step::Field(expr.field.clone()));
+                Ok(address)
+            },
+            Expression::Unary(expr) if expr.operator == Operator::Dereference => match self.eval(&expr.right)? {
+                Value::Pointer(Some(address), _) => Ok(address),
+                Value::Pointer(None, _) => Err(RuntimeError("[Interpreter] null pointer dereference".into())),
+                value => Err(RuntimeError(format!("[Interpreter] operator Dereference cannot be applied to {}", value.dtype()))),
+            },
+            expr => Err(RuntimeError(format!("[Interpreter] {} does not name a place", expr))),
+        }
+    }
+
+    // the value stored at `address`, which fails once the variable has gone out of scope
+    fn place(&mut self, address: &Address) -> Result<&mut Value, RuntimeError> {
+        let variable = match &address.variable {
+            Variable::Global(ident) => self.globals.get_mut(ident),
+            Variable::Local(frame, scope, ident) => self.frames.get_mut(*frame)
+                .and_then(|scopes| scopes.get_mut(*scope))
+                .and_then(|scope| scope.get_mut(ident)),
+            Variable::Heap(n) => self.heap.get_mut(*n).and_then(Option::as_mut),
+        };
+
+        let mut place = variable.ok_or_else(|| match address.variable {
+            Variable::Heap(_) => RuntimeError(format!("[Interpreter] {} is used after it was freed", address.variable)),
+            _ => RuntimeError(format!("[Interpreter] `{}` is no longer alive", address.variable)),
+        })?;
+
+        for step in &address.path {
+            place = match (place, step) {
+                (Value::Array(elements, _), Step::Index(n)) => {
+                    let n = index_of(*n, elements.len())?;
+                    &mut elements[n]
+                },
+                (Value::Struct(fields, dtype), Step::Field(field)) => {
+                    let (n, _) = dtype.field(field)
+                        .ok_or_else(|| RuntimeError(format!("[Interpreter] {} has no field `{}`", dtype, field)))?;
+                    &mut fields[n]
+                },
+                (value, Step::Index(_)) => return Err(RuntimeError(format!("[Interpreter] {} cannot be indexed", value.dtype()))),
+                (value, Step::Field(field)) => return Err(RuntimeError(format!("[Interpreter] {} has no field `{}`", value.dtype(), field))),
+            };
+        }
+
+        Ok(place)
+    }
+
+    // the array `pointer` points into and the position of the element it points to
+    fn buffer(&mut self, pointer: &Value) -> Result<(&mut Vec<Value>, usize), RuntimeError> {
+        let mut address = match pointer {
+            Value::Pointer(Some(address), _) => address.clone(),
+            Value::Pointer(None, _) => return Err(RuntimeError("[Interpreter] null pointer dereference".into())),
+            value => return Err(RuntimeError(format!("[Interpreter] {} is not a pointer", value.dtype()))),
+        };
+
+        let n = match address.path.pop() {
+            Some(Step::Index(n)) => n,
+            _ => return Err(RuntimeError(format!("[Interpreter] {} does not point into an array", pointer))),
+        };
+
+        match self.place(&address)? {
+            Value::Array(elements, _) => {
+                let n = index_of(n, elements.len())?;
+                Ok((elements, n))
+            },
+            value => Err(RuntimeError(format!("[Interpreter] {} cannot be indexed", value.dtype()))),
+        }
+    }
+
+    // the string a `*u8` points to, which ends at the first NUL like in C
+    pub fn read_string(&mut self, pointer: &Value) -> Result<String, RuntimeError> {
+        let (elements, n) = self.buffer(pointer)?;
+        let bytes = elements[n..].iter().map(|element| element.to_i128().unwrap_or_default() as u8).collect::<Vec<u8>>();
+
+        match bytes.iter().position(|byte| *byte == 0) {
+            Some(end) => Ok(String::from_utf8_lossy(&bytes[..end]).into()),
+            None => Err(RuntimeError(format!("[Interpreter] the string at {} has no terminating NUL", pointer))),
+        }
+    }
+
+    // a pointer to a new heap cell holding `value`, where the VM keeps a variable whose address is taken
+    pub fn boxed(&mut self, value: Value) -> Value {
+        let dtype = value.dtype();
+        self.heap.push(Some(value));
+
+        let address = Address { variable: Variable::Heap(self.heap.len() - 1), path: Vec::new() };
+        Value::Pointer(Some(address), dtype)
+    }
+
+    // the value `pointer` points to
+    pub fn pointee(&mut self, pointer: &Value) -> Result<Value, RuntimeError> {
+        match pointer {
+            Value::Pointer(Some(address), _) => self.place(address).cloned(),
+            Value::Pointer(None, _) => Err(RuntimeError("[Interpreter] null pointer dereference".into())),
+            value => Err(RuntimeError(format!("[Interpreter] {} is not a pointer", value.dtype()))),
+        }
+    }
+
+    // frees the cell `boxed` returned, once its variable goes out of scope
+    pub fn release(&mut self, pointer: &Value) {
+        if let Value::Pointer(Some(Address { variable: Variable::Heap(n), .. }), _) = pointer {
+            self.heap[*n] = None;
+        }
+    }
+
+    // `value` as a `dtype` for `name`, a `*u8` buffer being read when a string is expected
+    fn convert(&mut self, value: Value, dtype: DataType, name: &str) -> Result<Value, RuntimeError> {
+        if is_string_buffer(value.dtype(), dtype) {
+            return self.read_string(&value).map(Value::Str);
+        }
+
+        convert(value, dtype, name)
+    }
+
+    fn convert_args(&mut self, name: &str, params: &[(Identifier, DataType)], args: Vec<Value>) -> Result<Vec<Value>, RuntimeError> {
+        if params.len() != args.len() {
+            return Err(RuntimeError(format!("[Interpreter] `{}` takes {} arguments but {} were given", name, params.len(), args.len())));
+        }
+
+        params.iter()
+            .zip(args)
+            .map(|((ident, dtype), arg)| self.convert(arg, *dtype, ident))
+            .collect()
+    }
+
+    fn scopes(&mut self) -> Result<&mut Vec<HashMap<Identifier, Value>>, RuntimeError> {
+        self.frames.last_mut().ok_or_else(|| RuntimeError("[Interpreter] statements can only run inside a function".into()))
+    }
+}
+
+fn convert(value: Value, dtype: DataType, name: &str) -> Result<Value, RuntimeError> {
+    let found = value.dtype();
+
+    value.convert(dtype)
+        .ok_or_else(|| RuntimeError(format!("[Interpreter] `{}` expects {}, found {}", name, dtype, found)))
+}
+
+// the field `name` of a struct value, or the element `name` of a tuple
+fn field(value: Value, name: &str) -> Result<Value, RuntimeError> {
+    match value {
+        Value::Struct(mut fields, dtype) => match dtype.field(name) {
+            Some((n, _)) => Ok(fields.swap_remove(n)),
+            None => Err(RuntimeError(format!("[Interpreter] {} has no field `{}`", dtype, name))),
+        },
+        value => Err(RuntimeError(format!("[Interpreter] {} has no field `{}`", value.dtype(), name))),
+    }
+}
+
+fn integer(index: &Value) -> Result<i128, RuntimeError> {
+    index.to_i128().ok_or_else(|| RuntimeError(format!("[Interpreter] index must be an integer, found {}", index.dtype())))
+}
+
+// the position `n` refers to in an array of `len` elements
+fn index_of(n: i128, len: usize) -> Result<usize, RuntimeError> {
+    usize::try_from(n).ok()
+        .filter(|n| *n < len)
+        .ok_or_else(|| RuntimeError(format!("[Interpreter] index {} out of bounds for length {}", n, len)))
+}
\ No newline at end of file
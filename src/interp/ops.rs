@@ -0,0 +1,187 @@
+use std::cmp::max;
+
+use crate::error::RuntimeError;
+use crate::fold::{wrap_signed, wrap_unsigned};
+use crate::parser::Operator;
+use crate::types::{pointer_infix_type, DataType, SignedInteger, UnsignedInteger};
+use super::value::{Address, Step, Value};
+
+// brings both operands to a common type, the wider one wins and unsigned ranks above signed
+pub fn promote(left: Value, right: Value) -> (Value, Value) {
+    let dtype = max(left.dtype(), right.dtype());
+
+    match (left.convert(dtype), right.convert(dtype)) {
+        (Some(left), Some(right)) => (left, right),
+        _ => (left, right),
+    }
+}
+
+pub fn eval_unary(operator: Operator, right: Value) -> Result<Value, RuntimeError> {
+    Ok(match (operator, right) {
+        (Operator::UnaryPlus, right @ (Value::Signed(..) | Value::Unsigned(..))) => right,
+        (Operator::UnaryMinus, Value::Signed(n, dtype)) => Value::Signed(wrap_signed(n.wrapping_neg(), dtype), dtype),
+        (Operator::BitwiseNot, Value::Signed(n, dtype)) => Value::Signed(!n, dtype),
+        (Operator::BitwiseNot, Value::Unsigned(n, dtype)) => Value::Unsigned(wrap_unsigned(!n, dtype), dtype),
+        (operator, right) => return Err(unsupported(operator, right.dtype())),
+    })
+}
+
+pub fn eval_infix(operator: Operator, left: Value, right: Value) -> Result<Value, RuntimeError> {
+    if matches!(left, Value::Pointer(..)) || matches!(right, Value::Pointer(..)) {
+        return eval_pointer(operator, left, right);
+    }
+
+    match promote(left, right) {
+        (Value::Signed(l, dtype), Value::Signed(r, _)) => eval_signed(operator, l, r, dtype),
+        (Value::Unsigned(l, dtype), Value::Unsigned(r, _)) => eval_unsigned(operator, l, r, dtype),
+        (Value::Bool(l), Value::Bool(r)) => Ok(Value::Bool(match operator {
+            Operator::Equal => l == r,
+            Operator::NotEqual => l != r,
+            Operator::BitwiseAnd => l & r,
+            Operator::BitwiseOr => l | r,
+            Operator::BitwiseXor => l ^ r,
+            _ => return Err(unsupported(operator, DataType::bool)),
+        })),
+        (left, _) => Err(unsupported(operator, left.dtype())),
+    }
+}
+
+// a pointer only moves within the array around the element it points to
+fn eval_pointer(operator: Operator, left: Value, right: Value) -> Result<Value, RuntimeError> {
+    if pointer_infix_type(operator, left.dtype(), right.dtype()).is_none() {
+        return Err(RuntimeError(format!("[Interpreter] operator {:?} cannot be applied to {} and {}", operator, left.dtype(), right.dtype())));
+    }
+
+    let (left, right) = match (left, right) {
+        (Value::Pointer(left, _), Value::Pointer(right, _)) => (left, right),
+        (Value::Pointer(address, pointee), offset) | (offset, Value::Pointer(address, pointee)) => {
+            let offset = offset.to_i128().unwrap_or_default();
+            let offset = if operator == Operator::Minus { offset.saturating_neg() } else { offset };
+
+            let mut address = address.ok_or_else(|| RuntimeError("[Interpreter] arithmetic on a null pointer".into()))?;
+
+            match address.path.last_mut() {
+                Some(Step::Index(n)) => *n = n.saturating_add(offset),
+                _ => return Err(RuntimeError(format!("[Interpreter] &{} does not point into an array", address))),
+            }
+
+            return Ok(Value::Pointer(Some(address), pointee));
+        },
+        _ => unreachable!("one of the operands is a pointer"),
+    };
+
+    if let Operator::Equal | Operator::NotEqual = operator {
+        return Ok(Value::Bool((left == right) == (operator == Operator::Equal)));
+    }
+
+    let (l, r) = match (&left, &right) {
+        (Some(left), Some(right)) => positions(left, right),
+        _ => None,
+    }.ok_or_else(|| RuntimeError(format!("[Interpreter] operator {:?} needs two pointers into the same array", operator)))?;
+
+    Ok(match operator {
+        Operator::Minus => Value::Signed(wrap_signed(l - r, SignedInteger::i64), SignedInteger::i64),
+        Operator::Less => Value::Bool(l < r),
+        Operator::LessEqual => Value::Bool(l <= r),
+        Operator::Greater => Value::Bool(l > r),
+        _ => Value::Bool(l >= r),
+    })
+}
+
+// the positions of two pointers within the array they both point into
+fn positions(left: &Address, right: &Address) -> Option<(i128, i128)> {
+    if left.variable != right.variable || left.path.len() != right.path.len() {
+        return None;
+    }
+
+    match (left.path.split_last(), right.path.split_last()) {
+        (Some((Step::Index(l), left)), Some((Step::Index(r), right))) if left == right => Some((*l, *r)),
+        (None, None) => Some((0, 0)),
+        _ => None,
+    }
+}
+
+// signed arithmetic wraps around in two's complement, except where the hardware traps
+fn eval_signed(operator: Operator, l: i128, r: i128, dtype: SignedInteger) -> Result<Value, RuntimeError> {
+    let result = match operator {
+        Operator::Plus => l.wrapping_add(r),
+        Operator::Minus => l.wrapping_sub(r),
+        Operator::Multiply => l.wrapping_mul(r),
+        Operator::Divide | Operator::Modulo => {
+            if r == 0 {
+                return Err(RuntimeError("[Interpreter] attempt to divide by zero".into()));
+            }
+
+            // MIN / -1 does not fit, and `idiv` traps on MIN % -1 as well
+            if l == wrap_signed(1 << (dtype.bits() - 1), dtype) && r == -1 {
+                return Err(RuntimeError(format!("[Interpreter] attempt to divide {} by -1, which overflows {:?}", l, dtype)));
+            }
+
+            if let Operator::Divide = operator {
+                l / r
+            } else {
+                l % r
+            }
+        },
+        Operator::BitwiseAnd => l & r,
+        Operator::BitwiseOr => l | r,
+        Operator::BitwiseXor => l ^ r,
+        Operator::LeftShift => l << shift_amount(r, dtype.bits())?,
+        Operator::RightShift => l >> shift_amount(r, dtype.bits())?,
+        Operator::Equal => return Ok(Value::Bool(l == r)),
+        Operator::NotEqual => return Ok(Value::Bool(l != r)),
+        Operator::Less => return Ok(Value::Bool(l < r)),
+        Operator::LessEqual => return Ok(Value::Bool(l <= r)),
+        Operator::Greater => return Ok(Value::Bool(l > r)),
+        Operator::GreaterEqual => return Ok(Value::Bool(l >= r)),
+        _ => return Err(unsupported(operator, DataType::SignedInteger(dtype))),
+    };
+
+    Ok(Value::Signed(wrap_signed(result, dtype), dtype))
+}
+
+// unsigned arithmetic wraps around modulo 2^bits
+fn eval_unsigned(operator: Operator, l: u128, r: u128, dtype: UnsignedInteger) -> Result<Value, RuntimeError> {
+    let result = match operator {
+        Operator::Plus => l.wrapping_add(r),
+        Operator::Minus => l.wrapping_sub(r),
+        Operator::Multiply => l.wrapping_mul(r),
+        Operator::Divide | Operator::Modulo => {
+            if r == 0 {
+                return Err(RuntimeError("[Interpreter] attempt to divide by zero".into()));
+            }
+
+            if let Operator::Divide = operator {
+                l / r
+            } else {
+                l % r
+            }
+        },
+        Operator::BitwiseAnd => l & r,
+        Operator::BitwiseOr => l | r,
+        Operator::BitwiseXor => l ^ r,
+        Operator::LeftShift => l << shift_amount(r as i128, dtype.bits())?,
+        Operator::RightShift => l >> shift_amount(r as i128, dtype.bits())?,
+        Operator::Equal => return Ok(Value::Bool(l == r)),
+        Operator::NotEqual => return Ok(Value::Bool(l != r)),
+        Operator::Less => return Ok(Value::Bool(l < r)),
+        Operator::LessEqual => return Ok(Value::Bool(l <= r)),
+        Operator::Greater => return Ok(Value::Bool(l > r)),
+        Operator::GreaterEqual => return Ok(Value::Bool(l >= r)),
+        _ => return Err(unsupported(operator, DataType::UnsignedInteger(dtype))),
+    };
+
+    Ok(Value::Unsigned(wrap_unsigned(result, dtype), dtype))
+}
+
+fn shift_amount(amount: i128, bits: u32) -> Result<u32, RuntimeError> {
+    if (0..bits as i128).contains(&amount) {
+        Ok(amount as u32)
+    } else {
+        Err(RuntimeError(format!("[Interpreter] shift amount {} is out of range for a {}-bit integer", amount, bits)))
+    }
+}
+
+fn unsupported(operator: Operator, dtype: DataType) -> RuntimeError {
+    RuntimeError(format!("[Interpreter] operator {:?} cannot be applied to {}", operator, dtype))
+}
\ No newline at end of file
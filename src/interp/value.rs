@@ -0,0 +1,148 @@
+use std::fmt;
+
+use crate::fold::{wrap_signed, wrap_unsigned};
+use crate::lexer::Literal;
+use crate::parser::Identifier;
+use crate::types::{is_pointer_compatible, DataType, SignedInteger, UnsignedInteger};
+
+// A runtime value. Integers are kept in their widest Rust type, always already truncated to their own width.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Void,
+    Bool(bool),
+    Str(String),
+    Signed(i128, SignedInteger),
+    Unsigned(u128, UnsignedInteger),
+    // the elements and their type, which an empty array still needs
+    Array(Vec<Value>, DataType),
+    // where it points, `None` for null, and the type it points to
+    Pointer(Option<Address>, DataType),
+    // the fields in declaration order and the struct type naming them, or the elements of a tuple and its type
+    Struct(Vec<Value>, DataType),
+    // the variant, its values and the enum type declaring it
+    Enum(Identifier, Vec<Value>, DataType),
+}
+
+// a variable, then the array elements and struct fields that lead to the value pointed to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Address {
+    pub variable: Variable,
+    pub path: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    Index(i128),
+    Field(Identifier),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Variable {
+    Global(Identifier),
+    // the index of the call's frame, of the scope inside it, and the name
+    Local(usize, usize, Identifier),
+    // the block of bytes `calloc` allocated n-th
+    Heap(usize),
+}
+
+impl Value {
+    pub fn dtype(&self) -> DataType {
+        match self {
+            Value::Void => DataType::void,
+            Value::Bool(_) => DataType::bool,
+            Value::Str(_) => DataType::str,
+            Value::Signed(_, dtype) => DataType::SignedInteger(*dtype),
+            Value::Unsigned(_, dtype) => DataType::UnsignedInteger(*dtype),
+            Value::Array(elements, dtype) => DataType::array(*dtype, elements.len() as u64),
+            Value::Pointer(_, pointee) => DataType::pointer(*pointee),
+            Value::Struct(_, dtype) | Value::Enum(_, _, dtype) => *dtype,
+        }
+    }
+
+    // an integer as `i128`, unsigned ones beyond its range saturate
+    pub fn to_i128(&self) -> Option<i128> {
+        match self {
+            Value::Signed(n, _) => Some(*n),
+            Value::Unsigned(n, _) => Some(i128::try_from(*n).unwrap_or(i128::MAX)),
+            _ => None,
+        }
+    }
+
+    // converts between integer types like `as` in Rust: sign- or zero-extend according to the source, then truncate
+    pub fn convert(&self, dtype: DataType) -> Option<Value> {
+        match (self, dtype) {
+            (Value::Signed(n, _), DataType::SignedInteger(to)) => Some(Value::Signed(wrap_signed(*n, to), to)),
+            (Value::Signed(n, _), DataType::UnsignedInteger(to)) => Some(Value::Unsigned(wrap_unsigned(*n as u128, to), to)),
+            (Value::Unsigned(n, _), DataType::SignedInteger(to)) => Some(Value::Signed(wrap_signed(*n as i128, to), to)),
+            (Value::Unsigned(n, _), DataType::UnsignedInteger(to)) => Some(Value::Unsigned(wrap_unsigned(*n, to), to)),
+            (value, dtype) if value.dtype() == dtype => Some(value.clone()),
+            // arrays of the same length and tuples of the same arity convert element by element
+            (Value::Array(elements, _), DataType::Array(array)) if elements.len() as u64 == array.len => {
+                let elements = elements.iter().map(|element| element.convert(array.element)).collect::<Option<Vec<Value>>>()?;
+                Some(Value::Array(elements, array.element))
+            },
+            (Value::Struct(elements, DataType::Tuple(_)), DataType::Tuple(tuple)) if elements.len() == tuple.elements.len() => {
+                let elements = elements.iter().zip(&tuple.elements).map(|(element, dtype)| element.convert(*dtype)).collect::<Option<Vec<Value>>>()?;
+                Some(Value::Struct(elements, dtype))
+            },
+            (Value::Pointer(address, _), DataType::Pointer(pointee)) if is_pointer_compatible(self.dtype(), dtype) => Some(Value::Pointer(address.clone(), *pointee)),
+            _ => None,
+        }
+    }
+}
+
+impl From<&Literal> for Value {
+    fn from(literal: &Literal) -> Value {
+        match literal {
+            Literal::SignedInteger((n, dtype)) => Value::Signed(*n, *dtype),
+            Literal::UnsignedInteger((n, dtype)) => Value::Unsigned(*n, *dtype),
+            Literal::String(s) => Value::Str(s.clone()),
+            Literal::Boolean(b) => Value::Bool(*b),
+            Literal::Null(DataType::Pointer(pointee)) => Value::Pointer(None, **pointee),
+            Literal::Null(_) => Value::Pointer(None, DataType::void),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Void => write!(f, "void"),
+            Value::Bool(b) => write!(f, "{}", if *b { "True" } else { "False" }),
+            Value::Str(s) => write!(f, "{:?}", s),
+            Value::Signed(n, _) => write!(f, "{}", n),
+            Value::Unsigned(n, _) => write!(f, "{}", n),
+            Value::Array(elements, _) => write!(f, "[{}]", elements.iter().map(|element| element.to_string()).collect::<Vec<String>>().join(", ")),
+            Value::Pointer(Some(address), _) => write!(f, "&{}", address),
+            Value::Pointer(None, _) => write!(f, "null"),
+            Value::Struct(fields, DataType::Struct(structure)) => {
+                let fields = structure.fields.iter().zip(fields).map(|((ident, _), value)| format!("{}: {}", ident, value)).collect::<Vec<String>>();
+                write!(f, "{} {{ {} }}", structure.name, fields.join(", "))
+            },
+            Value::Struct(elements, DataType::Tuple(_)) => write!(f, "({})", elements.iter().map(|element| element.to_string()).collect::<Vec<String>>().join(", ")),
+            Value::Struct(_, dtype) => write!(f, "{}", dtype),
+            Value::Enum(variant, values, dtype) if values.is_empty() => write!(f, "{}.{}", dtype, variant),
+            Value::Enum(variant, values, dtype) => write!(f, "{}.{}({})", dtype, variant, values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(", ")),
+        }
+    }
+}
+
+impl fmt::Display for Variable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Variable::Global(ident) | Variable::Local(_, _, ident) => write!(f, "{}", ident),
+            Variable::Heap(n) => write!(f, "<heap {}>", n),
+        }
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.variable)?;
+
+        self.path.iter().try_for_each(|step| match step {
+            Step::Index(n) => write!(f, "[{}]", n),
+            Step::Field(field) => write!(f, ".{}", field),
+        })
+    }
+}
\ No newline at end of file
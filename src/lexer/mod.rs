@@ -1,5 +1,5 @@
 mod token;
-pub use token::{Token, Keyword, Literal};
+pub use token::{Token, Keyword, Literal, FormatPart, FormatSpec, Radix, Span};
 
 use std::cmp::Ordering;
 use crate::{error::LexerError, types::{DataType, FloatingPoint, SignedInteger, UnsignedInteger}};
@@ -9,6 +9,7 @@ pub struct Lexer {
     line: usize,
     pos: usize,
     indent: usize,
+    spans: Vec<Span>,
 }
 
 impl Lexer {
@@ -18,9 +19,15 @@ impl Lexer {
             line: 0,
             pos: 0,
             indent: 0,
+            spans: Vec::new(),
         }
     }
 
+    // position of every token returned by the last `get_tokens` call
+    pub fn get_spans(&self) -> Vec<Span> {
+        self.spans.clone()
+    }
+
     fn next(&self, n: usize) -> Option<char> {
         let line = &self.source[self.line];
 
@@ -41,6 +48,12 @@ impl Lexer {
             "let" => Token::Keyword(Keyword::Let),
             "return" => Token::Keyword(Keyword::Return),
             "while" => Token::Keyword(Keyword::While),
+            "struct" => Token::Keyword(Keyword::Struct),
+            "enum" => Token::Keyword(Keyword::Enum),
+            "match" => Token::Keyword(Keyword::Match),
+            "case" => Token::Keyword(Keyword::Case),
+            "import" => Token::Keyword(Keyword::Import),
+            "from" => Token::Keyword(Keyword::From),
 
             "void" => Token::Keyword(Keyword::DataType(DataType::void)),
             "bool" => Token::Keyword(Keyword::DataType(DataType::bool)),
@@ -61,6 +74,7 @@ impl Lexer {
 
             "True" => Token::Literal(Literal::Boolean(true)),
             "False" => Token::Literal(Literal::Boolean(false)),
+            "null" => Token::Literal(Literal::Null(DataType::pointer(DataType::void))),
 
             _ => Token::Identifier(ident.into()),
         }
@@ -121,6 +135,85 @@ impl Lexer {
         Ok(result)
     }
 
+    // the parts of `f"..."` with `pos` on its opening quotation mark; `{{` and `}}` stand for braces
+    fn read_format_string(&mut self) -> Result<Vec<FormatPart>, LexerError> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+
+        loop {
+            let c = self.next(1).ok_or_else(|| LexerError("closing quotation mark expected".into()))?;
+            self.pos += 1;
+
+            match c {
+                '"' => break,
+                '{' | '}' if self.next(1) == Some(c) => {
+                    text.push(c);
+                    self.pos += 1;
+                },
+                '{' => {
+                    if !text.is_empty() {
+                        parts.push(FormatPart::Text(std::mem::take(&mut text)));
+                    }
+
+                    parts.push(self.read_format_value()?);
+                },
+                '}' => return Err(LexerError(format!("single `}}` in format string at line {}, column {}", self.line + 1, self.pos + 1))),
+                c => text.push(c),
+            }
+        }
+
+        if !text.is_empty() {
+            parts.push(FormatPart::Text(text));
+        }
+
+        Ok(parts)
+    }
+
+    // `{expr:spec}` with `pos` on its `{`, which ends on its `}`
+    fn read_format_value(&mut self) -> Result<FormatPart, LexerError> {
+        let (line, column) = (self.line + 1, self.pos + 1);
+        let mut source = String::new();
+        let mut spec: Option<String> = None;
+        // the braces of a struct literal and the quotation marks of a string in the expression
+        let mut depth = 0;
+        let mut quoted = false;
+
+        loop {
+            let c = self.next(1).ok_or_else(|| LexerError(format!("`}}` expected in format string at line {}, column {}", line, column)))?;
+            self.pos += 1;
+
+            match c {
+                '"' => quoted = !quoted,
+                '{' if !quoted => depth += 1,
+                '}' if !quoted && depth == 0 => break,
+                '}' if !quoted => depth -= 1,
+                ':' if !quoted && depth == 0 && spec.is_none() => {
+                    spec = Some(String::new());
+                    continue;
+                },
+                _ => {},
+            }
+
+            match spec.as_mut() {
+                Some(spec) => spec.push(c),
+                None => source.push(c),
+            }
+        }
+
+        let source = source.trim();
+
+        if source.is_empty() {
+            return Err(LexerError(format!("empty expression in format string at line {}, column {}", line, column)));
+        }
+
+        let spec = match spec {
+            Some(spec) => parse_spec(&spec).ok_or_else(|| LexerError(format!("invalid format spec `{}` at line {}, column {}", spec, line, column)))?,
+            None => FormatSpec::default(),
+        };
+
+        Ok(FormatPart::Value(Lexer::new(source.into()).get_tokens()?, spec))
+    }
+
     fn read_ident(&mut self) -> String {
         let mut result = String::new();
 
@@ -140,6 +233,7 @@ impl Lexer {
 
     pub fn get_tokens(&mut self) -> Result<Vec<Token>, LexerError> {
         let mut tokens: Vec<Token> = Vec::new();
+        let mut spans: Vec<Span> = Vec::new();
         let mut indent_count: usize = 0;
 
         for i in 0..self.source.len() {
@@ -167,7 +261,13 @@ impl Lexer {
 
             self.indent = indent;
 
+            let mut span = Span { line: i + 1, column: 1 };
+
             while self.pos < self.source[self.line].len() {
+                // tokens pushed by the previous iteration started at `span`
+                spans.resize(tokens.len(), span);
+                span = Span { line: i + 1, column: self.pos + 1 };
+
                 match self.source[self.line][self.pos] {
                     ' ' => {
                         self.pos += 1;
@@ -179,7 +279,12 @@ impl Lexer {
                     // TODO: parse l, ll, lll, u, ul, ull, ulll suffix
                     '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => tokens.push(Token::Literal(Literal::SignedInteger((self.read_number(), SignedInteger::i32)))),
                     '"' => {
-                        tokens.push(Token::Literal(Literal::String(self.read_string().unwrap())));
+                        tokens.push(Token::Literal(Literal::String(self.read_string()?)));
+                        self.pos += 1;
+                    },
+                    'f' if self.next(1) == Some('"') => {
+                        self.pos += 1;
+                        tokens.push(Token::FormatString(self.read_format_string()?));
                         self.pos += 1;
                     },
                     '(' => {
@@ -362,6 +467,7 @@ impl Lexer {
                         if let Some(next) = &self.next(1) {
                             if *next == '=' {
                                 tokens.push(Token::PercentEqual);
+                                self.pos += 2;
                                 continue;
                             }
                         }
@@ -401,6 +507,8 @@ impl Lexer {
                                 continue;
                             }
                         }
+
+                        return Err(LexerError(format!("unexpected character `!` at line {}, column {}", i + 1, self.pos + 1)));
                     },
                     _ => {
                         tokens.push(Lexer::read_keyword(&self.read_ident()));
@@ -408,6 +516,8 @@ impl Lexer {
                     },
                 }
             }
+
+            spans.resize(tokens.len(), span);
         }
 
         for _ in 0..indent_count {
@@ -415,7 +525,50 @@ impl Lexer {
         }
         
         tokens.push(Token::EOF);
+        spans.resize(tokens.len(), Span { line: self.source.len(), column: 1 });
+        self.spans = spans;
 
         Ok(tokens)
     }
+}
+
+// `[-][0][width][.precision][x|X]`, `None` when `spec` is not of that form
+fn parse_spec(spec: &str) -> Option<FormatSpec> {
+    fn number(s: &str) -> (Option<u32>, &str) {
+        let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        (s[..end].parse().ok(), &s[end..])
+    }
+
+    let mut result = FormatSpec::default();
+    let mut rest = spec;
+
+    if let Some(s) = rest.strip_prefix('-') {
+        result.left = true;
+        rest = s;
+    }
+
+    if let Some(s) = rest.strip_prefix('0') {
+        result.zero = true;
+        rest = s;
+    }
+
+    if let (Some(width), s) = number(rest) {
+        result.width = width;
+        rest = s;
+    }
+
+    if let Some(s) = rest.strip_prefix('.') {
+        let (precision, s) = number(s);
+        result.precision = Some(precision?);
+        rest = s;
+    }
+
+    result.radix = match rest {
+        "" => Radix::Decimal,
+        "x" => Radix::Hex,
+        "X" => Radix::UpperHex,
+        _ => return None,
+    };
+
+    Some(result)
 }
\ No newline at end of file
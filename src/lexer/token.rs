@@ -1,3 +1,4 @@
+use std::fmt;
 use crate::{parser::Identifier, types::{DataType, SignedInteger, UnsignedInteger}};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -5,6 +6,8 @@ pub enum Token {
     Identifier(Identifier),
     Keyword(Keyword),
     Literal(Literal),
+    // `f"..."`, whose embedded expressions are lexed on their own
+    FormatString(Vec<FormatPart>),
     
     NewLine,
     Indent,
@@ -72,6 +75,12 @@ pub enum Keyword {
     Return,
     Extern,
     While,
+    Struct,
+    Enum,
+    Match,
+    Case,
+    Import,
+    From,
     DataType(DataType),
 }
 
@@ -81,4 +90,66 @@ pub enum Literal {
     UnsignedInteger((u128, UnsignedInteger)),
     String(String), 
     Boolean(bool),
+    // carries the pointer type its context expects, `*void` until the parser knows better
+    Null(DataType),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FormatPart {
+    Text(String),
+    // the tokens of `{expr:spec}`, ending with `EOF`
+    Value(Vec<Token>, FormatSpec),
+}
+
+// how an f-string formats a value, written `[-][0][width][.precision][x|X]` after a `:`
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct FormatSpec {
+    // pads on the right rather than the left
+    pub left: bool,
+    // pads an integer with zeros after its sign rather than with spaces in front of it
+    pub zero: bool,
+    pub width: u32,
+    // the fewest digits of an integer, the most characters of a string
+    pub precision: Option<u32>,
+    pub radix: Radix,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Radix {
+    #[default]
+    Decimal,
+    Hex,
+    UpperHex,
+}
+
+impl fmt::Display for FormatSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.left {
+            write!(f, "-")?;
+        }
+
+        if self.zero {
+            write!(f, "0")?;
+        }
+
+        if self.width > 0 {
+            write!(f, "{}", self.width)?;
+        }
+
+        if let Some(precision) = self.precision {
+            write!(f, ".{}", precision)?;
+        }
+
+        match self.radix {
+            Radix::Decimal => Ok(()),
+            Radix::Hex => write!(f, "x"),
+            Radix::UpperHex => write!(f, "X"),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
 }
\ No newline at end of file
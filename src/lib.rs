@@ -2,4 +2,11 @@ pub mod codegen;
 pub mod lexer;
 pub mod parser;
 pub mod error;
-pub mod types;
\ No newline at end of file
+pub mod types;
+pub mod fold;
+pub mod mono;
+pub mod format;
+pub mod module;
+pub mod interp;
+pub mod vm;
+pub mod driver;
\ No newline at end of file
@@ -1,80 +1,31 @@
-use std::io::{self, Write, BufRead};
-use std::fs::{self, File};
 use std::env;
-use std::process::Command;
+use std::process;
 
-use mamba::lexer::Lexer;
-use mamba::parser::Parser;
-use mamba::codegen::llvm::IRGen;
+use mamba::driver::{self, repl, Options, USAGE};
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let args: Vec<String> = env::args().skip(1).collect();
 
-    if args.len() < 2 {
-        std::process::Command::new("clear").status().unwrap();
-        println!("Mamba REPL");
+    let options = Options::parse(&args).unwrap_or_else(|err| {
+        eprintln!("{err}\n\n{USAGE}");
+        process::exit(err.exit_code());
+    });
 
-        loop {
-            print!("> ");
-            io::stdout().flush().unwrap();
-
-            let mut line = String::new();
-            io::stdin().lock().read_line(&mut line).unwrap();
-
-            let mut lexer = Lexer::new(line);
-            let tokens = lexer.get_tokens().unwrap();
-
-            let mut parser = Parser::new(tokens);
-            let ast = parser.parse_all();
-
-            let mut irgen = IRGen::new(ast);
-            let ir = irgen.generate_ir().unwrap();
-
-            println!("{ir}")
-        }
-    } else {
-        let file = &args[1];
-        let source: String = fs::read_to_string(file).unwrap();
-
-        let mut lexer = Lexer::new(source);
-        let tokens = lexer.get_tokens().unwrap();
-
-        let mut parser = Parser::new(tokens);
-        let ast = parser.parse_all();
-        
-        for stmt in &ast {
-            eprintln!("{}", stmt);
-        }
-
-        let mut irgen = IRGen::new(ast);
-        let ir = irgen.generate_ir().unwrap();
-        
-        eprintln!("===== Generated IR =====");
-        println!("{ir}");
-
-        let mut ir_file = File::create("./out/mamba.ll").unwrap();
-        
-        eprintln!("Writing IR to the file...");
-        write!(ir_file, "{}", ir).unwrap();
-
-        eprintln!("Invoking llc...");
-        Command::new("llc")
-            .args(["-filetype=obj", "./out/mamba.ll", "-o", "./out/mamba.o"])
-            .spawn()
-            .unwrap();
-
-        eprintln!("Invoking ld...");
-        Command::new("ld")
-            .args(["./out/mamba.o", "-e", "_start", "-lc", "-o", "./out/mamba"])
-            .spawn()
-            .unwrap();
+    if options.help {
+        println!("{USAGE}");
+        return;
+    }
 
-        eprintln!("Invoking launcher...\n");
-        let output = Command::new("./out/mamba")
-            .output()
-            .unwrap();
+    if options.input.is_none() {
+        repl::run(options.target);
+        return;
+    }
 
-        eprintln!("{}", String::from_utf8(output.stdout).unwrap());
-        eprintln!("mamba program exit with {}", output.status);
+    match driver::compile(&options) {
+        Ok(code) => process::exit(code),
+        Err(err) => {
+            eprintln!("{err}");
+            process::exit(err.exit_code());
+        },
     }
 }
\ No newline at end of file
@@ -0,0 +1,375 @@
+mod resolve;
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::iter;
+use std::path::{Path, PathBuf};
+
+use crate::error::ModuleError;
+use crate::lexer::Lexer;
+use crate::parser::{ExternStatement, Identifier, ImportStatement, Parser, Statement, AST};
+use crate::types::DataType;
+use resolve::Resolver;
+
+// A program is split into modules, one per file: `import a.b;` loads `a/b.mamba` from the directory of
+// the program, or else from the first directory of the search path that has it. The functions, globals
+// and types of a module are named `a.b.name` in the combined program, which puts every module before
+// the ones that import it. Names starting with `_` are private to their module.
+
+pub const EXTENSION: &str = "mamba";
+
+// the prelude, whose public functions every other module sees; a program only
+// gets the ones it reaches
+pub const STD: &str = "std";
+const PRELUDE: &str = include_str!("std.mamba");
+
+#[derive(Clone)]
+pub struct Loader {
+    // the directory of the program, where modules are looked for first
+    root: PathBuf,
+    search_path: Vec<PathBuf>,
+    // the program itself, named after its file
+    name: Identifier,
+    imports: Vec<ImportStatement>,
+    modules: HashMap<Identifier, Module>,
+    // every module after the ones it imports
+    order: Vec<Identifier>,
+    // the modules being loaded, each imported by the one before it
+    loading: Vec<Identifier>,
+}
+
+#[derive(Clone)]
+struct Module {
+    ast: AST,
+    exports: Exports,
+    // the functions and globals each statement of `ast` refers to
+    uses: Vec<HashSet<Identifier>>,
+}
+
+// the names a module declares at the top level, private ones included; externs are left out,
+// every module declares the C functions it calls itself
+#[derive(Debug, Clone, Default)]
+pub struct Exports {
+    module: Identifier,
+    functions: HashMap<Identifier, Identifier>,
+    globals: HashMap<Identifier, Identifier>,
+    types: HashMap<Identifier, DataType>,
+}
+
+pub enum Export {
+    // by its name in the combined program
+    Function(Identifier),
+    Global(Identifier),
+    Type(DataType),
+}
+
+// what the names used by one module refer to
+#[derive(Debug, Default)]
+pub struct Namespace {
+    functions: HashMap<Identifier, Identifier>,
+    globals: HashMap<Identifier, Identifier>,
+    // the modules usable as `module.name`
+    modules: HashMap<Identifier, Exports>,
+}
+
+impl Exports {
+    fn new(module: &str, ast: &AST, qualify: impl Fn(&str) -> Identifier) -> Exports {
+        let mut exports = Exports {
+            module: module.into(),
+            ..Exports::default()
+        };
+
+        for stmt in ast {
+            match stmt {
+                // methods are reached through their struct
+                Statement::Def(stmt) if !stmt.name.contains('.') => { exports.functions.insert(stmt.name.clone(), qualify(&stmt.name)); },
+                Statement::Let(stmt) => { exports.globals.insert(stmt.ident.clone(), qualify(&stmt.ident)); },
+                Statement::Struct(stmt) => { exports.types.insert(unqualified(&stmt.name, module), stmt.dtype); },
+                Statement::Enum(stmt) => { exports.types.insert(unqualified(&stmt.name, module), stmt.dtype); },
+                _ => {},
+            }
+        }
+
+        exports
+    }
+
+    // the public function, global or type `name` of the module
+    pub fn get(&self, name: &str) -> Result<Export, ModuleError> {
+        let export = if let Some(symbol) = self.functions.get(name) {
+            Export::Function(symbol.clone())
+        } else if let Some(symbol) = self.globals.get(name) {
+            Export::Global(symbol.clone())
+        } else if let Some(dtype) = self.types.get(name) {
+            Export::Type(*dtype)
+        } else {
+            return Err(ModuleError(format!("[Module] `{}` has no `{}`", self.module, name)));
+        };
+
+        if name.starts_with('_') {
+            return Err(ModuleError(format!("[Module] `{}` is private to `{}`", name, self.module)));
+        }
+
+        Ok(export)
+    }
+}
+
+impl Loader {
+    // `input` is the file of the program, which names it and whose directory modules are looked for in
+    pub fn new(input: &Path) -> Loader {
+        let name: Identifier = input.file_stem().map_or("main".into(), |stem| stem.to_string_lossy().into());
+
+        Loader {
+            root: input.parent().map(Path::to_path_buf).unwrap_or_default(),
+            search_path: Vec::new(),
+            name: name.clone(),
+            imports: Vec::new(),
+            modules: HashMap::new(),
+            order: Vec::new(),
+            loading: vec![name],
+        }
+    }
+
+    // directories to look for modules in when the one of the program does not have them
+    pub fn with_search_path(mut self, dirs: &[String]) -> Loader {
+        self.search_path = dirs.iter().map(PathBuf::from).collect();
+        self
+    }
+
+    // loads the modules the program imports, and the ones they import, and makes their types known to `parser`
+    pub fn load(&mut self, imports: Vec<ImportStatement>, parser: &mut Parser) -> Result<(), ModuleError> {
+        self.load_module(STD)?;
+        self.import(&imports, parser)?;
+        self.imports = imports;
+        Ok(())
+    }
+
+    // resolves the names the program uses and puts the modules it imports in front of it
+    pub fn link(mut self, mut ast: AST) -> Result<AST, ModuleError> {
+        let namespace = self.namespace(&self.name, &self.imports, &ast, |name| name.into())?;
+        let mut resolver = Resolver::new(&namespace);
+        resolver.resolve(&mut ast)?;
+
+        let mut used = resolver.take_uses();
+        used.extend(self.modules.iter().filter(|(name, _)| *name != STD).flat_map(|(_, module)| module.uses.iter().flatten().cloned()));
+
+        if let Some(prelude) = self.modules.get_mut(STD) {
+            prune(prelude, used);
+        }
+
+        let mut program = AST::new();
+        // where each extern and each function or global was declared first
+        let mut externs: HashMap<Identifier, (ExternStatement, Identifier)> = HashMap::new();
+        let mut declared: HashMap<Identifier, Identifier> = HashMap::new();
+
+        let modules = self.order.iter().map(|name| (name.clone(), self.modules.remove(name).map(|module| module.ast).unwrap_or_default()));
+
+        for (module, ast) in modules.chain(iter::once((self.name.clone(), ast))) {
+            for stmt in ast {
+                let name = match &stmt {
+                    // the same C function may be declared by every module that calls it
+                    Statement::Extern(stmt) => match externs.get(&stmt.name) {
+                        Some((other, _)) if same_signature(other, stmt) => continue,
+                        Some((_, other)) => return Err(ModuleError(format!("[Module] `{}` is declared differently by `{}` and `{}`", stmt.name, other, module))),
+                        None => {
+                            externs.insert(stmt.name.clone(), (stmt.clone(), module.clone()));
+                            Some(&stmt.name)
+                        },
+                    },
+                    Statement::Def(stmt) => Some(&stmt.name),
+                    Statement::Let(stmt) => Some(&stmt.ident),
+                    _ => None,
+                };
+
+                if let Some(name) = name {
+                    match declared.get(name) {
+                        Some(other) if *other != module => return Err(ModuleError(format!("[Module] `{}` is declared by both `{}` and `{}`", name, other, module))),
+                        Some(_) => {},
+                        None => { declared.insert(name.clone(), module.clone()); },
+                    }
+                }
+
+                program.push(stmt);
+            }
+        }
+
+        Ok(program)
+    }
+
+    fn import(&mut self, imports: &[ImportStatement], parser: &mut Parser) -> Result<(), ModuleError> {
+        // the module each name listed by `from` comes from
+        let mut imported: HashMap<&str, &str> = HashMap::new();
+
+        for import in imports {
+            self.load_module(&import.module)?;
+            let exports = &self.modules[&import.module].exports;
+
+            match &import.names {
+                None => {
+                    for (name, dtype) in exports.types.iter().filter(|(name, _)| !name.starts_with('_')) {
+                        parser.import_type(format!("{}.{}", import.module, name), *dtype);
+                    }
+                },
+                Some(names) => {
+                    for name in names {
+                        if let Some(other) = imported.insert(name, &import.module) {
+                            return Err(ModuleError(format!("[Module] `{}` is imported from both `{}` and `{}`", name, other, import.module)));
+                        }
+
+                        if let Export::Type(dtype) = exports.get(name)? {
+                            parser.import_type(name.clone(), dtype);
+                        }
+                    }
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    fn load_module(&mut self, name: &str) -> Result<(), ModuleError> {
+        if self.modules.contains_key(name) {
+            return Ok(());
+        }
+
+        if let Some(n) = self.loading.iter().position(|module| module == name) {
+            let cycle = self.loading[n..].iter().map(String::as_str).chain(iter::once(name)).collect::<Vec<&str>>();
+            return Err(ModuleError(format!("[Module] import cycle: {}", cycle.join(" -> "))));
+        }
+
+        let (path, source) = if name == STD {
+            (PathBuf::from("std.mamba"), PRELUDE.to_string())
+        } else {
+            let path = self.find(name)?;
+            let source = fs::read_to_string(&path)
+                .map_err(|err| ModuleError(format!("[Module] cannot read `{}`: {}", path.display(), err)))?;
+
+            (path, source)
+        };
+        let in_file = |err: String| ModuleError(format!("[Module] in `{}`: {}", path.display(), err));
+
+        let mut lexer = Lexer::new(source);
+        let tokens = lexer.get_tokens().map_err(|err| in_file(err.to_string()))?;
+        let mut parser = Parser::with_spans(tokens, lexer.get_spans()).with_module(name);
+        let imports = parser.parse_imports().map_err(|err| in_file(err.to_string()))?;
+
+        self.loading.push(name.into());
+        self.import(&imports, &mut parser)?;
+        self.loading.pop();
+
+        let mut ast = parser.parse_program().map_err(|err| in_file(err.to_string()))?;
+        let qualify = |ident: &str| format!("{}.{}", name, ident);
+
+        let exports = Exports::new(name, &ast, qualify);
+        let namespace = self.namespace(name, &imports, &ast, qualify)?;
+        let mut resolver = Resolver::new(&namespace);
+        let mut uses = Vec::new();
+
+        for stmt in ast.iter_mut() {
+            resolver.resolve_stmt(stmt)?;
+            uses.push(resolver.take_uses());
+        }
+
+        for stmt in ast.iter_mut() {
+            match stmt {
+                Statement::Def(stmt) if !stmt.name.contains('.') => stmt.name = qualify(&stmt.name),
+                Statement::Let(stmt) => stmt.ident = qualify(&stmt.ident),
+                _ => {},
+            }
+        }
+
+        self.modules.insert(name.into(), Module { ast, exports, uses });
+        self.order.push(name.into());
+
+        Ok(())
+    }
+
+    // `a.b` is `a/b.mamba` in the directory of the program or in the search path
+    fn find(&self, name: &str) -> Result<PathBuf, ModuleError> {
+        let file = name.split('.').collect::<PathBuf>().with_extension(EXTENSION);
+
+        iter::once(&self.root).chain(self.search_path.iter())
+            .map(|dir| dir.join(&file))
+            .find(|path| path.is_file())
+            .ok_or_else(|| ModuleError(format!("[Module] cannot find module `{}`, there is no `{}` in the directory of the program or the search path", name, file.display())))
+    }
+
+    // the declarations of `module` itself, under the names `qualify` gives them, and the names it imports
+    fn namespace(&self, module: &str, imports: &[ImportStatement], ast: &AST, qualify: impl Fn(&str) -> Identifier) -> Result<Namespace, ModuleError> {
+        let own = Exports::new(module, ast, qualify);
+        let mut namespace = Namespace {
+            functions: own.functions,
+            globals: own.globals,
+            modules: HashMap::new(),
+        };
+
+        for stmt in ast {
+            if let Statement::Extern(stmt) = stmt {
+                namespace.functions.insert(stmt.name.clone(), stmt.name.clone());
+            }
+        }
+
+        for import in imports {
+            let exports = &self.modules[&import.module].exports;
+
+            let names = match &import.names {
+                Some(names) => names,
+                None => {
+                    namespace.modules.insert(import.module.clone(), exports.clone());
+                    continue;
+                },
+            };
+
+            for name in names {
+                let (names, symbol) = match exports.get(name)? {
+                    Export::Function(symbol) => (&mut namespace.functions, symbol),
+                    Export::Global(symbol) => (&mut namespace.globals, symbol),
+                    Export::Type(_) => continue,
+                };
+
+                if names.insert(name.clone(), symbol).is_some() {
+                    return Err(ModuleError(format!("[Module] `{}` is imported from `{}` but `{}` declares it as well", name, import.module, module)));
+                }
+            }
+        }
+
+        // what the module declares or imports itself takes precedence over the prelude
+        if let Some(prelude) = self.modules.get(STD).filter(|_| module != STD) {
+            for (name, symbol) in prelude.exports.functions.iter().filter(|(name, _)| !name.starts_with('_')) {
+                namespace.functions.entry(name.clone()).or_insert_with(|| symbol.clone());
+            }
+        }
+
+        Ok(namespace)
+    }
+}
+
+fn unqualified(name: &str, module: &str) -> Identifier {
+    name.strip_prefix(module).and_then(|name| name.strip_prefix('.')).unwrap_or(name).into()
+}
+
+fn same_signature(a: &ExternStatement, b: &ExternStatement) -> bool {
+    a.r#type == b.r#type && a.params.len() == b.params.len() && a.params.iter().zip(&b.params).all(|((_, a), (_, b))| a == b)
+}
+
+// drops the functions of the prelude that `used` does not reach, and the externs only those call
+fn prune(prelude: &mut Module, mut used: HashSet<Identifier>) {
+    let mut reached = vec![false; prelude.ast.len()];
+    // what the reached functions call, which is all that the externs are kept for
+    let mut calls: HashSet<Identifier> = HashSet::new();
+
+    while let Some(n) = prelude.ast.iter().enumerate().position(|(n, stmt)| !reached[n] && matches!(stmt, Statement::Def(def) if used.contains(&def.name))) {
+        reached[n] = true;
+        calls.extend(prelude.uses[n].iter().cloned());
+        used.extend(prelude.uses[n].iter().cloned());
+    }
+
+    let ast = std::mem::take(&mut prelude.ast);
+
+    prelude.ast = ast.into_iter().zip(reached)
+        .filter(|(stmt, reached)| match stmt {
+            Statement::Extern(stmt) => calls.contains(&stmt.name),
+            _ => *reached,
+        })
+        .map(|(stmt, _)| stmt)
+        .collect();
+}
\ No newline at end of file
@@ -0,0 +1,206 @@
+use std::collections::HashSet;
+
+use crate::error::ModuleError;
+use crate::format;
+use crate::parser::{Expression, FnCallExpression, IfBranch, IfStatement, Identifier, Pattern, Statement};
+use crate::module::{Export, Namespace};
+
+// Rewrites the names a module uses into the names of what they refer to in the combined program:
+// `f` becomes `geo.f` after `from geo import f`, and `geo.f(x)` and `geo.g` become the call and the
+// global they spell after `import geo`. Variables shadow the globals, and the modules, of their name.
+pub struct Resolver<'a> {
+    namespace: &'a Namespace,
+    scopes: Vec<HashSet<Identifier>>,
+    // the functions and globals referred to since `take_uses` was called last
+    uses: HashSet<Identifier>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(namespace: &'a Namespace) -> Resolver<'a> {
+        Resolver {
+            namespace,
+            scopes: Vec::new(),
+            uses: HashSet::new(),
+        }
+    }
+
+    pub fn take_uses(&mut self) -> HashSet<Identifier> {
+        std::mem::take(&mut self.uses)
+    }
+
+    pub fn resolve(&mut self, stmts: &mut [Statement]) -> Result<(), ModuleError> {
+        stmts.iter_mut().try_for_each(|stmt| self.resolve_stmt(stmt))
+    }
+
+    pub fn resolve_stmt(&mut self, stmt: &mut Statement) -> Result<(), ModuleError> {
+        match stmt {
+            Statement::Let(stmt) => {
+                self.resolve_expr(&mut stmt.expr)?;
+
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.insert(stmt.ident.clone());
+                }
+            },
+            Statement::Destructure(stmt) => {
+                self.resolve_expr(&mut stmt.expr)?;
+
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.extend(stmt.names.iter().flatten().cloned());
+                }
+            },
+            Statement::Def(stmt) => {
+                self.scopes.push(stmt.params.iter().map(|(ident, _)| ident.clone()).collect());
+                let result = self.resolve_block(&mut stmt.stmts);
+                self.scopes.pop();
+                result?;
+            },
+            Statement::If(stmt) => self.resolve_if(stmt)?,
+            Statement::While(stmt) => {
+                self.resolve_expr(&mut stmt.condition)?;
+                self.resolve_block(&mut stmt.blocks)?;
+            },
+            Statement::Return(stmt) => self.resolve_expr(&mut stmt.expr)?,
+            Statement::Expression(stmt) => self.resolve_expr(&mut stmt.expr)?,
+            Statement::Assign(stmt) => {
+                self.resolve_expr(&mut stmt.target)?;
+                self.resolve_expr(&mut stmt.expr)?;
+            },
+            Statement::Match(stmt) => {
+                self.resolve_expr(&mut stmt.expr)?;
+
+                for case in stmt.cases.iter_mut() {
+                    if let Pattern::Variant { bindings, .. } = &case.pattern {
+                        self.scopes.push(bindings.iter().flatten().cloned().collect());
+                    } else {
+                        self.scopes.push(HashSet::new());
+                    }
+
+                    let result = self.resolve_block(&mut case.stmts);
+                    self.scopes.pop();
+                    result?;
+                }
+            },
+            Statement::Extern(_) | Statement::Struct(_) | Statement::Enum(_) => {},
+        }
+
+        Ok(())
+    }
+
+    fn resolve_if(&mut self, stmt: &mut IfStatement) -> Result<(), ModuleError> {
+        self.resolve_expr(&mut stmt.condition)?;
+        self.resolve_block(&mut stmt.then)?;
+
+        match stmt.r#else.as_mut() {
+            IfBranch::Elif(stmt) => self.resolve_if(stmt),
+            IfBranch::Else(stmts) => self.resolve_block(stmts),
+            IfBranch::None => Ok(()),
+        }
+    }
+
+    fn resolve_block(&mut self, stmts: &mut [Statement]) -> Result<(), ModuleError> {
+        self.scopes.push(HashSet::new());
+
+        let result = self.resolve(stmts);
+
+        self.scopes.pop();
+        result
+    }
+
+    fn is_local(&self, ident: &str) -> bool {
+        self.scopes.iter().any(|scope| scope.contains(ident))
+    }
+
+    // the module `expr` names, `a.b` after `import a.b`, unless a variable takes its first name
+    fn module_path(&self, expr: &Expression) -> Option<Identifier> {
+        self.dotted_name(expr).filter(|path| self.namespace.modules.contains_key(path))
+    }
+
+    fn dotted_name(&self, expr: &Expression) -> Option<Identifier> {
+        match expr {
+            Expression::Identifier(ident) if !self.is_local(ident) => Some(ident.clone()),
+            Expression::Member(expr) => Some(format!("{}.{}", self.dotted_name(&expr.expr)?, expr.field)),
+            _ => None,
+        }
+    }
+
+    fn resolve_expr(&mut self, expr: &mut Expression) -> Result<(), ModuleError> {
+        match expr {
+            Expression::Identifier(ident) => {
+                if !self.is_local(ident) {
+                    if let Some(symbol) = self.namespace.globals.get(ident) {
+                        *ident = symbol.clone();
+                    }
+
+                    self.uses.insert(ident.clone());
+                }
+            },
+            Expression::FnCall(call) => {
+                if let Some(symbol) = self.namespace.functions.get(&call.ident) {
+                    call.ident = symbol.clone();
+                }
+
+                self.uses.insert(call.ident.clone());
+
+                call.args.iter_mut().try_for_each(|arg| self.resolve_expr(arg))?;
+
+                // so that its buffer can be freed once it is printed
+                if let Some(printed) = format::printed(call) {
+                    *expr = Expression::FormatString(printed);
+                }
+            },
+            // `module.f(args)` calls a function of the module
+            Expression::MethodCall(call) => match self.module_path(&call.receiver) {
+                Some(module) => {
+                    let ident = match self.namespace.modules[&module].get(&call.method)? {
+                        Export::Function(symbol) => symbol,
+                        _ => return Err(ModuleError(format!("[Module] `{}.{}` is not a function", module, call.method))),
+                    };
+
+                    let mut args = std::mem::take(&mut call.args);
+                    args.iter_mut().try_for_each(|arg| self.resolve_expr(arg))?;
+
+                    self.uses.insert(ident.clone());
+
+                    *expr = Expression::FnCall(FnCallExpression { ident, args });
+                },
+                None => {
+                    self.resolve_expr(&mut call.receiver)?;
+                    call.args.iter_mut().try_for_each(|arg| self.resolve_expr(arg))?;
+                },
+            },
+            // `module.g` reads a global of the module
+            Expression::Member(member) => match self.module_path(&member.expr) {
+                Some(module) => {
+                    let symbol = match self.namespace.modules[&module].get(&member.field)? {
+                        Export::Global(symbol) => symbol,
+                        _ => return Err(ModuleError(format!("[Module] `{}.{}` is not a global", module, member.field))),
+                    };
+
+                    self.uses.insert(symbol.clone());
+                    *expr = Expression::Identifier(symbol);
+                },
+                None => self.resolve_expr(&mut member.expr)?,
+            },
+            Expression::Unary(expr) => self.resolve_expr(&mut expr.right)?,
+            Expression::Infix(expr) => {
+                self.resolve_expr(&mut expr.left)?;
+                self.resolve_expr(&mut expr.right)?;
+            },
+            Expression::Index(expr) => {
+                self.resolve_expr(&mut expr.array)?;
+                self.resolve_expr(&mut expr.index)?;
+            },
+            Expression::Array(elements) | Expression::Tuple(elements) => elements.iter_mut().try_for_each(|element| self.resolve_expr(element))?,
+            Expression::Struct(expr) => expr.fields.iter_mut().try_for_each(|(_, field)| self.resolve_expr(field))?,
+            Expression::Enum(expr) => expr.values.iter_mut().try_for_each(|value| self.resolve_expr(value))?,
+            // which routines the values need is only known once their types are
+            Expression::FormatString(expr) => {
+                self.uses.extend(format::ROUTINES.iter().map(|routine| routine.to_string()));
+                expr.values_mut().try_for_each(|value| self.resolve_expr(value))?;
+            },
+            Expression::Literal(_) => {},
+        }
+
+        Ok(())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,186 @@
+use crate::error::MonoError;
+use crate::lexer::Literal;
+use crate::parser::{DefStatement, Expression, IfBranch, IfStatement, Identifier, Operator, Statement};
+use crate::types::{DataType, TypeParam};
+
+// Generic functions are monomorphized: a call infers the type parameters from the types of its arguments,
+// and every distinct set of types gets a copy of the function in which the parameters are substituted.
+
+// an integer literal converts to whatever integer type its parameter has, so it only decides
+// a type parameter that no other argument does
+pub fn is_integer_literal(expr: &Expression) -> bool {
+    match expr {
+        Expression::Literal((Literal::SignedInteger(_) | Literal::UnsignedInteger(_), _)) => true,
+        Expression::Unary(expr) if matches!(expr.operator, Operator::UnaryPlus | Operator::UnaryMinus) => is_integer_literal(&expr.right),
+        _ => false,
+    }
+}
+
+// the types of `function`'s type parameters, in their order, for arguments of the given types and literal-ness
+pub fn infer(function: &DefStatement, args: &[(DataType, bool)]) -> Result<Vec<DataType>, MonoError> {
+    if function.params.len() != args.len() {
+        return Err(MonoError(format!("[Mono] `{}` takes {} arguments but {} were given", function.name, function.params.len(), args.len())));
+    }
+
+    let mut bindings: Vec<(&'static TypeParam, DataType)> = Vec::new();
+
+    for literals in [false, true] {
+        for ((_, param), (arg, is_literal)) in function.params.iter().zip(args) {
+            if *is_literal == literals {
+                unify(function, *param, *arg, literals, &mut bindings)?;
+            }
+        }
+    }
+
+    function.type_params.iter()
+        .map(|param| {
+            let dtype = bindings.iter().find(|(bound, _)| bound == param).map(|(_, dtype)| *dtype)
+                .ok_or_else(|| MonoError(format!("[Mono] cannot infer `{}` of `{}` from its arguments", param.name, function.name)))?;
+
+            match param.constraint {
+                Some(constraint) if !constraint.admits(dtype) => Err(MonoError(format!("[Mono] `{}` of `{}` must be {}, found {}", param.name, function.name, constraint, dtype))),
+                _ => Ok(dtype),
+            }
+        })
+        .collect()
+}
+
+// matches the type of a parameter against that of its argument; a mismatch that binds nothing
+// is left for the conversion of the argument to report
+fn unify(function: &DefStatement, param: DataType, arg: DataType, literal: bool, bindings: &mut Vec<(&'static TypeParam, DataType)>) -> Result<(), MonoError> {
+    match (param, arg) {
+        (DataType::Param(param), arg) => match bindings.iter().find(|(bound, _)| *bound == param) {
+            None => bindings.push((param, arg)),
+            Some((_, dtype)) if *dtype == arg || literal => {},
+            Some((_, dtype)) => return Err(MonoError(format!("[Mono] `{}` of `{}` is inferred as both {} and {}", param.name, function.name, dtype, arg))),
+        },
+        (DataType::Array(param), DataType::Array(arg)) if param.len == arg.len => unify(function, param.element, arg.element, literal, bindings)?,
+        (DataType::Pointer(param), DataType::Pointer(arg)) => unify(function, *param, *arg, literal, bindings)?,
+        (DataType::Tuple(param), DataType::Tuple(arg)) if param.elements.len() == arg.elements.len() => {
+            for (param, arg) in param.elements.iter().zip(&arg.elements) {
+                unify(function, *param, *arg, literal, bindings)?;
+            }
+        },
+        _ => {},
+    }
+
+    Ok(())
+}
+
+// the symbol of an instance, `$` keeps it apart from every name in the source
+pub fn instance_name(function: &DefStatement, types: &[DataType]) -> Identifier {
+    fn mangle(dtype: DataType) -> String {
+        match dtype {
+            DataType::Array(array) => format!("arr{}.{}", array.len, mangle(array.element)),
+            DataType::Pointer(pointee) => format!("ptr.{}", mangle(*pointee)),
+            DataType::Tuple(tuple) => format!("tup{}.{}", tuple.elements.len(), tuple.elements.iter().map(|dtype| mangle(*dtype)).collect::<Vec<String>>().join(".")),
+            dtype => dtype.to_string(),
+        }
+    }
+
+    format!("{}${}", function.name, types.iter().map(|dtype| mangle(*dtype)).collect::<Vec<String>>().join("$"))
+}
+
+// a copy of `function` for the types `infer` found, which is no longer generic
+pub fn instantiate(function: &DefStatement, types: &[DataType]) -> DefStatement {
+    let bindings: Vec<(&TypeParam, DataType)> = function.type_params.iter().copied().zip(types.iter().copied()).collect();
+
+    let mut instance = function.clone();
+    instance.name = instance_name(function, types);
+    instance.type_params = Vec::new();
+
+    for (_, dtype) in instance.params.iter_mut() {
+        *dtype = substitute(*dtype, &bindings);
+    }
+
+    instance.r#type = substitute(instance.r#type, &bindings);
+    substitute_stmts(&mut instance.stmts, &bindings);
+
+    instance
+}
+
+pub fn substitute(dtype: DataType, bindings: &[(&TypeParam, DataType)]) -> DataType {
+    match dtype {
+        DataType::Param(param) => bindings.iter().find(|(bound, _)| *bound == param).map_or(dtype, |(_, dtype)| *dtype),
+        DataType::Array(array) => DataType::array(substitute(array.element, bindings), array.len),
+        DataType::Pointer(pointee) => DataType::pointer(substitute(*pointee, bindings)),
+        DataType::Tuple(tuple) => DataType::tuple(tuple.elements.iter().map(|dtype| substitute(*dtype, bindings)).collect()),
+        dtype => dtype,
+    }
+}
+
+// types occur in declarations and in the literals that took their type from one
+fn substitute_stmts(stmts: &mut [Statement], bindings: &[(&TypeParam, DataType)]) {
+    for stmt in stmts {
+        match stmt {
+            Statement::Let(stmt) => {
+                stmt.r#type = substitute(stmt.r#type, bindings);
+                substitute_expr(&mut stmt.expr, bindings);
+            },
+            Statement::Destructure(stmt) => {
+                stmt.r#type = stmt.r#type.map(|dtype| substitute(dtype, bindings));
+                substitute_expr(&mut stmt.expr, bindings);
+            },
+            Statement::If(stmt) => substitute_if(stmt, bindings),
+            Statement::While(stmt) => {
+                substitute_expr(&mut stmt.condition, bindings);
+                substitute_stmts(&mut stmt.blocks, bindings);
+            },
+            Statement::Return(stmt) => substitute_expr(&mut stmt.expr, bindings),
+            Statement::Expression(stmt) => substitute_expr(&mut stmt.expr, bindings),
+            Statement::Assign(stmt) => {
+                substitute_expr(&mut stmt.target, bindings);
+                substitute_expr(&mut stmt.expr, bindings);
+            },
+            Statement::Match(stmt) => {
+                substitute_expr(&mut stmt.expr, bindings);
+                stmt.cases.iter_mut().for_each(|case| substitute_stmts(&mut case.stmts, bindings));
+            },
+            Statement::Def(_) | Statement::Extern(_) | Statement::Struct(_) | Statement::Enum(_) => {},
+        }
+    }
+}
+
+fn substitute_if(stmt: &mut IfStatement, bindings: &[(&TypeParam, DataType)]) {
+    substitute_expr(&mut stmt.condition, bindings);
+    substitute_stmts(&mut stmt.then, bindings);
+
+    match stmt.r#else.as_mut() {
+        IfBranch::Elif(stmt) => substitute_if(stmt, bindings),
+        IfBranch::Else(stmts) => substitute_stmts(stmts, bindings),
+        IfBranch::None => {},
+    }
+}
+
+fn substitute_expr(expr: &mut Expression, bindings: &[(&TypeParam, DataType)]) {
+    match expr {
+        Expression::Unary(expr) => substitute_expr(&mut expr.right, bindings),
+        Expression::Infix(expr) => {
+            substitute_expr(&mut expr.left, bindings);
+            substitute_expr(&mut expr.right, bindings);
+        },
+        Expression::FnCall(expr) => expr.args.iter_mut().for_each(|arg| substitute_expr(arg, bindings)),
+        Expression::MethodCall(expr) => {
+            substitute_expr(&mut expr.receiver, bindings);
+            expr.args.iter_mut().for_each(|arg| substitute_expr(arg, bindings));
+        },
+        Expression::Index(expr) => {
+            substitute_expr(&mut expr.array, bindings);
+            substitute_expr(&mut expr.index, bindings);
+        },
+        Expression::Array(elements) | Expression::Tuple(elements) => elements.iter_mut().for_each(|element| substitute_expr(element, bindings)),
+        Expression::Struct(expr) => expr.fields.iter_mut().for_each(|(_, field)| substitute_expr(field, bindings)),
+        Expression::Member(expr) => substitute_expr(&mut expr.expr, bindings),
+        Expression::Enum(expr) => expr.values.iter_mut().for_each(|value| substitute_expr(value, bindings)),
+        Expression::FormatString(expr) => expr.values_mut().for_each(|value| substitute_expr(value, bindings)),
+        // `null` takes the pointer type of its declaration, `*T` included
+        Expression::Literal((literal, dtype)) => {
+            *dtype = substitute(*dtype, bindings);
+
+            if let Literal::Null(pointer) = literal {
+                *pointer = *dtype;
+            }
+        },
+        Expression::Identifier(_) => {},
+    }
+}
\ No newline at end of file
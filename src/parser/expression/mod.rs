@@ -1,23 +1,31 @@
 use std::fmt;
-use crate::{lexer::Literal, types::DataType};
+use crate::{lexer::{FormatSpec, Literal}, types::DataType};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Expression {
     Unary(UnaryExpression),
     Infix(InfixExpression),
     FnCall(FnCallExpression),
+    MethodCall(MethodCallExpression),
+    Index(IndexExpression),
+    Array(Vec<Expression>),
+    Tuple(Vec<Expression>),
+    Struct(StructExpression),
+    Member(MemberExpression),
+    Enum(EnumExpression),
+    FormatString(FormatStringExpression),
     Identifier(Identifier),
     Literal((Literal, DataType)),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct UnaryExpression {
     pub operator: Operator,
     pub right: Box<Expression>,
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct InfixExpression {
     pub operator: Operator,
     pub left: Box<Expression>,
@@ -25,20 +33,113 @@ pub struct InfixExpression {
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FnCallExpression {
     pub ident: Identifier,
     pub args: Vec<Expression>,
 }
 
+// `receiver.method(args)`, a call to the function `types::StructType::method` names with the receiver as `self`
+#[derive(Debug, Clone)]
+pub struct MethodCallExpression {
+    pub receiver: Box<Expression>,
+    pub method: Identifier,
+    pub args: Vec<Expression>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexExpression {
+    pub array: Box<Expression>,
+    pub index: Box<Expression>,
+}
+
+// `Name { field: expr, .. }`, the fields in the order they are written
+#[derive(Debug, Clone)]
+pub struct StructExpression {
+    pub dtype: DataType,
+    pub fields: Vec<(Identifier, Expression)>,
+}
+
+// `expr.field`, where `expr` is a struct or a pointer to one, or `expr.0` for an element of a tuple
+#[derive(Debug, Clone)]
+pub struct MemberExpression {
+    pub expr: Box<Expression>,
+    pub field: Identifier,
+}
+
+// `Name.Variant` or `Name.Variant(a, b)`, a value of an enum with the values of its variant
+#[derive(Debug, Clone)]
+pub struct EnumExpression {
+    pub dtype: DataType,
+    pub variant: Identifier,
+    pub values: Vec<Expression>,
+}
+
+// `f"text {expr:spec} text"`, the text and the values in the order they are written
+#[derive(Debug, Clone)]
+pub struct FormatStringExpression {
+    pub pieces: Vec<FormatPiece>,
+    // the routine of `std` that prints the f-string and frees its buffer when it is handed straight to
+    // `print` or `println`, `None` when it is a `str`
+    pub printer: Option<Identifier>,
+}
+
+#[derive(Debug, Clone)]
+pub enum FormatPiece {
+    Text(String),
+    Value(Expression, FormatSpec),
+}
+
+impl FormatStringExpression {
+    pub fn values(&self) -> impl Iterator<Item = &Expression> {
+        self.pieces.iter().filter_map(|piece| match piece {
+            FormatPiece::Value(expr, _) => Some(expr),
+            FormatPiece::Text(_) => None,
+        })
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut Expression> {
+        self.pieces.iter_mut().filter_map(|piece| match piece {
+            FormatPiece::Value(expr, _) => Some(expr),
+            FormatPiece::Text(_) => None,
+        })
+    }
+
+    pub fn dtype(&self) -> DataType {
+        match self.printer {
+            Some(_) => DataType::void,
+            None => DataType::str,
+        }
+    }
+}
+
 pub type Identifier = String;
 
+// whether `expr` names a location in memory: a variable, an element of one, or what a pointer points to
+pub fn is_place(expr: &Expression) -> bool {
+    match expr {
+        Expression::Identifier(_) => true,
+        Expression::Index(expr) => is_place(&expr.array),
+        Expression::Member(expr) => is_place(&expr.expr),
+        Expression::Unary(expr) => expr.operator == Operator::Dereference,
+        _ => false,
+    }
+}
+
 impl fmt::Display for Expression {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::Unary(expr) => write!(f, "{}", expr),
             Self::Infix(expr) => write!(f, "{}", expr),
             Self::FnCall(expr) => write!(f, "{}", expr),
+            Self::MethodCall(expr) => write!(f, "{}", expr),
+            Self::Index(expr) => write!(f, "{}", expr),
+            Self::Struct(expr) => write!(f, "{}", expr),
+            Self::Member(expr) => write!(f, "{}", expr),
+            Self::Enum(expr) => write!(f, "{}", expr),
+            Self::FormatString(expr) => write!(f, "{}", expr),
+            Self::Array(elements) => write!(f, "{{ type: array, elements: {{ {} }} }}", elements.iter().map(|element| element.to_string()).collect::<Vec<String>>().join(", ")),
+            Self::Tuple(elements) => write!(f, "{{ type: tuple, elements: {{ {} }} }}", elements.iter().map(|element| element.to_string()).collect::<Vec<String>>().join(", ")),
             Self::Identifier(ident) => write!(f, "{}", ident),
             Self::Literal(literal) => write!(f, "{:?}", literal),
         }
@@ -63,6 +164,50 @@ impl fmt::Display for FnCallExpression {
     }
 }
 
+impl fmt::Display for MethodCallExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{ type: methodCall, receiver: {}, method: {}, args: {{ {} }} }}", self.receiver, self.method, self.args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>().join(", "))
+    }
+}
+
+impl fmt::Display for IndexExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{ type: index, array: {}, index: {} }}", self.array, self.index)
+    }
+}
+
+impl fmt::Display for StructExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{ type: struct, name: {}, fields: {{ {} }} }}", self.dtype, self.fields.iter().map(|(ident, expr)| format!("{}: {}", ident, expr)).collect::<Vec<String>>().join(", "))
+    }
+}
+
+impl fmt::Display for MemberExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{ type: member, expr: {}, field: {} }}", self.expr, self.field)
+    }
+}
+
+impl fmt::Display for EnumExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{ type: enum, name: {}, variant: {}, values: {{ {} }} }}", self.dtype, self.variant, self.values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(", "))
+    }
+}
+
+impl fmt::Display for FormatStringExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pieces: Vec<String> = self.pieces.iter()
+            .map(|piece| match piece {
+                FormatPiece::Text(text) => format!("{:?}", text),
+                FormatPiece::Value(expr, spec) if *spec == FormatSpec::default() => expr.to_string(),
+                FormatPiece::Value(expr, spec) => format!("{}:{}", expr, spec),
+            })
+            .collect();
+
+        write!(f, "{{ type: formatString, pieces: {{ {} }} }}", pieces.join(", "))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Operator {
     UnaryPlus,
@@ -85,6 +230,10 @@ pub enum Operator {
     LeftShift,
     RightShift,
     FnCall,
+    Index,
+    Member,
+    AddressOf,
+    Dereference,
 }
 
 impl Operator {
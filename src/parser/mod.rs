@@ -1,6 +1,9 @@
-use crate::lexer::{Token, Keyword};
+use std::collections::HashMap;
+
+use crate::lexer::{Token, Keyword, Literal, Span};
 use crate::parser::pratt::PrattParser;
 use crate::error::ParseError;
+use crate::types::DataType;
 
 pub mod pratt;
 mod expression;
@@ -11,7 +14,14 @@ pub use statement::*;
 
 pub struct Parser {
     tokens: Vec<Token>,
+    spans: Vec<Span>,
     pos: usize,
+    // the structs and enums declared so far, a type name is only known after its declaration
+    types: HashMap<Identifier, DataType>,
+    // the methods of the struct parsed last, waiting to be declared after it
+    methods: Vec<DefStatement>,
+    // the module being parsed, `None` for the program itself
+    module: Option<Identifier>,
 }
 
 pub type AST = Vec<Statement>;
@@ -20,8 +30,100 @@ impl Parser {
     pub fn new(tokens: Vec<Token>) -> Parser {
         Parser {
             tokens,
+            spans: Vec::new(),
+            pos: 0,
+            types: HashMap::new(),
+            methods: Vec::new(),
+            module: None,
+        }
+    }
+
+    pub fn with_spans(tokens: Vec<Token>, spans: Vec<Span>) -> Parser {
+        Parser {
+            tokens,
+            spans,
             pos: 0,
+            types: HashMap::new(),
+            methods: Vec::new(),
+            module: None,
+        }
+    }
+
+    // makes the structs and enums declared in `ast` known, for code that is parsed after it
+    pub fn with_types(mut self, ast: &AST) -> Parser {
+        for stmt in ast {
+            match stmt {
+                Statement::Struct(stmt) => { self.types.insert(stmt.name.clone(), stmt.dtype); },
+                Statement::Enum(stmt) => { self.types.insert(stmt.name.clone(), stmt.dtype); },
+                _ => {},
+            }
+        }
+
+        self
+    }
+
+    // parses the module `name`, whose structs and enums are named `name.Type` outside of it
+    pub fn with_module(mut self, name: &str) -> Parser {
+        self.module = Some(name.into());
+        self
+    }
+
+    // makes a struct or enum of another module known as `name`, `module.Type` after `import module`
+    pub fn import_type(&mut self, name: Identifier, dtype: DataType) {
+        self.types.insert(name, dtype);
+    }
+
+    // a parser for the tokens of an expression in an f-string, which knows the same types
+    fn embedded(&self, tokens: Vec<Token>) -> Parser {
+        Parser {
+            tokens,
+            spans: Vec::new(),
+            pos: 0,
+            types: self.types.clone(),
+            methods: Vec::new(),
+            module: self.module.clone(),
+        }
+    }
+
+    // the name of a struct or enum declared as `name`
+    fn qualify(&self, name: &str) -> Identifier {
+        match &self.module {
+            Some(module) => format!("{}.{}", module, name),
+            None => name.into(),
+        }
+    }
+
+    // when the next tokens spell `module.Type`, a type that `import_type` made known under a dotted name,
+    // moves onto the last of them and returns the name; the longest such name wins
+    fn parse_type_path(&mut self) -> Option<Identifier> {
+        let mut path = match self.next(0) {
+            Some(Token::Identifier(ident)) => ident,
+            _ => return None,
+        };
+        let mut n = 0;
+        let mut found = None;
+
+        while let (Some(Token::Dot), Some(Token::Identifier(ident))) = (self.next(n + 1), self.next(n + 2)) {
+            path = format!("{}.{}", path, ident);
+            n += 2;
+
+            if self.types.contains_key(&path) {
+                found = Some((path.clone(), n));
+            }
         }
+
+        let (path, n) = found?;
+        self.pos += n;
+        Some(path)
+    }
+
+    fn span(&self, n: usize) -> Span {
+        self.spans.get(self.pos + n).copied().unwrap_or_default()
+    }
+
+    // span of the token that was consumed last, usually the keyword of a statement
+    fn last_span(&self) -> Span {
+        self.spans.get(self.pos.wrapping_sub(1)).copied().unwrap_or_default()
     }
 
     fn next(&self, n: usize) -> Option<Token> {
@@ -32,6 +134,94 @@ impl Parser {
         }
     }
 
+    // parses a type keyword, a struct or enum name, `[T; N]`, `*T` or `(T, U, ..)`; nothing is consumed when the next token cannot start a type
+    fn parse_type(&mut self) -> Result<Option<DataType>, ParseError> {
+        if let Some(path) = self.parse_type_path() {
+            self.pos += 1;
+            return Ok(Some(self.types[&path]));
+        }
+
+        match self.next(0) {
+            Some(Token::Keyword(Keyword::DataType(dtype))) => {
+                self.pos += 1;
+                Ok(Some(dtype))
+            },
+            Some(Token::Identifier(ident)) if self.types.contains_key(&ident) => {
+                self.pos += 1;
+                Ok(Some(self.types[&ident]))
+            },
+            Some(Token::LSqBr) => {
+                self.pos += 1;
+
+                let element = match self.next(0) {
+                    Some(token) => self.parse_type()?.ok_or_else(|| ParseError(format!("[Parser::parse_type] expected element type, found {token:?}")))?,
+                    None => return Err(ParseError("[Parser::parse_type] insufficient tokens".into())),
+                };
+
+                match self.next(0) {
+                    Some(Token::Semicolon) => self.pos += 1,
+                    Some(token) => return Err(ParseError(format!("[Parser::parse_type] expected `;`, found {token:?}"))),
+                    None => return Err(ParseError("[Parser::parse_type] insufficient tokens".into())),
+                }
+
+                let len = match self.next(0) {
+                    Some(Token::Literal(Literal::SignedInteger((n, _)))) if u64::try_from(n).is_ok() => n as u64,
+                    Some(Token::Literal(Literal::UnsignedInteger((n, _)))) if u64::try_from(n).is_ok() => n as u64,
+                    Some(token) => return Err(ParseError(format!("[Parser::parse_type] expected array length, found {token:?}"))),
+                    None => return Err(ParseError("[Parser::parse_type] insufficient tokens".into())),
+                };
+
+                self.pos += 1;
+
+                match self.next(0) {
+                    Some(Token::RSqBr) => self.pos += 1,
+                    Some(token) => return Err(ParseError(format!("[Parser::parse_type] expected `]`, found {token:?}"))),
+                    None => return Err(ParseError("[Parser::parse_type] insufficient tokens".into())),
+                }
+
+                Ok(Some(DataType::array(element, len)))
+            },
+            Some(Token::Star) => {
+                self.pos += 1;
+
+                let pointee = match self.next(0) {
+                    Some(token) => self.parse_type()?.ok_or_else(|| ParseError(format!("[Parser::parse_type] expected pointee type, found {token:?}")))?,
+                    None => return Err(ParseError("[Parser::parse_type] insufficient tokens".into())),
+                };
+
+                Ok(Some(DataType::pointer(pointee)))
+            },
+            Some(Token::LParen) => {
+                self.pos += 1;
+
+                let mut elements = Vec::new();
+
+                loop {
+                    match self.next(0) {
+                        Some(token) => elements.push(self.parse_type()?.ok_or_else(|| ParseError(format!("[Parser::parse_type] expected element type, found {token:?}")))?),
+                        None => return Err(ParseError("[Parser::parse_type] insufficient tokens".into())),
+                    }
+
+                    match self.next(0) {
+                        Some(Token::Comma) => self.pos += 1,
+                        Some(Token::RParen) => break,
+                        Some(token) => return Err(ParseError(format!("[Parser::parse_type] expected `,` or `)`, found {token:?}"))),
+                        None => return Err(ParseError("[Parser::parse_type] insufficient tokens".into())),
+                    }
+                }
+
+                self.pos += 1;
+
+                if elements.len() < 2 {
+                    return Err(ParseError("[Parser::parse_type] a tuple needs at least two elements".into()));
+                }
+
+                Ok(Some(DataType::tuple(elements)))
+            },
+            _ => Ok(None),
+        }
+    }
+
     pub fn parse_stmt(&mut self) -> Result<Option<Statement>, ParseError> {
         let token = &self.tokens[self.pos];
 
@@ -40,28 +230,48 @@ impl Parser {
                 match keyword {
                     Keyword::Def => {
                         self.pos += 1;
-                        Some(Statement::Def(parse_def(self).unwrap()))
+                        Some(Statement::Def(parse_def(self)?))
                     },
                     Keyword::If => {
                         self.pos += 1;
-                        Some(Statement::If(parse_if(self).unwrap()))
+                        Some(Statement::If(parse_if(self)?))
+                    },
+                    Keyword::Let if self.next(1) == Some(Token::LParen) => {
+                        self.pos += 1;
+                        Some(Statement::Destructure(parse_destructure(self)?))
                     },
                     Keyword::Let => {
                         self.pos += 1;
-                        Some(Statement::Let(parse_let(self).unwrap()))
+                        Some(Statement::Let(parse_let(self)?))
                     },
                     Keyword::Return => {
                         self.pos += 1;
-                        Some(Statement::Return(parse_return(self).unwrap()))
+                        Some(Statement::Return(parse_return(self)?))
                     },
                     Keyword::Extern => {
                         self.pos += 1;
-                        Some(Statement::Extern(parse_extern(self).unwrap()))
+                        Some(Statement::Extern(parse_extern(self)?))
                     },
                     Keyword::While => {
                         self.pos += 1;
-                        Some(Statement::While(parse_while(self).unwrap()))
-                    }
+                        Some(Statement::While(parse_while(self)?))
+                    },
+                    Keyword::Struct => {
+                        self.pos += 1;
+                        Some(Statement::Struct(parse_struct(self)?))
+                    },
+                    Keyword::Enum => {
+                        self.pos += 1;
+                        Some(Statement::Enum(parse_enum(self)?))
+                    },
+                    Keyword::Match => {
+                        self.pos += 1;
+                        Some(Statement::Match(parse_match(self)?))
+                    },
+                    Keyword::Import | Keyword::From => {
+                        self.pos += 1;
+                        return Err(ParseError("[Parser::parse_stmt] `import` and `from` must come before every other statement".into()));
+                    },
                     _ => {
                         self.pos += 1;
                         return Err(ParseError(format!("[Parser::parse_stmt] unexpected keyword {:?}", keyword)));
@@ -71,25 +281,47 @@ impl Parser {
             Token::EOF => {
                 None
             },
-            _ => Some(Statement::Expression(parse_expr_stmt(self).unwrap())),
+            _ => Some(parse_expr_stmt(self)?),
         };
 
         Ok(stmt)
     }
 
-    pub fn parse_all(&mut self) -> AST {
-        let mut ast = AST::new();
+    // parses the `import` and `from` statements a program starts with, which `parse_program` does not accept
+    pub fn parse_imports(&mut self) -> Result<Vec<ImportStatement>, ParseError> {
+        let mut imports = Vec::new();
 
         loop {
-            let stmt = self.parse_stmt ().unwrap();
-
-            if let Some(stmt) = stmt {
-                ast.push(stmt);
-            } else {
-                break;
+            match self.next(0) {
+                Some(Token::Keyword(Keyword::Import)) => {
+                    self.pos += 1;
+                    imports.push(parse_import(self)?);
+                },
+                Some(Token::Keyword(Keyword::From)) => {
+                    self.pos += 1;
+                    imports.push(parse_from(self)?);
+                },
+                _ => break,
             }
         }
 
-        ast
+        Ok(imports)
+    }
+
+    pub fn parse_all(&mut self) -> AST {
+        self.parse_program().unwrap()
+    }
+
+    // same as `parse_all`, but hands the first syntax error back to the caller
+    pub fn parse_program(&mut self) -> Result<AST, ParseError> {
+        let mut ast = AST::new();
+
+        while let Some(stmt) = self.parse_stmt()? {
+            ast.push(stmt);
+            // methods become functions of their own, named by `types::StructType::method`
+            ast.extend(self.methods.drain(..).map(Statement::Def));
+        }
+
+        Ok(ast)
     }
 }
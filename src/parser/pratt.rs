@@ -1,5 +1,5 @@
-use crate::parser::{Parser, Expression, ParseError, Operator};
-use crate::lexer::{Literal, Token};
+use crate::parser::{Parser, Expression, Identifier, ParseError, Operator};
+use crate::lexer::{FormatPart, Literal, Token};
 use crate::types::{DataType, SignedInteger, UnsignedInteger};
 
 use super::expression::*;
@@ -26,6 +26,7 @@ pub enum Precedence {
     // Await,
     Paren,
     FnCall,
+    Index,
 }
 
 pub struct PrattParser;
@@ -37,6 +38,8 @@ impl PrattParser {
                 Token::Plus => Some(Operator::UnaryPlus),
                 Token::Minus => Some(Operator::UnaryMinus),
                 Token::Tilde => Some(Operator::BitwiseNot),
+                Token::Ampersand => Some(Operator::AddressOf),
+                Token::Star => Some(Operator::Dereference),
                 _ => None,
             }
         } else {
@@ -55,7 +58,11 @@ impl PrattParser {
                 Token::Ampersand => Some(Operator::BitwiseAnd),
                 Token::Circumflex => Some(Operator::BitwiseXor),
                 Token::VBar => Some(Operator::BitwiseOr),
+                Token::LeftShift => Some(Operator::LeftShift),
+                Token::RightShift => Some(Operator::RightShift),
                 Token::LParen => Some(Operator::FnCall),
+                Token::LSqBr => Some(Operator::Index),
+                Token::Dot => Some(Operator::Member),
                 _ => None,
             }
         }
@@ -72,36 +79,51 @@ impl PrattParser {
             Operator::Less | Operator::LessEqual | Operator::Greater |
             Operator::GreaterEqual => Precedence::LessGreater,
             Operator::Equal | Operator::NotEqual => Precedence::EqualNotEqual,
-            Operator::FnCall => Precedence::FnCall,
+            Operator::FnCall | Operator::Member => Precedence::FnCall,
+            Operator::Index => Precedence::Index,
             _ => return Err(ParseError(format!("[PrattParser::get_precedence] unknown operator {:?}", operator)))
         })
     }
 
     // TODO: Support various types
     pub fn parse_expr(parser: &mut Parser, precedence: Precedence, expected_dtype: Option<DataType>) -> Result<Expression, ParseError> {
-        let token = parser.next(0).unwrap();
+        let token = parser.next(0).ok_or_else(|| ParseError("[PrattParser::parse_expr] insufficient tokens".into()))?;
+
+        // `module.Name { .. }` and `module.Name.Variant` use a struct or enum of another module
+        let token = match parser.parse_type_path() {
+            Some(path) => Token::Identifier(path),
+            None => token,
+        };
 
         // TODO: refactor
         // TODO: support type casting
         let prefix: Option<Expression> = match token.clone() {
+            Token::Identifier(ident) if parser.next(1) == Some(Token::LBrace) => Some(Expression::Struct(PrattParser::parse_struct(parser, ident)?)),
+            Token::Identifier(ident) if matches!(parser.types.get(&ident), Some(DataType::Enum(_))) => Some(Expression::Enum(PrattParser::parse_enum(parser, ident)?)),
             Token::Identifier(ident) => Some(Expression::Identifier(ident)),
             Token::Literal(literal) => {
+                // a type parameter's type is only known per instance, which converts the literal like any other value
+                let expected_dtype = expected_dtype.filter(|dtype| !matches!(dtype, DataType::Param(_)));
+
                 match literal {
                     Literal::SignedInteger((n, _)) => {
-                        let dtype = if let DataType::SignedInteger(signed) = expected_dtype.unwrap_or(DataType::SignedInteger(SignedInteger::i32)) {
-                            signed
-                        } else {
-                            panic!();
-                        };
-
-                        let literal = Literal::SignedInteger((n, dtype));
-                        Some(Expression::Literal((literal, DataType::SignedInteger(dtype))))
+                        match expected_dtype.unwrap_or(DataType::SignedInteger(SignedInteger::i32)) {
+                            DataType::SignedInteger(dtype) => {
+                                let literal = Literal::SignedInteger((n, dtype));
+                                Some(Expression::Literal((literal, DataType::SignedInteger(dtype))))
+                            },
+                            // the lexer never yields negative numbers, so the value carries over as is
+                            DataType::UnsignedInteger(dtype) => {
+                                let literal = Literal::UnsignedInteger((n as u128, dtype));
+                                Some(Expression::Literal((literal, DataType::UnsignedInteger(dtype))))
+                            },
+                            dtype => return Err(ParseError(format!("[PrattParser::parse_expr] expected {}, found integer literal {}", dtype.to_mnemonic(), n))),
+                        }
                     },
                     Literal::UnsignedInteger((n, _)) => {
-                        let dtype = if let DataType::UnsignedInteger(unsigned) = expected_dtype.unwrap_or(DataType::UnsignedInteger(UnsignedInteger::u32)) {
-                            unsigned
-                        } else {
-                            panic!();
+                        let dtype = match expected_dtype.unwrap_or(DataType::UnsignedInteger(UnsignedInteger::u32)) {
+                            DataType::UnsignedInteger(unsigned) => unsigned,
+                            dtype => return Err(ParseError(format!("[PrattParser::parse_expr] expected {}, found integer literal {}", dtype.to_mnemonic(), n))),
                         };
 
                         let literal = Literal::UnsignedInteger((n, dtype));
@@ -112,28 +134,52 @@ impl PrattParser {
                     },
                     Literal::Boolean(b) => {
                         Some(Expression::Literal((Literal::Boolean(b), DataType::bool)))
-                    }
+                    },
+                    // like integer literals, `null` takes the pointer type the context expects
+                    Literal::Null(dtype) => {
+                        let dtype = match expected_dtype.unwrap_or(dtype) {
+                            dtype @ DataType::Pointer(_) => dtype,
+                            dtype => return Err(ParseError(format!("[PrattParser::parse_expr] expected {}, found null", dtype.to_mnemonic()))),
+                        };
+
+                        Some(Expression::Literal((Literal::Null(dtype), dtype)))
+                    },
                     // TODO: Unsigned Integer with 'u' suffix
                 }
             },
+            Token::FormatString(parts) => Some(Expression::FormatString(PrattParser::parse_format_string(parser, parts)?)),
             Token::LParen => {
                 parser.pos += 1;
 
-                let expr = PrattParser::parse_expr(parser, Precedence::Lowest, None);
+                // the elements of a tuple literal take the element types the context expects
+                let element_dtypes = match expected_dtype {
+                    Some(DataType::Tuple(tuple)) => tuple.elements.as_slice(),
+                    _ => &[],
+                };
 
-                if let Some(token) = parser.next(1) {
-                    if token == Token::RParen {
+                let expr = PrattParser::parse_expr(parser, Precedence::Lowest, element_dtypes.first().copied())?;
+
+                match parser.next(1) {
+                    Some(Token::RParen) => {
                         parser.pos += 1;
-                        Some(expr.unwrap())
-                    } else {
-                        return Err(ParseError("[PrattParser::parse_expr] RParen not found".into()));
-                    }
-                } else {
-                    return Err(ParseError("[PrattParser::parse_expr] insufficient tokens".into()));
+                        Some(expr)
+                    },
+                    Some(Token::Comma) => Some(Expression::Tuple(PrattParser::parse_tuple(parser, expr, element_dtypes)?)),
+                    Some(_) => return Err(ParseError("[PrattParser::parse_expr] RParen not found".into())),
+                    None => return Err(ParseError("[PrattParser::parse_expr] insufficient tokens".into())),
                 }
-            }
-            Token::Plus | Token::Minus | Token::Tilde => Some(PrattParser::parse_nud(parser).unwrap()),
-            _ => { println!("unexpected token {token:?} found"); None }
+            },
+            Token::LSqBr => {
+                // the elements of an array literal take the element type the context expects
+                let element_dtype = match expected_dtype {
+                    Some(DataType::Array(array)) => Some(array.element),
+                    _ => None,
+                };
+
+                Some(Expression::Array(PrattParser::parse_array(parser, element_dtype)?))
+            },
+            Token::Plus | Token::Minus | Token::Tilde | Token::Ampersand | Token::Star => Some(PrattParser::parse_nud(parser, expected_dtype)?),
+            _ => return Err(ParseError(format!("[PrattParser::parse_expr] unexpected token {token:?} found"))),
         };
 
         let mut expr = prefix.unwrap();
@@ -159,7 +205,7 @@ impl PrattParser {
                 return Err(ParseError("[PrattParser::parse_expr] insufficient tokens".into()));
             };
 
-            if precedence >= PrattParser::get_precedence(&operator).unwrap() {
+            if precedence >= PrattParser::get_precedence(&operator)? {
                 return Ok(expr);
             }
 
@@ -170,7 +216,9 @@ impl PrattParser {
                 Token::Star | Token::EqualEqual | Token::NotEqual |
                 Token::Less | Token::LessEqual | Token::Greater | 
                 Token::GreaterEqual | Token::Percent | Token::Ampersand |
-                Token::Circumflex | Token::VBar | Token::LParen => expr = PrattParser::parse_led(parser, expr).unwrap(),
+                Token::Circumflex | Token::VBar | Token::LeftShift |
+                Token::RightShift | Token::LParen | Token::LSqBr |
+                Token::Dot => expr = PrattParser::parse_led(parser, expr)?,
                 Token::EOF => {
                     return Ok(expr);
                 }
@@ -182,16 +230,47 @@ impl PrattParser {
         };
     }
 
-    pub fn parse_nud(parser: &mut Parser) -> Result<Expression, ParseError> {
+    pub fn parse_format_string(parser: &Parser, parts: Vec<FormatPart>) -> Result<FormatStringExpression, ParseError> {
+        let pieces = parts.into_iter()
+            .map(|part| match part {
+                FormatPart::Text(text) => Ok(FormatPiece::Text(text)),
+                FormatPart::Value(tokens, spec) => {
+                    let mut embedded = parser.embedded(tokens);
+                    let expr = PrattParser::parse_expr(&mut embedded, Precedence::Lowest, None)?;
+
+                    match embedded.next(1) {
+                        Some(Token::EOF) => Ok(FormatPiece::Value(expr, spec)),
+                        Some(token) => Err(ParseError(format!("[PrattParser::parse_format_string] expected `}}` after the expression, found {token:?}"))),
+                        None => Err(ParseError("[PrattParser::parse_format_string] insufficient tokens".into())),
+                    }
+                },
+            })
+            .collect::<Result<Vec<FormatPiece>, ParseError>>()?;
+
+        Ok(FormatStringExpression { pieces, printer: None })
+    }
+
+    pub fn parse_nud(parser: &mut Parser, expected_dtype: Option<DataType>) -> Result<Expression, ParseError> {
         let operator = if let Some(token) = parser.next(0) {
-            PrattParser::get_operator(&token, true).unwrap()
+            PrattParser::get_operator(&token, true).ok_or_else(|| ParseError(format!("[PrattParser::parse_nud] expected prefix operator, found {token:?}")))?
         } else {
             return Err(ParseError("[PrattParser::parse_nud] insufficient tokens".into()));
         };
 
         parser.pos += 1;
 
-        let right = PrattParser::parse_expr(parser, Precedence::Unary, None).unwrap();
+        // the operand of `+`, `-` and `~` takes the expected type, except that a negated one stays signed
+        let operand_dtype = match (operator, expected_dtype) {
+            (Operator::UnaryMinus, Some(DataType::UnsignedInteger(_))) => None,
+            (Operator::UnaryPlus | Operator::UnaryMinus | Operator::BitwiseNot, dtype) => dtype,
+            _ => None,
+        };
+
+        let right = PrattParser::parse_expr(parser, Precedence::Unary, operand_dtype)?;
+
+        if operator == Operator::AddressOf && !is_place(&right) {
+            return Err(ParseError(format!("[PrattParser::parse_nud] cannot take the address of {}", right)));
+        }
 
         let unary_expr = UnaryExpression {
             operator,
@@ -203,22 +282,31 @@ impl PrattParser {
 
     pub fn parse_led(parser: &mut Parser, left: Expression) -> Result<Expression, ParseError> {
         let operator = if let Some(token) = parser.next(0) {
-            PrattParser::get_operator(&token, false).unwrap()
+            PrattParser::get_operator(&token, false).ok_or_else(|| ParseError(format!("[PrattParser::parse_led] expected infix operator, found {token:?}")))?
         } else {
             return Err(ParseError("[PrattParser::parse_led] insufficient tokens".into()));
         };
 
-        let precedence = PrattParser::get_precedence(&operator).unwrap();
+        let precedence = PrattParser::get_precedence(&operator)?;
         
         if let Operator::FnCall = operator {
-            let fncall_expr = PrattParser::parse_fncall(parser, left).unwrap();
-            return Ok(Expression::FnCall(fncall_expr));
+            return PrattParser::parse_fncall(parser, left);
+        }
+
+        if let Operator::Index = operator {
+            let index_expr = PrattParser::parse_index(parser, left)?;
+            return Ok(Expression::Index(index_expr));
+        }
+
+        if let Operator::Member = operator {
+            let member_expr = PrattParser::parse_member(parser, left)?;
+            return Ok(Expression::Member(member_expr));
         }
 
         parser.pos += 1;
 
 
-        let right = PrattParser::parse_expr(parser, precedence, None).unwrap();
+        let right = PrattParser::parse_expr(parser, precedence, None)?;
 
         let infix_expr = InfixExpression {
             operator,
@@ -229,29 +317,39 @@ impl PrattParser {
         Ok(Expression::Infix(infix_expr))
     }
 
-    pub fn parse_fncall(parser: &mut Parser, left: Expression) -> Result<FnCallExpression, ParseError> {
-        let ident = if let Expression::Identifier(ident) = left {
-            ident
-        } else {
-            return Err(ParseError("[FnCallExpression] expected identifier, found ?".into()));
-        };
+    // a function is called by its name, a method through a member of its receiver
+    pub fn parse_fncall(parser: &mut Parser, left: Expression) -> Result<Expression, ParseError> {
+        let args = PrattParser::parse_args(parser)?;
+
+        match left {
+            Expression::Identifier(ident) => Ok(Expression::FnCall(FnCallExpression {
+                ident,
+                args,
+            })),
+            Expression::Member(member) => Ok(Expression::MethodCall(MethodCallExpression {
+                receiver: member.expr,
+                method: member.field,
+                args,
+            })),
+            callee => Err(ParseError(format!("[FnCallExpression] {} cannot be called", callee))),
+        }
+    }
+
+    fn parse_args(parser: &mut Parser) -> Result<Vec<Expression>, ParseError> {
         let mut args: Vec<Expression> = Vec::new();
 
         parser.pos += 1;
 
         if let Some(token) = parser.next(0) {
             if let Token::RParen = token {
-                return Ok(FnCallExpression {
-                    ident,
-                    args,
-                })
+                return Ok(args);
             }
         } else {
             return Err(ParseError("[FnCallExpression] insufficient tokens".into()));
         }
 
         loop {
-            let arg = PrattParser::parse_expr(parser, Precedence::Lowest, None).unwrap();
+            let arg = PrattParser::parse_expr(parser, Precedence::Lowest, None)?;
             args.push(arg);
 
             parser.pos += 1;
@@ -274,9 +372,213 @@ impl PrattParser {
             }
         }
 
-        Ok(FnCallExpression {
-            ident,
-            args,
+        Ok(args)
+    }
+
+    pub fn parse_index(parser: &mut Parser, array: Expression) -> Result<IndexExpression, ParseError> {
+        parser.pos += 1;
+
+        if parser.next(0).is_none() {
+            return Err(ParseError("[IndexExpression] insufficient tokens".into()));
+        }
+
+        let index = PrattParser::parse_expr(parser, Precedence::Lowest, None)?;
+
+        parser.pos += 1;
+
+        match parser.next(0) {
+            Some(Token::RSqBr) => {},
+            Some(token) => return Err(ParseError(format!("[IndexExpression] expected `]`, found {token:?}"))),
+            None => return Err(ParseError("[IndexExpression] insufficient tokens".into())),
+        }
+
+        Ok(IndexExpression {
+            array: Box::new(array),
+            index: Box::new(index),
+        })
+    }
+
+    // `[a, b, c]`, a trailing comma is allowed
+    pub fn parse_array(parser: &mut Parser, element_dtype: Option<DataType>) -> Result<Vec<Expression>, ParseError> {
+        let mut elements: Vec<Expression> = Vec::new();
+
+        parser.pos += 1;
+
+        loop {
+            match parser.next(0) {
+                Some(Token::RSqBr) => break,
+                Some(_) => {},
+                None => return Err(ParseError("[ArrayExpression] insufficient tokens".into())),
+            }
+
+            elements.push(PrattParser::parse_expr(parser, Precedence::Lowest, element_dtype)?);
+
+            parser.pos += 1;
+
+            match parser.next(0) {
+                Some(Token::Comma) => parser.pos += 1,
+                Some(Token::RSqBr) => break,
+                Some(token) => return Err(ParseError(format!("[ArrayExpression] expected `,` or `]`, found {token:?}"))),
+                None => return Err(ParseError("[ArrayExpression] insufficient tokens".into())),
+            }
+        }
+
+        Ok(elements)
+    }
+
+    pub fn parse_member(parser: &mut Parser, expr: Expression) -> Result<MemberExpression, ParseError> {
+        parser.pos += 1;
+
+        // the elements of a tuple are named by their position
+        let field = match parser.next(0) {
+            Some(Token::Identifier(field)) => field,
+            Some(Token::Literal(Literal::SignedInteger((n, _)))) => n.to_string(),
+            Some(token) => return Err(ParseError(format!("[MemberExpression] expected field name, found {token:?}"))),
+            None => return Err(ParseError("[MemberExpression] insufficient tokens".into())),
+        };
+
+        Ok(MemberExpression {
+            expr: Box::new(expr),
+            field,
+        })
+    }
+
+    // `(a, b, ..)` after its first element, which is followed by a comma
+    pub fn parse_tuple(parser: &mut Parser, first: Expression, element_dtypes: &[DataType]) -> Result<Vec<Expression>, ParseError> {
+        let mut elements = vec![first];
+
+        parser.pos += 1;
+
+        while let Some(Token::Comma) = parser.next(0) {
+            parser.pos += 1;
+
+            let element_dtype = element_dtypes.get(elements.len()).copied();
+            elements.push(PrattParser::parse_expr(parser, Precedence::Lowest, element_dtype)?);
+
+            parser.pos += 1;
+        }
+
+        match parser.next(0) {
+            Some(Token::RParen) => Ok(elements),
+            Some(token) => Err(ParseError(format!("[TupleExpression] expected `,` or `)`, found {token:?}"))),
+            None => Err(ParseError("[TupleExpression] insufficient tokens".into())),
+        }
+    }
+
+    // `Name { a: x, b: y }`, every field exactly once and in any order, a trailing comma is allowed
+    pub fn parse_struct(parser: &mut Parser, name: Identifier) -> Result<StructExpression, ParseError> {
+        let dtype = *parser.types.get(&name).ok_or_else(|| ParseError(format!("[StructExpression] unknown struct `{}`", name)))?;
+
+        let structure = match dtype {
+            DataType::Struct(structure) => structure,
+            dtype => return Err(ParseError(format!("[StructExpression] {} is not a struct", dtype))),
+        };
+
+        let mut fields: Vec<(Identifier, Expression)> = Vec::new();
+
+        parser.pos += 2;
+
+        loop {
+            let field = match parser.next(0) {
+                Some(Token::RBrace) => break,
+                Some(Token::Identifier(field)) => field,
+                Some(token) => return Err(ParseError(format!("[StructExpression] expected field name, found {token:?}"))),
+                None => return Err(ParseError("[StructExpression] insufficient tokens".into())),
+            };
+
+            let (_, field_dtype) = structure.field(&field)
+                .ok_or_else(|| ParseError(format!("[StructExpression] `{}` has no field `{}`", name, field)))?;
+
+            if fields.iter().any(|(ident, _)| *ident == field) {
+                return Err(ParseError(format!("[StructExpression] field `{}` is given twice", field)));
+            }
+
+            parser.pos += 1;
+
+            match parser.next(0) {
+                Some(Token::Colon) => parser.pos += 1,
+                Some(token) => return Err(ParseError(format!("[StructExpression] expected `:`, found {token:?}"))),
+                None => return Err(ParseError("[StructExpression] insufficient tokens".into())),
+            }
+
+            fields.push((field, PrattParser::parse_expr(parser, Precedence::Lowest, Some(field_dtype))?));
+
+            parser.pos += 1;
+
+            match parser.next(0) {
+                Some(Token::Comma) => parser.pos += 1,
+                Some(Token::RBrace) => break,
+                Some(token) => return Err(ParseError(format!("[StructExpression] expected `,` or `}}`, found {token:?}"))),
+                None => return Err(ParseError("[StructExpression] insufficient tokens".into())),
+            }
+        }
+
+        if let Some((missing, _)) = structure.fields.iter().find(|(ident, _)| fields.iter().all(|(field, _)| field != ident)) {
+            return Err(ParseError(format!("[StructExpression] missing field `{}` of `{}`", missing, name)));
+        }
+
+        Ok(StructExpression {
+            dtype,
+            fields,
+        })
+    }
+
+    // `Name.Variant`, or `Name.Variant(a, b)` with exactly the values the variant declares
+    pub fn parse_enum(parser: &mut Parser, name: Identifier) -> Result<EnumExpression, ParseError> {
+        let dtype = parser.types[&name];
+
+        let enumeration = match dtype {
+            DataType::Enum(enumeration) => enumeration,
+            dtype => return Err(ParseError(format!("[EnumExpression] {} is not an enum", dtype))),
+        };
+
+        match parser.next(1) {
+            Some(Token::Dot) => parser.pos += 2,
+            Some(token) => return Err(ParseError(format!("[EnumExpression] expected `.`, found {token:?}"))),
+            None => return Err(ParseError("[EnumExpression] insufficient tokens".into())),
+        }
+
+        let variant = match parser.next(0) {
+            Some(Token::Identifier(variant)) => enumeration.variant(&variant)
+                .ok_or_else(|| ParseError(format!("[EnumExpression] `{}` has no variant `{}`", name, variant)))?,
+            Some(token) => return Err(ParseError(format!("[EnumExpression] expected variant name, found {token:?}"))),
+            None => return Err(ParseError("[EnumExpression] insufficient tokens".into())),
+        };
+
+        let mut values: Vec<Expression> = Vec::new();
+
+        if let Some(Token::LParen) = parser.next(1) {
+            parser.pos += 2;
+
+            loop {
+                match parser.next(0) {
+                    Some(Token::RParen) => break,
+                    Some(_) => {},
+                    None => return Err(ParseError("[EnumExpression] insufficient tokens".into())),
+                }
+
+                let value_dtype = variant.payload.get(values.len()).copied();
+                values.push(PrattParser::parse_expr(parser, Precedence::Lowest, value_dtype)?);
+
+                parser.pos += 1;
+
+                match parser.next(0) {
+                    Some(Token::Comma) => parser.pos += 1,
+                    Some(Token::RParen) => break,
+                    Some(token) => return Err(ParseError(format!("[EnumExpression] expected `,` or `)`, found {token:?}"))),
+                    None => return Err(ParseError("[EnumExpression] insufficient tokens".into())),
+                }
+            }
+        }
+
+        if values.len() != variant.payload.len() {
+            return Err(ParseError(format!("[EnumExpression] `{}.{}` takes {} values but {} were given", name, variant.name, variant.payload.len(), values.len())));
+        }
+
+        Ok(EnumExpression {
+            dtype,
+            variant: variant.name.clone(),
+            values,
         })
     }
 }
\ No newline at end of file
@@ -0,0 +1,58 @@
+use crate::parser::{Parser, PrattParser, Token};
+use crate::parser::{is_place, Expression};
+use crate::error::ParseError;
+use crate::parser::pratt::Precedence;
+use crate::lexer::Span;
+use std::fmt;
+
+// `target = expr;`, where the target is an element of an array variable or what a pointer points to
+#[derive(Debug, Clone)]
+pub struct AssignStatement {
+    pub target: Expression,
+    pub expr: Expression,
+    pub span: Span,
+}
+
+// called with the target parsed and `=` as the next token
+pub fn parse_assign(parser: &mut Parser, target: Expression, span: Span) -> Result<AssignStatement, ParseError> {
+    if !is_assignable(&target) {
+        return Err(ParseError(format!("[AssignStatement] cannot assign to {}", target)));
+    }
+
+    parser.pos += 1;
+
+    if parser.next(0).is_none() {
+        return Err(ParseError("[AssignStatement] insufficient tokens".into()));
+    }
+
+    let expr = PrattParser::parse_expr(parser, Precedence::Lowest, None)?;
+
+    parser.pos += 1;
+
+    if let Some(token) = parser.next(0) {
+        if let Token::Semicolon = token {
+            parser.pos += 1;
+        } else {
+            return Err(ParseError(format!("[AssignStatement] Expected `;`, found {token:?}")));
+        }
+    } else {
+        return Err(ParseError("[AssignStatement] insufficient tokens".into()));
+    }
+
+    Ok(AssignStatement {
+        target,
+        expr,
+        span,
+    })
+}
+
+// variables themselves cannot be reassigned, only their elements and pointees
+fn is_assignable(target: &Expression) -> bool {
+    !matches!(target, Expression::Identifier(_)) && is_place(target)
+}
+
+impl fmt::Display for AssignStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{ type: assign, target: {}, expr: {} }}", self.target, self.expr)
+    }
+}
\ No newline at end of file
@@ -1,20 +1,31 @@
 use std::fmt;
-use crate::lexer::Keyword;
+use crate::lexer::Span;
 use crate::parser::{Parser, Token, Identifier, Statement};
 use crate::error::ParseError;
-use crate::types::DataType;
+use crate::types::{Constraint, DataType, TypeParam};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DefStatement {
     pub name: Identifier,
+    // `[T, U: int]` after the name, every call instantiates the function for the types it infers
+    pub type_params: Vec<&'static TypeParam>,
     pub params: Vec<(Identifier, DataType)>,
     pub r#type: DataType,
     pub stmts: Vec<Statement>,
+    pub span: Span,
 }
 
 pub fn parse_def(parser: &mut Parser) -> Result<DefStatement, ParseError> {
-    let mut params: Vec<(Identifier, DataType)> = Vec::new();
-    let mut stmts: Vec<Statement> = Vec::new();
+    parse_fn(parser, None)
+}
+
+// a `def` in the block of the struct `receiver`, whose first parameter is `self` or `*self`
+pub fn parse_method(parser: &mut Parser, receiver: DataType) -> Result<DefStatement, ParseError> {
+    parse_fn(parser, Some(receiver))
+}
+
+fn parse_fn(parser: &mut Parser, receiver: Option<DataType>) -> Result<DefStatement, ParseError> {
+    let span = parser.last_span();
 
     let name = if let Some(token) = parser.next(0) {
         if let Token::Identifier(ident) = token {
@@ -27,6 +38,91 @@ pub fn parse_def(parser: &mut Parser) -> Result<DefStatement, ParseError> {
         return Err(ParseError("[DefStatement] insufficient tokens".into()));
     };
 
+    let type_params = if let Some(Token::LSqBr) = parser.next(0) {
+        if receiver.is_some() {
+            return Err(ParseError(format!("[DefStatement] method `{}` cannot take type parameters", name)));
+        }
+
+        parse_type_params(parser)?
+    } else {
+        Vec::new()
+    };
+
+    // the type parameters are types of their own until the end of the function
+    for param in &type_params {
+        parser.types.insert(param.name.clone(), DataType::Param(param));
+    }
+
+    let names: Vec<Identifier> = type_params.iter().map(|param| param.name.clone()).collect();
+    let result = parse_signature(parser, name, type_params, receiver, span);
+
+    for name in names {
+        parser.types.remove(&name);
+    }
+
+    result
+}
+
+// `[T, U: int]`, each name optionally followed by the constraint its types must satisfy
+fn parse_type_params(parser: &mut Parser) -> Result<Vec<&'static TypeParam>, ParseError> {
+    let mut type_params: Vec<&'static TypeParam> = Vec::new();
+
+    parser.pos += 1;
+
+    loop {
+        let name = match parser.next(0) {
+            Some(Token::Identifier(ident)) => ident,
+            Some(token) => return Err(ParseError(format!("[DefStatement] expected type parameter, found {token:?}"))),
+            None => return Err(ParseError("[DefStatement] insufficient tokens".into())),
+        };
+
+        if type_params.iter().any(|param| param.name == name) {
+            return Err(ParseError(format!("[DefStatement] type parameter `{}` is declared twice", name)));
+        }
+
+        if parser.types.contains_key(&name) {
+            return Err(ParseError(format!("[DefStatement] type parameter `{}` has the name of a type", name)));
+        }
+
+        parser.pos += 1;
+
+        let constraint = if let Some(Token::Colon) = parser.next(0) {
+            parser.pos += 1;
+
+            let constraint = match parser.next(0) {
+                Some(Token::Identifier(ident)) => Constraint::from_name(&ident)
+                    .ok_or_else(|| ParseError(format!("[DefStatement] unknown constraint `{}`, expected `int`, `signed` or `unsigned`", ident)))?,
+                Some(token) => return Err(ParseError(format!("[DefStatement] expected constraint, found {token:?}"))),
+                None => return Err(ParseError("[DefStatement] insufficient tokens".into())),
+            };
+
+            parser.pos += 1;
+            Some(constraint)
+        } else {
+            None
+        };
+
+        match DataType::param(name, constraint) {
+            DataType::Param(param) => type_params.push(param),
+            _ => unreachable!(),
+        }
+
+        match parser.next(0) {
+            Some(Token::Comma) => parser.pos += 1,
+            Some(Token::RSqBr) => {
+                parser.pos += 1;
+                return Ok(type_params);
+            },
+            Some(token) => return Err(ParseError(format!("[DefStatement] expected `,` or `]`, found {token:?}"))),
+            None => return Err(ParseError("[DefStatement] insufficient tokens".into())),
+        }
+    }
+}
+
+fn parse_signature(parser: &mut Parser, name: Identifier, type_params: Vec<&'static TypeParam>, receiver: Option<DataType>, span: Span) -> Result<DefStatement, ParseError> {
+    let mut params: Vec<(Identifier, DataType)> = Vec::new();
+    let mut stmts: Vec<Statement> = Vec::new();
+
     if let Some(token) = parser.next(0) {
         if let Token::LParen = token {
             parser.pos += 1;
@@ -37,6 +133,29 @@ pub fn parse_def(parser: &mut Parser) -> Result<DefStatement, ParseError> {
         return Err(ParseError("[DefStatement] insufficient tokens".into()));
     }
 
+    if let Some(receiver) = receiver {
+        let dtype = if let Some(Token::Star) = parser.next(0) {
+            parser.pos += 1;
+            DataType::pointer(receiver)
+        } else {
+            receiver
+        };
+
+        match parser.next(0) {
+            Some(Token::Identifier(ident)) if ident == "self" => parser.pos += 1,
+            _ => return Err(ParseError(format!("[DefStatement] method `{}` must take `self` or `*self` first", name))),
+        }
+
+        params.push((String::from("self"), dtype));
+
+        match parser.next(0) {
+            Some(Token::Comma) => parser.pos += 1,
+            Some(Token::RParen) => {},
+            Some(token) => return Err(ParseError(format!("[DefStatement] expected `,` or `)`, found {token:?}"))),
+            None => return Err(ParseError("[DefStatement] insufficient tokens".into())),
+        }
+    }
+
     loop {
         let ident = if let Some(token) = parser.next(0) {
             if let Token::Identifier(ident) = token {
@@ -63,9 +182,7 @@ pub fn parse_def(parser: &mut Parser) -> Result<DefStatement, ParseError> {
         }
 
         let r#type = if let Some(token) = parser.next(0) {
-            parser.pos += 1;
-
-            if let Token::Keyword(Keyword::DataType(dtype)) = token {
+            if let Some(dtype) = parser.parse_type()? {
                 dtype
             } else {
                 return Err(ParseError(format!("[DefStatement] expected keyword, found {token:?}")));
@@ -106,9 +223,7 @@ pub fn parse_def(parser: &mut Parser) -> Result<DefStatement, ParseError> {
     }
 
     let r#type = if let Some(token) = parser.next(0) {
-        parser.pos += 1;
-
-        if let Token::Keyword(Keyword::DataType(dtype)) = token {
+        if let Some(dtype) = parser.parse_type()? {
             dtype
         } else {
             return Err(ParseError(format!("[DefStatement] expected keyword(data type), found {token:?}")));
@@ -117,6 +232,13 @@ pub fn parse_def(parser: &mut Parser) -> Result<DefStatement, ParseError> {
         return Err(ParseError("[DefStatement] insufficient tokens".into()));
     };
 
+    // type parameters are only ever inferred from the arguments
+    for param in &type_params {
+        if !params.iter().any(|(_, dtype)| dtype.has_param(param)) {
+            return Err(ParseError(format!("[DefStatement] type parameter `{}` of `{}` is not used by its parameters", param.name, name)));
+        }
+    }
+
     if let Some(token) = parser.next(0) {
         if let Token::Colon = token {
             parser.pos += 1;
@@ -147,7 +269,7 @@ pub fn parse_def(parser: &mut Parser) -> Result<DefStatement, ParseError> {
             return Err(ParseError("[DefStatement] insufficient tokens".into()));
         }
 
-        let stmt = parser.parse_stmt().unwrap();
+        let stmt = parser.parse_stmt()?;
         if let Some(stmt) = stmt {
             stmts.push(stmt);
         } else {
@@ -157,14 +279,22 @@ pub fn parse_def(parser: &mut Parser) -> Result<DefStatement, ParseError> {
 
     Ok(DefStatement {
         name,
+        type_params,
         params,
         r#type,
         stmts,
+        span,
     })
 }
 
 impl fmt::Display for DefStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{{ type: fnDef, name: {}, returnType: {}, args: {:?}, stmts: {{ {} }}", &self.name, &self.r#type.to_mnemonic(), self.params, self.stmts.iter().map(|stmt| stmt.to_string()).collect::<Vec<String>>().join(", "))
+        let name = if self.type_params.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}[{}]", self.name, self.type_params.iter().map(|param| param.to_string()).collect::<Vec<String>>().join(", "))
+        };
+
+        write!(f, "{{ type: fnDef, name: {}, returnType: {}, args: {:?}, stmts: {{ {} }}", name, self.r#type.to_mnemonic(), self.params, self.stmts.iter().map(|stmt| stmt.to_string()).collect::<Vec<String>>().join(", "))
     }
 }
\ No newline at end of file
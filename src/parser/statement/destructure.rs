@@ -0,0 +1,98 @@
+use std::fmt;
+use crate::lexer::Span;
+use crate::parser::{Expression, Identifier, Parser, PrattParser, Token};
+use crate::parser::pratt::Precedence;
+use crate::error::ParseError;
+use crate::types::DataType;
+
+// `let (a, b) = expr;`, which binds every element of a tuple to a variable of its own
+#[derive(Debug, Clone)]
+pub struct DestructureStatement {
+    // `None` for an element that is skipped with `_`
+    pub names: Vec<Option<Identifier>>,
+    // the tuple type if it is written out, otherwise the elements keep the types of the value
+    pub r#type: Option<DataType>,
+    pub expr: Expression,
+    pub span: Span,
+}
+
+pub fn parse_destructure(parser: &mut Parser) -> Result<DestructureStatement, ParseError> {
+    let span = parser.last_span();
+
+    let mut names: Vec<Option<Identifier>> = Vec::new();
+
+    parser.pos += 1;
+
+    loop {
+        match parser.next(0) {
+            Some(Token::Identifier(ident)) if ident == "_" => names.push(None),
+            Some(Token::Identifier(ident)) if names.contains(&Some(ident.clone())) => return Err(ParseError(format!("[DestructureStatement] `{}` is bound twice", ident))),
+            Some(Token::Identifier(ident)) => names.push(Some(ident)),
+            Some(token) => return Err(ParseError(format!("[DestructureStatement] expected identifier, found {token:?}"))),
+            None => return Err(ParseError("[DestructureStatement] insufficient tokens".into())),
+        }
+
+        parser.pos += 1;
+
+        match parser.next(0) {
+            Some(Token::Comma) => parser.pos += 1,
+            Some(Token::RParen) => break,
+            Some(token) => return Err(ParseError(format!("[DestructureStatement] expected `,` or `)`, found {token:?}"))),
+            None => return Err(ParseError("[DestructureStatement] insufficient tokens".into())),
+        }
+    }
+
+    parser.pos += 1;
+
+    if names.len() < 2 {
+        return Err(ParseError("[DestructureStatement] a tuple needs at least two elements".into()));
+    }
+
+    let r#type = match parser.next(0) {
+        Some(Token::Colon) => {
+            parser.pos += 1;
+
+            match parser.next(0) {
+                Some(token) => match parser.parse_type()? {
+                    Some(dtype @ DataType::Tuple(tuple)) if tuple.elements.len() == names.len() => Some(dtype),
+                    Some(dtype) => return Err(ParseError(format!("[DestructureStatement] {} cannot be destructured into {} names", dtype, names.len()))),
+                    None => return Err(ParseError(format!("[DestructureStatement] expected tuple type, found {token:?}"))),
+                },
+                None => return Err(ParseError("[DestructureStatement] insufficient tokens".into())),
+            }
+        },
+        _ => None,
+    };
+
+    match parser.next(0) {
+        Some(Token::Equal) => parser.pos += 1,
+        Some(token) => return Err(ParseError(format!("[DestructureStatement] expected `=`, found {token:?}"))),
+        None => return Err(ParseError("[DestructureStatement] insufficient tokens".into())),
+    }
+
+    let expr = PrattParser::parse_expr(parser, Precedence::Lowest, r#type)?;
+
+    parser.pos += 1;
+
+    match parser.next(0) {
+        Some(Token::Semicolon) => parser.pos += 1,
+        Some(token) => return Err(ParseError(format!("[DestructureStatement] expected `;`, found {token:?}"))),
+        None => return Err(ParseError("[DestructureStatement] insufficient tokens".into())),
+    }
+
+    Ok(DestructureStatement {
+        names,
+        r#type,
+        expr,
+        span,
+    })
+}
+
+impl fmt::Display for DestructureStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = self.names.iter().map(|name| name.as_deref().unwrap_or("_")).collect::<Vec<&str>>();
+        let r#type = self.r#type.map_or("_".into(), |dtype| dtype.to_string());
+
+        write!(f, "{{ type: let, names: ({}), dataType: {}, expr: {} }}", names.join(", "), r#type, self.expr)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,151 @@
+use std::fmt;
+use crate::lexer::{Literal, Span};
+use crate::parser::{Parser, Token, Identifier};
+use crate::error::ParseError;
+use crate::types::{DataType, Variant};
+
+#[derive(Debug, Clone)]
+pub struct EnumStatement {
+    pub name: Identifier,
+    // the interned type, which lists the variants in declaration order
+    pub dtype: DataType,
+    pub span: Span,
+}
+
+// `enum Name:` followed by an indented `Variant;`, `Variant(type, ..);` or either with `= discriminant` per line;
+// a discriminant that is not given is one more than the previous one, starting at 0 like in C
+pub fn parse_enum(parser: &mut Parser) -> Result<EnumStatement, ParseError> {
+    let span = parser.last_span();
+
+    let mut variants: Vec<Variant> = Vec::new();
+
+    let name = match parser.next(0) {
+        Some(Token::Identifier(ident)) => {
+            parser.pos += 1;
+            ident
+        },
+        Some(token) => return Err(ParseError(format!("[EnumStatement] expected identifier, found {token:?}"))),
+        None => return Err(ParseError("[EnumStatement] insufficient tokens".into())),
+    };
+
+    if parser.types.contains_key(&name) {
+        return Err(ParseError(format!("[EnumStatement] `{}` is already declared", name)));
+    }
+
+    match parser.next(0) {
+        Some(Token::Colon) => parser.pos += 1,
+        Some(token) => return Err(ParseError(format!("[EnumStatement] expected `:`, found {token:?}"))),
+        None => return Err(ParseError("[EnumStatement] insufficient tokens".into())),
+    }
+
+    match parser.next(0) {
+        Some(Token::Indent) => parser.pos += 1,
+        Some(token) => return Err(ParseError(format!("[EnumStatement] expected indent, found {token:?}"))),
+        None => return Err(ParseError("[EnumStatement] insufficient tokens".into())),
+    }
+
+    loop {
+        let ident = match parser.next(0) {
+            Some(Token::Dedent) => {
+                parser.pos += 1;
+                break;
+            },
+            Some(Token::Identifier(ident)) => {
+                parser.pos += 1;
+                ident
+            },
+            Some(token) => return Err(ParseError(format!("[EnumStatement] expected variant name, found {token:?}"))),
+            None => return Err(ParseError("[EnumStatement] insufficient tokens".into())),
+        };
+
+        if variants.iter().any(|variant| variant.name == ident) {
+            return Err(ParseError(format!("[EnumStatement] variant `{}` is declared twice", ident)));
+        }
+
+        let mut payload: Vec<DataType> = Vec::new();
+
+        if let Some(Token::LParen) = parser.next(0) {
+            parser.pos += 1;
+
+            loop {
+                // the enum itself is not declared yet, so it cannot contain itself
+                let dtype = match parser.next(0) {
+                    Some(token) => parser.parse_type()?.ok_or_else(|| ParseError(format!("[EnumStatement] expected value type, found {token:?}")))?,
+                    None => return Err(ParseError("[EnumStatement] insufficient tokens".into())),
+                };
+
+                payload.push(dtype);
+
+                match parser.next(0) {
+                    Some(Token::Comma) => parser.pos += 1,
+                    Some(Token::RParen) => {
+                        parser.pos += 1;
+                        break;
+                    },
+                    Some(token) => return Err(ParseError(format!("[EnumStatement] expected `,` or `)`, found {token:?}"))),
+                    None => return Err(ParseError("[EnumStatement] insufficient tokens".into())),
+                }
+            }
+        }
+
+        let discriminant = if let Some(Token::Equal) = parser.next(0) {
+            parser.pos += 1;
+
+            let negative = parser.next(0) == Some(Token::Minus);
+
+            if negative {
+                parser.pos += 1;
+            }
+
+            let n = match parser.next(0) {
+                Some(Token::Literal(Literal::SignedInteger((n, _)))) => n,
+                Some(Token::Literal(Literal::UnsignedInteger((n, _)))) => n as i128,
+                Some(token) => return Err(ParseError(format!("[EnumStatement] expected discriminant, found {token:?}"))),
+                None => return Err(ParseError("[EnumStatement] insufficient tokens".into())),
+            };
+
+            parser.pos += 1;
+
+            let n = if negative { -n } else { n };
+            i32::try_from(n).map_err(|_| ParseError(format!("[EnumStatement] discriminant {} of `{}` does not fit in i32", n, ident)))?
+        } else {
+            match variants.last() {
+                Some(variant) => variant.discriminant.checked_add(1)
+                    .ok_or_else(|| ParseError(format!("[EnumStatement] discriminant of `{}` does not fit in i32", ident)))?,
+                None => 0,
+            }
+        };
+
+        if let Some(variant) = variants.iter().find(|variant| variant.discriminant == discriminant) {
+            return Err(ParseError(format!("[EnumStatement] `{}` has the same discriminant as `{}`", ident, variant.name)));
+        }
+
+        match parser.next(0) {
+            Some(Token::Semicolon) => parser.pos += 1,
+            Some(token) => return Err(ParseError(format!("[EnumStatement] expected `;`, found {token:?}"))),
+            None => return Err(ParseError("[EnumStatement] insufficient tokens".into())),
+        }
+
+        variants.push(Variant { name: ident, discriminant, payload });
+    }
+
+    let dtype = DataType::enumeration(parser.qualify(&name), variants);
+    parser.types.insert(name.clone(), dtype);
+
+    Ok(EnumStatement {
+        name: parser.qualify(&name),
+        dtype,
+        span,
+    })
+}
+
+impl fmt::Display for EnumStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let variants = match self.dtype {
+            DataType::Enum(enumeration) => enumeration.variants.iter().map(|variant| variant.to_string()).collect(),
+            _ => Vec::new(),
+        };
+
+        write!(f, "{{ type: enum, name: {}, variants: [{}] }}", self.name, variants.join(", "))
+    }
+}
\ No newline at end of file
@@ -1,29 +1,40 @@
-use crate::{error::ParseError, lexer::Token, parser::{pratt::{PrattParser, Precedence}, Expression, Parser}};
+use crate::{error::ParseError, lexer::Token, parser::{pratt::{PrattParser, Precedence}, Expression, Parser, Statement}};
+use crate::parser::statement::parse_assign;
+use crate::lexer::Span;
 use std::fmt;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ExpressionStatement {
     pub expr: Expression,
+    pub span: Span,
 }
 
-pub fn parse_expr_stmt(parser: &mut Parser) -> Result<ExpressionStatement, ParseError> {
+// an expression followed by `=` starts an assignment instead
+pub fn parse_expr_stmt(parser: &mut Parser) -> Result<Statement, ParseError> {
+    let span = parser.span(0);
+
     let expr = if parser.next(0).is_some() {
-        PrattParser::parse_expr(parser, Precedence::Lowest, None).unwrap()
+        PrattParser::parse_expr(parser, Precedence::Lowest, None)?
     } else {
         return Err(ParseError("[ExpressionStatement] insufficient tokens".into()));
     };
 
     parser.pos += 1;
 
+    if let Some(Token::Equal) = parser.next(0) {
+        return Ok(Statement::Assign(parse_assign(parser, expr, span)?));
+    }
+
     if let Some(token) = parser.next(0) {
         if Token::Semicolon == token {
             parser.pos += 1;
         }
     }
 
-    Ok(ExpressionStatement {
+    Ok(Statement::Expression(ExpressionStatement {
         expr,
-    })
+        span,
+    }))
 }
 
 
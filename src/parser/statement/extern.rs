@@ -1,17 +1,20 @@
 use std::fmt;
-use crate::lexer::Keyword;
+use crate::lexer::Span;
 use crate::parser::{Parser, Token, Identifier};
 use crate::error::ParseError;
 use crate::types::DataType;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ExternStatement {
     pub name: Identifier,
     pub params: Vec<(Identifier, DataType)>,
     pub r#type: DataType,
+    pub span: Span,
 }
 
 pub fn parse_extern(parser: &mut Parser) -> Result<ExternStatement, ParseError> {
+    let span = parser.last_span();
+
     let mut params: Vec<(Identifier, DataType)> = Vec::new();
 
     let name = if let Some(token) = parser.next(0) {
@@ -61,9 +64,7 @@ pub fn parse_extern(parser: &mut Parser) -> Result<ExternStatement, ParseError>
         }
 
         let r#type = if let Some(token) = parser.next(0) {
-            parser.pos += 1;
-
-            if let Token::Keyword(Keyword::DataType(dtype)) = token {
+            if let Some(dtype) = parser.parse_type()? {
                 dtype
             } else {
                 return Err(ParseError(format!("[ExternStatement] expected keyword, found {token:?}")));
@@ -104,9 +105,7 @@ pub fn parse_extern(parser: &mut Parser) -> Result<ExternStatement, ParseError>
     }
 
     let r#type = if let Some(token) = parser.next(0) {
-        parser.pos += 1;
-
-        if let Token::Keyword(Keyword::DataType(dtype)) = token {
+        if let Some(dtype) = parser.parse_type()? {
             dtype
         } else {
             return Err(ParseError(format!("[ExternStatement] expected keyword, found {token:?}")));
@@ -129,6 +128,7 @@ pub fn parse_extern(parser: &mut Parser) -> Result<ExternStatement, ParseError>
         name,
         params,
         r#type,
+        span,
     })
 }
 
@@ -4,17 +4,19 @@ use crate::error::ParseError;
 use crate::parser::pratt::Precedence;
 use super::Statement;
 use std::borrow::Borrow;
+use crate::lexer::Span;
 use std::fmt;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct IfStatement {
     pub condition: Expression,
     pub then: Vec<Statement>,
     pub r#else: Box<IfBranch>,
     // pub r#else: Option<Box<Statement>>,
+    pub span: Span,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum IfBranch {
     Elif(IfStatement),
     Else(Vec<Statement>),
@@ -22,7 +24,9 @@ pub enum IfBranch {
 }
 
 pub fn parse_if(parser: &mut Parser) -> Result<IfStatement, ParseError> {
-    let condition = PrattParser::parse_expr(parser, Precedence::Lowest, None).unwrap();
+    let span = parser.last_span();
+
+    let condition = PrattParser::parse_expr(parser, Precedence::Lowest, None)?;
     parser.pos += 1;
 
     if let Some(token) = parser.next(0) {
@@ -58,7 +62,7 @@ pub fn parse_if(parser: &mut Parser) -> Result<IfStatement, ParseError> {
                 }
             }
 
-            let stmt = parser.parse_stmt().unwrap().unwrap();
+            let stmt = parser.parse_stmt()?.ok_or_else(|| ParseError("[IfStatement] insufficient tokens".into()))?;
             then.push(stmt);
         } else {
             return Err(ParseError("[IfStatement] insufficient tokens".into()));
@@ -69,7 +73,7 @@ pub fn parse_if(parser: &mut Parser) -> Result<IfStatement, ParseError> {
         if keyword == Keyword::Elif {
             parser.pos += 1;
             
-            let elif = parse_if(parser).unwrap();
+            let elif = parse_if(parser)?;
             IfBranch::Elif(elif)
         } else if keyword == Keyword::Else {
             parser.pos += 1;
@@ -102,7 +106,7 @@ pub fn parse_if(parser: &mut Parser) -> Result<IfStatement, ParseError> {
                         break;
                     }
 
-                    let stmt = parser.parse_stmt().unwrap().unwrap();
+                    let stmt = parser.parse_stmt()?.ok_or_else(|| ParseError("[IfStatement] insufficient tokens".into()))?;
                     r#else.push(stmt);
                 } else {
                     return Err(ParseError("[IfStatement] insufficient tokens".into()));
@@ -132,6 +136,7 @@ pub fn parse_if(parser: &mut Parser) -> Result<IfStatement, ParseError> {
         condition,
         then,
         r#else: Box::new(branch),
+        span,
     })
 }
 
@@ -0,0 +1,103 @@
+use std::fmt;
+use crate::lexer::{Keyword, Span};
+use crate::parser::{Identifier, Parser, Token};
+use crate::error::ParseError;
+
+// `import a.b;`, which makes the public names of the module `a.b` usable as `a.b.name`,
+// or `from a.b import x, y;`, which makes `x` and `y` usable by themselves
+#[derive(Debug, Clone)]
+pub struct ImportStatement {
+    // the dotted path of the module, `a.b` is the file `a/b.mamba`
+    pub module: Identifier,
+    // the names listed by `from`, `None` for `import`
+    pub names: Option<Vec<Identifier>>,
+    pub span: Span,
+}
+
+pub fn parse_import(parser: &mut Parser) -> Result<ImportStatement, ParseError> {
+    let span = parser.last_span();
+
+    let module = parse_module_path(parser)?;
+
+    match parser.next(0) {
+        Some(Token::Semicolon) => parser.pos += 1,
+        Some(token) => return Err(ParseError(format!("[ImportStatement] expected `;`, found {token:?}"))),
+        None => return Err(ParseError("[ImportStatement] insufficient tokens".into())),
+    }
+
+    Ok(ImportStatement {
+        module,
+        names: None,
+        span,
+    })
+}
+
+pub fn parse_from(parser: &mut Parser) -> Result<ImportStatement, ParseError> {
+    let span = parser.last_span();
+
+    let module = parse_module_path(parser)?;
+    let mut names: Vec<Identifier> = Vec::new();
+
+    match parser.next(0) {
+        Some(Token::Keyword(Keyword::Import)) => parser.pos += 1,
+        Some(token) => return Err(ParseError(format!("[ImportStatement] expected `import`, found {token:?}"))),
+        None => return Err(ParseError("[ImportStatement] insufficient tokens".into())),
+    }
+
+    loop {
+        match parser.next(0) {
+            Some(Token::Identifier(ident)) if names.contains(&ident) => return Err(ParseError(format!("[ImportStatement] `{}` is imported twice", ident))),
+            Some(Token::Identifier(ident)) => names.push(ident),
+            Some(token) => return Err(ParseError(format!("[ImportStatement] expected identifier, found {token:?}"))),
+            None => return Err(ParseError("[ImportStatement] insufficient tokens".into())),
+        }
+
+        parser.pos += 1;
+
+        match parser.next(0) {
+            Some(Token::Comma) => parser.pos += 1,
+            Some(Token::Semicolon) => break,
+            Some(token) => return Err(ParseError(format!("[ImportStatement] expected `,` or `;`, found {token:?}"))),
+            None => return Err(ParseError("[ImportStatement] insufficient tokens".into())),
+        }
+    }
+
+    parser.pos += 1;
+
+    Ok(ImportStatement {
+        module,
+        names: Some(names),
+        span,
+    })
+}
+
+// `a` or `a.b.c`
+fn parse_module_path(parser: &mut Parser) -> Result<Identifier, ParseError> {
+    let mut path: Vec<Identifier> = Vec::new();
+
+    loop {
+        match parser.next(0) {
+            Some(Token::Identifier(ident)) => path.push(ident),
+            Some(token) => return Err(ParseError(format!("[ImportStatement] expected module name, found {token:?}"))),
+            None => return Err(ParseError("[ImportStatement] insufficient tokens".into())),
+        }
+
+        parser.pos += 1;
+
+        match parser.next(0) {
+            Some(Token::Dot) => parser.pos += 1,
+            _ => break,
+        }
+    }
+
+    Ok(path.join("."))
+}
+
+impl fmt::Display for ImportStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.names {
+            Some(names) => write!(f, "{{ type: from, module: {}, names: [{}] }}", self.module, names.join(", ")),
+            None => write!(f, "{{ type: import, module: {} }}", self.module),
+        }
+    }
+}
\ No newline at end of file
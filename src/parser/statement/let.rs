@@ -1,18 +1,21 @@
-use crate::lexer::Keyword;
+use crate::lexer::Span;
 use crate::parser::{Expression, Identifier, Parser, PrattParser, Token};
 use crate::error::ParseError;
 use crate::types::DataType;
 use crate::parser::pratt::Precedence;
 use std::fmt;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LetStatement {
     pub ident: Identifier,
     pub r#type: DataType,
     pub expr: Expression,
+    pub span: Span,
 }
 
 pub fn parse_let(parser: &mut Parser) -> Result<LetStatement, ParseError> {        
+    let span = parser.last_span();
+
     let ident = if let Some(token) = parser.next(0) {
         parser.pos += 1;
 
@@ -34,9 +37,7 @@ pub fn parse_let(parser: &mut Parser) -> Result<LetStatement, ParseError> {
     }
 
     let r#type = if let Some(token) = parser.next(0) {
-        parser.pos += 1;
-
-        if let Token::Keyword(Keyword::DataType(dtype)) = token {
+        if let Some(dtype) = parser.parse_type()? {
             dtype
         } else {
             return Err(ParseError(format!("[LetStatement] expected keyword, found {token:?}")));
@@ -55,7 +56,7 @@ pub fn parse_let(parser: &mut Parser) -> Result<LetStatement, ParseError> {
         return Err(ParseError("[LetStatement] insufficient tokens".into()));
     }
 
-    let expr = PrattParser::parse_expr(parser, Precedence::Lowest, Some(r#type)).unwrap();
+    let expr = PrattParser::parse_expr(parser, Precedence::Lowest, Some(r#type))?;
 
     parser.pos += 1;
 
@@ -73,6 +74,7 @@ pub fn parse_let(parser: &mut Parser) -> Result<LetStatement, ParseError> {
         ident,
         r#type,
         expr,
+        span,
     })
 
 }
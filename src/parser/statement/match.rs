@@ -0,0 +1,241 @@
+use std::fmt;
+use crate::lexer::{Keyword, Literal, Span};
+use crate::parser::{Expression, Identifier, Parser, PrattParser, Statement, Token};
+use crate::parser::pratt::Precedence;
+use crate::error::ParseError;
+use crate::types::DataType;
+
+#[derive(Debug, Clone)]
+pub struct MatchStatement {
+    pub expr: Expression,
+    pub cases: Vec<MatchCase>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchCase {
+    pub pattern: Pattern,
+    pub stmts: Vec<Statement>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    // `_`, which matches every value
+    Wildcard,
+    // an integer literal, which is converted to the type of the value it is compared with
+    Integer(i128),
+    // `Name.Variant` or `Name.Variant(a, _)`, which binds the values of the variant to names
+    Variant {
+        dtype: DataType,
+        variant: Identifier,
+        bindings: Vec<Option<Identifier>>,
+    },
+}
+
+// `match expr:` followed by indented `case pattern:` blocks; the first case that matches is run,
+// and every value must be matched by one of them, which `case _` always does
+pub fn parse_match(parser: &mut Parser) -> Result<MatchStatement, ParseError> {
+    let span = parser.last_span();
+
+    let expr = PrattParser::parse_expr(parser, Precedence::Lowest, None)?;
+    parser.pos += 1;
+
+    match parser.next(0) {
+        Some(Token::Colon) => parser.pos += 1,
+        Some(token) => return Err(ParseError(format!("[MatchStatement] expected `:`, found {token:?}"))),
+        None => return Err(ParseError("[MatchStatement] insufficient tokens".into())),
+    }
+
+    match parser.next(0) {
+        Some(Token::Indent) => parser.pos += 1,
+        Some(token) => return Err(ParseError(format!("[MatchStatement] expected indent, found {token:?}"))),
+        None => return Err(ParseError("[MatchStatement] insufficient tokens".into())),
+    }
+
+    let mut cases: Vec<MatchCase> = Vec::new();
+
+    loop {
+        match parser.next(0) {
+            Some(Token::Dedent) if !cases.is_empty() => {
+                parser.pos += 1;
+                break;
+            },
+            Some(Token::Keyword(Keyword::Case)) => parser.pos += 1,
+            Some(token) => return Err(ParseError(format!("[MatchStatement] expected `case`, found {token:?}"))),
+            None => return Err(ParseError("[MatchStatement] insufficient tokens".into())),
+        }
+
+        let pattern = parse_pattern(parser)?;
+
+        if cases.iter().any(|case| case.pattern == Pattern::Wildcard || case.pattern == pattern) {
+            return Err(ParseError(format!("[MatchStatement] case `{}` is unreachable", pattern)));
+        }
+
+        if let Some(case) = cases.iter().find(|case| !pattern.is_like(&case.pattern)) {
+            return Err(ParseError(format!("[MatchStatement] case `{}` cannot match the same value as `{}`", pattern, case.pattern)));
+        }
+
+        match parser.next(0) {
+            Some(Token::Colon) => parser.pos += 1,
+            Some(token) => return Err(ParseError(format!("[MatchStatement] expected `:`, found {token:?}"))),
+            None => return Err(ParseError("[MatchStatement] insufficient tokens".into())),
+        }
+
+        match parser.next(0) {
+            Some(Token::Indent) => parser.pos += 1,
+            Some(token) => return Err(ParseError(format!("[MatchStatement] expected indent, found {token:?}"))),
+            None => return Err(ParseError("[MatchStatement] insufficient tokens".into())),
+        }
+
+        let mut stmts: Vec<Statement> = Vec::new();
+
+        loop {
+            match parser.next(0) {
+                Some(Token::Dedent) => {
+                    parser.pos += 1;
+                    break;
+                },
+                Some(_) => stmts.push(parser.parse_stmt()?.ok_or_else(|| ParseError("[MatchStatement] insufficient tokens".into()))?),
+                None => return Err(ParseError("[MatchStatement] insufficient tokens".into())),
+            }
+        }
+
+        cases.push(MatchCase { pattern, stmts });
+    }
+
+    check_exhaustive(&cases)?;
+
+    Ok(MatchStatement {
+        expr,
+        cases,
+        span,
+    })
+}
+
+fn parse_pattern(parser: &mut Parser) -> Result<Pattern, ParseError> {
+    let pattern = match parser.next(0) {
+        Some(Token::Identifier(ident)) if ident == "_" => Pattern::Wildcard,
+        Some(Token::Literal(Literal::SignedInteger((n, _)))) => Pattern::Integer(n),
+        Some(Token::Literal(Literal::UnsignedInteger((n, _)))) => Pattern::Integer(n as i128),
+        Some(Token::Minus) => match parser.next(1) {
+            Some(Token::Literal(Literal::SignedInteger((n, _)))) => {
+                parser.pos += 1;
+                Pattern::Integer(-n)
+            },
+            Some(token) => return Err(ParseError(format!("[MatchStatement] expected integer, found {token:?}"))),
+            None => return Err(ParseError("[MatchStatement] insufficient tokens".into())),
+        },
+        Some(Token::Identifier(name)) => {
+            let name = parser.parse_type_path().unwrap_or(name);
+            let enumeration = match parser.types.get(&name) {
+                Some(DataType::Enum(enumeration)) => *enumeration,
+                _ => return Err(ParseError(format!("[MatchStatement] unknown enum `{}`", name))),
+            };
+
+            match parser.next(1) {
+                Some(Token::Dot) => parser.pos += 2,
+                Some(token) => return Err(ParseError(format!("[MatchStatement] expected `.`, found {token:?}"))),
+                None => return Err(ParseError("[MatchStatement] insufficient tokens".into())),
+            }
+
+            let variant = match parser.next(0) {
+                Some(Token::Identifier(variant)) => enumeration.variant(&variant)
+                    .ok_or_else(|| ParseError(format!("[MatchStatement] `{}` has no variant `{}`", name, variant)))?,
+                Some(token) => return Err(ParseError(format!("[MatchStatement] expected variant name, found {token:?}"))),
+                None => return Err(ParseError("[MatchStatement] insufficient tokens".into())),
+            };
+
+            let mut bindings: Vec<Option<Identifier>> = Vec::new();
+
+            if let Some(Token::LParen) = parser.next(1) {
+                parser.pos += 2;
+
+                loop {
+                    match parser.next(0) {
+                        Some(Token::Identifier(ident)) if ident == "_" => bindings.push(None),
+                        Some(Token::Identifier(ident)) if bindings.contains(&Some(ident.clone())) => return Err(ParseError(format!("[MatchStatement] `{}` is bound twice", ident))),
+                        Some(Token::Identifier(ident)) => bindings.push(Some(ident)),
+                        Some(token) => return Err(ParseError(format!("[MatchStatement] expected identifier, found {token:?}"))),
+                        None => return Err(ParseError("[MatchStatement] insufficient tokens".into())),
+                    }
+
+                    parser.pos += 1;
+
+                    match parser.next(0) {
+                        Some(Token::Comma) => parser.pos += 1,
+                        Some(Token::RParen) => break,
+                        Some(token) => return Err(ParseError(format!("[MatchStatement] expected `,` or `)`, found {token:?}"))),
+                        None => return Err(ParseError("[MatchStatement] insufficient tokens".into())),
+                    }
+                }
+            }
+
+            if bindings.len() != variant.payload.len() {
+                return Err(ParseError(format!("[MatchStatement] `{}.{}` has {} values but {} are bound", name, variant.name, variant.payload.len(), bindings.len())));
+            }
+
+            Pattern::Variant { dtype: DataType::Enum(enumeration), variant: variant.name.clone(), bindings }
+        },
+        Some(token) => return Err(ParseError(format!("[MatchStatement] expected pattern, found {token:?}"))),
+        None => return Err(ParseError("[MatchStatement] insufficient tokens".into())),
+    };
+
+    parser.pos += 1;
+    Ok(pattern)
+}
+
+// without `case _`, every variant of an enum needs a case of its own, and integers cannot be covered at all
+fn check_exhaustive(cases: &[MatchCase]) -> Result<(), ParseError> {
+    if cases.iter().any(|case| case.pattern == Pattern::Wildcard) {
+        return Ok(());
+    }
+
+    match cases.first().map(|case| &case.pattern) {
+        Some(Pattern::Variant { dtype: DataType::Enum(enumeration), .. }) => {
+            let missing = enumeration.variants.iter()
+                .find(|variant| !cases.iter().any(|case| matches!(&case.pattern, Pattern::Variant { variant: name, .. } if *name == variant.name)));
+
+            match missing {
+                Some(variant) => Err(ParseError(format!("[MatchStatement] `{}.{}` is not covered", enumeration.name, variant.name))),
+                None => Ok(()),
+            }
+        },
+        _ => Err(ParseError("[MatchStatement] integers are only covered with `case _`".into())),
+    }
+}
+
+impl Pattern {
+    // whether both patterns can be compared with values of the same type
+    fn is_like(&self, other: &Pattern) -> bool {
+        match (self, other) {
+            (Pattern::Wildcard, _) | (_, Pattern::Wildcard) => true,
+            (Pattern::Integer(_), Pattern::Integer(_)) => true,
+            (Pattern::Variant { dtype, .. }, Pattern::Variant { dtype: other, .. }) => dtype == other,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Integer(n) => write!(f, "{}", n),
+            Pattern::Variant { dtype, variant, bindings } if bindings.is_empty() => write!(f, "{}.{}", dtype, variant),
+            Pattern::Variant { dtype, variant, bindings } => {
+                let bindings = bindings.iter().map(|binding| binding.as_deref().unwrap_or("_")).collect::<Vec<&str>>();
+                write!(f, "{}.{}({})", dtype, variant, bindings.join(", "))
+            },
+        }
+    }
+}
+
+impl fmt::Display for MatchStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cases = self.cases.iter()
+            .map(|case| format!("{{ pattern: {}, stmts: {{ {} }} }}", case.pattern, case.stmts.iter().map(|stmt| stmt.to_string()).collect::<Vec<String>>().join(", ")))
+            .collect::<Vec<String>>();
+
+        write!(f, "{{ type: match, expr: {}, cases: {{ {} }} }}", self.expr, cases.join(", "))
+    }
+}
\ No newline at end of file
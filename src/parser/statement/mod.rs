@@ -1,31 +1,70 @@
 
-pub use def::{DefStatement, parse_def};
+pub use assign::{AssignStatement, parse_assign};
+pub use destructure::{DestructureStatement, parse_destructure};
+pub use def::{DefStatement, parse_def, parse_method};
 pub use expr_stmt::{ExpressionStatement, parse_expr_stmt};
+pub use r#enum::{EnumStatement, parse_enum};
 pub use r#extern::{ExternStatement, parse_extern};
 pub use r#if::{IfStatement, IfBranch, parse_if};
+pub use import::{ImportStatement, parse_import, parse_from};
 pub use r#let::{LetStatement, parse_let};
+pub use r#match::{MatchStatement, MatchCase, Pattern, parse_match};
 pub use r#return::{ReturnStatement, parse_return};
+pub use r#struct::{StructStatement, parse_struct};
 pub use r#while::{WhileStatement, parse_while};
 
+use std::collections::HashSet;
 use std::fmt;
+use crate::lexer::Span;
+use crate::parser::{Expression, Identifier, Operator};
 
+mod assign;
 mod def;
+mod destructure;
 mod expr_stmt;
+mod r#enum;
 mod r#extern;
 mod r#if;
+mod import;
 mod r#let;
+mod r#match;
 mod r#return;
+mod r#struct;
 mod r#while;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Statement {
     Expression(ExpressionStatement),
     Def(DefStatement),
     If(IfStatement),
     Let(LetStatement),
+    Destructure(DestructureStatement),
     Return(ReturnStatement),
     Extern(ExternStatement),
     While(WhileStatement),
+    Assign(AssignStatement),
+    Struct(StructStatement),
+    Enum(EnumStatement),
+    Match(MatchStatement),
+}
+
+impl Statement {
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Expression(stmt) => stmt.span,
+            Statement::Def(stmt) => stmt.span,
+            Statement::If(stmt) => stmt.span,
+            Statement::Let(stmt) => stmt.span,
+            Statement::Destructure(stmt) => stmt.span,
+            Statement::Return(stmt) => stmt.span,
+            Statement::Extern(stmt) => stmt.span,
+            Statement::While(stmt) => stmt.span,
+            Statement::Assign(stmt) => stmt.span,
+            Statement::Struct(stmt) => stmt.span,
+            Statement::Enum(stmt) => stmt.span,
+            Statement::Match(stmt) => stmt.span,
+        }
+    }
 }
 
 impl fmt::Display for Statement {
@@ -35,9 +74,101 @@ impl fmt::Display for Statement {
             Statement::Def(stmt) => write!(f, "{}", stmt),
             Statement::If(stmt) => write!(f, "{}", stmt),
             Statement::Let(stmt) => write!(f, "{}", stmt),
+            Statement::Destructure(stmt) => write!(f, "{}", stmt),
             Statement::Return(stmt) => write!(f, "{}", stmt),
             Statement::Extern(stmt) => write!(f, "{}", stmt),
             Statement::While(stmt) => write!(f, "{}", stmt),
+            Statement::Assign(stmt) => write!(f, "{}", stmt),
+            Statement::Struct(stmt) => write!(f, "{}", stmt),
+            Statement::Enum(stmt) => write!(f, "{}", stmt),
+            Statement::Match(stmt) => write!(f, "{}", stmt),
+        }
+    }
+}
+
+// the variables in `stmts` whose address `&` takes, directly or through one of their elements
+pub fn addressed_variables(stmts: &[Statement]) -> HashSet<Identifier> {
+    fn visit_stmts(stmts: &[Statement], result: &mut HashSet<Identifier>) {
+        for stmt in stmts {
+            match stmt {
+                Statement::Let(stmt) => visit_expr(&stmt.expr, result),
+                Statement::Destructure(stmt) => visit_expr(&stmt.expr, result),
+                Statement::If(stmt) => visit_if(stmt, result),
+                Statement::While(stmt) => {
+                    visit_expr(&stmt.condition, result);
+                    visit_stmts(&stmt.blocks, result);
+                },
+                Statement::Return(stmt) => visit_expr(&stmt.expr, result),
+                Statement::Expression(stmt) => visit_expr(&stmt.expr, result),
+                Statement::Assign(stmt) => {
+                    visit_expr(&stmt.target, result);
+                    visit_expr(&stmt.expr, result);
+                },
+                Statement::Match(stmt) => {
+                    visit_expr(&stmt.expr, result);
+                    stmt.cases.iter().for_each(|case| visit_stmts(&case.stmts, result));
+                },
+                Statement::Def(stmt) => visit_stmts(&stmt.stmts, result),
+                Statement::Extern(_) | Statement::Struct(_) | Statement::Enum(_) => {},
+            }
+        }
+    }
+
+    fn visit_if(stmt: &IfStatement, result: &mut HashSet<Identifier>) {
+        visit_expr(&stmt.condition, result);
+        visit_stmts(&stmt.then, result);
+
+        match stmt.r#else.as_ref() {
+            IfBranch::Elif(stmt) => visit_if(stmt, result),
+            IfBranch::Else(stmts) => visit_stmts(stmts, result),
+            IfBranch::None => {},
+        }
+    }
+
+    fn visit_expr(expr: &Expression, result: &mut HashSet<Identifier>) {
+        match expr {
+            Expression::Unary(expr) => {
+                if expr.operator == Operator::AddressOf {
+                    let mut place = expr.right.as_ref();
+
+                    loop {
+                        place = match place {
+                            Expression::Index(expr) => &expr.array,
+                            Expression::Member(expr) => &expr.expr,
+                            _ => break,
+                        };
+                    }
+
+                    if let Expression::Identifier(ident) = place {
+                        result.insert(ident.clone());
+                    }
+                }
+
+                visit_expr(&expr.right, result);
+            },
+            Expression::Infix(expr) => {
+                visit_expr(&expr.left, result);
+                visit_expr(&expr.right, result);
+            },
+            Expression::FnCall(expr) => expr.args.iter().for_each(|arg| visit_expr(arg, result)),
+            Expression::MethodCall(expr) => {
+                visit_expr(&expr.receiver, result);
+                expr.args.iter().for_each(|arg| visit_expr(arg, result));
+            },
+            Expression::Index(expr) => {
+                visit_expr(&expr.array, result);
+                visit_expr(&expr.index, result);
+            },
+            Expression::Array(elements) | Expression::Tuple(elements) => elements.iter().for_each(|element| visit_expr(element, result)),
+            Expression::Struct(expr) => expr.fields.iter().for_each(|(_, field)| visit_expr(field, result)),
+            Expression::Member(expr) => visit_expr(&expr.expr, result),
+            Expression::Enum(expr) => expr.values.iter().for_each(|value| visit_expr(value, result)),
+            Expression::FormatString(expr) => expr.values().for_each(|value| visit_expr(value, result)),
+            Expression::Identifier(_) | Expression::Literal(_) => {},
         }
     }
+
+    let mut result = HashSet::new();
+    visit_stmts(stmts, &mut result);
+    result
 }
\ No newline at end of file
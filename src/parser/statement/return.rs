@@ -2,15 +2,19 @@ use crate::parser::{Parser, PrattParser, Token};
 use crate::parser::Expression;
 use crate::error::ParseError;
 use crate::parser::pratt::Precedence;
+use crate::lexer::Span;
 use std::fmt;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ReturnStatement {
     pub expr: Expression,
+    pub span: Span,
 }
 
 pub fn parse_return(parser: &mut Parser) -> Result<ReturnStatement, ParseError> {
-    let expr = PrattParser::parse_expr(parser, Precedence::Lowest, None).unwrap();
+    let span = parser.last_span();
+
+    let expr = PrattParser::parse_expr(parser, Precedence::Lowest, None)?;
 
     parser.pos += 1;
 
@@ -26,6 +30,7 @@ pub fn parse_return(parser: &mut Parser) -> Result<ReturnStatement, ParseError>
 
     Ok(ReturnStatement {
         expr,
+        span,
     })
 } 
 
@@ -0,0 +1,151 @@
+use std::fmt;
+use crate::lexer::Span;
+use crate::lexer::Keyword;
+use crate::parser::{parse_method, Parser, Token, Identifier};
+use crate::error::ParseError;
+use crate::types::DataType;
+
+#[derive(Debug, Clone)]
+pub struct StructStatement {
+    pub name: Identifier,
+    // the interned type, which lists the fields in declaration order
+    pub dtype: DataType,
+    pub span: Span,
+}
+
+// `struct Name:` followed by an indented `field: type;` per line, then its methods;
+// the methods are kept in `Parser::methods` until `Parser::parse_program` declares them as functions
+pub fn parse_struct(parser: &mut Parser) -> Result<StructStatement, ParseError> {
+    let span = parser.last_span();
+
+    let mut fields: Vec<(Identifier, DataType)> = Vec::new();
+    let mut methods: Vec<Identifier> = Vec::new();
+    // interned once the first method is reached, so that the methods can use the struct
+    let mut declared: Option<DataType> = None;
+
+    let name = match parser.next(0) {
+        Some(Token::Identifier(ident)) => {
+            parser.pos += 1;
+            ident
+        },
+        Some(token) => return Err(ParseError(format!("[StructStatement] expected identifier, found {token:?}"))),
+        None => return Err(ParseError("[StructStatement] insufficient tokens".into())),
+    };
+
+    if parser.types.contains_key(&name) {
+        return Err(ParseError(format!("[StructStatement] `{}` is already declared", name)));
+    }
+
+    // declared without fields while they are parsed, so that they can point to the struct
+    let forward = DataType::structure(parser.qualify(&name), Vec::new());
+    parser.types.insert(name.clone(), forward);
+
+    match parser.next(0) {
+        Some(Token::Colon) => parser.pos += 1,
+        Some(token) => return Err(ParseError(format!("[StructStatement] expected `:`, found {token:?}"))),
+        None => return Err(ParseError("[StructStatement] insufficient tokens".into())),
+    }
+
+    match parser.next(0) {
+        Some(Token::Indent) => parser.pos += 1,
+        Some(token) => return Err(ParseError(format!("[StructStatement] expected indent, found {token:?}"))),
+        None => return Err(ParseError("[StructStatement] insufficient tokens".into())),
+    }
+
+    loop {
+        let ident = match parser.next(0) {
+            Some(Token::Dedent) => {
+                parser.pos += 1;
+                break;
+            },
+            Some(Token::Keyword(Keyword::Def)) => {
+                parser.pos += 1;
+
+                let dtype = *declared.get_or_insert_with(|| {
+                    let dtype = DataType::structure(parser.qualify(&name), std::mem::take(&mut fields));
+                    parser.types.insert(name.clone(), dtype);
+                    dtype
+                });
+
+                let mut method = parse_method(parser, dtype)?;
+
+                if methods.contains(&method.name) {
+                    return Err(ParseError(format!("[StructStatement] method `{}` is declared twice", method.name)));
+                }
+
+                methods.push(method.name.clone());
+
+                if let DataType::Struct(structure) = dtype {
+                    method.name = structure.method(&method.name);
+                }
+
+                parser.methods.push(method);
+                continue;
+            },
+            Some(Token::Identifier(ident)) if declared.is_some() => return Err(ParseError(format!("[StructStatement] field `{}` must be declared before the methods", ident))),
+            Some(Token::Identifier(ident)) => {
+                parser.pos += 1;
+                ident
+            },
+            Some(token) => return Err(ParseError(format!("[StructStatement] expected field name, found {token:?}"))),
+            None => return Err(ParseError("[StructStatement] insufficient tokens".into())),
+        };
+
+        if fields.iter().any(|(field, _)| *field == ident) {
+            return Err(ParseError(format!("[StructStatement] field `{}` is declared twice", ident)));
+        }
+
+        match parser.next(0) {
+            Some(Token::Colon) => parser.pos += 1,
+            Some(token) => return Err(ParseError(format!("[StructStatement] expected `:`, found {token:?}"))),
+            None => return Err(ParseError("[StructStatement] insufficient tokens".into())),
+        }
+
+        // only the forward declaration is known here, a field can point to it but not contain it
+        let dtype = match parser.next(0) {
+            Some(token) => parser.parse_type()?.ok_or_else(|| ParseError(format!("[StructStatement] expected field type, found {token:?}")))?,
+            None => return Err(ParseError("[StructStatement] insufficient tokens".into())),
+        };
+
+        if contains(dtype, forward) {
+            return Err(ParseError(format!("[StructStatement] field `{}` of `{}` cannot contain `{}` itself, only point to it", ident, name, name)));
+        }
+
+        match parser.next(0) {
+            Some(Token::Semicolon) => parser.pos += 1,
+            Some(token) => return Err(ParseError(format!("[StructStatement] expected `;`, found {token:?}"))),
+            None => return Err(ParseError("[StructStatement] insufficient tokens".into())),
+        }
+
+        fields.push((ident, dtype));
+    }
+
+    let dtype = declared.unwrap_or_else(|| DataType::structure(parser.qualify(&name), fields));
+    parser.types.insert(name.clone(), dtype);
+
+    Ok(StructStatement {
+        name: parser.qualify(&name),
+        dtype,
+        span,
+    })
+}
+
+// whether a value of `dtype` holds a `structure` in place, which would make it infinitely large
+fn contains(dtype: DataType, structure: DataType) -> bool {
+    match dtype {
+        DataType::Array(array) => contains(array.element, structure),
+        DataType::Tuple(tuple) => tuple.elements.iter().any(|element| contains(*element, structure)),
+        dtype => dtype == structure,
+    }
+}
+
+impl fmt::Display for StructStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fields = match self.dtype {
+            DataType::Struct(structure) => structure.fields.as_slice(),
+            _ => &[],
+        };
+
+        write!(f, "{{ type: struct, name: {}, fields: {:?} }}", self.name, fields)
+    }
+}
\ No newline at end of file
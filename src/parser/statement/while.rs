@@ -3,16 +3,20 @@ use crate::error::ParseError;
 
 use crate::parser::pratt::Precedence;
 use super::Statement;
+use crate::lexer::Span;
 use std::fmt;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct WhileStatement {
     pub condition: Expression,
     pub blocks: Vec<Statement>,
+    pub span: Span,
 }
 
 pub fn parse_while(parser: &mut Parser) -> Result<WhileStatement, ParseError> {
-    let condition = PrattParser::parse_expr(parser, Precedence::Lowest, None).unwrap();
+    let span = parser.last_span();
+
+    let condition = PrattParser::parse_expr(parser, Precedence::Lowest, None)?;
     parser.pos += 1;
 
     if let Some(token) = parser.next(0) {
@@ -45,7 +49,7 @@ pub fn parse_while(parser: &mut Parser) -> Result<WhileStatement, ParseError> {
                 break
             }
         
-            blocks.push(parser.parse_stmt().unwrap().unwrap());
+            blocks.push(parser.parse_stmt()?.ok_or_else(|| ParseError("[WhileStatement] insufficient tokens".into()))?);
         } else {
             return Err(ParseError("[WhileStatement] insufficient tokens".into()));
         }
@@ -55,6 +59,7 @@ pub fn parse_while(parser: &mut Parser) -> Result<WhileStatement, ParseError> {
     Ok(WhileStatement {
         condition,
         blocks,
+        span,
     })
 }
 
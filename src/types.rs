@@ -1,3 +1,9 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+
+use crate::parser::{Identifier, Operator};
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
 #[allow(non_camel_case_types)]
 pub enum DataType {
@@ -8,6 +14,63 @@ pub enum DataType {
     SignedInteger(SignedInteger),
     UnsignedInteger(UnsignedInteger),
     FloatingPoint(FloatingPoint),
+    // `[T; N]`, interned so that types stay `Copy`
+    Array(&'static ArrayType),
+    // `*T`, interned like arrays
+    Pointer(&'static DataType),
+    // a `struct` declaration, interned like arrays
+    Struct(&'static StructType),
+    // an `enum` declaration, a discriminant and the values of its variant
+    Enum(&'static EnumType),
+    // `(T, U, ..)`, laid out like a struct whose fields are named by their position
+    Tuple(&'static TupleType),
+    // a type parameter of a generic `def`, only its instances are generated
+    Param(&'static TypeParam),
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ArrayType {
+    pub element: DataType,
+    pub len: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StructType {
+    pub name: Identifier,
+    pub fields: Vec<(Identifier, DataType)>,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TupleType {
+    pub elements: Vec<DataType>,
+}
+
+// `T` or `T: int` in `def f[T: int](..)`
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TypeParam {
+    pub name: Identifier,
+    pub constraint: Option<Constraint>,
+}
+
+// the types a type parameter can be instantiated with
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum Constraint {
+    Integer,
+    Signed,
+    Unsigned,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EnumType {
+    pub name: Identifier,
+    pub variants: Vec<Variant>,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Variant {
+    pub name: Identifier,
+    pub discriminant: i32,
+    pub payload: Vec<DataType>,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
@@ -36,4 +99,315 @@ pub enum FloatingPoint {
     f32,
     f64,
     f128,
+}
+
+impl SignedInteger {
+    pub fn bits(&self) -> u32 {
+        match self {
+            SignedInteger::i8 => 8,
+            SignedInteger::i16 => 16,
+            SignedInteger::i32 => 32,
+            SignedInteger::i64 => 64,
+            SignedInteger::i128 => 128,
+        }
+    }
+}
+
+impl UnsignedInteger {
+    pub fn bits(&self) -> u32 {
+        match self {
+            UnsignedInteger::u8 => 8,
+            UnsignedInteger::u16 => 16,
+            UnsignedInteger::u32 => 32,
+            UnsignedInteger::u64 => 64,
+            UnsignedInteger::u128 => 128,
+        }
+    }
+}
+
+impl DataType {
+    // the type of `[element; len]`; every distinct array type is allocated once and lives as long as the process
+    pub fn array(element: DataType, len: u64) -> DataType {
+        static ARRAYS: OnceLock<Mutex<HashSet<&'static ArrayType>>> = OnceLock::new();
+
+        let mut arrays = ARRAYS.get_or_init(Default::default).lock().unwrap_or_else(|err| err.into_inner());
+        let array = ArrayType { element, len };
+
+        match arrays.get(&array) {
+            Some(array) => DataType::Array(array),
+            None => {
+                let array: &'static ArrayType = Box::leak(Box::new(array));
+                arrays.insert(array);
+                DataType::Array(array)
+            },
+        }
+    }
+
+    // the type of `*pointee`, interned the same way as arrays
+    pub fn pointer(pointee: DataType) -> DataType {
+        static POINTERS: OnceLock<Mutex<HashSet<&'static DataType>>> = OnceLock::new();
+
+        let mut pointers = POINTERS.get_or_init(Default::default).lock().unwrap_or_else(|err| err.into_inner());
+
+        match pointers.get(&pointee) {
+            Some(pointee) => DataType::Pointer(pointee),
+            None => {
+                let pointee: &'static DataType = Box::leak(Box::new(pointee));
+                pointers.insert(pointee);
+                DataType::Pointer(pointee)
+            },
+        }
+    }
+
+    // the type of a `struct` declaration, interned the same way as arrays
+    pub fn structure(name: Identifier, fields: Vec<(Identifier, DataType)>) -> DataType {
+        static STRUCTS: OnceLock<Mutex<HashSet<&'static StructType>>> = OnceLock::new();
+
+        let mut structs = STRUCTS.get_or_init(Default::default).lock().unwrap_or_else(|err| err.into_inner());
+        let structure = StructType { name, fields };
+
+        match structs.get(&structure) {
+            Some(structure) => DataType::Struct(structure),
+            None => {
+                let structure: &'static StructType = Box::leak(Box::new(structure));
+                structs.insert(structure);
+                DataType::Struct(structure)
+            },
+        }
+    }
+
+    // the type of an `enum` declaration, interned the same way as arrays
+    pub fn enumeration(name: Identifier, variants: Vec<Variant>) -> DataType {
+        static ENUMS: OnceLock<Mutex<HashSet<&'static EnumType>>> = OnceLock::new();
+
+        let mut enums = ENUMS.get_or_init(Default::default).lock().unwrap_or_else(|err| err.into_inner());
+        let enumeration = EnumType { name, variants };
+
+        match enums.get(&enumeration) {
+            Some(enumeration) => DataType::Enum(enumeration),
+            None => {
+                let enumeration: &'static EnumType = Box::leak(Box::new(enumeration));
+                enums.insert(enumeration);
+                DataType::Enum(enumeration)
+            },
+        }
+    }
+
+    // the type with every occurrence of `from` in it replaced by `to`
+    pub fn replace(self, from: DataType, to: DataType) -> DataType {
+        match self {
+            dtype if dtype == from => to,
+            DataType::Array(array) => DataType::array(array.element.replace(from, to), array.len),
+            DataType::Pointer(pointee) => DataType::pointer(pointee.replace(from, to)),
+            DataType::Tuple(tuple) => DataType::tuple(tuple.elements.iter().map(|dtype| dtype.replace(from, to)).collect()),
+            dtype => dtype,
+        }
+    }
+}
+
+impl DataType {
+    // the type of `(elements..)`, interned the same way as arrays
+    pub fn tuple(elements: Vec<DataType>) -> DataType {
+        static TUPLES: OnceLock<Mutex<HashSet<&'static TupleType>>> = OnceLock::new();
+
+        let mut tuples = TUPLES.get_or_init(Default::default).lock().unwrap_or_else(|err| err.into_inner());
+        let tuple = TupleType { elements };
+
+        match tuples.get(&tuple) {
+            Some(tuple) => DataType::Tuple(tuple),
+            None => {
+                let tuple: &'static TupleType = Box::leak(Box::new(tuple));
+                tuples.insert(tuple);
+                DataType::Tuple(tuple)
+            },
+        }
+    }
+
+    // a type parameter, interned the same way as arrays
+    pub fn param(name: Identifier, constraint: Option<Constraint>) -> DataType {
+        static PARAMS: OnceLock<Mutex<HashSet<&'static TypeParam>>> = OnceLock::new();
+
+        let mut params = PARAMS.get_or_init(Default::default).lock().unwrap_or_else(|err| err.into_inner());
+        let param = TypeParam { name, constraint };
+
+        match params.get(&param) {
+            Some(param) => DataType::Param(param),
+            None => {
+                let param: &'static TypeParam = Box::leak(Box::new(param));
+                params.insert(param);
+                DataType::Param(param)
+            },
+        }
+    }
+
+    // whether `param` occurs in the type, which is then only known per instance
+    pub fn has_param(&self, param: &TypeParam) -> bool {
+        match self {
+            DataType::Param(other) => *other == param,
+            DataType::Array(array) => array.element.has_param(param),
+            DataType::Pointer(pointee) => pointee.has_param(param),
+            DataType::Tuple(tuple) => tuple.elements.iter().any(|dtype| dtype.has_param(param)),
+            _ => false,
+        }
+    }
+
+    // the position and type of the field `name` of a struct, or of the element `.n` of a tuple
+    pub fn field(&self, name: &str) -> Option<(usize, DataType)> {
+        match self {
+            DataType::Struct(structure) => structure.field(name),
+            DataType::Tuple(tuple) => name.parse::<usize>().ok().and_then(|n| Some((n, *tuple.elements.get(n)?))),
+            _ => None,
+        }
+    }
+}
+
+impl StructType {
+    // the position and type of the field `name`, a field pointing to the forward declaration of the struct points to the struct
+    pub fn field(&'static self, name: &str) -> Option<(usize, DataType)> {
+        let forward = DataType::structure(self.name.clone(), Vec::new());
+        self.fields.iter().position(|(ident, _)| ident == name).map(|n| (n, self.fields[n].1.replace(forward, DataType::Struct(self))))
+    }
+
+    // the function a method is declared as, `.` keeps it apart from every name in the source
+    pub fn method(&self, name: &str) -> Identifier {
+        format!("{}.{}", self.name, name)
+    }
+}
+
+impl Constraint {
+    pub fn from_name(name: &str) -> Option<Constraint> {
+        match name {
+            "int" => Some(Constraint::Integer),
+            "signed" => Some(Constraint::Signed),
+            "unsigned" => Some(Constraint::Unsigned),
+            _ => None,
+        }
+    }
+
+    pub fn admits(&self, dtype: DataType) -> bool {
+        match self {
+            Constraint::Integer => is_integer(dtype),
+            Constraint::Signed => matches!(dtype, DataType::SignedInteger(_)),
+            Constraint::Unsigned => matches!(dtype, DataType::UnsignedInteger(_)),
+        }
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Constraint::Integer => write!(f, "int"),
+            Constraint::Signed => write!(f, "signed"),
+            Constraint::Unsigned => write!(f, "unsigned"),
+        }
+    }
+}
+
+impl fmt::Display for TypeParam {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.constraint {
+            Some(constraint) => write!(f, "{}: {}", self.name, constraint),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl EnumType {
+    // the discriminant is stored as an `i32`, like a C enum
+    pub const TAG: DataType = DataType::SignedInteger(SignedInteger::i32);
+
+    pub fn variant(&self, name: &str) -> Option<&Variant> {
+        self.variants.iter().find(|variant| variant.name == name)
+    }
+
+    // whether any variant carries values, a C-like enum is only its discriminant
+    pub fn has_payload(&self) -> bool {
+        self.variants.iter().any(|variant| !variant.payload.is_empty())
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+
+        if !self.payload.is_empty() {
+            write!(f, "({})", self.payload.iter().map(|dtype| dtype.to_string()).collect::<Vec<String>>().join(", "))?;
+        }
+
+        write!(f, " = {}", self.discriminant)
+    }
+}
+
+// the name a type is written with in mamba source
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DataType::void => write!(f, "void"),
+            DataType::bool => write!(f, "bool"),
+            DataType::str => write!(f, "str"),
+            DataType::SignedInteger(dtype) => write!(f, "{:?}", dtype),
+            DataType::UnsignedInteger(dtype) => write!(f, "{:?}", dtype),
+            DataType::FloatingPoint(dtype) => write!(f, "{:?}", dtype),
+            DataType::Array(array) => write!(f, "[{}; {}]", array.element, array.len),
+            DataType::Pointer(pointee) => write!(f, "*{}", pointee),
+            DataType::Struct(structure) => write!(f, "{}", structure.name),
+            DataType::Enum(enumeration) => write!(f, "{}", enumeration.name),
+            DataType::Tuple(tuple) => write!(f, "({})", tuple.elements.iter().map(|dtype| dtype.to_string()).collect::<Vec<String>>().join(", ")),
+            DataType::Param(param) => write!(f, "{}", param.name),
+        }
+    }
+}
+
+pub fn is_integer(dtype: DataType) -> bool {
+    matches!(dtype, DataType::SignedInteger(_) | DataType::UnsignedInteger(_))
+}
+
+// `*void` points to memory of unknown type, which converts to and from every other pointer
+pub fn is_pointer_compatible(from: DataType, to: DataType) -> bool {
+    match (from, to) {
+        (DataType::Pointer(from), DataType::Pointer(to)) => from == to || *from == DataType::void || *to == DataType::void,
+        _ => false,
+    }
+}
+
+// a `*u8` buffer converts to the string it holds, like a `char *` passed for a `const char *`
+pub fn is_string_buffer(from: DataType, to: DataType) -> bool {
+    matches!(from, DataType::Pointer(pointee) if *pointee == DataType::UnsignedInteger(UnsignedInteger::u8)) && to == DataType::str
+}
+
+// the type `operator` yields on a `dtype` operand, `None` when it does not apply
+pub fn unary_type(operator: Operator, dtype: DataType) -> Option<DataType> {
+    match (operator, dtype) {
+        (Operator::UnaryPlus | Operator::BitwiseNot, _) if is_integer(dtype) => Some(dtype),
+        (Operator::UnaryMinus, DataType::SignedInteger(_)) => Some(dtype),
+        _ => None,
+    }
+}
+
+// the type `operator` yields on two operands already converted to `dtype`
+pub fn infix_type(operator: Operator, dtype: DataType) -> Option<DataType> {
+    match operator {
+        Operator::Equal | Operator::NotEqual if dtype == DataType::bool || is_integer(dtype) => Some(DataType::bool),
+        Operator::Less | Operator::LessEqual | Operator::Greater | Operator::GreaterEqual if is_integer(dtype) => Some(DataType::bool),
+        Operator::BitwiseAnd | Operator::BitwiseOr | Operator::BitwiseXor if dtype == DataType::bool || is_integer(dtype) => Some(dtype),
+        Operator::Plus | Operator::Minus | Operator::Multiply | Operator::Divide | Operator::Modulo
+            | Operator::LeftShift | Operator::RightShift if is_integer(dtype) => Some(dtype),
+        _ => None,
+    }
+}
+
+// the type `operator` yields when at least one operand is a pointer, `None` when it does not apply.
+// a pointer moves by whole elements, so `*void` only supports comparisons.
+pub fn pointer_infix_type(operator: Operator, left: DataType, right: DataType) -> Option<DataType> {
+    let sized = |dtype: DataType| matches!(dtype, DataType::Pointer(pointee) if *pointee != DataType::void);
+
+    match operator {
+        Operator::Plus if sized(left) && is_integer(right) => Some(left),
+        Operator::Plus if is_integer(left) && sized(right) => Some(right),
+        Operator::Minus if sized(left) && is_integer(right) => Some(left),
+        Operator::Minus if sized(left) && left == right => Some(DataType::SignedInteger(SignedInteger::i64)),
+        Operator::Equal | Operator::NotEqual if is_pointer_compatible(left, right) => Some(DataType::bool),
+        Operator::Less | Operator::LessEqual | Operator::Greater | Operator::GreaterEqual if sized(left) && left == right => Some(DataType::bool),
+        _ => None,
+    }
 }
\ No newline at end of file
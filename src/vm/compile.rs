@@ -0,0 +1,440 @@
+use std::cmp::max;
+use std::collections::{HashMap, HashSet};
+
+use crate::error::BytecodeError;
+use crate::format;
+use crate::interp::Value;
+use crate::parser::{addressed_variables, DefStatement, Expression, Identifier, IfBranch, IfStatement, Operator, Statement, AST};
+use crate::types::{infix_type, is_integer, is_pointer_compatible, is_string_buffer, unary_type, DataType};
+use super::instr::{Extern, Function, Global, Instr, Program};
+
+// Translates a folded AST into bytecode, resolving every type statically the way `IRGen` does.
+pub struct Compiler {
+    program: Program,
+    functions: HashMap<Identifier, usize>,
+    externs: HashMap<Identifier, usize>,
+    globals: HashMap<Identifier, usize>,
+    // local slots and types of the function being compiled, innermost scope last
+    scopes: Vec<HashMap<Identifier, (u32, DataType)>>,
+    locals: u32,
+    // the variables of the function whose address is taken, and the slots that hold a pointer to
+    // the cell such a variable is kept in instead of its value
+    addressed: HashSet<Identifier>,
+    boxed: HashSet<u32>,
+    // name and return type of the function being compiled
+    current: (Identifier, DataType),
+}
+
+impl Default for Compiler {
+    fn default() -> Compiler {
+        Compiler {
+            program: Program {
+                constants: Vec::new(),
+                globals: Vec::new(),
+                externs: Vec::new(),
+                functions: Vec::new(),
+                init: Function { name: "<init>".into(), params: Vec::new(), r#type: DataType::void, locals: 0, code: Vec::new() },
+            },
+            functions: HashMap::new(),
+            externs: HashMap::new(),
+            globals: HashMap::new(),
+            scopes: Vec::new(),
+            locals: 0,
+            addressed: HashSet::new(),
+            boxed: HashSet::new(),
+            current: (String::new(), DataType::void),
+        }
+    }
+}
+
+impl Compiler {
+    pub fn new() -> Compiler {
+        Compiler::default()
+    }
+
+    pub fn compile(mut self, ast: &AST) -> Result<Program, BytecodeError> {
+        // declare everything first so functions can refer to each other in any order
+        for stmt in ast {
+            match stmt {
+                Statement::Def(stmt) if !stmt.type_params.is_empty() => return Err(BytecodeError(format!("[Bytecode] generic function `{}` is not supported", stmt.name))),
+                Statement::Def(stmt) => {
+                    check_signature(&stmt.name, &stmt.params, stmt.r#type)?;
+                    self.functions.insert(stmt.name.clone(), self.program.functions.len());
+                    self.program.functions.push(Function {
+                        name: stmt.name.clone(),
+                        params: stmt.params.iter().map(|(_, dtype)| *dtype).collect(),
+                        r#type: stmt.r#type,
+                        locals: 0,
+                        code: Vec::new(),
+                    });
+                },
+                Statement::Extern(stmt) => {
+                    check_signature(&stmt.name, &stmt.params, stmt.r#type)?;
+                    self.externs.insert(stmt.name.clone(), self.program.externs.len());
+                    self.program.externs.push(Extern {
+                        name: stmt.name.clone(),
+                        params: stmt.params.iter().map(|(_, dtype)| *dtype).collect(),
+                        r#type: stmt.r#type,
+                    });
+                },
+                Statement::Let(stmt) => {
+                    check_type(stmt.r#type, &stmt.ident)?;
+                    self.globals.insert(stmt.ident.clone(), self.program.globals.len());
+                    self.program.globals.push(Global { name: stmt.ident.clone(), r#type: stmt.r#type });
+                },
+                // a declaration only introduces a type, which the values using it are checked for
+                Statement::Struct(_) | Statement::Enum(_) => {},
+                _ => return Err(BytecodeError(format!("[Bytecode] {} cannot be global", stmt))),
+            }
+        }
+
+        let mut init = Vec::new();
+
+        for stmt in ast {
+            match stmt {
+                Statement::Def(stmt) => self.compile_def(stmt)?,
+                Statement::Let(stmt) => {
+                    let dtype = self.compile_expr(&stmt.expr, &mut init)?;
+                    coerce(&mut init, dtype, stmt.r#type, &stmt.ident)?;
+                    init.push(Instr::StoreGlobal(self.globals[&stmt.ident] as u32));
+                },
+                _ => {},
+            }
+        }
+
+        init.push(Instr::ReturnVoid);
+        self.program.init.code = init;
+
+        Ok(self.program)
+    }
+
+    fn compile_def(&mut self, stmt: &DefStatement) -> Result<(), BytecodeError> {
+        let params = stmt.params.iter()
+            .enumerate()
+            .map(|(slot, (ident, dtype))| (ident.clone(), (slot as u32, *dtype)))
+            .collect();
+
+        self.scopes = vec![params];
+        self.locals = stmt.params.len() as u32;
+        self.current = (stmt.name.clone(), stmt.r#type);
+        self.addressed = addressed_variables(&stmt.stmts);
+        self.boxed = HashSet::new();
+
+        let mut code = Vec::new();
+
+        for (slot, (ident, _)) in stmt.params.iter().enumerate() {
+            if self.addressed.contains(ident) {
+                code.extend([Instr::LoadLocal(slot as u32), Instr::Box, Instr::StoreLocal(slot as u32)]);
+                self.boxed.insert(slot as u32);
+            }
+        }
+
+        self.compile_block(&stmt.stmts, &mut code)?;
+
+        if stmt.r#type == DataType::void {
+            code.push(Instr::ReturnVoid);
+        } else {
+            code.push(Instr::MissingReturn);
+        }
+
+        let function = &mut self.program.functions[self.functions[&stmt.name]];
+        function.locals = self.locals;
+        function.code = code;
+
+        Ok(())
+    }
+
+    fn compile_block(&mut self, stmts: &[Statement], code: &mut Vec<Instr>) -> Result<(), BytecodeError> {
+        self.scopes.push(HashMap::new());
+
+        for stmt in stmts {
+            self.compile_stmt(stmt, code)?;
+        }
+
+        self.scopes.pop();
+        Ok(())
+    }
+
+    fn compile_stmt(&mut self, stmt: &Statement, code: &mut Vec<Instr>) -> Result<(), BytecodeError> {
+        match stmt {
+            Statement::Let(stmt) => {
+                check_type(stmt.r#type, &stmt.ident)?;
+                let dtype = self.compile_expr(&stmt.expr, code)?;
+                coerce(code, dtype, stmt.r#type, &stmt.ident)?;
+
+                // every `let` gets a slot of its own, shadowed variables keep theirs
+                let slot = self.locals;
+                self.locals += 1;
+
+                if self.addressed.contains(&stmt.ident) {
+                    code.push(Instr::Box);
+                    self.boxed.insert(slot);
+                }
+
+                code.push(Instr::StoreLocal(slot));
+
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.insert(stmt.ident.clone(), (slot, stmt.r#type));
+                }
+            },
+            Statement::If(stmt) => self.compile_if(stmt, code)?,
+            Statement::While(stmt) => {
+                let start = code.len() as u32;
+                self.compile_condition(&stmt.condition, code)?;
+
+                let exit = code.len();
+                code.push(Instr::JumpIfFalse(0));
+
+                self.compile_block(&stmt.blocks, code)?;
+                code.push(Instr::Jump(start));
+                code[exit] = Instr::JumpIfFalse(code.len() as u32);
+            },
+            Statement::Return(stmt) => {
+                let (name, r#type) = self.current.clone();
+                let dtype = self.compile_expr(&stmt.expr, code)?;
+
+                // like the interpreter, a void function drops whatever it is asked to return
+                if r#type == DataType::void {
+                    code.push(Instr::Pop);
+                    code.push(Instr::ReturnVoid);
+                } else {
+                    coerce(code, dtype, r#type, &name)?;
+                    code.push(Instr::Return);
+                }
+            },
+            Statement::Expression(stmt) => {
+                self.compile_expr(&stmt.expr, code)?;
+                code.push(Instr::Pop);
+            },
+            Statement::Match(_) => return Err(BytecodeError("[Bytecode] `match` is not supported".into())),
+            Statement::Destructure(_) => return Err(BytecodeError("[Bytecode] tuples are not supported".into())),
+            Statement::Def(_) | Statement::Extern(_) | Statement::Struct(_) | Statement::Enum(_) => return Err(BytecodeError(format!("[Bytecode] {} cannot be local", stmt))),
+            Statement::Assign(_) => return Err(BytecodeError("[Bytecode] arrays, pointers and structs are not supported".into())),
+        }
+
+        Ok(())
+    }
+
+    fn compile_if(&mut self, stmt: &IfStatement, code: &mut Vec<Instr>) -> Result<(), BytecodeError> {
+        self.compile_condition(&stmt.condition, code)?;
+
+        let skip_then = code.len();
+        code.push(Instr::JumpIfFalse(0));
+        self.compile_block(&stmt.then, code)?;
+
+        if let IfBranch::None = stmt.r#else.as_ref() {
+            code[skip_then] = Instr::JumpIfFalse(code.len() as u32);
+            return Ok(());
+        }
+
+        let skip_else = code.len();
+        code.push(Instr::Jump(0));
+        code[skip_then] = Instr::JumpIfFalse(code.len() as u32);
+
+        match stmt.r#else.as_ref() {
+            IfBranch::Elif(stmt) => self.compile_if(stmt, code)?,
+            IfBranch::Else(stmts) => self.compile_block(stmts, code)?,
+            IfBranch::None => {},
+        }
+
+        code[skip_else] = Instr::Jump(code.len() as u32);
+        Ok(())
+    }
+
+    fn compile_condition(&mut self, expr: &Expression, code: &mut Vec<Instr>) -> Result<(), BytecodeError> {
+        match self.compile_expr(expr, code)? {
+            DataType::bool => Ok(()),
+            dtype => Err(BytecodeError(format!("[Bytecode] expected a bool condition, found {}", dtype))),
+        }
+    }
+
+    // emits the code of `expr` and returns the type of the value it leaves on the stack
+    fn compile_expr(&mut self, expr: &Expression, code: &mut Vec<Instr>) -> Result<DataType, BytecodeError> {
+        match expr {
+            Expression::Unary(expr) if expr.operator == Operator::AddressOf => {
+                let (slot, dtype) = self.addressed_local(&expr.right)?;
+                code.push(Instr::LoadLocal(slot));
+                Ok(DataType::pointer(dtype))
+            },
+            Expression::Unary(expr) if expr.operator == Operator::Dereference => Err(BytecodeError(format!("[Bytecode] operator {:?} is not supported", expr.operator))),
+            Expression::Struct(_) | Expression::Member(_) | Expression::MethodCall(_) => Err(BytecodeError("[Bytecode] structs are not supported".into())),
+            Expression::Enum(_) => Err(BytecodeError("[Bytecode] enums are not supported".into())),
+            Expression::Tuple(_) => Err(BytecodeError("[Bytecode] tuples are not supported".into())),
+            Expression::Literal((literal, _)) => {
+                let value = Value::from(literal);
+                let dtype = value.dtype();
+
+                code.push(Instr::Const(self.constant(value)));
+                Ok(dtype)
+            },
+            Expression::Identifier(ident) => {
+                if let Some((slot, dtype)) = self.scopes.iter().rev().find_map(|scope| scope.get(ident)) {
+                    code.push(Instr::LoadLocal(*slot));
+
+                    if self.boxed.contains(slot) {
+                        code.push(Instr::Load);
+                    }
+
+                    Ok(*dtype)
+                } else if let Some(&index) = self.globals.get(ident) {
+                    code.push(Instr::LoadGlobal(index as u32));
+                    Ok(self.program.globals[index].r#type)
+                } else {
+                    Err(BytecodeError(format!("[Bytecode] identifier `{}` not found", ident)))
+                }
+            },
+            Expression::Unary(expr) => {
+                let dtype = self.compile_expr(&expr.right, code)?;
+                let result = unary_type(expr.operator, dtype)
+                    .ok_or_else(|| BytecodeError(format!("[Bytecode] operator {:?} cannot be applied to {}", expr.operator, dtype)))?;
+
+                code.push(Instr::Unary(expr.operator, dtype));
+                Ok(result)
+            },
+            Expression::Infix(expr) => {
+                let left = self.compile_expr(&expr.left, code)?;
+
+                // the right operand is compiled aside so the left one can still be cast before it is pushed
+                let mut right_code = Vec::new();
+                let right = self.compile_expr(&expr.right, &mut right_code)?;
+
+                let dtype = max(left, right);
+                let result = infix_type(expr.operator, dtype)
+                    .filter(|_| left == right || (is_integer(left) && is_integer(right)))
+                    .ok_or_else(|| BytecodeError(format!("[Bytecode] operator {:?} cannot be applied to {} and {}", expr.operator, left, right)))?;
+
+                if left != dtype {
+                    code.push(Instr::Cast(dtype));
+                }
+
+                code.extend(right_code);
+
+                if right != dtype {
+                    code.push(Instr::Cast(dtype));
+                }
+
+                code.push(Instr::Infix(expr.operator, dtype));
+                Ok(result)
+            },
+            Expression::Index(_) | Expression::Array(_) => Err(BytecodeError("[Bytecode] arrays are not supported".into())),
+            Expression::FormatString(expr) => {
+                let types = expr.values()
+                    .map(|value| self.type_of(value))
+                    .collect::<Result<Vec<DataType>, BytecodeError>>()?;
+
+                self.compile_expr(&format::lower(expr, &types).map_err(|err| BytecodeError(err.0))?, code)
+            },
+            Expression::FnCall(expr) => {
+                let (instr, params, r#type) = if let Some(&index) = self.functions.get(&expr.ident) {
+                    let function = &self.program.functions[index];
+                    (Instr::Call(index as u32), function.params.clone(), function.r#type)
+                } else if let Some(&index) = self.externs.get(&expr.ident) {
+                    let function = &self.program.externs[index];
+                    (Instr::CallExtern(index as u32), function.params.clone(), function.r#type)
+                } else {
+                    return Err(BytecodeError(format!("[Bytecode] unable to find function `{}`", expr.ident)));
+                };
+
+                if params.len() != expr.args.len() {
+                    return Err(BytecodeError(format!("[Bytecode] `{}` takes {} arguments but {} were given", expr.ident, params.len(), expr.args.len())));
+                }
+
+                for (arg, param) in expr.args.iter().zip(params) {
+                    let dtype = self.compile_expr(arg, code)?;
+                    coerce(code, dtype, param, &expr.ident)?;
+                }
+
+                code.push(instr);
+                Ok(r#type)
+            },
+        }
+    }
+
+    // the type of `expr` without compiling it, for the values of an f-string
+    fn type_of(&self, expr: &Expression) -> Result<DataType, BytecodeError> {
+        match expr {
+            Expression::Unary(expr) if expr.operator == Operator::AddressOf => self.addressed_local(&expr.right).map(|(_, dtype)| DataType::pointer(dtype)),
+            Expression::Unary(expr) if expr.operator == Operator::Dereference => Err(BytecodeError(format!("[Bytecode] operator {:?} is not supported", expr.operator))),
+            Expression::Struct(_) | Expression::Member(_) | Expression::MethodCall(_) => Err(BytecodeError("[Bytecode] structs are not supported".into())),
+            Expression::Enum(_) => Err(BytecodeError("[Bytecode] enums are not supported".into())),
+            Expression::Tuple(_) => Err(BytecodeError("[Bytecode] tuples are not supported".into())),
+            Expression::FormatString(expr) => Ok(expr.dtype()),
+            Expression::Literal((literal, _)) => Ok(Value::from(literal).dtype()),
+            Expression::Identifier(ident) => self.scopes.iter().rev()
+                .find_map(|scope| scope.get(ident).map(|(_, dtype)| *dtype))
+                .or_else(|| self.globals.get(ident).map(|&index| self.program.globals[index].r#type))
+                .ok_or_else(|| BytecodeError(format!("[Bytecode] identifier `{}` not found", ident))),
+            Expression::Unary(expr) => {
+                let dtype = self.type_of(&expr.right)?;
+                unary_type(expr.operator, dtype)
+                    .ok_or_else(|| BytecodeError(format!("[Bytecode] operator {:?} cannot be applied to {}", expr.operator, dtype)))
+            },
+            Expression::Infix(expr) => {
+                let (left, right) = (self.type_of(&expr.left)?, self.type_of(&expr.right)?);
+                infix_type(expr.operator, max(left, right))
+                    .filter(|_| left == right || (is_integer(left) && is_integer(right)))
+                    .ok_or_else(|| BytecodeError(format!("[Bytecode] operator {:?} cannot be applied to {} and {}", expr.operator, left, right)))
+            },
+            Expression::Index(_) | Expression::Array(_) => Err(BytecodeError("[Bytecode] arrays are not supported".into())),
+            Expression::FnCall(expr) => self.functions.get(&expr.ident)
+                .map(|&index| self.program.functions[index].r#type)
+                .or_else(|| self.externs.get(&expr.ident).map(|&index| self.program.externs[index].r#type))
+                .ok_or_else(|| BytecodeError(format!("[Bytecode] unable to find function `{}`", expr.ident))),
+        }
+    }
+
+    // the slot holding the pointer to the cell of the local `expr` names, and the type of the local;
+    // only the address of a whole local can be taken
+    fn addressed_local(&self, expr: &Expression) -> Result<(u32, DataType), BytecodeError> {
+        let ident = match expr {
+            Expression::Identifier(ident) => ident,
+            expr => return Err(BytecodeError(format!("[Bytecode] taking the address of {} is not supported", expr))),
+        };
+
+        match self.scopes.iter().rev().find_map(|scope| scope.get(ident)) {
+            Some((slot, dtype)) if self.boxed.contains(slot) => Ok((*slot, *dtype)),
+            Some(_) => unreachable!("`addressed_variables` finds every local whose address is taken"),
+            None if self.globals.contains_key(ident) => Err(BytecodeError(format!("[Bytecode] taking the address of global `{}` is not supported", ident))),
+            None => Err(BytecodeError(format!("[Bytecode] identifier `{}` not found", ident))),
+        }
+    }
+
+    // index of `value` in the constant pool, added on first use
+    fn constant(&mut self, value: Value) -> u32 {
+        let constants = &mut self.program.constants;
+
+        constants.iter()
+            .position(|constant| *constant == value)
+            .unwrap_or_else(|| {
+                constants.push(value);
+                constants.len() - 1
+            }) as u32
+    }
+}
+
+// composite types are only supported by the interpreter and the LLVM backend so far, and pointers
+// are only passed around, to the host functions that read and write what they point to
+fn check_type(dtype: DataType, name: &str) -> Result<(), BytecodeError> {
+    match dtype {
+        DataType::Pointer(pointee) if *pointee == DataType::void || check_type(*pointee, name).is_ok() => Ok(()),
+        DataType::Array(_) | DataType::Pointer(_) | DataType::Struct(_) | DataType::Enum(_) | DataType::Tuple(_) => Err(BytecodeError(format!("[Bytecode] {} of `{}` is not supported", dtype, name))),
+        _ => Ok(()),
+    }
+}
+
+fn check_signature(name: &str, params: &[(Identifier, DataType)], r#type: DataType) -> Result<(), BytecodeError> {
+    params.iter().try_for_each(|(ident, dtype)| check_type(*dtype, ident))?;
+    check_type(r#type, name)
+}
+
+// converts the value on top of the stack from `from` to `to`, which integers, pointers and `*u8` to `str` allow
+fn coerce(code: &mut Vec<Instr>, from: DataType, to: DataType, name: &str) -> Result<(), BytecodeError> {
+    if from == to {
+        Ok(())
+    } else if (is_integer(from) && is_integer(to)) || is_pointer_compatible(from, to) || is_string_buffer(from, to) {
+        code.push(Instr::Cast(to));
+        Ok(())
+    } else {
+        Err(BytecodeError(format!("[Bytecode] `{}` expects {}, found {}", name, to, from)))
+    }
+}
\ No newline at end of file
@@ -0,0 +1,81 @@
+use std::fmt;
+
+use super::instr::{Function, Instr, Program};
+
+impl fmt::Display for Instr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instr::Const(n) => write!(f, "const {}", n),
+            Instr::LoadLocal(n) => write!(f, "load_local {}", n),
+            Instr::StoreLocal(n) => write!(f, "store_local {}", n),
+            Instr::LoadGlobal(n) => write!(f, "load_global {}", n),
+            Instr::StoreGlobal(n) => write!(f, "store_global {}", n),
+            Instr::Unary(operator, dtype) => write!(f, "unary {:?} {}", operator, dtype),
+            Instr::Infix(operator, dtype) => write!(f, "infix {:?} {}", operator, dtype),
+            Instr::Cast(dtype) => write!(f, "cast {}", dtype),
+            Instr::Jump(n) => write!(f, "jump {}", n),
+            Instr::JumpIfFalse(n) => write!(f, "jump_if_false {}", n),
+            Instr::Call(n) => write!(f, "call {}", n),
+            Instr::CallExtern(n) => write!(f, "call_extern {}", n),
+            Instr::Return => write!(f, "return"),
+            Instr::ReturnVoid => write!(f, "return_void"),
+            Instr::Pop => write!(f, "pop"),
+            Instr::MissingReturn => write!(f, "missing_return"),
+            Instr::Box => write!(f, "box"),
+            Instr::Load => write!(f, "load"),
+        }
+    }
+}
+
+// lists the tables of `program` and the code of every function, one instruction per line
+pub fn disassemble(program: &Program) -> String {
+    let mut result = String::new();
+
+    for (n, constant) in program.constants.iter().enumerate() {
+        result += &format!("const {}: {}: {}\n", n, constant, constant.dtype());
+    }
+
+    for (n, global) in program.globals.iter().enumerate() {
+        result += &format!("global {}: {}: {}\n", n, global.name, global.r#type);
+    }
+
+    for (n, function) in program.externs.iter().enumerate() {
+        result += &format!("extern {}: {}({}) -> {}\n", n, function.name, join(&function.params), function.r#type);
+    }
+
+    result += &disassemble_function(program, &program.init);
+
+    for function in &program.functions {
+        result += &disassemble_function(program, function);
+    }
+
+    result
+}
+
+fn disassemble_function(program: &Program, function: &Function) -> String {
+    let mut result = format!("\n{}({}) -> {}, {} locals:\n", function.name, join(&function.params), function.r#type, function.locals);
+
+    for (pc, instr) in function.code.iter().enumerate() {
+        // name what an index refers to, so the listing reads without the tables
+        let comment = match instr {
+            Instr::Const(n) => program.constants.get(*n as usize).map(|constant| constant.to_string()),
+            Instr::LoadGlobal(n) | Instr::StoreGlobal(n) => program.globals.get(*n as usize).map(|global| global.name.clone()),
+            Instr::Call(n) => program.functions.get(*n as usize).map(|function| function.name.clone()),
+            Instr::CallExtern(n) => program.externs.get(*n as usize).map(|function| function.name.clone()),
+            _ => None,
+        };
+
+        let line = match comment {
+            Some(comment) => format!("{:<24}; {}", instr.to_string(), comment),
+            None => instr.to_string(),
+        };
+
+        result += &format!("    {:04}  {}\n", pc, line.trim_end());
+    }
+
+    result
+}
+
+fn join<T: fmt::Display>(items: &[T]) -> String {
+    items.iter().map(|item| item.to_string()).collect::<Vec<String>>().join(", ")
+}
\ No newline at end of file
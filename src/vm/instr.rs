@@ -0,0 +1,86 @@
+use crate::interp::Value;
+use crate::parser::Operator;
+use crate::types::DataType;
+
+// One instruction of the stack machine. Operands are popped from and results pushed to the value stack;
+// indices refer to the tables of the `Program` and jump targets to positions in the current function's code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instr {
+    // pushes `constants[n]`
+    Const(u32),
+    // pushes a copy of local slot `n`; parameters come first, in order
+    LoadLocal(u32),
+    // pops a value into local slot `n`
+    StoreLocal(u32),
+    // pushes a copy of `globals[n]`
+    LoadGlobal(u32),
+    // pops a value into `globals[n]`
+    StoreGlobal(u32),
+    // pops one operand of the given type and pushes the result, like `unary_op`
+    Unary(Operator, DataType),
+    // pops the right then the left operand, both of the given type, and pushes the result, like `infix_op`
+    Infix(Operator, DataType),
+    // pops an integer and pushes it converted to another integer type, like `get_cast`,
+    // a pointer as another pointer type, or the string a `*u8` buffer holds
+    Cast(DataType),
+    // continues at instruction `n`
+    Jump(u32),
+    // pops a bool and continues at instruction `n` if it is false
+    JumpIfFalse(u32),
+    // calls `functions[n]` with its arguments on top of the stack, which become its first local slots
+    Call(u32),
+    // calls the host function behind `externs[n]` with its arguments on top of the stack
+    CallExtern(u32),
+    // pops the return value, drops the frame and pushes the value for the caller
+    Return,
+    // drops the frame and pushes `void` for the caller
+    ReturnVoid,
+    // discards the top of the stack
+    Pop,
+    // fails because a non-void function reached its end without returning
+    MissingReturn,
+    // pops a value and pushes a pointer to a new cell holding it, which is freed when the function returns
+    Box,
+    // pops a pointer and pushes a copy of the value it points to
+    Load,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    pub params: Vec<DataType>,
+    pub r#type: DataType,
+    // number of local slots, parameters included
+    pub locals: u32,
+    pub code: Vec<Instr>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extern {
+    pub name: String,
+    pub params: Vec<DataType>,
+    pub r#type: DataType,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Global {
+    pub name: String,
+    pub r#type: DataType,
+}
+
+// A compiled module: everything the VM needs to run it, and all that is stored in a `.mbc` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub constants: Vec<Value>,
+    pub globals: Vec<Global>,
+    pub externs: Vec<Extern>,
+    pub functions: Vec<Function>,
+    // stores the initial value of every global, runs before anything else
+    pub init: Function,
+}
+
+impl Program {
+    pub fn function(&self, name: &str) -> Option<usize> {
+        self.functions.iter().position(|function| function.name == name)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,339 @@
+use crate::error::BytecodeError;
+use crate::interp::Value;
+use crate::parser::Operator;
+use crate::types::{DataType, FloatingPoint, SignedInteger, UnsignedInteger};
+use super::instr::{Extern, Function, Global, Instr, Program};
+
+// `.mbc` files start with these bytes, the last one being the format version
+pub const MAGIC: &[u8; 4] = b"MBC\x01";
+
+// Operators and types are stored as their index in these tables.
+const OPERATORS: [Operator; 20] = [
+    Operator::UnaryPlus,
+    Operator::UnaryMinus,
+    Operator::BitwiseAnd,
+    Operator::BitwiseOr,
+    Operator::BitwiseXor,
+    Operator::BitwiseNot,
+    Operator::Plus,
+    Operator::Minus,
+    Operator::Multiply,
+    Operator::Divide,
+    Operator::Modulo,
+    Operator::Equal,
+    Operator::NotEqual,
+    Operator::Less,
+    Operator::LessEqual,
+    Operator::Greater,
+    Operator::GreaterEqual,
+    Operator::LeftShift,
+    Operator::RightShift,
+    Operator::FnCall,
+];
+
+const DATA_TYPES: [DataType; 16] = [
+    DataType::void,
+    DataType::bool,
+    DataType::str,
+    DataType::SignedInteger(SignedInteger::i8),
+    DataType::SignedInteger(SignedInteger::i16),
+    DataType::SignedInteger(SignedInteger::i32),
+    DataType::SignedInteger(SignedInteger::i64),
+    DataType::SignedInteger(SignedInteger::i128),
+    DataType::UnsignedInteger(UnsignedInteger::u8),
+    DataType::UnsignedInteger(UnsignedInteger::u16),
+    DataType::UnsignedInteger(UnsignedInteger::u32),
+    DataType::UnsignedInteger(UnsignedInteger::u64),
+    DataType::UnsignedInteger(UnsignedInteger::u128),
+    DataType::FloatingPoint(FloatingPoint::f32),
+    DataType::FloatingPoint(FloatingPoint::f64),
+    DataType::FloatingPoint(FloatingPoint::f128),
+];
+
+// the code after the table, followed by the type a pointer points to
+const POINTER: u8 = DATA_TYPES.len() as u8;
+
+// Every number is little-endian, counts and indices are u32 and strings are length-prefixed UTF-8.
+impl Program {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+
+        put_u32(&mut out, self.constants.len() as u32);
+        for constant in &self.constants {
+            put_value(&mut out, constant);
+        }
+
+        put_u32(&mut out, self.globals.len() as u32);
+        for global in &self.globals {
+            put_str(&mut out, &global.name);
+            put_dtype(&mut out, global.r#type);
+        }
+
+        put_u32(&mut out, self.externs.len() as u32);
+        for function in &self.externs {
+            put_str(&mut out, &function.name);
+            put_dtypes(&mut out, &function.params);
+            put_dtype(&mut out, function.r#type);
+        }
+
+        put_u32(&mut out, self.functions.len() as u32);
+        for function in &self.functions {
+            put_function(&mut out, function);
+        }
+
+        put_function(&mut out, &self.init);
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Program, BytecodeError> {
+        if bytes.len() < MAGIC.len() || bytes[..3] != MAGIC[..3] {
+            return Err(BytecodeError("[Bytecode] not a mamba bytecode file".into()));
+        }
+
+        if bytes[3] != MAGIC[3] {
+            return Err(BytecodeError(format!("[Bytecode] unsupported bytecode version {}", bytes[3])));
+        }
+
+        let mut reader = Reader { bytes, pos: MAGIC.len() };
+
+        let constants = reader.list(Reader::value)?;
+        let globals = reader.list(|reader| Ok(Global { name: reader.str()?, r#type: reader.dtype()? }))?;
+        let externs = reader.list(|reader| Ok(Extern { name: reader.str()?, params: reader.list(Reader::dtype)?, r#type: reader.dtype()? }))?;
+        let functions = reader.list(Reader::function)?;
+        let init = reader.function()?;
+
+        if reader.pos != bytes.len() {
+            return Err(BytecodeError(format!("[Bytecode] {} trailing bytes", bytes.len() - reader.pos)));
+        }
+
+        let program = Program { constants, globals, externs, functions, init };
+
+        for function in program.functions.iter().chain([&program.init]) {
+            validate(&program, function)?;
+        }
+
+        Ok(program)
+    }
+}
+
+// checks every index so a corrupt file fails here rather than inside the VM
+fn validate(program: &Program, function: &Function) -> Result<(), BytecodeError> {
+    if function.params.len() > function.locals as usize {
+        return Err(BytecodeError(format!("[Bytecode] `{}` has fewer local slots than parameters", function.name)));
+    }
+
+    for (pc, instr) in function.code.iter().enumerate() {
+        let (n, len, table) = match *instr {
+            Instr::Const(n) => (n, program.constants.len(), "constant"),
+            Instr::LoadLocal(n) | Instr::StoreLocal(n) => (n, function.locals as usize, "local"),
+            Instr::LoadGlobal(n) | Instr::StoreGlobal(n) => (n, program.globals.len(), "global"),
+            Instr::Jump(n) | Instr::JumpIfFalse(n) => (n, function.code.len(), "instruction"),
+            Instr::Call(n) => (n, program.functions.len(), "function"),
+            Instr::CallExtern(n) => (n, program.externs.len(), "extern"),
+            _ => continue,
+        };
+
+        if n as usize >= len {
+            return Err(BytecodeError(format!("[Bytecode] instruction {} of `{}` refers to missing {} {}", pc, function.name, table, n)));
+        }
+    }
+
+    Ok(())
+}
+
+fn put_u32(out: &mut Vec<u8>, n: u32) {
+    out.extend_from_slice(&n.to_le_bytes());
+}
+
+fn put_str(out: &mut Vec<u8>, s: &str) {
+    put_u32(out, s.len() as u32);
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn put_dtype(out: &mut Vec<u8>, dtype: DataType) {
+    if let DataType::Pointer(pointee) = dtype {
+        out.push(POINTER);
+        put_dtype(out, *pointee);
+    } else {
+        out.push(DATA_TYPES.iter().position(|d| *d == dtype).unwrap_or_default() as u8);
+    }
+}
+
+fn put_dtypes(out: &mut Vec<u8>, dtypes: &[DataType]) {
+    put_u32(out, dtypes.len() as u32);
+    for dtype in dtypes {
+        put_dtype(out, *dtype);
+    }
+}
+
+fn put_operator(out: &mut Vec<u8>, operator: Operator) {
+    out.push(OPERATORS.iter().position(|op| *op == operator).unwrap_or_default() as u8);
+}
+
+fn put_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Void => out.push(0),
+        Value::Bool(b) => out.extend_from_slice(&[1, *b as u8]),
+        Value::Str(s) => {
+            out.push(2);
+            put_str(out, s);
+        },
+        Value::Signed(n, dtype) => {
+            out.push(3);
+            put_dtype(out, DataType::SignedInteger(*dtype));
+            out.extend_from_slice(&n.to_le_bytes());
+        },
+        Value::Unsigned(n, dtype) => {
+            out.push(4);
+            put_dtype(out, DataType::UnsignedInteger(*dtype));
+            out.extend_from_slice(&n.to_le_bytes());
+        },
+        // `null` is the only pointer constant
+        Value::Pointer(_, pointee) => {
+            out.push(5);
+            put_dtype(out, *pointee);
+        },
+        Value::Array(..) | Value::Struct(..) | Value::Enum(..) => unreachable!("the compiler never emits array, struct or enum constants"),
+    }
+}
+
+fn put_function(out: &mut Vec<u8>, function: &Function) {
+    put_str(out, &function.name);
+    put_dtypes(out, &function.params);
+    put_dtype(out, function.r#type);
+    put_u32(out, function.locals);
+
+    put_u32(out, function.code.len() as u32);
+    for instr in &function.code {
+        put_instr(out, instr);
+    }
+}
+
+fn put_instr(out: &mut Vec<u8>, instr: &Instr) {
+    match *instr {
+        Instr::Const(n) => { out.push(0); put_u32(out, n); },
+        Instr::LoadLocal(n) => { out.push(1); put_u32(out, n); },
+        Instr::StoreLocal(n) => { out.push(2); put_u32(out, n); },
+        Instr::LoadGlobal(n) => { out.push(3); put_u32(out, n); },
+        Instr::StoreGlobal(n) => { out.push(4); put_u32(out, n); },
+        Instr::Unary(operator, dtype) => { out.push(5); put_operator(out, operator); put_dtype(out, dtype); },
+        Instr::Infix(operator, dtype) => { out.push(6); put_operator(out, operator); put_dtype(out, dtype); },
+        Instr::Cast(dtype) => { out.push(7); put_dtype(out, dtype); },
+        Instr::Jump(n) => { out.push(8); put_u32(out, n); },
+        Instr::JumpIfFalse(n) => { out.push(9); put_u32(out, n); },
+        Instr::Call(n) => { out.push(10); put_u32(out, n); },
+        Instr::CallExtern(n) => { out.push(11); put_u32(out, n); },
+        Instr::Return => out.push(12),
+        Instr::ReturnVoid => out.push(13),
+        Instr::Pop => out.push(14),
+        Instr::MissingReturn => out.push(15),
+        Instr::Box => out.push(16),
+        Instr::Load => out.push(17),
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], BytecodeError> {
+        let bytes = self.bytes.get(self.pos..self.pos + n)
+            .ok_or_else(|| BytecodeError("[Bytecode] unexpected end of file".into()))?;
+
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, BytecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, BytecodeError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u128(&mut self) -> Result<u128, BytecodeError> {
+        let mut bytes = [0; 16];
+        bytes.copy_from_slice(self.take(16)?);
+        Ok(u128::from_le_bytes(bytes))
+    }
+
+    fn str(&mut self) -> Result<String, BytecodeError> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+
+        String::from_utf8(bytes.to_vec()).map_err(|_| BytecodeError("[Bytecode] string is not valid UTF-8".into()))
+    }
+
+    fn list<T>(&mut self, mut item: impl FnMut(&mut Self) -> Result<T, BytecodeError>) -> Result<Vec<T>, BytecodeError> {
+        let len = self.u32()?;
+        (0..len).map(|_| item(self)).collect()
+    }
+
+    fn dtype(&mut self) -> Result<DataType, BytecodeError> {
+        match self.u8()? {
+            POINTER => Ok(DataType::pointer(self.dtype()?)),
+            code => DATA_TYPES.get(code as usize).copied().ok_or_else(|| BytecodeError(format!("[Bytecode] unknown type code {}", code))),
+        }
+    }
+
+    fn operator(&mut self) -> Result<Operator, BytecodeError> {
+        let code = self.u8()?;
+        OPERATORS.get(code as usize).copied().ok_or_else(|| BytecodeError(format!("[Bytecode] unknown operator code {}", code)))
+    }
+
+    fn value(&mut self) -> Result<Value, BytecodeError> {
+        match self.u8()? {
+            0 => Ok(Value::Void),
+            1 => Ok(Value::Bool(self.u8()? != 0)),
+            2 => Ok(Value::Str(self.str()?)),
+            3 => match self.dtype()? {
+                DataType::SignedInteger(dtype) => Ok(Value::Signed(self.u128()? as i128, dtype)),
+                dtype => Err(BytecodeError(format!("[Bytecode] {} is not a signed integer type", dtype))),
+            },
+            4 => match self.dtype()? {
+                DataType::UnsignedInteger(dtype) => Ok(Value::Unsigned(self.u128()?, dtype)),
+                dtype => Err(BytecodeError(format!("[Bytecode] {} is not an unsigned integer type", dtype))),
+            },
+            5 => Ok(Value::Pointer(None, self.dtype()?)),
+            tag => Err(BytecodeError(format!("[Bytecode] unknown constant tag {}", tag))),
+        }
+    }
+
+    fn function(&mut self) -> Result<Function, BytecodeError> {
+        Ok(Function {
+            name: self.str()?,
+            params: self.list(Reader::dtype)?,
+            r#type: self.dtype()?,
+            locals: self.u32()?,
+            code: self.list(Reader::instr)?,
+        })
+    }
+
+    fn instr(&mut self) -> Result<Instr, BytecodeError> {
+        Ok(match self.u8()? {
+            0 => Instr::Const(self.u32()?),
+            1 => Instr::LoadLocal(self.u32()?),
+            2 => Instr::StoreLocal(self.u32()?),
+            3 => Instr::LoadGlobal(self.u32()?),
+            4 => Instr::StoreGlobal(self.u32()?),
+            5 => Instr::Unary(self.operator()?, self.dtype()?),
+            6 => Instr::Infix(self.operator()?, self.dtype()?),
+            7 => Instr::Cast(self.dtype()?),
+            8 => Instr::Jump(self.u32()?),
+            9 => Instr::JumpIfFalse(self.u32()?),
+            10 => Instr::Call(self.u32()?),
+            11 => Instr::CallExtern(self.u32()?),
+            12 => Instr::Return,
+            13 => Instr::ReturnVoid,
+            14 => Instr::Pop,
+            15 => Instr::MissingReturn,
+            16 => Instr::Box,
+            17 => Instr::Load,
+            opcode => return Err(BytecodeError(format!("[Bytecode] unknown opcode {}", opcode))),
+        })
+    }
+}
\ No newline at end of file
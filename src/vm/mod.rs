@@ -0,0 +1,236 @@
+pub mod compile;
+pub mod disasm;
+pub mod instr;
+pub mod mbc;
+
+pub use compile::Compiler;
+pub use disasm::disassemble;
+pub use instr::{Extern, Function, Global, Instr, Program};
+
+use crate::error::RuntimeError;
+use crate::interp::{ops, HostFn, Interpreter, Value};
+use crate::types::{is_string_buffer, DataType, SignedInteger};
+
+const MAX_CALL_DEPTH: usize = 10000;
+
+struct Frame<'a> {
+    function: &'a Function,
+    pc: usize,
+    // position of local slot 0 on the value stack
+    base: usize,
+    // the cells `Box` made, freed on return
+    boxes: Vec<Value>,
+}
+
+// Runs compiled `Program`s. Arithmetic and host functions are shared with the interpreter,
+// which also collects the program's output.
+pub struct Vm {
+    host: Interpreter,
+    stack: Vec<Value>,
+    globals: Vec<Value>,
+}
+
+impl Default for Vm {
+    fn default() -> Vm {
+        Vm {
+            host: Interpreter::new(),
+            stack: Vec::new(),
+            globals: Vec::new(),
+        }
+    }
+}
+
+impl Vm {
+    pub fn new() -> Vm {
+        Vm::default()
+    }
+
+    // keeps whatever the program prints, to be collected with `take_output`
+    pub fn capture_output(mut self) -> Vm {
+        self.host = self.host.capture_output();
+        self
+    }
+
+    pub fn with_host_function(mut self, name: &str, function: HostFn) -> Vm {
+        self.host = self.host.with_host_function(name, function);
+        self
+    }
+
+    pub fn take_output(&mut self) -> String {
+        self.host.take_output()
+    }
+
+    // evaluates the globals of `program`
+    pub fn load(&mut self, program: &Program) -> Result<(), RuntimeError> {
+        self.globals = vec![Value::Void; program.globals.len()];
+        self.execute(program, &program.init, Vec::new())?;
+
+        Ok(())
+    }
+
+    // runs the program's `main` and returns its exit code
+    pub fn run(&mut self, program: &Program) -> Result<i32, RuntimeError> {
+        let main = program.function("main")
+            .map(|index| &program.functions[index])
+            .ok_or_else(|| RuntimeError("[Entry] the program has no `main` function".into()))?;
+
+        let valid_type = matches!(main.r#type, DataType::void | DataType::SignedInteger(SignedInteger::i32));
+
+        if !main.params.is_empty() || !valid_type {
+            return Err(RuntimeError("[Entry] `main` must be declared as `def main() -> i32` or `def main() -> void`".into()));
+        }
+
+        self.load(program)?;
+
+        let code = match self.execute(program, main, Vec::new()) {
+            Ok(Value::Signed(code, _)) => code as i32,
+            Ok(_) => 0,
+            Err(err) => self.host.exit_code().ok_or(err)?,
+        };
+
+        self.host.flush()?;
+        Ok(code)
+    }
+
+    pub fn call(&mut self, program: &Program, name: &str, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let function = program.function(name)
+            .map(|index| &program.functions[index])
+            .ok_or_else(|| RuntimeError(format!("[VM] unable to find function `{}`", name)))?;
+
+        if function.params.len() != args.len() {
+            return Err(RuntimeError(format!("[VM] `{}` takes {} arguments but {} were given", name, function.params.len(), args.len())));
+        }
+
+        let args = args.into_iter()
+            .zip(&function.params)
+            .map(|(arg, dtype)| self.convert(arg, *dtype, name))
+            .collect::<Result<Vec<Value>, RuntimeError>>()?;
+
+        self.execute(program, function, args)
+    }
+
+    // runs `function` until it returns; calls made from it get frames of their own instead of Rust stack
+    fn execute<'a>(&mut self, program: &'a Program, function: &'a Function, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let base = self.stack.len();
+        self.stack.extend(args);
+        self.stack.resize(base + function.locals as usize, Value::Void);
+
+        let mut frames = vec![Frame { function, pc: 0, base, boxes: Vec::new() }];
+
+        while let Some(frame) = frames.last_mut() {
+            let instr = *frame.function.code.get(frame.pc)
+                .ok_or_else(|| RuntimeError(format!("[VM] `{}` ran past the end of its code", frame.function.name)))?;
+            let base = frame.base;
+            frame.pc += 1;
+
+            match instr {
+                Instr::Const(n) => self.stack.push(program.constants[n as usize].clone()),
+                Instr::LoadLocal(n) => self.stack.push(self.stack[base + n as usize].clone()),
+                Instr::StoreLocal(n) => self.stack[base + n as usize] = self.pop()?,
+                Instr::LoadGlobal(n) => self.stack.push(self.globals[n as usize].clone()),
+                Instr::StoreGlobal(n) => self.globals[n as usize] = self.pop()?,
+                Instr::Unary(operator, _) => {
+                    let right = self.pop()?;
+                    self.stack.push(ops::eval_unary(operator, right)?);
+                },
+                Instr::Infix(operator, _) => {
+                    let right = self.pop()?;
+                    let left = self.pop()?;
+                    self.stack.push(ops::eval_infix(operator, left, right)?);
+                },
+                Instr::Cast(dtype) => {
+                    let value = self.pop()?;
+                    let found = value.dtype();
+
+                    let value = if is_string_buffer(found, dtype) {
+                        Value::Str(self.host.read_string(&value)?)
+                    } else {
+                        value.convert(dtype).ok_or_else(|| RuntimeError(format!("[VM] cannot cast {} to {}", found, dtype)))?
+                    };
+
+                    self.stack.push(value);
+                },
+                Instr::Jump(n) => frame.pc = n as usize,
+                Instr::JumpIfFalse(n) => match self.pop()? {
+                    Value::Bool(true) => {},
+                    Value::Bool(false) => frame.pc = n as usize,
+                    value => return Err(RuntimeError(format!("[VM] expected a bool condition, found {}", value.dtype()))),
+                },
+                Instr::Call(n) => {
+                    let function = &program.functions[n as usize];
+
+                    if frames.len() >= MAX_CALL_DEPTH {
+                        return Err(RuntimeError(format!("[VM] stack overflow while calling `{}`", function.name)));
+                    }
+
+                    let base = self.stack.len().checked_sub(function.params.len())
+                        .ok_or_else(|| RuntimeError("[VM] stack underflow".into()))?;
+                    self.stack.resize(base + function.locals as usize, Value::Void);
+
+                    frames.push(Frame { function, pc: 0, base, boxes: Vec::new() });
+                },
+                Instr::CallExtern(n) => {
+                    let function = &program.externs[n as usize];
+                    let host = self.host.host_function(&function.name)
+                        .ok_or_else(|| RuntimeError(format!("[VM] extern `{}` has no host implementation", function.name)))?;
+
+                    let split = self.stack.len().checked_sub(function.params.len())
+                        .ok_or_else(|| RuntimeError("[VM] stack underflow".into()))?;
+                    let args = self.stack.split_off(split);
+
+                    let value = host(&mut self.host, &args)?;
+                    let value = self.convert(value, function.r#type, &function.name)?;
+                    self.stack.push(value);
+                },
+                Instr::Return | Instr::ReturnVoid => {
+                    let value = if let Instr::Return = instr { self.pop()? } else { Value::Void };
+
+                    frame.boxes.iter().for_each(|pointer| self.host.release(pointer));
+                    frames.pop();
+                    self.stack.truncate(base);
+
+                    if frames.is_empty() {
+                        return Ok(value);
+                    }
+
+                    self.stack.push(value);
+                },
+                Instr::Pop => {
+                    self.pop()?;
+                },
+                Instr::Box => {
+                    let value = self.pop()?;
+                    let pointer = self.host.boxed(value);
+
+                    frame.boxes.push(pointer.clone());
+                    self.stack.push(pointer);
+                },
+                Instr::Load => {
+                    let pointer = self.pop()?;
+                    self.stack.push(self.host.pointee(&pointer)?);
+                },
+                Instr::MissingReturn => {
+                    return Err(RuntimeError(format!("[VM] `{}` reached its end without returning a value", frame.function.name)));
+                },
+            }
+        }
+
+        Ok(Value::Void)
+    }
+
+    fn pop(&mut self) -> Result<Value, RuntimeError> {
+        self.stack.pop().ok_or_else(|| RuntimeError("[VM] stack underflow".into()))
+    }
+
+    // the heap a `*u8` buffer points into is the host interpreter's
+    fn convert(&mut self, value: Value, dtype: DataType, name: &str) -> Result<Value, RuntimeError> {
+        let found = value.dtype();
+
+        if is_string_buffer(found, dtype) {
+            return self.host.read_string(&value).map(Value::Str);
+        }
+
+        value.convert(dtype)
+            .ok_or_else(|| RuntimeError(format!("[VM] `{}` expects {}, found {}", name, dtype, found)))
+    }
+}
\ No newline at end of file
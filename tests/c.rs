@@ -0,0 +1,166 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use mamba::codegen::c::CGen;
+use mamba::codegen::llvm::entry::Entry;
+use mamba::fold::ConstFolder;
+use mamba::lexer::Lexer;
+use mamba::module::Loader;
+use mamba::parser::{Parser, AST};
+
+fn generate_c(source: &str) -> Result<String, String> {
+    let mut lexer = Lexer::new(source.into());
+    let tokens = lexer.get_tokens().unwrap();
+
+    let mut ast = Parser::with_spans(tokens, lexer.get_spans()).parse_all();
+    ConstFolder::new().fold(&mut ast).unwrap();
+
+    CGen::new(ast).with_entry(Entry::C).generate_c().map_err(|err| err.0)
+}
+
+// a corpus program, linked with the parts of `std` it uses like the driver links it
+fn load(path: &Path) -> AST {
+    let mut lexer = Lexer::new(fs::read_to_string(path).unwrap());
+    let tokens = lexer.get_tokens().unwrap();
+
+    let mut parser = Parser::with_spans(tokens, lexer.get_spans());
+    let mut loader = Loader::new(path);
+    let imports = parser.parse_imports().unwrap();
+    loader.load(imports, &mut parser).unwrap();
+
+    let mut ast = loader.link(parser.parse_program().unwrap()).unwrap();
+    ConstFolder::new().fold(&mut ast).unwrap();
+    ast
+}
+
+#[test]
+fn test_program() {
+    let c = generate_c("extern puts(text: str) -> i32;\n\nlet limit: i64 = 3;\n\ndef count(n: i64) -> i64:\n    if n < 0:\n        return 0;\n    elif n == 0:\n        return 1;\n    else:\n        while n > 100:\n            puts(\"big\");\n    return n * 2;\n\ndef main() -> void:\n    count(limit);").unwrap();
+
+    assert_eq!(c, "\
+#include <stdbool.h>
+#include <stdint.h>
+
+int32_t puts(const char *text);
+int64_t count(int64_t n);
+void mamba_main(void);
+
+int64_t limit = ((int64_t)3);
+
+int64_t count(int64_t n) {
+    if (n < ((int64_t)0)) {
+        return ((int64_t)0);
+    } else if (n == ((int64_t)0)) {
+        return ((int64_t)1);
+    } else {
+        while (n > ((int64_t)100)) {
+            puts(\"big\");
+        }
+    }
+    return ((int64_t)((uint64_t)n * (uint64_t)((int64_t)2)));
+}
+
+void mamba_main(void) {
+    count(((int64_t)3));
+}
+
+int main(void) {
+    mamba_main();
+    return 0;
+}
+");
+}
+
+// arithmetic that may overflow wraps around in an unsigned type instead of being undefined
+#[test]
+fn test_arithmetic() {
+    let cases = [
+        ("i8", "a + b", "return ((int8_t)((uint32_t)a + (uint32_t)b));"),
+        ("u16", "a * b", "return ((uint16_t)((uint32_t)a * (uint32_t)b));"),
+        ("i32", "a << b", "return ((int32_t)((uint32_t)a << (uint32_t)b));"),
+        ("u32", "a - b", "return (a - b);"),
+        ("i64", "a / b", "return (a / b);"),
+        ("i8", "a % b", "return ((int8_t)(a % b));"),
+        ("i128", "a - b", "return ((__int128)((unsigned __int128)a - (unsigned __int128)b));"),
+        ("u128", "a >> b", "return (a >> b);"),
+        ("i16", "-a", "return ((int16_t)(0 - (uint32_t)a));"),
+        ("u8", "~a", "return ((uint8_t)~a);"),
+    ];
+
+    for (dtype, expr, expected) in cases {
+        let c = generate_c(&format!("def f(a: {dtype}, b: {dtype}) -> {dtype}:\n    return {expr};\n\ndef main() -> i32:\n    return 0;")).unwrap();
+        assert!(c.contains(&format!("\n    {expected}\n")), "{dtype}: {expr}:\n{c}");
+    }
+
+    // operands are converted to the wider type first
+    let c = generate_c("def f(a: i8, b: u64) -> bool:\n    return a < b;\n\ndef main() -> i32:\n    return 0;").unwrap();
+    assert!(c.contains("\n    return (((uint64_t)a) < b);\n"));
+}
+
+#[test]
+fn test_literals() {
+    let c = generate_c("let a: i64 = 9223372036854775807;\nlet b: u64 = 18446744073709551615;\nlet c: u128 = 18446744073709551616;\nlet d: bool = False;\nlet e: str = \"say ??= \\\";\n\ndef main() -> i32:\n    return -1;").unwrap();
+
+    assert!(c.contains("\nint64_t a = ((int64_t)INT64_C(9223372036854775807));\n"));
+    assert!(c.contains("\nuint64_t b = ((uint64_t)UINT64_C(18446744073709551615));\n"));
+    assert!(c.contains("\nunsigned __int128 c = ((unsigned __int128)(((unsigned __int128)UINT64_C(1) << 64) | UINT64_C(0)));\n"));
+    assert!(c.contains("\nbool d = false;\n"));
+    assert!(c.contains("\nconst char *e = \"say \\?\\?= \\\\\";\n"));
+    assert!(c.contains("\n    return (-1);\n"));
+}
+
+// shadowing variables and names C reserves are renamed
+#[test]
+fn test_names() {
+    let c = generate_c("def int(char: i32) -> i32:\n    let x: i32 = char;\n    let x: i32 = x + 1;\n    if True:\n        let x: i32 = x;\n        return x;\n    return x;\n\ndef main() -> i32:\n    return int(1);").unwrap();
+
+    assert!(c.contains("\nint32_t int_(int32_t char_) {\n    int32_t x = char_;\n    int32_t x_1 = ((int32_t)((uint32_t)x + (uint32_t)1));\n    if (true) {\n        int32_t x_2 = x_1;\n        return x_2;\n    }\n    return x_1;\n}\n"));
+    assert!(c.contains("\n    return int_(1);\n"));
+}
+
+#[test]
+fn test_address_of() {
+    let c = generate_c("extern fill(n: *i64) -> void;\n\nlet g: i64 = 1;\n\ndef main() -> i32:\n    let n: i64 = 0;\n    let n: i64 = n;\n    fill(&n);\n    fill(&g);\n    return n;").unwrap();
+
+    // the address of the shadowing variable is taken, and globals have one as well
+    assert!(c.contains("\n    fill((&n_1));\n    fill((&g));\n"), "{}", c);
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(generate_c("def main() -> i32:\n    return g();"), Err("[C] unable to find function `g`".into()));
+    assert_eq!(generate_c("def main() -> i32:\n    return x;"), Err("[C] identifier `x` not found".into()));
+    assert_eq!(generate_c("def main() -> i32:\n    if 1:\n        return 1;\n    return 0;"), Err("[C] expected a bool condition, found i32".into()));
+    assert_eq!(generate_c("def f(a: str) -> i32:\n    return 0;\n\ndef main() -> i32:\n    return f(1);"), Err("[C] `f` expects str, found i32".into()));
+    assert_eq!(generate_c("def f() -> i32:\n    return 0;"), Err("[Entry] the program has no `main` function".into()));
+}
+
+// the output must be accepted by a strict C99 compiler without a single warning, for every program the backend supports
+#[test]
+fn test_corpus_compiles() {
+    if Command::new("cc").arg("--version").output().is_err() {
+        eprintln!("skipping: cc is not installed");
+        return;
+    }
+
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    let source = env::temp_dir().join(format!("mamba-c-{}.c", std::process::id()));
+
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let program = match CGen::new(load(&path)).with_entry(Entry::C).generate_c().map_err(|err| err.0) {
+            Ok(program) => program,
+            Err(err) if err.ends_with("not supported") => continue,
+            Err(err) => panic!("{}: {}", path.display(), err),
+        };
+        fs::write(&source, program).unwrap();
+
+        // like the driver, the externs of `std` are not compared against the built-in prototypes
+        let output = Command::new("cc").args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-fno-builtin", "-fsyntax-only"]).arg(&source).output().unwrap();
+        assert!(output.status.success(), "{}:\n{}", path.display(), String::from_utf8_lossy(&output.stderr));
+    }
+
+    fs::remove_file(source).unwrap();
+}
\ No newline at end of file
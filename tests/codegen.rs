@@ -0,0 +1,255 @@
+use mamba::codegen::llvm::IRGen;
+use mamba::codegen::llvm::entry::Entry;
+use mamba::codegen::target::Target;
+use mamba::lexer::Lexer;
+use mamba::parser::{EnumStatement, Parser, Statement, StructStatement};
+use mamba::types::{DataType, SignedInteger};
+
+fn generate_ir(source: &str, target: Target) -> String {
+    let mut lexer = Lexer::new(source.into());
+    let tokens = lexer.get_tokens().unwrap();
+
+    let mut parser = Parser::new(tokens);
+    let ast = parser.parse_all();
+
+    IRGen::with_target(ast, target).generate_ir().unwrap()
+}
+
+#[test]
+fn test_target_header() {
+    let ir = generate_ir("let a: i32 = 1;", Target::from_name("riscv64").unwrap());
+
+    assert!(ir.starts_with("target datalayout = \"e-m:e-p:64:64-i64:64-i128:128-n32:64-S128\"\ntarget triple = \"riscv64-unknown-linux-gnu\"\n"));
+    assert_eq!(Target::from_name("aarch64-unknown-linux-gnu").unwrap().name, "aarch64");
+    assert!(Target::from_name("z80").is_none());
+}
+
+#[test]
+fn test_target_alignment() {
+    let ir = generate_ir("let a: i128 = 1;\nlet b: bool = True;\n\ndef f() -> i16:\n    let c: i16 = 2;\n    return c;", Target::from_name("x86_64").unwrap());
+
+    assert!(ir.contains("@a = global i128 1, align 16\n"));
+    assert!(ir.contains("@b = global i1 1, align 1\n"));
+    assert!(ir.contains("%c = alloca i16, align 2\n"));
+    assert!(ir.contains("store i16 2, ptr %c, align 2\n"));
+    assert!(ir.contains("load i16, ptr %c, align 2\n"));
+    assert!(!ir.contains("align 4"));
+}
+
+#[test]
+fn test_integer_lowering() {
+    let source = "def f(a: i8, b: u32, c: u32) -> i64:\n    let d: u32 = b / c >> 1;\n    if b < c:\n        let d: i64 = ~a;\n        return d;\n    return a;";
+    let ir = generate_ir(source, Target::from_name("x86_64").unwrap());
+
+    assert!(ir.contains("%t.0 = udiv i32 %b, %c\n"));
+    assert!(ir.contains("%t.3 = lshr i32 %t.0, %t.2\n%d = alloca i32, align 4\nstore i32 %t.3, ptr %d, align 4\n"));
+    assert!(ir.contains("%t.7 = icmp ult i32 %b, %c\n"));
+    assert!(ir.contains("%t.8 = xor i8 %a, -1\n%t.9 = sext i8 %t.8 to i64\n"));
+    assert!(ir.contains("%d.s10 = alloca i64, align 8\nstore i64 %t.9, ptr %d.s10, align 8\n"));
+    assert!(ir.contains("%t.12 = sext i8 %a to i64\n"));
+    assert!(!ir.contains("zext"));
+}
+
+#[test]
+fn test_debug_info() {
+    let source = "def add(a: i32, b: i32) -> i32:\n    let c: i32 = 3;\n    return a + b + c;";
+
+    let mut lexer = Lexer::new(source.into());
+    let tokens = lexer.get_tokens().unwrap();
+
+    let mut parser = Parser::with_spans(tokens, lexer.get_spans());
+    let ast = parser.parse_all();
+
+    let ir = IRGen::with_target(ast, Target::from_name("x86_64").unwrap())
+        .with_debug_info("add.mamba", "/src")
+        .generate_ir()
+        .unwrap();
+
+    assert!(ir.contains("define i32 @add(i32 %a, i32 %b) !dbg !5 {\n"));
+    assert!(ir.contains("!0 = !DIFile(filename: \"add.mamba\", directory: \"/src\")\n"));
+    assert!(ir.contains("!5 = distinct !DISubprogram(name: \"add\", scope: !0, file: !0, line: 1, type: !3, scopeLine: 1, spFlags: DISPFlagDefinition, unit: !1, retainedNodes: !4)\n"));
+    assert!(ir.contains("call void @llvm.dbg.declare(metadata ptr %a.addr, metadata !6, metadata !DIExpression()), !dbg !7\n"));
+    assert!(ir.contains("!6 = !DILocalVariable(name: \"a\", arg: 1, scope: !5, file: !0, line: 1, type: !2)\n"));
+    assert!(ir.contains("!DILocalVariable(name: \"c\", scope: !5, file: !0, line: 2, type: !2)\n"));
+    assert!(ir.contains("!DILocation(line: 3, column: 5, scope: !5)\n"));
+    assert!(ir.contains("!llvm.dbg.cu = !{!1}\n"));
+
+    let body = &ir[ir.find("define i32 @add").unwrap()..];
+    let body = &body[..body.find("\n}\n").unwrap()];
+    assert!(body.lines().skip(1).filter(|line| !line.is_empty()).all(|line| line.contains(", !dbg !")));
+}
+
+#[test]
+fn test_array_lowering() {
+    let source = "def sum(a: [i32; 3]) -> i32:\n    return a[0] + a[2];\n\ndef f() -> [[u8; 2]; 2]:\n    let m: [[u8; 2]; 2] = [[1, 2], [3, 4]];\n    m[1][0] = 10;\n    return m;";
+    let ast = Parser::new(Lexer::new(source.into()).get_tokens().unwrap()).parse_all();
+
+    let ir = IRGen::with_target(ast.clone(), Target::from_name("x86_64").unwrap()).generate_ir().unwrap();
+    assert!(ir.contains("define i32 @sum([3 x i32] %a) {\n%a.s0 = alloca [3 x i32], align 4\nstore [3 x i32] %a, ptr %a.s0, align 4\n"));
+    assert!(ir.contains("getelementptr inbounds [3 x i32], ptr %a.s0, i64 0, i64 %t.3\n"));
+    assert!(ir.contains("define [2 x [2 x i8]] @f() {\n"));
+    assert!(ir.contains("%m = alloca [2 x [2 x i8]], align 1\n"));
+    assert!(!ir.contains("panic"));
+
+    let ir = IRGen::with_target(ast, Target::from_name("x86_64").unwrap()).with_bounds_checks().generate_ir().unwrap();
+    assert!(ir.contains("%t.4 = icmp ult i64 %t.3, 3\nbr i1 %t.4, label %l.5, label %l.6\nl.6:\ncall void @mamba.panic_bounds(i64 %t.3, i1 1, i64 3)\nunreachable\nl.5:\n"));
+    assert!(ir.contains("define private void @mamba.panic_bounds(i64 %index, i1 %signed, i64 %len) noreturn nounwind cold {\n"));
+    assert!(ir.contains("(i64 60, i64 101)\n"));
+}
+
+#[test]
+fn test_pointer_lowering() {
+    let source = "let g: i32 = 1;\n\ndef bump(n: i32, p: *i32) -> i32:\n    let q: *i32 = &n;\n    *q = *p + g;\n    return *(p + 1) + (q - p);\n\ndef reset() -> *i32:\n    let p: *i32 = &g;\n    *p = 0;\n    return null;";
+    let ast = Parser::new(Lexer::new(source.into()).get_tokens().unwrap()).parse_all();
+
+    let ir = IRGen::with_target(ast, Target::from_name("x86_64").unwrap()).generate_ir().unwrap();
+    assert!(ir.contains("define i32 @bump(i32 %n, ptr %p) {\n%n.s0 = alloca i32, align 4\nstore i32 %n, ptr %n.s0, align 4\n%q = alloca ptr, align 8\nstore ptr %n.s0, ptr %q, align 8\n"));
+    assert!(ir.contains("%t.1 = load i32, ptr %p, align 4\n%t.2 = load i32, ptr @g, align 4\n"));
+    assert!(ir.contains("%t.8 = getelementptr i32, ptr %p, i64 %t.7\n"));
+    assert!(ir.contains("%t.14 = sdiv exact i64 %t.13, ptrtoint (ptr getelementptr (i32, ptr null, i64 1) to i64)\n"));
+    assert!(ir.contains("store ptr @g, ptr %p, align 8\n"));
+    assert!(ir.contains("ret ptr null\n"));
+}
+
+#[test]
+fn test_struct_lowering() {
+    let source = "struct Mixed:\n    a: i8;\n    b: i64;\n    c: i128;\n    d: bool;\n\ndef f(m: Mixed, p: *Mixed) -> i64:\n    p.b = Mixed { d: False, c: 3, b: 2, a: 1 }.b;\n    return m.b;";
+    let ast = Parser::new(Lexer::new(source.into()).get_tokens().unwrap()).parse_all();
+
+    let structure = match &ast[0] {
+        Statement::Struct(StructStatement { dtype: DataType::Struct(structure), .. }) => *structure,
+        stmt => panic!("expected a struct, found {}", stmt),
+    };
+
+    // the layout of the same C struct
+    let target = Target::from_name("x86_64").unwrap();
+    assert_eq!(target.field_offsets(structure), vec![0, 8, 16, 32]);
+    assert_eq!(target.size_of(DataType::Struct(structure)), 48);
+    assert_eq!(target.align_of(DataType::Struct(structure)), 16);
+
+    let ir = IRGen::with_target(ast, target).generate_ir().unwrap();
+    assert!(ir.contains("%Mixed = type { i8, i64, i128, i1 }\n"));
+    assert!(ir.contains("define i64 @f(%Mixed %m, ptr %p) {\n%m.s0 = alloca %Mixed, align 16\nstore %Mixed %m, ptr %m.s0, align 16\n"));
+    assert!(ir.contains("%t.9 = insertvalue %Mixed undef, i1 %t.2, 3\n%t.10 = insertvalue %Mixed %t.9, i128 %t.4, 2\n"));
+    assert!(ir.contains("= getelementptr inbounds %Mixed, ptr %p, i32 0, i32 1\n"));
+    assert!(ir.contains("= getelementptr inbounds %Mixed, ptr %m.s0, i32 0, i32 1\n"));
+}
+
+#[test]
+fn test_method_lowering() {
+    let source = "struct P:\n    x: i32;\n    def get(self) -> i32:\n        return self.x;\n    def set(*self, x: i32) -> void:\n        self.x = x;\n\ndef f(p: P, q: *P) -> i32:\n    p.set(q.get());\n    q.set(1);\n    return p.get();";
+    let ir = generate_ir(source, Target::from_name("x86_64").unwrap());
+
+    assert!(ir.contains("define i32 @P.get(%P %self) {\n"));
+    assert!(ir.contains("define void @P.set(ptr %self, i32 %x) {\n"));
+    // a place is passed by address, a pointer is followed for a method that takes `self`
+    assert!(ir.contains("%t.5 = load %P, ptr %q, align 4\n%t.6 = call i32 @P.get(%P %t.5)\ncall void @P.set(ptr %p.s4, i32 %t.6)\n"));
+    assert!(ir.contains("call void @P.set(ptr %q, i32 %t.8)\n"));
+    assert!(ir.contains("%t.9 = load %P, ptr %p.s4, align 4\n%t.10 = call i32 @P.get(%P %t.9)\n"));
+}
+
+#[test]
+fn test_enum_lowering() {
+    let source = "enum Color:\n    Red;\n    Blue = 3;\n\nenum E:\n    A;\n    B(i8, i64) = 4;\n    C(i128);\n\ndef f(e: E) -> i64:\n    match e:\n        case E.B(_, y):\n            return y;\n        case E.C(z):\n            return 1;\n        case E.A:\n            return 0;\n\ndef g(n: u8) -> E:\n    match n:\n        case 255:\n            return E.B(1, 2);\n        case _:\n            return E.A;";
+    let ast = Parser::new(Lexer::new(source.into()).get_tokens().unwrap()).parse_all();
+
+    let enumeration = match &ast[1] {
+        Statement::Enum(EnumStatement { dtype: DataType::Enum(enumeration), .. }) => *enumeration,
+        stmt => panic!("expected an enum, found {}", stmt),
+    };
+
+    // the discriminant, then the values of `B` or `C` in the same 16 bytes
+    let target = Target::from_name("x86_64").unwrap();
+    assert_eq!(target.payload_offset(enumeration), 16);
+    assert_eq!(target.payload_size(enumeration), 16);
+    assert_eq!(target.payload_offsets(&enumeration.variants[1]), vec![0, 8]);
+    assert_eq!(target.size_of(DataType::Enum(enumeration)), 32);
+    assert_eq!(target.align_of(DataType::Enum(enumeration)), 16);
+
+    let ir = IRGen::with_target(ast, target).generate_ir().unwrap();
+    assert!(ir.contains("%Color = type { i32 }\n"));
+    assert!(ir.contains("%E = type { i32, [1 x i128] }\n"));
+    assert!(ir.contains("%t.1 = load i32, ptr %t.0, align 4\nswitch i32 %t.1, label %l.6 [ i32 4, label %l.2 i32 5, label %l.3 i32 0, label %l.4 ]\nl.6:\nunreachable\n"));
+    assert!(ir.contains("%t.8 = getelementptr inbounds { i8, i64 }, ptr %t.7, i32 0, i32 1\n%t.9 = load i64, ptr %t.8, align 8\n%y = alloca i64, align 8\n"));
+    assert!(ir.contains("switch i8 %n, label %l.21 [ i8 255, label %l.20 ]\n"));
+    assert!(ir.contains("store i32 4, ptr %t.27, align 4\n"));
+}
+
+#[test]
+fn test_tuple_lowering() {
+    let source = "def divmod(a: i32, b: i32) -> (i32, i32):\n    return (a / b, a % b);\n\ndef main() -> i32:\n    let (q, r) = divmod(7, 2);\n    let t: (i8, i64) = (1, 2);\n    t.1 = 5;\n    return q + r + divmod(9, 4).0;";
+    let ast = Parser::new(Lexer::new(source.into()).get_tokens().unwrap()).parse_all();
+
+    // the elements are laid out like the fields of a struct
+    let target = Target::from_name("x86_64").unwrap();
+    let tuple = DataType::tuple(vec![DataType::SignedInteger(SignedInteger::i8), DataType::SignedInteger(SignedInteger::i64)]);
+    assert_eq!(target.size_of(tuple), 16);
+    assert_eq!(target.align_of(tuple), 8);
+    match tuple {
+        DataType::Tuple(tuple) => assert_eq!(target.element_offsets(tuple), vec![0, 8]),
+        dtype => panic!("expected a tuple, found {}", dtype),
+    }
+
+    let ir = IRGen::with_target(ast, target).generate_ir().unwrap();
+    assert!(ir.contains("define { i32, i32 } @divmod(i32 %a, i32 %b) {\n"));
+    assert!(ir.contains("%t.2 = insertvalue { i32, i32 } undef, i32 %t.0, 0\n%t.3 = insertvalue { i32, i32 } %t.2, i32 %t.1, 1\nret { i32, i32 } %t.3\n"));
+    assert!(ir.contains("%t.9 = extractvalue { i32, i32 } %t.8, 0\n%q = alloca i32, align 4\nstore i32 %t.9, ptr %q, align 4\n"));
+    assert!(ir.contains("%t = alloca { i8, i64 }, align 8\n"));
+    assert!(ir.contains("%t.19 = getelementptr inbounds { i8, i64 }, ptr %t, i32 0, i32 1\n"));
+    assert!(ir.contains("%t.29 = extractvalue { i32, i32 } %t.28, 0\n"));
+}
+
+#[test]
+fn test_generic_lowering() {
+    let source = "def max[T: int](a: T, b: T) -> T:\n    if a > b:\n        return a;\n    return b;\n\ndef swap[A, B](p: (A, B)) -> (B, A):\n    return (p.1, p.0);\n\ndef main() -> i32:\n    let x: i64 = 1;\n    let y: u8 = 2;\n    let (z, _) = swap((&x, y));\n    return max(x, 3) + max(4, x) + max(y, z);";
+    let ast = Parser::new(Lexer::new(source.into()).get_tokens().unwrap()).parse_all();
+    let ir = IRGen::with_target(ast, Target::from_name("x86_64").unwrap()).generate_ir().unwrap();
+
+    // one instance per set of types, a literal takes the type the other arguments decide
+    assert_eq!(ir.matches("define i64 @max$i64(i64 %a, i64 %b) {\n").count(), 1);
+    assert_eq!(ir.matches("define i8 @max$u8(i8 %a, i8 %b) {\n").count(), 1);
+    assert!(ir.contains("define { i8, ptr } @swap$ptr.i64$u8({ ptr, i8 } %p) {\n"));
+    assert!(ir.contains("%t.8 = sext i32 %t.7 to i64\n%t.9 = call i64 @max$i64(i64 %t.5, i64 %t.8)\n"));
+    assert!(ir.contains("%t.13 = sext i32 %t.11 to i64\n%t.14 = call i64 @max$i64(i64 %t.13, i64 %t.12)\n"));
+    assert!(ir.contains("%t.18 = call i8 @max$u8(i8 %t.16, i8 %t.17)\n"));
+    assert!(!ir.contains("@max("));
+}
+
+fn generate_program(source: &str, entry: Entry) -> Result<String, String> {
+    let mut lexer = Lexer::new(source.into());
+    let tokens = lexer.get_tokens().unwrap();
+
+    let mut parser = Parser::with_spans(tokens, lexer.get_spans());
+    let ast = parser.parse_all();
+
+    IRGen::with_target(ast, Target::from_name("x86_64").unwrap())
+        .with_entry(entry)
+        .generate_ir()
+        .map_err(|err| err.0)
+}
+
+#[test]
+fn test_entry() {
+    let source = "def main() -> i32:\n    return main();";
+
+    let ir = generate_program(source, Entry::C).unwrap();
+    assert!(ir.contains("define i32 @main(i32 %argc, ptr %argv) {\n    %exit_code = call i32 @mamba.main()\n    ret i32 %exit_code\n}\n"));
+    assert!(ir.contains("define i32 @mamba.main() {\n"));
+    assert!(ir.contains("call i32 @mamba.main()\nret i32"));
+    assert!(!ir.contains("_start"));
+
+    let ir = generate_program(source, Entry::Freestanding).unwrap();
+    assert!(ir.contains("define void @_start() noreturn nounwind alignstack(16) {\n"));
+    assert!(ir.contains("call void asm sideeffect \"syscall\", \"{rax},{rdi},~{rcx},~{r11},~{memory}\"(i64 60, i64 %status)\n"));
+
+    let ir = generate_program("def main() -> void:\n    let x: i32 = 1;", Entry::C).unwrap();
+    assert!(ir.contains("define void @mamba.main() {\n"));
+    assert!(ir.contains("    call void @mamba.main()\n    ret i32 0\n"));
+}
+
+#[test]
+fn test_entry_errors() {
+    assert_eq!(generate_program("def mian() -> i32:\n    return 0;", Entry::C).unwrap_err(), "[Entry] the program has no `main` function");
+    assert_eq!(generate_program("\ndef main(argc: i32) -> i32:\n    return argc;", Entry::C).unwrap_err(), "[Entry] `main` at line 2 must be declared as `def main() -> i32` or `def main() -> void`");
+    assert_eq!(generate_program("def main() -> i64:\n    return 0;", Entry::Freestanding).unwrap_err(), "[Entry] `main` at line 1 must be declared as `def main() -> i32` or `def main() -> void`");
+}
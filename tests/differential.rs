@@ -0,0 +1,154 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// One way of executing a program through the `mamba` binary.
+struct Backend {
+    name: &'static str,
+    // external tools the backend needs, it is skipped when one of them is missing
+    tools: &'static [&'static str],
+    // arguments that run `source`, building an executable at `exe` if the backend needs one
+    args: fn(&Path, &Path) -> Vec<String>,
+}
+
+// the first backend is the reference the others are compared with
+const BACKENDS: [Backend; 6] = [
+    Backend { name: "interp", tools: &[], args: interp_args },
+    Backend { name: "vm", tools: &[], args: vm_args },
+    Backend { name: "llvm", tools: &["llc", "cc"], args: llvm_args },
+    Backend { name: "x86_64", tools: X86_64_TOOLS, args: x86_64_args },
+    Backend { name: "c", tools: &["cc"], args: c_args },
+    Backend { name: "riscv64", tools: &["riscv64-linux-gnu-gcc", "qemu-riscv64"], args: riscv_args },
+];
+
+// the x86_64 backend runs natively on an x86_64 host and under emulation anywhere else
+const X86_64_TOOLS: &[&str] = if cfg!(target_arch = "x86_64") { &["cc"] } else { &["x86_64-linux-gnu-gcc", "qemu-x86_64"] };
+
+fn interp_args(source: &Path, _: &Path) -> Vec<String> {
+    vec!["run".into(), source.display().to_string()]
+}
+
+fn vm_args(source: &Path, _: &Path) -> Vec<String> {
+    vec!["run".into(), "--vm".into(), source.display().to_string()]
+}
+
+fn llvm_args(source: &Path, exe: &Path) -> Vec<String> {
+    vec![source.display().to_string(), "--backend".into(), "llvm".into(), "-o".into(), exe.display().to_string(), "--run".into()]
+}
+
+fn x86_64_args(source: &Path, exe: &Path) -> Vec<String> {
+    vec![source.display().to_string(), "--target".into(), "x86_64".into(), "-o".into(), exe.display().to_string(), "--run".into()]
+}
+
+fn c_args(source: &Path, exe: &Path) -> Vec<String> {
+    vec![source.display().to_string(), "--backend".into(), "c".into(), "-o".into(), exe.display().to_string(), "--run".into()]
+}
+
+fn riscv_args(source: &Path, exe: &Path) -> Vec<String> {
+    vec![source.display().to_string(), "--target".into(), "riscv64".into(), "-o".into(), exe.display().to_string(), "--run".into()]
+}
+
+// the exit code of a compiled program that panics
+const PANIC_EXIT_CODE: i32 = 101;
+
+#[derive(Debug, PartialEq, Eq)]
+struct Outcome {
+    code: Option<i32>,
+    stdout: String,
+    stderr: String,
+}
+
+impl Backend {
+    fn is_available(&self) -> bool {
+        self.tools.iter().all(|tool| Command::new(tool).arg("--version").output().is_ok())
+    }
+
+    fn run(&self, source: &Path) -> Outcome {
+        let stem = source.file_stem().unwrap().to_string_lossy();
+        let exe = env::temp_dir().join(format!("mamba-differential-{}-{}-{}", std::process::id(), self.name, stem));
+
+        let output = Command::new(env!("CARGO_BIN_EXE_mamba")).args((self.args)(source, &exe)).output().unwrap();
+        let _ = fs::remove_file(exe);
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        // the interpreter reports an index out of bounds as a runtime error, where a compiled program panics
+        match stderr.strip_prefix("runtime error: [Interpreter] index ") {
+            Some(message) => Outcome {
+                code: Some(PANIC_EXIT_CODE),
+                stdout: String::from_utf8_lossy(&output.stdout).into(),
+                stderr: format!("panic: index {}", message),
+            },
+            None => Outcome {
+                code: output.status.code(),
+                stdout: String::from_utf8_lossy(&output.stdout).into(),
+                stderr: stderr.into(),
+            },
+        }
+    }
+}
+
+fn corpus() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+
+    let mut programs: Vec<PathBuf> = fs::read_dir(dir).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "mamba"))
+        .collect();
+
+    programs.sort();
+    programs
+}
+
+// the backends a `# backends: interp, llvm` line among the leading comments names, all of them without one;
+// a program using what some backends do not support leaves them out
+fn backends_of(program: &Path) -> Option<Vec<String>> {
+    fs::read_to_string(program).unwrap().lines()
+        .take_while(|line| line.starts_with('#'))
+        .find_map(|line| line.strip_prefix("# backends:"))
+        .map(|names| names.split(',').map(|name| name.trim().to_string()).collect())
+}
+
+fn describe(name: &str, outcome: &Outcome) -> String {
+    format!("    {}: exit code {:?}\n        stdout: {:?}\n        stderr: {:?}\n", name, outcome.code, outcome.stdout, outcome.stderr)
+}
+
+// every backend must print the same and exit with the same code as the reference on every program
+#[test]
+fn test_backends_agree() {
+    let (backends, missing): (Vec<&Backend>, Vec<&Backend>) = BACKENDS.iter().partition(|backend| backend.is_available());
+
+    for backend in missing {
+        eprintln!("skipping `{}`: one of {} is not installed", backend.name, backend.tools.join(", "));
+    }
+
+    let programs = corpus();
+    assert!(!programs.is_empty(), "tests/programs has no programs");
+
+    for program in &programs {
+        let (reference, others) = backends.split_first().unwrap();
+        let names = backends_of(program);
+
+        if let Some(names) = &names {
+            for name in names {
+                assert!(BACKENDS.iter().any(|backend| backend.name == name), "`{}` names the unknown backend `{}`", program.display(), name);
+            }
+
+            assert!(names.contains(&reference.name.to_string()), "`{}` must run on the reference `{}`", program.display(), reference.name);
+        }
+
+        let expected = reference.run(program);
+
+        // a program the reference rejects cannot be compared with anything, one that panics can
+        assert!(expected.stderr.is_empty() || expected.code == Some(PANIC_EXIT_CODE), "`{}` fails on `{}`:\n{}", program.display(), reference.name, describe(reference.name, &expected));
+
+        for backend in others.iter().filter(|backend| names.as_ref().is_none_or(|names| names.iter().any(|name| name == backend.name))) {
+            let outcome = backend.run(program);
+
+            if outcome != expected {
+                panic!("`{}` diverges on `{}`:\n{}{}", program.display(), backend.name, describe(reference.name, &expected), describe(backend.name, &outcome));
+            }
+        }
+    }
+}
\ No newline at end of file
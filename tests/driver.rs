@@ -0,0 +1,603 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use mamba::codegen::llvm::entry::Entry;
+use mamba::codegen::target::Target;
+use mamba::driver::{Backend, Emit, Executor, Options};
+
+fn parse(args: &[&str]) -> Result<Options, String> {
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    Options::parse(&args).map_err(|err| err.to_string())
+}
+
+// writes `source` to a file of its own so tests running in parallel do not collide
+fn source_file(name: &str, source: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("mamba-driver-{}-{}.mamba", std::process::id(), name));
+    fs::write(&path, source).unwrap();
+    path
+}
+
+// runs `command` with `input` as its stdin
+fn run_with_input(command: &mut Command, input: &str) -> Output {
+    let mut child = command.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn has_toolchain() -> bool {
+    ["llc", "cc"].iter().all(|tool| Command::new(tool).arg("--version").output().is_ok())
+}
+
+#[test]
+fn test_options() {
+    let options = parse(&["main.mamba", "--emit=asm", "-O2", "--target", "aarch64", "-o", "main.s", "-g"]).unwrap();
+
+    assert_eq!(options.input.as_deref(), Some("main.mamba"));
+    assert_eq!(options.output.as_deref(), Some("main.s"));
+    assert_eq!(options.emit, Emit::Asm);
+    assert_eq!(options.opt_level, 2);
+    assert_eq!(options.target, Target::from_name("aarch64").unwrap());
+    assert!(options.debug_info);
+    assert_eq!(options.backend, None);
+
+    let options = parse(&["--run", "--entry", "freestanding", "--linker=ld.lld", "main.mamba", "--", "-o", "x"]).unwrap();
+
+    assert!(options.run);
+    assert_eq!(options.entry, Entry::Freestanding);
+    assert_eq!(options.linker.as_deref(), Some("ld.lld"));
+    assert_eq!(options.output, None);
+    assert_eq!(options.program_args, vec!["-o", "x"]);
+    assert_eq!(parse(&["-I", "lib", "main.mamba", "-Ivendor"]).unwrap().search_path, vec!["lib", "vendor"]);
+
+    assert_eq!(parse(&["main.mamba", "--backend=llvm"]).unwrap().backend, Some(Backend::Llvm));
+    assert_eq!(parse(&["main.mamba", "--backend=c", "--emit", "c"]).unwrap().emit, Emit::C);
+    assert_eq!(parse(&["main.mamba", "--emit", "wat"]).unwrap().output_path(), Some(PathBuf::from("main.wat")));
+    assert_eq!(Backend::default_for(&Target::from_name("x86_64").unwrap()), Backend::Native);
+    assert_eq!(Backend::default_for(&Target::from_name("aarch64").unwrap()), Backend::Llvm);
+    assert!(parse(&["main.mamba"]).unwrap().bounds_checks);
+    assert!(!parse(&["main.mamba", "--no-bounds-checks"]).unwrap().bounds_checks);
+
+    let options = parse(&["run", "main.mamba"]).unwrap();
+
+    assert_eq!(options.executor, Some(Executor::Interp));
+    assert_eq!(options.input.as_deref(), Some("main.mamba"));
+
+    let options = parse(&["run", "--vm", "main.mbc"]).unwrap();
+
+    assert_eq!(options.executor, Some(Executor::Vm));
+    assert_eq!(options.input.as_deref(), Some("main.mbc"));
+}
+
+#[test]
+fn test_options_errors() {
+    assert_eq!(parse(&["--emit", "bin"]).unwrap_err(), "usage error: unknown stage `bin`, expected one of: tokens, ast, bytecode, mbc, ir, c, wat, asm, obj, exe");
+    assert_eq!(parse(&["-O4"]).unwrap_err(), "usage error: unknown optimization level `4`, expected 0 to 3");
+    assert_eq!(parse(&["--target"]).unwrap_err(), "usage error: `--target` expects a value");
+    assert_eq!(parse(&["--fast"]).unwrap_err(), "usage error: unknown option `--fast`");
+    assert_eq!(parse(&["a.mamba", "b.mamba"]).unwrap_err(), "usage error: unexpected argument `b.mamba`, only one input file is supported");
+    assert_eq!(parse(&["a.mamba", "--emit=ir", "--run"]).unwrap_err(), "usage error: `--run` can only be combined with `--emit exe`");
+    assert_eq!(parse(&["a.mamba", "--vm"]).unwrap_err(), "usage error: `--vm` can only be used with `mamba run`");
+    assert_eq!(parse(&["a.mamba", "--backend", "gcc"]).unwrap_err(), "usage error: unknown backend `gcc`, expected `native`, `llvm` or `c`");
+    assert_eq!(parse(&["a.mamba", "--target", "aarch64", "--backend", "native"]).unwrap_err(), "usage error: aarch64 has no native backend, use `--backend llvm`");
+}
+
+#[test]
+fn test_output_path() {
+    assert_eq!(parse(&["src/fibo.mamba"]).unwrap().output_path(), Some(PathBuf::from("fibo")));
+    assert_eq!(parse(&["src/fibo.mamba", "--emit=ir"]).unwrap().output_path(), Some(PathBuf::from("fibo.ll")));
+    assert_eq!(parse(&["src/fibo.mamba", "--emit=obj"]).unwrap().output_path(), Some(PathBuf::from("fibo.o")));
+    assert_eq!(parse(&["src/fibo.mamba", "--emit=mbc"]).unwrap().output_path(), Some(PathBuf::from("fibo.mbc")));
+    assert_eq!(parse(&["src/fibo.mamba", "--emit=ast"]).unwrap().output_path(), None);
+    assert_eq!(parse(&["src/fibo.mamba", "--emit=bytecode"]).unwrap().output_path(), None);
+    assert_eq!(parse(&["src/fibo.mamba", "--emit=ir", "-o", "-"]).unwrap().output_path(), None);
+}
+
+#[test]
+fn test_exit_codes() {
+    let valid = source_file("valid", "def main() -> i32:\n    return 0;");
+    let invalid = source_file("invalid", "let a: i32 = ;");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&valid).args(["--emit=ir", "-o", "-"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("define i32 @main(i32 %argc, ptr %argv) {\n"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&invalid).arg("--emit=ir").output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "parser error: [PrattParser::parse_expr] unexpected token Semicolon found\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&valid).arg("-O9").output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg("missing.mamba").output().unwrap();
+    assert_eq!(output.status.code(), Some(3));
+
+    fs::remove_file(valid).unwrap();
+    fs::remove_file(invalid).unwrap();
+}
+
+#[test]
+fn test_run() {
+    if !has_toolchain() {
+        eprintln!("skipping: llc or cc is not installed");
+        return;
+    }
+
+    let source = source_file("run", "extern puts(text: str) -> i32;\n\ndef main() -> i32:\n    puts(\"hello\");\n    return 42;");
+    let exe = source.with_extension("");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).arg("-o").arg(&exe).arg("--run").output().unwrap();
+    assert_eq!(output.status.code(), Some(42));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hello\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).arg("-o").arg(&exe).args(["--linker", "false"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("`false` failed with exit code 1"));
+
+    fs::remove_file(source).unwrap();
+    fs::remove_file(exe).unwrap();
+}
+
+#[test]
+fn test_bounds_checks() {
+    if !has_toolchain() {
+        eprintln!("skipping: llc or cc is not installed");
+        return;
+    }
+
+    let source = source_file("bounds", "def main() -> i32:\n    let a: [i32; 3] = [1, 2, 3];\n    let i: i64 = 5;\n    return a[i];");
+    let exe = source.with_extension("");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).arg("-o").arg(&exe).args(["--backend", "llvm", "--run"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(101));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "panic: index 5 out of bounds for length 3\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).args(["--backend", "llvm", "--no-bounds-checks", "--emit=ir", "-o", "-"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("panic"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).args(["--target", "x86_64", "--backend", "native", "--emit=asm"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("[x86-64] [i32; 3] of `a` is not supported, use `--backend llvm`"));
+
+    fs::remove_file(source).unwrap();
+    fs::remove_file(exe).unwrap();
+}
+
+#[test]
+fn test_pointers() {
+    if !has_toolchain() {
+        eprintln!("skipping: llc or cc is not installed");
+        return;
+    }
+
+    let source = source_file("pointers", "extern memset(dst: *void, c: i32, n: u64) -> *void;\n\ndef main() -> i32:\n    let a: [u8; 4] = [1, 2, 3, 4];\n    let p: *u8 = &a[1];\n    memset(p, 9, 2);\n    *(p + 2) = 7;\n    return a[0] + a[1] + a[2] + a[3];");
+    let exe = source.with_extension("");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).arg("-o").arg(&exe).args(["--backend", "llvm", "--run"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(26));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).args(["--target", "x86_64", "--backend", "native", "--emit=asm"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("[x86-64] [u8; 4] of `a` is not supported, use `--backend llvm`"));
+
+    fs::remove_file(source).unwrap();
+    fs::remove_file(exe).unwrap();
+}
+
+#[test]
+fn test_structs() {
+    if !has_toolchain() {
+        eprintln!("skipping: llc or cc is not installed");
+        return;
+    }
+
+    let source = source_file("structs", "struct Point:\n    x: i32;\n    y: i32;\n\ndef shift(p: Point, by: i32) -> Point:\n    p.x = p.x + by;\n    return p;\n\ndef main() -> i32:\n    let p: Point = shift(Point { x: 1, y: 2 }, 10);\n    let q: *Point = &p;\n    q.y = q.y * 3;\n    return p.x + p.y;");
+    let exe = source.with_extension("");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).arg("-o").arg(&exe).args(["--backend", "llvm", "--run"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(17));
+
+    fs::remove_file(source).unwrap();
+    fs::remove_file(exe).unwrap();
+}
+
+#[test]
+fn test_methods() {
+    if !has_toolchain() {
+        eprintln!("skipping: llc or cc is not installed");
+        return;
+    }
+
+    let source = source_file("methods", "struct Counter:\n    n: i32;\n    def bump(*self, by: i32) -> void:\n        self.n = self.n + by;\n    def get(self) -> i32:\n        return self.n;\n\ndef main() -> i32:\n    let c: Counter = Counter { n: 1 };\n    c.bump(4);\n    let p: *Counter = &c;\n    p.bump(2);\n    return c.get() * p.get();");
+    let exe = source.with_extension("");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).arg("-o").arg(&exe).args(["--backend", "llvm", "--run"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(49));
+
+    fs::remove_file(source).unwrap();
+    fs::remove_file(exe).unwrap();
+}
+
+#[test]
+fn test_enums() {
+    if !has_toolchain() {
+        eprintln!("skipping: llc or cc is not installed");
+        return;
+    }
+
+    let source = source_file("enums", "enum Shape:\n    Circle(i64);\n    Rect(i8, i32);\n    Empty;\n\ndef area(s: Shape) -> i64:\n    match s:\n        case Shape.Circle(r):\n            return 3 * r * r;\n        case Shape.Rect(w, h):\n            return w * h;\n        case Shape.Empty:\n            return 0;\n\ndef kind(n: u8) -> i32:\n    match n:\n        case 255:\n            return 1;\n        case _:\n            return 0;\n\ndef main() -> i32:\n    return area(Shape.Circle(2)) + area(Shape.Rect(3, 4)) + area(Shape.Empty) + kind(255);");
+    let exe = source.with_extension("");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).arg("-o").arg(&exe).args(["--backend", "llvm", "--run"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(25));
+
+    fs::remove_file(source).unwrap();
+    fs::remove_file(exe).unwrap();
+}
+
+#[test]
+fn test_tuples() {
+    if !has_toolchain() {
+        eprintln!("skipping: llc or cc is not installed");
+        return;
+    }
+
+    let source = source_file("tuples", "def divmod(a: i32, b: i32) -> (i32, i32):\n    return (a / b, a % b);\n\ndef main() -> i32:\n    let (q, r) = divmod(17, 5);\n    let t: (i8, (i64, bool)) = (1, (2, True));\n    t.1.0 = 40;\n    return q * 10 + r + t.1.0 + t.0;");
+    let exe = source.with_extension("");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).arg("-o").arg(&exe).args(["--backend", "llvm", "--run"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(73));
+
+    fs::remove_file(source).unwrap();
+    fs::remove_file(exe).unwrap();
+}
+
+#[test]
+fn test_generics() {
+    if !has_toolchain() {
+        eprintln!("skipping: llc or cc is not installed");
+        return;
+    }
+
+    let source = source_file("generics", "def fact[T: int](n: T) -> T:\n    if n < 2:\n        return 1;\n    return n * fact(n - 1);\n\ndef pick[T](a: T, b: T, first: bool) -> T:\n    if first:\n        return a;\n    return b;\n\ndef main() -> i32:\n    let n: i64 = 5;\n    let m: u8 = 3;\n    return fact(n) - fact(m) + pick(1, 2, False);");
+    let exe = source.with_extension("");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).arg("-o").arg(&exe).args(["--backend", "llvm", "--run"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(116));
+
+    fs::remove_file(source).unwrap();
+    fs::remove_file(exe).unwrap();
+}
+
+#[test]
+fn test_modules() {
+    let dir = env::temp_dir().join(format!("mamba-driver-{}-modules", std::process::id()));
+    let lib = dir.join("lib");
+    fs::create_dir_all(dir.join("util")).unwrap();
+    fs::create_dir_all(&lib).unwrap();
+
+    fs::write(dir.join("geo.mamba"), "import util.math;\n\nlet unit: i32 = 2;\n\nstruct Point:\n    x: i32;\n    y: i32;\n    def norm(self) -> i32:\n        return util.math.abs(self.x) + util.math.abs(self.y);\n\ndef scale(p: Point) -> Point:\n    return Point { x: _twice(p.x), y: _twice(p.y) };\n\ndef _twice(n: i32) -> i32:\n    return n * unit;").unwrap();
+    fs::write(dir.join("util").join("math.mamba"), "def abs(n: i32) -> i32:\n    if n < 0:\n        return -n;\n    return n;").unwrap();
+    fs::write(lib.join("greet.mamba"), "extern puts(text: str) -> i32;\n\ndef hello() -> i32:\n    return puts(\"hello from a module\");").unwrap();
+    fs::write(dir.join("main.mamba"), "import geo;\nfrom util.math import abs;\nfrom greet import hello;\n\nextern puts(text: str) -> i32;\n\ndef main() -> i32:\n    hello();\n    let p: geo.Point = geo.scale(geo.Point { x: -3, y: 4 });\n    return p.norm() + abs(-geo.unit);").unwrap();
+    fs::write(dir.join("private.mamba"), "import geo;\n\ndef main() -> i32:\n    return geo._twice(1);").unwrap();
+    fs::write(dir.join("a.mamba"), "import b;").unwrap();
+    fs::write(dir.join("b.mamba"), "import a;").unwrap();
+    fs::write(dir.join("cycle.mamba"), "import a;\n\ndef main() -> i32:\n    return 0;").unwrap();
+
+    let main = dir.join("main.mamba");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg("run").arg(&main).arg("-I").arg(&lib).output().unwrap();
+    assert_eq!(output.status.code(), Some(16));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hello from a module\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg("run").arg(&main).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "module error: [Module] cannot find module `greet`, there is no `greet.mamba` in the directory of the program or the search path\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg("run").arg(dir.join("private.mamba")).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "module error: [Module] `_twice` is private to `geo`\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg("run").arg(dir.join("cycle.mamba")).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "module error: [Module] import cycle: a -> b -> a\n");
+
+    if has_toolchain() {
+        let exe = dir.join("main");
+
+        let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&main).arg("-I").arg(&lib).arg("-o").arg(&exe).args(["--backend", "llvm", "--run"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(16));
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hello from a module\n");
+    } else {
+        eprintln!("skipping: llc or cc is not installed");
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_std() {
+    let source = source_file("std", "def main() -> i32:\n    print(\"name? \");\n    let name: str = read_line();\n    println(name);\n    let n: i64 = read_int();\n    print_int(n * -2);\n    println(\"\");\n    println_bool(n > 3);\n    if n > 100:\n        abort();\n    exit(3);\n    return 0;");
+    let output_only = source_file("std-output", "def main() -> i32:\n    print(\"n? \");\n    print_int(-42);\n    println(\"\");\n    println_bool(True);\n    let big: u64 = 18446744073709551615;\n    println_uint(big);\n    exit(3);\n    return 0;");
+    let shadowed = source_file("std-shadowed", "extern puts(text: str) -> i32;\n\ndef println(n: i32) -> i32:\n    return n + 1;\n\ndef main() -> i32:\n    return println(41);");
+    // `read_line` loops rather than recurses, so a long line takes neither stack nor quadratic time
+    let long = "y".repeat(100000);
+    let expected = format!("name? {}\n-42\nTrue\n", long);
+    let input = format!("{}\n21\n", long);
+
+    let output = run_with_input(Command::new(env!("CARGO_BIN_EXE_mamba")).arg("run").arg(&source), &input);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+
+    let output = run_with_input(Command::new(env!("CARGO_BIN_EXE_mamba")).arg("run").arg(&source), "x\n101\n");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "runtime error: [Interpreter] the program raised signal 6\n");
+
+    // `read_line` passes the address of its locals, which the VM keeps in cells of the host's heap
+    let output = run_with_input(Command::new(env!("CARGO_BIN_EXE_mamba")).args(["run", "--vm"]).arg(&source), &input);
+    assert_eq!(output.status.code(), Some(3), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).args(["run", "--vm"]).arg(&output_only).output().unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "n? -42\nTrue\n18446744073709551615\n");
+
+    // the program's own functions take precedence and the unused parts of `std` are left out
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg("run").arg(&shadowed).output().unwrap();
+    assert_eq!(output.status.code(), Some(42));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&shadowed).args(["--backend", "llvm", "--emit=ir", "-o", "-"]).output().unwrap();
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("std."));
+
+    if has_toolchain() {
+        let exe = source.with_extension("");
+
+        // without `--backend`, the program falls back to LLVM like with it
+        for args in [&["--backend", "llvm"][..], &[]] {
+            let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).arg("-o").arg(&exe).args(args).output().unwrap();
+            assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+
+            let output = run_with_input(&mut Command::new(&exe), &input);
+            assert_eq!(output.status.code(), Some(3));
+            assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+
+            let output = run_with_input(&mut Command::new(&exe), "x\n101\n");
+            assert_eq!(output.status.code(), None);
+        }
+
+        let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).arg("-o").arg(&exe).args(["--backend", "c"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+
+        let output = run_with_input(&mut Command::new(&exe), &input);
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+
+        for backend in ["native", "c"] {
+            let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&output_only).arg("-o").arg(&exe).args(["--backend", backend]).output().unwrap();
+            assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+
+            let output = Command::new(&exe).output().unwrap();
+            assert_eq!(output.status.code(), Some(3));
+            assert_eq!(String::from_utf8_lossy(&output.stdout), "n? -42\nTrue\n18446744073709551615\n");
+        }
+
+        fs::remove_file(exe).unwrap();
+    } else {
+        eprintln!("skipping: llc or cc is not installed");
+    }
+
+    fs::remove_file(source).unwrap();
+    fs::remove_file(output_only).unwrap();
+    fs::remove_file(shadowed).unwrap();
+}
+
+#[test]
+fn test_format_strings() {
+    let source = source_file("format", "def main() -> i32:\n    let n: i32 = -42;\n    println(f\"n = {n:05} {-n:x} [{\"ab\":-3}] {n < 0} {{}}\");\n    return 0;");
+    let unformattable = source_file("format-struct", "struct P:\n    x: i32;\n\ndef main() -> i32:\n    let p: P = P { x: 1 };\n    println(f\"{p}\");\n    return 0;");
+    let hex_bool = source_file("format-bool", "def main() -> i32:\n    println(f\"{True:x}\");\n    return 0;");
+    let wide = source_file("format-wide", "def main() -> i32:\n    let n: u128 = 1;\n    println(f\"{n}\");\n    return 0;");
+
+    for args in [&["run"][..], &["run", "--vm"]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_mamba")).args(args).arg(&source).output().unwrap();
+        assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "n = -0042 2a [ab ] True {}\n");
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg("run").arg(&unformattable).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "runtime error: [Format] a value of type P cannot be formatted\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&hex_bool).args(["--target", "x86_64", "--backend", "native", "--emit=asm", "-o", "-"]).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "codegen error: [Format] `0`, `x` and `X` only apply to integers, found bool\n");
+
+    for args in [&["run"][..], &["--emit=ir", "-o", "-"]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_mamba")).args(args).arg(&wide).output().unwrap();
+        assert!(String::from_utf8_lossy(&output.stderr).ends_with("[Format] integers wider than 64 bits cannot be formatted, found u128\n"));
+    }
+
+    fs::remove_file(source).unwrap();
+    fs::remove_file(unformattable).unwrap();
+    fs::remove_file(hex_bool).unwrap();
+    fs::remove_file(wide).unwrap();
+}
+
+// the buffer of an f-string handed straight to `print` is freed once printed, so a loop printing
+// 80 MB of them stays within 40 MB of memory
+#[test]
+fn test_printed_format_strings_are_freed() {
+    let source = source_file("format-loop", "def repeat(n: i64) -> i64:\n    if n == 0:\n        return 0;\n    print(f\"{n:04000}\");\n    return repeat(n - 1);\n\ndef main() -> i32:\n    let kept: str = f\"{1}\";\n    println(kept);\n    repeat(20000);\n    return 0;");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).args(["--backend", "llvm", "--emit=ir", "-o", "-"]).output().unwrap();
+    let ir = String::from_utf8_lossy(&output.stdout);
+    assert!(ir.contains("call void @std._format_print(ptr"), "{}", ir);
+    assert!(ir.contains("call ptr @std._format_end(ptr"), "{}", ir);
+
+    if has_toolchain() {
+        let exe = source.with_extension("");
+
+        for backend in ["llvm", "c", "native"] {
+            let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).arg("-o").arg(&exe).args(["--backend", backend]).output().unwrap();
+            assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+
+            let status = Command::new("sh").args(["-c", "ulimit -v 40000 && exec \"$0\""]).arg(&exe).stdout(Stdio::null()).status().unwrap();
+            assert!(status.success(), "{}: {}", backend, status);
+        }
+
+        fs::remove_file(exe).unwrap();
+    } else {
+        eprintln!("skipping: llc or cc is not installed");
+    }
+
+    fs::remove_file(source).unwrap();
+}
+
+#[test]
+fn test_interpret() {
+    let source = source_file("interpret", "extern puts(text: str) -> i32;\n\ndef main() -> i32:\n    puts(\"interpreted\");\n    return 7 * 6;");
+    let failing = source_file("interpret-failing", "def main() -> i32:\n    return main();");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg("run").arg(&source).output().unwrap();
+    assert_eq!(output.status.code(), Some(42));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "interpreted\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg("run").arg(&failing).output().unwrap();
+    assert_eq!(output.status.code(), Some(5));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "runtime error: [Interpreter] stack overflow while calling `main`\n");
+
+    fs::remove_file(source).unwrap();
+    fs::remove_file(failing).unwrap();
+}
+
+#[test]
+fn test_vm() {
+    let source = source_file("vm", "extern puts(text: str) -> i32;\n\ndef main() -> i32:\n    puts(\"bytecode\");\n    return 7 * 6;");
+    let bytecode = source.with_extension("mbc");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).args(["run", "--vm"]).arg(&source).output().unwrap();
+    assert_eq!(output.status.code(), Some(42));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "bytecode\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).arg("--emit=mbc").arg("-o").arg(&bytecode).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).args(["run", "--vm"]).arg(&bytecode).output().unwrap();
+    assert_eq!(output.status.code(), Some(42));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "bytecode\n");
+
+    fs::write(&bytecode, "def main() -> i32:").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).args(["run", "--vm"]).arg(&bytecode).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "bytecode error: [Bytecode] not a mamba bytecode file\n");
+
+    fs::remove_file(source).unwrap();
+    fs::remove_file(bytecode).unwrap();
+}
+
+#[test]
+fn test_riscv() {
+    let source = source_file("riscv", "def main() -> i32:\n    return 42;");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).args(["--target", "riscv64", "--emit=asm", "-o", "-"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\n    call mamba.main\n"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).args(["--target", "riscv64", "--backend", "native", "--emit=asm", "-o", "-", "-g"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "usage error: `-g` is not supported by the native backends, use `--backend llvm`\n");
+
+    fs::remove_file(source).unwrap();
+}
+
+#[test]
+fn test_x86_64() {
+    if Command::new("cc").arg("--version").output().is_err() {
+        eprintln!("skipping: cc is not installed");
+        return;
+    }
+
+    let source = source_file("x86_64", "extern puts(text: str) -> i32;\n\ndef main() -> i32:\n    puts(\"native\");\n    return 6 * 7;");
+    let exe = source.with_extension("");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).args(["--target", "x86_64", "--emit=asm", "-o", "-"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\n    call puts@PLT\n"));
+
+    // the default backend of an x86_64 host builds the program without `llc`
+    if Target::host() == Target::from_name("x86_64").unwrap() {
+        let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).arg("-o").arg(&exe).arg("--run").output().unwrap();
+        assert_eq!(output.status.code(), Some(42));
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "native\n");
+
+        fs::remove_file(exe).unwrap();
+    }
+
+    fs::remove_file(source).unwrap();
+}
+
+// without `--backend`, what the native backends cannot build goes through LLVM
+#[test]
+fn test_native_fallback() {
+    let source = source_file("fallback", "def main() -> i32:\n    let a: [i32; 2] = [20, 22];\n    return a[0] + a[1];");
+    let exe = source.with_extension("");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).args(["--target", "x86_64", "--backend", "native", "--emit=asm", "-o", "-"]).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "codegen error: [x86-64] [i32; 2] of `a` is not supported, use `--backend llvm`\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).args(["--target", "x86_64", "--backend", "native", "--emit=asm", "-o", "-", "-O2"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "usage error: `-O2` is not supported by the native backends, use `--backend llvm`\n");
+
+    if has_toolchain() {
+        for flags in [&[][..], &["-g"], &["-O2"]] {
+            let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).arg("-o").arg(&exe).args(flags).arg("--run").output().unwrap();
+            assert_eq!(output.status.code(), Some(42), "{:?}: {}", flags, String::from_utf8_lossy(&output.stderr));
+        }
+
+        fs::remove_file(exe).unwrap();
+    } else {
+        eprintln!("skipping: llc or cc is not installed");
+    }
+
+    fs::remove_file(source).unwrap();
+}
+
+#[test]
+fn test_c() {
+    let source = source_file("c", "extern puts(text: str) -> i32;\n\ndef main() -> i32:\n    puts(\"portable\");\n    return 6 * 7;");
+    let exe = source.with_extension("");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).args(["--emit=c", "-o", "-"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\nint main(void) {\n    return mamba_main();\n}\n"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).args(["--emit=c", "--entry", "freestanding"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "codegen error: [C] a freestanding entry point cannot be written in portable C\n");
+
+    if Command::new("cc").arg("--version").output().is_ok() {
+        let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).arg("-o").arg(&exe).args(["--backend", "c", "--run"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(42));
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "portable\n");
+
+        fs::remove_file(exe).unwrap();
+    }
+
+    fs::remove_file(source).unwrap();
+}
+#[test]
+fn test_wat() {
+    let source = source_file("wat", "extern puts(text: str) -> i32;\n\ndef main() -> i32:\n    puts(\"sandboxed\");\n    return 0;");
+    let wat = source.with_extension("wat");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mamba")).arg(&source).args(["--emit=wat", "-o"]).arg(&wat).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+
+    let module = fs::read_to_string(&wat).unwrap();
+    assert!(module.starts_with("(module\n  (import \"env\" \"puts\" (func $puts (param i32) (result i32)))\n"));
+    assert!(module.contains("\n  (data (i32.const 1024) \"sandboxed\\00\")\n"));
+
+    fs::remove_file(wat).unwrap();
+    fs::remove_file(source).unwrap();
+}
\ No newline at end of file
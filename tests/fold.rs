@@ -0,0 +1,103 @@
+use mamba::error::FoldError;
+use mamba::fold::ConstFolder;
+use mamba::lexer::{Lexer, Literal};
+use mamba::parser::*;
+use mamba::types::{SignedInteger, UnsignedInteger};
+
+fn fold(source: &str) -> Result<AST, FoldError> {
+    let mut lexer = Lexer::new(source.into());
+    let tokens = lexer.get_tokens().unwrap();
+
+    let mut parser = Parser::with_spans(tokens, lexer.get_spans());
+    let mut ast = parser.parse_all();
+
+    ConstFolder::new().fold(&mut ast)?;
+    Ok(ast)
+}
+
+fn let_value(stmt: &Statement) -> Option<Literal> {
+    if let Statement::Let(LetStatement { expr: Expression::Literal((literal, _)), .. }) = stmt {
+        Some(literal.clone())
+    } else {
+        None
+    }
+}
+
+#[test]
+fn test_fold_globals() {
+    let ast = fold("let a: i32 = 2;\nlet b: i32 = 5;\nlet c: i32 = -(3 - (a * b));\nlet d: i64 = (c << 4) | 1;").unwrap();
+
+    assert_eq!(let_value(&ast[2]), Some(Literal::SignedInteger((7, SignedInteger::i32))));
+    assert_eq!(let_value(&ast[3]), Some(Literal::SignedInteger((113, SignedInteger::i64))));
+}
+
+#[test]
+fn test_fold_comparison() {
+    let ast = fold("let a: i32 = 2;\nlet b: bool = a * 3 >= 6 == True;").unwrap();
+
+    assert_eq!(let_value(&ast[1]), Some(Literal::Boolean(true)));
+}
+
+#[test]
+fn test_fold_unsigned_wraps() {
+    let ast = fold("let a: u8 = 250 + 10;\nlet b: u16 = 65535 + 1;\nlet c: u32 = 3 - 5;").unwrap();
+
+    assert_eq!(let_value(&ast[0]), Some(Literal::UnsignedInteger((4, UnsignedInteger::u8))));
+    assert_eq!(let_value(&ast[1]), Some(Literal::UnsignedInteger((0, UnsignedInteger::u16))));
+    assert_eq!(let_value(&ast[2]), Some(Literal::UnsignedInteger((u32::MAX as u128 - 1, UnsignedInteger::u32))));
+}
+
+#[test]
+fn test_fold_respects_shadowing() {
+    let ast = fold("let n: i32 = 2;\n\ndef f(n: i32) -> i32:\n    return n + n * 3;").unwrap();
+
+    if let Statement::Def(stmt) = &ast[1] {
+        assert_eq!(stmt.stmts[0].to_string(), "{ type: return, expr: Infix(InfixExpression { operator: Plus, left: Identifier(\"n\"), right: Infix(InfixExpression { operator: Multiply, left: Identifier(\"n\"), right: Literal((SignedInteger((3, i32)), SignedInteger(i32))) }) }) }");
+    } else {
+        panic!("expected def statement");
+    }
+}
+
+#[test]
+fn test_fold_local_expressions() {
+    let ast = fold("let n: i32 = 2;\n\ndef f() -> i32:\n    return n * 21;").unwrap();
+
+    if let Statement::Def(stmt) = &ast[1] {
+        assert_eq!(stmt.stmts[0].to_string(), "{ type: return, expr: Literal((SignedInteger((42, i32)), SignedInteger(i32))) }");
+    } else {
+        panic!("expected def statement");
+    }
+}
+
+#[test]
+fn test_fold_diagnostics() {
+    assert_eq!(fold("let a: i32 = 1 / (2 - 2);").unwrap_err().0, "[ConstFolder] attempt to compute `1 / 0`, which is a division by zero");
+    assert_eq!(fold("let a: i32 = 7 % 0;").unwrap_err().0, "[ConstFolder] attempt to compute `7 % 0`, which is a division by zero");
+    assert_eq!(fold("let a: i32 = 2147483647 + 1;").unwrap_err().0, "[ConstFolder] `2147483647 + 1` overflows i32");
+    assert_eq!(fold("let a: i8 = 100;\nlet b: i8 = a + a;").unwrap_err().0, "[ConstFolder] `100 + 100` overflows i8");
+    assert_eq!(fold("let a: i8 = 300;").unwrap_err().0, "[ConstFolder] `300` overflows i8 at line 1");
+    assert_eq!(fold("let a: i32 = 1 << 32;").unwrap_err().0, "[ConstFolder] shift amount 32 of `<<` is out of range for i32");
+}
+
+#[test]
+fn test_fold_literal_overflows() {
+    assert_eq!(fold("def f(a: i64) -> i64:\n    return a;\n\ndef g() -> i64:\n    return f(3000000000);").unwrap_err().0, "[ConstFolder] `3000000000` overflows i32 at line 5");
+    assert_eq!(fold("def f() -> i64:\n    let a: i64 = 1;\n    return 3000000000;").unwrap_err().0, "[ConstFolder] `3000000000` overflows i32 at line 3");
+    assert_eq!(fold("def f(a: i64) -> i64:\n    let b: i64 = a + 3000000000;\n    return b;").unwrap_err().0, "[ConstFolder] `3000000000` overflows i32 at line 2");
+    assert_eq!(fold("def f() -> i32:\n    return -2147483649;").unwrap_err().0, "[ConstFolder] `-2147483649` overflows i32");
+    assert!(fold("def f() -> i32:\n    return -2147483648;").is_ok());
+}
+
+#[test]
+fn test_fold_negative_literals() {
+    let ast = fold("let a: i64 = -5000000000;\nlet b: i64 = -9223372036854775807 - 1;\nlet c: i64 = -9223372036854775808;\nlet d: i8 = -128;\nlet e: u8 = ~1;").unwrap();
+
+    assert_eq!(let_value(&ast[0]), Some(Literal::SignedInteger((-5000000000, SignedInteger::i64))));
+    assert_eq!(let_value(&ast[1]), Some(Literal::SignedInteger((i64::MIN as i128, SignedInteger::i64))));
+    assert_eq!(let_value(&ast[2]), Some(Literal::SignedInteger((i64::MIN as i128, SignedInteger::i64))));
+    assert_eq!(let_value(&ast[3]), Some(Literal::SignedInteger((-128, SignedInteger::i8))));
+    assert_eq!(let_value(&ast[4]), Some(Literal::UnsignedInteger((254, UnsignedInteger::u8))));
+
+    assert_eq!(fold("let a: i64 = -9223372036854775809;").unwrap_err().0, "[ConstFolder] `-9223372036854775809` overflows i64");
+    assert_eq!(fold("let a: u32 = -1;").unwrap_err().0, "[ConstFolder] `-1` does not fit in u32");
+}
\ No newline at end of file
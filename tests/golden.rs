@@ -0,0 +1,192 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+// Every `.mamba` file in tests/golden starts with a header comment naming the stages it checks,
+// e.g. `# expect: ast, stdout, exit`. The expected output of each stage is kept in a sibling
+// `.expected` file with one `--- <stage>` section per stage. Running with `BLESS=1` rewrites
+// the `.expected` files from the current output instead of comparing against them.
+
+const HEADER: &str = "# expect:";
+const STAGES: [&str; 7] = ["tokens", "ast", "ir", "c", "stdout", "exit", "diagnostics"];
+
+fn mamba(path: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_mamba"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(args)
+        .arg(path)
+        .output()
+        .unwrap()
+}
+
+// the output of an emit stage; when the compiler gives up, its diagnostics are what gets compared
+fn emit(path: &Path, stage: &str, extra: &[&str]) -> String {
+    let output = mamba(path, &[&["--emit", stage, "-o", "-"], extra].concat());
+    let mut result = String::from_utf8_lossy(&output.stdout).into_owned();
+
+    if !output.status.success() {
+        result += &String::from_utf8_lossy(&output.stderr);
+    }
+
+    result
+}
+
+fn run_stages(path: &Path, stages: &[String]) -> Vec<(String, String)> {
+    let mut run = None;
+
+    stages.iter().map(|stage| {
+        let output = match stage.as_str() {
+            "tokens" | "ast" | "c" => emit(path, stage, &[]),
+            "ir" => emit(path, stage, &["--target", "x86_64"]),
+            _ => {
+                let output: &Output = run.get_or_insert_with(|| mamba(path, &["run"]));
+
+                match stage.as_str() {
+                    "stdout" => String::from_utf8_lossy(&output.stdout).into_owned(),
+                    "exit" => output.status.code().map_or("killed by a signal".into(), |code| code.to_string()),
+                    _ => String::from_utf8_lossy(&output.stderr).into_owned(),
+                }
+            },
+        };
+
+        (stage.clone(), output)
+    }).collect()
+}
+
+fn parse_header(source: &str) -> Result<Vec<String>, String> {
+    let header = source.lines().next()
+        .and_then(|line| line.strip_prefix(HEADER))
+        .ok_or_else(|| format!("the first line must be a `{} <stages>` comment", HEADER))?;
+
+    let stages: Vec<String> = header.split(',').map(|stage| stage.trim().to_string()).filter(|stage| !stage.is_empty()).collect();
+
+    if stages.is_empty() {
+        return Err("no stages are listed in the header".into());
+    }
+
+    match stages.iter().find(|stage| !STAGES.contains(&stage.as_str())) {
+        Some(stage) => Err(format!("unknown stage `{}`, expected one of {}", stage, STAGES.join(", "))),
+        None => Ok(stages),
+    }
+}
+
+// sections always end in a newline, so output without one reads the same once blessed
+fn normalize(text: &str) -> String {
+    if text.is_empty() || text.ends_with('\n') {
+        text.to_string()
+    } else {
+        format!("{}\n", text)
+    }
+}
+
+fn format_sections(sections: &[(String, String)]) -> String {
+    sections.iter().map(|(stage, text)| format!("--- {}\n{}", stage, normalize(text))).collect()
+}
+
+fn parse_sections(text: &str) -> Vec<(String, String)> {
+    let mut sections: Vec<(String, String)> = Vec::new();
+
+    for line in text.split_inclusive('\n') {
+        match (line.strip_prefix("--- "), sections.last_mut()) {
+            (Some(stage), _) => sections.push((stage.trim_end().to_string(), String::new())),
+            (None, Some((_, section))) => *section += line,
+            (None, None) => {},
+        }
+    }
+
+    sections
+}
+
+fn compare(stage: &str, expected: &str, actual: &str) -> Option<String> {
+    let actual = normalize(actual);
+
+    if expected == actual {
+        return None;
+    }
+
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+
+    for line in 1.. {
+        let (expected, actual) = (expected_lines.next(), actual_lines.next());
+
+        if expected != actual {
+            return Some(format!("`{}` differs at line {}\n        expected: {:?}\n        actual:   {:?}", stage, line, expected, actual));
+        }
+    }
+
+    unreachable!()
+}
+
+fn check(path: &Path, bless: bool) -> Vec<String> {
+    let source = fs::read_to_string(path).unwrap();
+    let stages = match parse_header(&source) {
+        Ok(stages) => stages,
+        Err(err) => return vec![err],
+    };
+
+    let actual = run_stages(path, &stages);
+    let expected_path = path.with_extension("expected");
+
+    if bless {
+        fs::write(&expected_path, format_sections(&actual)).unwrap();
+        return Vec::new();
+    }
+
+    let expected = match fs::read_to_string(&expected_path) {
+        Ok(expected) => parse_sections(&expected),
+        Err(_) => return vec![format!("{} is missing", expected_path.display())],
+    };
+
+    let mut failures: Vec<String> = actual.iter().filter_map(|(stage, actual)| {
+        match expected.iter().find(|(name, _)| name == stage) {
+            Some((_, expected)) => compare(stage, expected, actual),
+            None => Some(format!("`{}` has no section in {}", stage, expected_path.display())),
+        }
+    }).collect();
+
+    failures.extend(expected.iter()
+        .filter(|(stage, _)| !stages.contains(stage))
+        .map(|(stage, _)| format!("`{}` has a section but is not listed in the header", stage)));
+
+    failures
+}
+
+fn golden_files() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+
+    let mut files: Vec<PathBuf> = fs::read_dir(dir).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "mamba"))
+        .collect();
+
+    files.sort();
+    files
+}
+
+#[test]
+fn test_golden() {
+    let bless = env::var("BLESS").is_ok_and(|value| value == "1");
+
+    let files = golden_files();
+    assert!(!files.is_empty(), "tests/golden has no test files");
+
+    let mut report = String::new();
+    let mut failed = 0;
+
+    for path in &files {
+        let failures = check(path, bless);
+
+        if !failures.is_empty() {
+            failed += 1;
+            report += &format!("{}:\n", path.display());
+
+            for failure in failures {
+                report += &format!("    {}\n", failure);
+            }
+        }
+    }
+
+    assert!(failed == 0, "{} of {} golden files failed, rerun with BLESS=1 to accept the current output:\n{}", failed, files.len(), report);
+}
\ No newline at end of file
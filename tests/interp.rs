@@ -0,0 +1,211 @@
+use mamba::error::RuntimeError;
+use mamba::interp::{Interpreter, Value};
+use mamba::lexer::Lexer;
+use mamba::parser::Parser;
+use mamba::types::{SignedInteger, UnsignedInteger};
+
+// runs `source` and returns its exit code and everything it printed
+fn run(source: &str) -> Result<(i32, String), String> {
+    let mut lexer = Lexer::new(source.into());
+    let tokens = lexer.get_tokens().unwrap();
+
+    let mut parser = Parser::with_spans(tokens, lexer.get_spans());
+    let ast = parser.parse_all();
+
+    let mut interp = Interpreter::new().capture_output();
+    let code = interp.run(&ast).map_err(|err| err.0)?;
+
+    Ok((code, interp.take_output()))
+}
+
+// evaluates `expr` inside a function whose parameters `a` and `b` have type `dtype`
+fn eval(dtype: &str, a: &str, b: &str, expr: &str) -> Result<Value, String> {
+    let source = format!("def f(a: {dtype}, b: {dtype}) -> {dtype}:\n    return {expr};\n\ndef main() -> i32:\n    return 0;");
+
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.get_tokens().unwrap();
+
+    let mut parser = Parser::new(tokens);
+    let ast = parser.parse_all();
+
+    let mut lexer = Lexer::new(format!("f({a}, {b});"));
+    let tokens = lexer.get_tokens().unwrap();
+
+    let mut parser = Parser::new(tokens);
+    let call = parser.parse_all();
+
+    let mut interp = Interpreter::new();
+    interp.load(&ast).map_err(|err| err.0)?;
+
+    if let mamba::parser::Statement::Expression(stmt) = &call[0] {
+        interp.eval_expr(&stmt.expr).map_err(|err| err.0)
+    } else {
+        panic!("expected expression statement");
+    }
+}
+
+#[test]
+fn test_fixed_width_arithmetic() {
+    assert_eq!(eval("i8", "100", "100", "a + b"), Ok(Value::Signed(-56, SignedInteger::i8)));
+    assert_eq!(eval("i8", "0", "1", "~a - b"), Ok(Value::Signed(-2, SignedInteger::i8)));
+    assert_eq!(eval("i16", "300", "300", "a * b"), Ok(Value::Signed(24464, SignedInteger::i16)));
+    assert_eq!(eval("i32", "7", "2", "-a / b"), Ok(Value::Signed(-3, SignedInteger::i32)));
+    assert_eq!(eval("i32", "7", "2", "-a % b"), Ok(Value::Signed(-1, SignedInteger::i32)));
+    assert_eq!(eval("i64", "1", "62", "(a << b) >> 61"), Ok(Value::Signed(2, SignedInteger::i64)));
+    assert_eq!(eval("i128", "1", "127", "(a << b) >> 127"), Ok(Value::Signed(-1, SignedInteger::i128)));
+
+    assert_eq!(eval("u8", "250", "10", "a + b"), Ok(Value::Unsigned(4, UnsignedInteger::u8)));
+    assert_eq!(eval("u16", "3", "5", "a - b"), Ok(Value::Unsigned(65534, UnsignedInteger::u16)));
+    assert_eq!(eval("u32", "0", "0", "~a >> 28"), Ok(Value::Unsigned(15, UnsignedInteger::u32)));
+    assert_eq!(eval("u64", "1", "63", "a << b"), Ok(Value::Unsigned(1 << 63, UnsignedInteger::u64)));
+    assert_eq!(eval("u128", "0", "1", "a - b"), Ok(Value::Unsigned(u128::MAX, UnsignedInteger::u128)));
+}
+
+#[test]
+fn test_arithmetic_errors() {
+    assert_eq!(eval("i32", "1", "0", "a / b"), Err("[Interpreter] attempt to divide by zero".into()));
+    assert_eq!(eval("u8", "1", "0", "a % b"), Err("[Interpreter] attempt to divide by zero".into()));
+    assert_eq!(eval("i8", "-128", "-1", "a / b"), Err("[Interpreter] attempt to divide -128 by -1, which overflows i8".into()));
+    assert_eq!(eval("i32", "1", "32", "a << b"), Err("[Interpreter] shift amount 32 is out of range for a 32-bit integer".into()));
+}
+
+#[test]
+fn test_run() {
+    let source = "extern puts(text: str) -> i32;\n\ndef fibo(n: i32) -> i32:\n    if n <= 1:\n        return n;\n    else:\n        return fibo(n - 2) + fibo(n - 1);\n\ndef main() -> i32:\n    puts(\"fibo\");\n    return fibo(15);";
+    assert_eq!(run(source), Ok((610, "fibo\n".into())));
+
+    let source = "extern putchar(c: i32) -> i32;\nlet greeting: str = \"hi\";\n\ndef main() -> void:\n    let c: i32 = 65;\n    putchar(c);\n    putchar(10);";
+    assert_eq!(run(source), Ok((0, "A\n".into())));
+}
+
+#[test]
+fn test_runtime_errors() {
+    assert_eq!(run("def f() -> i32:\n    return 1;"), Err("[Entry] the program has no `main` function".into()));
+    assert_eq!(run("extern abort() -> void;\n\ndef main() -> i32:\n    abort();\n    return 0;"), Err("[Interpreter] extern `abort` has no host implementation".into()));
+    assert_eq!(run("def f(n: i32) -> i32:\n    return f(n + 1);\n\ndef main() -> i32:\n    return f(0);"), Err("[Interpreter] stack overflow while calling `f`".into()));
+    assert_eq!(run("def f(n: i32) -> i32:\n    if n > 0:\n        return n;\n\ndef main() -> i32:\n    return f(0);"), Err("[Interpreter] `f` reached its end without returning a value".into()));
+}
+
+#[test]
+fn test_arrays() {
+    let source = "let primes: [u8; 4] = [2, 3, 5, 7];\n\ndef main() -> i32:\n    let m: [[i32; 2]; 2] = [[1, 2], [3, 4]];\n    m[1][0] = m[0][1] * 10;\n    primes[0] = 11;\n    return m[1][0] + primes[0] + [5, 6, 7][2];";
+    assert_eq!(run(source), Ok((38, String::new())));
+
+    assert_eq!(run("def main() -> i32:\n    let a: [i32; 3] = [1, 2, 3];\n    let i: i64 = 5;\n    return a[i];"), Err("[Interpreter] index 5 out of bounds for length 3".into()));
+    assert_eq!(run("def main() -> i32:\n    let a: [i32; 3] = [1, 2, 3];\n    let i: i8 = -2;\n    a[i] = 0;\n    return 0;"), Err("[Interpreter] index -2 out of bounds for length 3".into()));
+    assert_eq!(run("def main() -> i32:\n    let a: i32 = 1;\n    return a[0];"), Err("[Interpreter] i32 cannot be indexed".into()));
+}
+
+#[test]
+fn test_pointers() {
+    let source = "let g: i32 = 7;\n\ndef swap(a: *i32, b: *i32) -> void:\n    let t: i32 = *a;\n    *a = *b;\n    *b = t;\n\ndef main() -> i32:\n    let x: i32 = 1;\n    swap(&x, &g);\n    let a: [i32; 4] = [1, 2, 3, 4];\n    let p: *i32 = &a[1];\n    *(p + 2) = 40;\n    return x * 100 + g + a[3] + (p + 2 - p);";
+    assert_eq!(run(source), Ok((743, String::new())));
+
+    assert_eq!(run("def main() -> i32:\n    let p: *i32 = null;\n    return *p;"), Err("[Interpreter] null pointer dereference".into()));
+    assert_eq!(run("def f() -> *i32:\n    let x: i32 = 1;\n    return &x;\n\ndef main() -> i32:\n    return *f();"), Err("[Interpreter] `x` is no longer alive".into()));
+    assert_eq!(run("def main() -> i32:\n    let x: i32 = 1;\n    let p: *i32 = &x + 1;\n    return 0;"), Err("[Interpreter] &x does not point into an array".into()));
+    assert_eq!(run("def main() -> i32:\n    let a: [i32; 2] = [1, 2];\n    let p: *i32 = &a[0] + 3;\n    return *p;"), Err("[Interpreter] index 3 out of bounds for length 2".into()));
+}
+
+#[test]
+fn test_structs() {
+    let source = "struct Point:\n    x: i32;\n    y: i32;\n\nstruct Rect:\n    min: Point;\n    max: Point;\n\ndef area(r: Rect) -> i32:\n    return (r.max.x - r.min.x) * (r.max.y - r.min.y);\n\ndef grow(r: *Rect, by: i32) -> void:\n    r.max.x = r.max.x + by;\n    r.max.y = r.max.y + by;\n\ndef main() -> i32:\n    let r: Rect = Rect { min: Point { x: 1, y: 2 }, max: Point { x: 4, y: 6 } };\n    let a: i32 = area(r);\n    grow(&r, 2);\n    let p: *i32 = &r.min.x;\n    *p = 0;\n    return a * 100 + area(r);";
+    assert_eq!(run(source), Ok((1236, String::new())));
+
+    // a list whose nodes point to the next one
+    let source = "struct Node:\n    value: i32;\n    next: *Node;\n    def sum(*self) -> i32:\n        if self.next == null:\n            return self.value;\n        return self.value + self.next.sum();\n\ndef main() -> i32:\n    let c: Node = Node { value: 30, next: null };\n    let b: Node = Node { value: 10, next: &c };\n    let a: Node = Node { value: 2, next: &b };\n    return a.sum();";
+    assert_eq!(run(source), Ok((42, String::new())));
+
+    assert_eq!(run("struct P:\n    x: i32;\n\ndef main() -> i32:\n    let p: *P = null;\n    return p.x;"), Err("[Interpreter] null pointer dereference".into()));
+    assert_eq!(run("def main() -> i32:\n    let x: i32 = 1;\n    return x.y;"), Err("[Interpreter] i32 has no field `y`".into()));
+}
+
+#[test]
+fn test_methods() {
+    let source = "struct Point:\n    x: i32;\n    y: i32;\n    def sum(self) -> i32:\n        return self.x + self.y;\n    def scale(*self, by: i32) -> void:\n        self.x = self.x * by;\n        self.y = self.y * by;\n    def moved(self, dx: i32) -> Point:\n        return Point { x: self.x + dx, y: self.y };\n\ndef twice(p: *Point) -> i32:\n    p.scale(2);\n    return p.sum();\n\ndef main() -> i32:\n    let p: Point = Point { x: 1, y: 2 };\n    p.scale(3);\n    let t: i32 = twice(&p);\n    return p.x * 100 + t + p.moved(10).moved(1).sum();";
+    assert_eq!(run(source), Ok((647, String::new())));
+
+    let source = "struct P:\n    x: i32;\n    def set(*self, x: i32) -> void:\n        self.x = x;\n\ndef make() -> P:\n    return P { x: 1 };\n\ndef main() -> i32:\n    ";
+    assert_eq!(run(&format!("{}make().set(2);\n    return 0;", source)), Err("[Interpreter] { type: fnCall, name: make, args: {  } } does not name a place".into()));
+    assert_eq!(run(&format!("{}return make().get();", source)), Err("[Interpreter] P has no method `get`".into()));
+    assert_eq!(run(&format!("{}let n: i32 = 1;\n    n.set(1);\n    return 0;", source)), Err("[Interpreter] i32 has no method `set`".into()));
+    assert_eq!(run(&format!("{}let p: *P = null;\n    p.set(1);\n    return 0;", source)), Err("[Interpreter] null pointer dereference".into()));
+}
+
+#[test]
+fn test_host_function() {
+    fn twice(_: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+        match args {
+            [Value::Signed(n, dtype)] => Ok(Value::Signed(n * 2, *dtype)),
+            _ => Err(RuntimeError("twice expects an integer".into())),
+        }
+    }
+
+    let mut lexer = Lexer::new("extern twice(n: i64) -> i64;\n\ndef main() -> i32:\n    return twice(21);".into());
+    let tokens = lexer.get_tokens().unwrap();
+    let ast = Parser::new(tokens).parse_all();
+
+    assert_eq!(Interpreter::new().with_host_function("twice", twice).run(&ast).map_err(|err| err.0), Ok(42));
+}
+
+#[test]
+fn test_heap() {
+    let externs = "extern calloc(count: u64, size: u64) -> *void;\nextern realloc(p: *void, size: u64) -> *void;\nextern strcat(text: *u8, suffix: str) -> *u8;\nextern free(p: *void) -> void;\nextern puts(text: str) -> i32;\n\n";
+    let run_with = |main: &str| run(&format!("{}def main() -> i32:\n{}", externs, main));
+
+    // a `*u8` buffer is passed for a string as what it holds up to its NUL
+    let source = "    let p: *u8 = calloc(4, 1);\n    strcat(p, \"abc\");\n    let q: *u8 = realloc(p, 8);\n    strcat(q, \"def\");\n    *(q + 1) = 66;\n    puts(q);\n    free(q);\n    return *q;";
+    assert_eq!(run_with(source), Err("[Interpreter] <heap 0> is used after it was freed".into()));
+
+    let source = "    let p: *u8 = calloc(4, 1);\n    strcat(p, \"abc\");\n    puts(p);\n    free(p);\n    free(null);\n    return 0;";
+    assert_eq!(run_with(source), Ok((0, "abc\n".into())));
+
+    assert_eq!(run_with("    let p: *u8 = calloc(4, 1);\n    strcat(p, \"abcd\");\n    return 0;"), Err("[Interpreter] index 4 out of bounds for length 4".into()));
+    assert_eq!(run_with("    let p: *u8 = calloc(2, 1);\n    *(p + 1) = 65;\n    puts(p + 1);\n    return 0;"), Err("[Interpreter] the string at &<heap 0>[1] has no terminating NUL".into()));
+    assert_eq!(run_with("    let p: *u8 = calloc(2, 1);\n    free(p);\n    free(p);\n    return 0;"), Err("[Interpreter] <heap 0> is used after it was freed".into()));
+    assert_eq!(run_with("    let a: [u8; 2] = [0, 0];\n    free(&a[0]);\n    return 0;"), Err("[Interpreter] `free` was given &a[0], which `calloc` did not return".into()));
+}
+
+#[test]
+fn test_streams() {
+    let externs = "extern open_memstream(buffer: **u8, size: *u64) -> *void;\nextern fputc(c: i32, stream: *void) -> i32;\nextern fclose(stream: *void) -> i32;\nextern calloc(count: u64, size: u64) -> *void;\nextern puts(text: str) -> i32;\n\n";
+    let run_with = |main: &str| run(&format!("{}def main() -> i32:\n{}", externs, main));
+
+    // closing the stream stores what was written to it and its length in the two variables
+    let source = "    let text: *u8 = null;\n    let size: u64 = 0;\n    let stream: *void = open_memstream(&text, &size);\n    fputc(104, stream);\n    fputc(105, stream);\n    fclose(stream);\n    puts(text);\n    return size;";
+    assert_eq!(run_with(source), Ok((2, "hi\n".into())));
+
+    assert_eq!(run_with("    let p: *void = calloc(1, 1);\n    fputc(104, p);\n    return 0;"), Err("[Interpreter] `fputc` was given &<heap 0>[0], which `open_memstream` did not return".into()));
+
+    let source = "    let text: *u8 = null;\n    let size: u64 = 0;\n    let stream: *void = open_memstream(&text, &size);\n    fclose(stream);\n    fclose(stream);\n    return 0;";
+    assert_eq!(run_with(source), Err("[Interpreter] `fclose` was given &<heap 0>[0], which `open_memstream` did not return".into()));
+}
+
+#[test]
+fn test_enums() {
+    let source = "enum Color:\n    Red;\n    Green = 5;\n    Blue;\n\nenum Shape:\n    Circle(i64);\n    Rect(i32, i32);\n    Empty;\n\ndef area(s: Shape) -> i64:\n    match s:\n        case Shape.Circle(r):\n            return 3 * r * r;\n        case Shape.Rect(w, h):\n            return w * h;\n        case Shape.Empty:\n            return 0;\n    return 0;\n\ndef code(c: Color) -> i32:\n    match c:\n        case Color.Red:\n            return 1;\n        case _:\n            return 2;\n\ndef small(n: i32) -> i32:\n    match n:\n        case -1:\n            return 10;\n        case 3:\n            return 30;\n        case _:\n            return 0;\n\ndef main() -> i32:\n    let shapes: [Shape; 3] = [Shape.Circle(2), Shape.Rect(3, 4), Shape.Empty];\n    let total: i64 = area(shapes[0]) + area(shapes[1]) + area(shapes[2]);\n    return total + code(Color.Blue) * 100 + code(Color.Red) * 1000 + small(-1) + small(3) + small(7);";
+    assert_eq!(run(source), Ok((1264, String::new())));
+
+    let source = "def main() -> i32:\n    let n: u8 = 3;\n    match n:\n        case 300:\n            return 1;\n        case _:\n            return 2;";
+    assert_eq!(run(source), Err("[Interpreter] case 300 does not fit in u8".into()));
+}
+
+#[test]
+fn test_tuples() {
+    let source = "def divmod(a: i32, b: i32) -> (i32, i32):\n    return (a / b, a % b);\n\ndef bump(p: *(i64, bool)) -> void:\n    p.0 = p.0 + 1;\n    p.1 = False;\n\ndef main() -> i32:\n    let (q, r) = divmod(17, 5);\n    let t: (i64, bool) = (40, True);\n    bump(&t);\n    let (x, _): (i64, bool) = t;\n    let pair: (i8, (i32, i32)) = (1, divmod(7, 2));\n    if t.1:\n        return 0;\n    return q * 100 + r * 10 + divmod(9, 4).1 + x + pair.1.0 + pair.0;";
+    assert_eq!(run(source), Ok((366, String::new())));
+
+    assert_eq!(run("def main() -> i32:\n    let (a, b) = 1;\n    return a;"), Err("[Interpreter] i32 cannot be destructured into 2 names".into()));
+    assert_eq!(run("def main() -> i32:\n    let t: (i32, i32) = (1, 2);\n    return t.2;"), Err("[Interpreter] (i32, i32) has no field `2`".into()));
+}
+
+#[test]
+fn test_generics() {
+    let source = "def max[T: int](a: T, b: T) -> T:\n    if a > b:\n        return a;\n    return b;\n\ndef first[T](xs: [T; 3]) -> T:\n    return xs[0];\n\ndef swap[A, B](p: (A, B)) -> (B, A):\n    return (p.1, p.0);\n\ndef bump[T: int](p: *T, by: T) -> void:\n    let one: T = 1;\n    *p = *p + by * one;\n\ndef main() -> i32:\n    let x: i64 = 40;\n    let y: u8 = 7;\n    bump(&x, 2);\n    let (b, a) = swap((True, y));\n    if a:\n        return max(y, 200) + first([1, 2, 3]) + max(x, 3) + b;\n    return 0;";
+    assert_eq!(run(source), Ok((250, String::new())));
+
+    let source = "def f[T: int](a: T, b: T) -> T:\n    return a;\n\ndef main() -> i32:\n    let x: i64 = 1;\n    ";
+    assert_eq!(run(&format!("{}let y: u8 = 2;\n    return f(x, y);", source)), Err("[Mono] `T` of `f` is inferred as both i64 and u8".into()));
+    assert_eq!(run(&format!("{}return f(True, False);", source)), Err("[Mono] `T` of `f` must be int, found bool".into()));
+    assert_eq!(run("def f[T](a: *T) -> i32:\n    return 0;\n\ndef main() -> i32:\n    return f(1);"), Err("[Mono] cannot infer `T` of `f` from its arguments".into()));
+}
\ No newline at end of file
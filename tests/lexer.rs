@@ -1,4 +1,4 @@
-use mamba::lexer::{Lexer, Token, Keyword, Literal};
+use mamba::lexer::{Lexer, Token, Keyword, Literal, Span, FormatPart, FormatSpec, Radix};
 use mamba::types::{DataType, SignedInteger};
 
 const SAMPLE_CODE: &str = "
@@ -58,4 +58,52 @@ fn test_lexer() {
     assert_eq!(tokens[40], Token::Semicolon);
     assert_eq!(tokens[41], Token::Dedent);
     assert_eq!(tokens[42], Token::EOF);
+}
+
+#[test]
+fn test_spans() {
+    let mut lexer = Lexer::new(SAMPLE_CODE.into());
+    let tokens = lexer.get_tokens().unwrap();
+    let spans = lexer.get_spans();
+
+    assert_eq!(tokens.len(), spans.len());
+    assert_eq!(spans[0], Span { line: 2, column: 1 });
+    assert_eq!(spans[5], Span { line: 2, column: 16 });
+    assert_eq!(spans[7], Span { line: 4, column: 1 });
+    assert_eq!(spans[21], Span { line: 5, column: 1 });
+    assert_eq!(spans[22], Span { line: 5, column: 5 });
+    assert_eq!(spans[38], Span { line: 8, column: 11 });
+}
+
+#[test]
+fn test_lexer_errors() {
+    assert_eq!(Lexer::new("a %= 2;".into()).get_tokens().unwrap()[1], Token::PercentEqual);
+    assert_eq!(Lexer::new("let a: bool = !b;".into()).get_tokens().unwrap_err().0, "unexpected character `!` at line 1, column 15");
+    assert_eq!(Lexer::new("puts(\"hello);".into()).get_tokens().unwrap_err().0, "closing quotation mark expected");
+}
+
+#[test]
+fn test_format_strings() {
+    let tokens = Lexer::new("f\"n = {n + 1:-08.2x} {{ok}}\";".into()).get_tokens().unwrap();
+    let spec = FormatSpec { left: true, zero: true, width: 8, precision: Some(2), radix: Radix::Hex };
+
+    assert_eq!(tokens[0], Token::FormatString(vec![
+        FormatPart::Text("n = ".into()),
+        FormatPart::Value(vec![Token::Identifier("n".into()), Token::Plus, Token::Literal(Literal::SignedInteger((1, SignedInteger::i32))), Token::EOF], spec),
+        FormatPart::Text(" {ok}".into()),
+    ]));
+    assert_eq!(tokens[1], Token::Semicolon);
+    assert_eq!(spec.to_string(), "-08.2x");
+
+    // a `:` inside quotes or nested braces does not start the spec
+    match &Lexer::new("f\"{f\"{a:X}\"}\"".into()).get_tokens().unwrap()[0] {
+        Token::FormatString(parts) => assert!(matches!(&parts[..], [FormatPart::Value(tokens, spec)] if matches!(tokens[0], Token::FormatString(_)) && *spec == FormatSpec::default())),
+        token => panic!("unexpected token {token:?}"),
+    }
+
+    assert_eq!(Lexer::new("f\"{}\"".into()).get_tokens().unwrap_err().0, "empty expression in format string at line 1, column 3");
+    assert_eq!(Lexer::new("f\"a } b\"".into()).get_tokens().unwrap_err().0, "single `}` in format string at line 1, column 5");
+    assert_eq!(Lexer::new("f\"{a:>4}\"".into()).get_tokens().unwrap_err().0, "invalid format spec `>4` at line 1, column 3");
+    assert_eq!(Lexer::new("f\"{a\"".into()).get_tokens().unwrap_err().0, "`}` expected in format string at line 1, column 3");
+    assert_eq!(Lexer::new("f\"{a}".into()).get_tokens().unwrap_err().0, "closing quotation mark expected");
 }
\ No newline at end of file
@@ -7,23 +7,25 @@ fn get_tokens(source: &str) -> Vec<Token> {
     lexer.get_tokens().unwrap()
 }
 
+#[allow(clippy::let_and_return)]
 fn test_prefix_expression(tokens: Vec<Token>) -> Expression {
     let mut parser = Parser::new(tokens);
-    let result = PrattParser::parse_nud(&mut parser).unwrap();
+    let result = PrattParser::parse_nud(&mut parser, None).unwrap();
     
     result
 }
 
 #[test]
 fn test_prefix_expressions() {
-    assert_eq!(test_prefix_expression(get_tokens("+123")).to_string(), "{ operator: UnaryPlus, right: Integer(123) }");
-    assert_eq!(test_prefix_expression(get_tokens("-123")).to_string(), "{ operator: UnaryMinus, right: Integer(123) }");
-    assert_eq!(test_prefix_expression(get_tokens("~123")).to_string(), "{ operator: BitwiseNot, right: Integer(123) }");
+    assert_eq!(test_prefix_expression(get_tokens("+123")).to_string(), "{ operator: UnaryPlus, right: (SignedInteger((123, i32)), SignedInteger(i32)) }");
+    assert_eq!(test_prefix_expression(get_tokens("-123")).to_string(), "{ operator: UnaryMinus, right: (SignedInteger((123, i32)), SignedInteger(i32)) }");
+    assert_eq!(test_prefix_expression(get_tokens("~123")).to_string(), "{ operator: BitwiseNot, right: (SignedInteger((123, i32)), SignedInteger(i32)) }");
     assert_eq!(test_prefix_expression(get_tokens("+foo")).to_string(), "{ operator: UnaryPlus, right: foo }");
     assert_eq!(test_prefix_expression(get_tokens("-foo")).to_string(), "{ operator: UnaryMinus, right: foo }");
     assert_eq!(test_prefix_expression(get_tokens("~foo")).to_string(), "{ operator: BitwiseNot, right: foo }");
 }
 
+#[allow(clippy::let_and_return)]
 fn test_expression(tokens: Vec<Token>) -> Expression {
     let mut parser = Parser::new(tokens);
     let result = PrattParser::parse_expr(&mut parser, mamba::parser::pratt::Precedence::Lowest, None).unwrap();
@@ -52,10 +54,182 @@ fn test_mixed_expressions() {
     assert_eq!(test_expression(get_tokens("a * b * c")).to_string(), "{ operator: Multiply, left: { operator: Multiply, left: a, right: b }, right: c }");
     assert_eq!(test_expression(get_tokens("a * b / c")).to_string(), "{ operator: Divide, left: { operator: Multiply, left: a, right: b }, right: c }");
     assert_eq!(test_expression(get_tokens("a + b * c + d / e - f")).to_string(), "{ operator: Minus, left: { operator: Plus, left: { operator: Plus, left: a, right: { operator: Multiply, left: b, right: c } }, right: { operator: Divide, left: d, right: e } }, right: f }");
-    assert_eq!(test_expression(get_tokens("5 > 4 == 3 < 4")).to_string(), "{ operator: Equal, left: { operator: Greater, left: Integer(5), right: Integer(4) }, right: { operator: Less, left: Integer(3), right: Integer(4) } }");
-    assert_eq!(test_expression(get_tokens("5 < 4 != 3 > 4")).to_string(), "{ operator: NotEqual, left: { operator: Less, left: Integer(5), right: Integer(4) }, right: { operator: Greater, left: Integer(3), right: Integer(4) } }");
-    assert_eq!(test_expression(get_tokens("1 + (2 + 3) + 4")).to_string(), "{ operator: Plus, left: { operator: Plus, left: Integer(1), right: { operator: Plus, left: Integer(2), right: Integer(3) } }, right: Integer(4) }");
-    assert_eq!(test_expression(get_tokens("(5 + 5) * 2")).to_string(), "{ operator: Multiply, left: { operator: Plus, left: Integer(5), right: Integer(5) }, right: Integer(2) }");
-    assert_eq!(test_expression(get_tokens("2 / (5 + 5)")).to_string(), "{ operator: Divide, left: Integer(2), right: { operator: Plus, left: Integer(5), right: Integer(5) } }");
-    assert_eq!(test_expression(get_tokens("-(5 + 5)")).to_string(), "{ operator: UnaryMinus, right: { operator: Plus, left: Integer(5), right: Integer(5) } }");
+    assert_eq!(test_expression(get_tokens("5 > 4 == 3 < 4")).to_string(), "{ operator: Equal, left: { operator: Greater, left: (SignedInteger((5, i32)), SignedInteger(i32)), right: (SignedInteger((4, i32)), SignedInteger(i32)) }, right: { operator: Less, left: (SignedInteger((3, i32)), SignedInteger(i32)), right: (SignedInteger((4, i32)), SignedInteger(i32)) } }");
+    assert_eq!(test_expression(get_tokens("5 < 4 != 3 > 4")).to_string(), "{ operator: NotEqual, left: { operator: Less, left: (SignedInteger((5, i32)), SignedInteger(i32)), right: (SignedInteger((4, i32)), SignedInteger(i32)) }, right: { operator: Greater, left: (SignedInteger((3, i32)), SignedInteger(i32)), right: (SignedInteger((4, i32)), SignedInteger(i32)) } }");
+    assert_eq!(test_expression(get_tokens("1 + (2 + 3) + 4")).to_string(), "{ operator: Plus, left: { operator: Plus, left: (SignedInteger((1, i32)), SignedInteger(i32)), right: { operator: Plus, left: (SignedInteger((2, i32)), SignedInteger(i32)), right: (SignedInteger((3, i32)), SignedInteger(i32)) } }, right: (SignedInteger((4, i32)), SignedInteger(i32)) }");
+    assert_eq!(test_expression(get_tokens("(5 + 5) * 2")).to_string(), "{ operator: Multiply, left: { operator: Plus, left: (SignedInteger((5, i32)), SignedInteger(i32)), right: (SignedInteger((5, i32)), SignedInteger(i32)) }, right: (SignedInteger((2, i32)), SignedInteger(i32)) }");
+    assert_eq!(test_expression(get_tokens("2 / (5 + 5)")).to_string(), "{ operator: Divide, left: (SignedInteger((2, i32)), SignedInteger(i32)), right: { operator: Plus, left: (SignedInteger((5, i32)), SignedInteger(i32)), right: (SignedInteger((5, i32)), SignedInteger(i32)) } }");
+    assert_eq!(test_expression(get_tokens("-(5 + 5)")).to_string(), "{ operator: UnaryMinus, right: { operator: Plus, left: (SignedInteger((5, i32)), SignedInteger(i32)), right: (SignedInteger((5, i32)), SignedInteger(i32)) } }");
+}
+
+#[test]
+fn test_index_expressions() {
+    assert_eq!(test_expression(get_tokens("a[i + 1]")).to_string(), "{ type: index, array: a, index: { operator: Plus, left: i, right: (SignedInteger((1, i32)), SignedInteger(i32)) } }");
+    assert_eq!(test_expression(get_tokens("m[1][0] * 2")).to_string(), "{ operator: Multiply, left: { type: index, array: { type: index, array: m, index: (SignedInteger((1, i32)), SignedInteger(i32)) }, index: (SignedInteger((0, i32)), SignedInteger(i32)) }, right: (SignedInteger((2, i32)), SignedInteger(i32)) }");
+    assert_eq!(test_expression(get_tokens("f(x)[2]")).to_string(), "{ type: index, array: { type: fnCall, name: f, args: { x } }, index: (SignedInteger((2, i32)), SignedInteger(i32)) }");
+    assert_eq!(test_expression(get_tokens("[1, 2,]")).to_string(), "{ type: array, elements: { (SignedInteger((1, i32)), SignedInteger(i32)), (SignedInteger((2, i32)), SignedInteger(i32)) } }");
+}
+
+fn parse_program(source: &str) -> Result<String, String> {
+    let mut parser = Parser::new(get_tokens(source));
+    parser.parse_program().map(|ast| ast[0].to_string()).map_err(|err| err.0)
+}
+
+#[test]
+fn test_arrays() {
+    assert_eq!(parse_program("def main() -> i32:\n    let m: [[u8; 2]; 2] = [[1, 2], [3, 4]];\n    m[1][0] = 10;\n    return 0;").unwrap(), "{ type: fnDef, name: main, returnType: i32, args: [], stmts: { { type: let, name: m, dataType: [2 x [2 x i8]], expr: { type: array, elements: { { type: array, elements: { (UnsignedInteger((1, u8)), UnsignedInteger(u8)), (UnsignedInteger((2, u8)), UnsignedInteger(u8)) } }, { type: array, elements: { (UnsignedInteger((3, u8)), UnsignedInteger(u8)), (UnsignedInteger((4, u8)), UnsignedInteger(u8)) } } } } }, { type: assign, target: { type: index, array: { type: index, array: m, index: (SignedInteger((1, i32)), SignedInteger(i32)) }, index: (SignedInteger((0, i32)), SignedInteger(i32)) }, expr: (SignedInteger((10, i32)), SignedInteger(i32)) }, { type: return, expr: Literal((SignedInteger((0, i32)), SignedInteger(i32))) } }");
+
+    assert_eq!(parse_program("let a: [i32; 3] = 1;").unwrap_err(), "[PrattParser::parse_expr] expected [3 x i32], found integer literal 1");
+    assert_eq!(parse_program("let a: [i32; -1] = [];").unwrap_err(), "[Parser::parse_type] expected array length, found Minus");
+    assert_eq!(parse_program("let a: [i32 3] = [];").unwrap_err(), "[Parser::parse_type] expected `;`, found Literal(SignedInteger((3, i32)))");
+    assert_eq!(parse_program("let a: [i32; 2] = [1, 2;").unwrap_err(), "[ArrayExpression] expected `,` or `]`, found Semicolon");
+    assert_eq!(parse_program("def main() -> i32:\n    a = 2;\n    return 0;").unwrap_err(), "[AssignStatement] cannot assign to a");
+    assert_eq!(parse_program("def main() -> i32:\n    f()[0] = 1;\n    return 0;").unwrap_err(), "[AssignStatement] cannot assign to { type: index, array: { type: fnCall, name: f, args: {  } }, index: (SignedInteger((0, i32)), SignedInteger(i32)) }");
+}
+
+#[test]
+fn test_pointers() {
+    assert_eq!(test_expression(get_tokens("*p + 1")).to_string(), "{ operator: Plus, left: { operator: Dereference, right: p }, right: (SignedInteger((1, i32)), SignedInteger(i32)) }");
+    assert_eq!(test_expression(get_tokens("&a[i]")).to_string(), "{ operator: AddressOf, right: { type: index, array: a, index: i } }");
+    assert_eq!(parse_program("def main() -> i32:\n    let p: **i32 = null;\n    **p = 1;\n    return 0;").unwrap(), "{ type: fnDef, name: main, returnType: i32, args: [], stmts: { { type: let, name: p, dataType: ptr, expr: (Null(Pointer(Pointer(SignedInteger(i32)))), Pointer(Pointer(SignedInteger(i32)))) }, { type: assign, target: { operator: Dereference, right: { operator: Dereference, right: p } }, expr: (SignedInteger((1, i32)), SignedInteger(i32)) }, { type: return, expr: Literal((SignedInteger((0, i32)), SignedInteger(i32))) } }");
+
+    assert_eq!(parse_program("let p: * = null;").unwrap_err(), "[Parser::parse_type] expected pointee type, found Equal");
+    assert_eq!(parse_program("let x: i32 = null;").unwrap_err(), "[PrattParser::parse_expr] expected i32, found null");
+    assert_eq!(parse_program("let p: *i32 = &f();").unwrap_err(), "[PrattParser::parse_nud] cannot take the address of { type: fnCall, name: f, args: {  } }");
+}
+
+#[test]
+fn test_structs() {
+    let source = "struct P:\n    x: i32;\n    y: i32;\n\ndef f(p: *P) -> i32:\n    p.x = P { y: 2, x: 1 }.y;\n    return p.x;";
+    let ast = Parser::new(get_tokens(source)).parse_program().unwrap();
+
+    assert_eq!(ast[0].to_string(), "{ type: struct, name: P, fields: [(\"x\", SignedInteger(i32)), (\"y\", SignedInteger(i32))] }");
+    assert!(ast[1].to_string().contains("stmts: { { type: assign, target: { type: member, expr: p, field: x }, expr: { type: member, expr: { type: struct, name: P, fields: { y: (SignedInteger((2, i32)), SignedInteger(i32)), x: (SignedInteger((1, i32)), SignedInteger(i32)) } }, field: y } }"));
+
+    // the same declaration parsed again is the same type
+    let again = Parser::new(get_tokens(source)).parse_program().unwrap();
+    match (&ast[0], &again[0]) {
+        (Statement::Struct(first), Statement::Struct(second)) => assert_eq!(first.dtype, second.dtype),
+        _ => panic!("expected two struct statements"),
+    }
+
+    assert_eq!(parse_program("struct P:\n    x: i32;\n\nstruct P:\n    y: i32;").unwrap_err(), "[StructStatement] `P` is already declared");
+    assert_eq!(parse_program("struct P:\n    x: i32;\n    x: i8;").unwrap_err(), "[StructStatement] field `x` is declared twice");
+    // a field can point to the forward declaration of its own struct, but not contain it
+    assert_eq!(parse_program("struct P:\n    x: i32;\n    next: *P;").unwrap(), "{ type: struct, name: P, fields: [(\"x\", SignedInteger(i32)), (\"next\", Pointer(Struct(StructType { name: \"P\", fields: [] })))] }");
+    assert_eq!(parse_program("struct P:\n    x: i32;\n    next: [P; 2];").unwrap_err(), "[StructStatement] field `next` of `P` cannot contain `P` itself, only point to it");
+    assert_eq!(parse_program("let x: i32 = Q { x: 1 }.x;").unwrap_err(), "[StructExpression] unknown struct `Q`");
+    assert_eq!(parse_program("struct P:\n    x: i32;\n\nlet x: i32 = P { y: 1 }.x;").unwrap_err(), "[StructExpression] `P` has no field `y`");
+    assert_eq!(parse_program("struct P:\n    x: i32;\n    y: i32;\n\nlet x: i32 = P { x: 1 }.x;").unwrap_err(), "[StructExpression] missing field `y` of `P`");
+    assert_eq!(parse_program("struct P:\n    x: i32;\n\nlet x: i32 = P { x: 1 }.\"x\";").unwrap_err(), "[MemberExpression] expected field name, found Literal(String(\"x\"))");
+}
+
+#[test]
+fn test_methods() {
+    let source = "struct P:\n    x: i32;\n    def scale(*self, by: i32) -> void:\n        self.x = self.x * by;\n\ndef f(p: P) -> void:\n    p.scale(2);";
+    let ast = Parser::new(get_tokens(source)).parse_program().unwrap();
+
+    assert_eq!(ast.len(), 3);
+    assert!(ast[1].to_string().starts_with("{ type: fnDef, name: P.scale, returnType: void, args: [(\"self\", Pointer(Struct(StructType { name: \"P\", fields: [(\"x\", SignedInteger(i32))] }))), (\"by\", SignedInteger(i32))]"));
+    assert!(ast[2].to_string().ends_with("stmts: { { type: methodCall, receiver: p, method: scale, args: { (SignedInteger((2, i32)), SignedInteger(i32)) } } }"));
+    assert_eq!(test_expression(get_tokens("a.b.c(1)")).to_string(), "{ type: methodCall, receiver: { type: member, expr: a, field: b }, method: c, args: { (SignedInteger((1, i32)), SignedInteger(i32)) } }");
+
+    assert_eq!(parse_program("struct P:\n    x: i32;\n    def f(x: i32) -> i32:\n        return x;").unwrap_err(), "[DefStatement] method `f` must take `self` or `*self` first");
+    assert_eq!(parse_program("struct P:\n    x: i32;\n    def f(self) -> i32:\n        return 1;\n    def f(*self) -> i32:\n        return 2;").unwrap_err(), "[StructStatement] method `f` is declared twice");
+    assert_eq!(parse_program("struct P:\n    def f(self) -> i32:\n        return 1;\n    x: i32;").unwrap_err(), "[StructStatement] field `x` must be declared before the methods");
+    assert_eq!(parse_program("let x: i32 = a[0](1);").unwrap_err(), "[FnCallExpression] { type: index, array: a, index: (SignedInteger((0, i32)), SignedInteger(i32)) } cannot be called");
+}
+
+#[test]
+fn test_enums() {
+    let source = "enum Shape:\n    Circle(i64);\n    Rect(i32, i32) = 4;\n    Empty;\n\ndef f(s: Shape) -> i64:\n    match s:\n        case Shape.Circle(r):\n            return r;\n        case Shape.Rect(_, h):\n            return h;\n        case _:\n            return 0;";
+    let ast = Parser::new(get_tokens(source)).parse_program().unwrap();
+
+    assert_eq!(ast.len(), 2);
+    assert_eq!(ast[0].to_string(), "{ type: enum, name: Shape, variants: [Circle(i64) = 0, Rect(i32, i32) = 4, Empty = 5] }");
+    assert!(ast[1].to_string().contains("{ type: match, expr: s, cases: { { pattern: Shape.Circle(r), stmts: { "));
+    assert!(ast[1].to_string().contains("{ pattern: Shape.Rect(_, h), stmts: { "));
+    assert!(ast[1].to_string().contains("{ pattern: _, stmts: { "));
+
+    let source = "enum E:\n    A;\n    B(i32);\n\ndef f(n: i32) -> E:\n    ";
+    let ast = Parser::new(get_tokens(&format!("{}E.B(n + 1);", source))).parse_program().unwrap();
+    assert!(ast[1].to_string().ends_with("stmts: { { type: enum, name: E, variant: B, values: { { operator: Plus, left: n, right: (SignedInteger((1, i32)), SignedInteger(i32)) } } } }"));
+
+    assert_eq!(parse_program(&format!("{}return E.C;", source)).unwrap_err(), "[EnumExpression] `E` has no variant `C`");
+    assert_eq!(parse_program(&format!("{}return E.B;", source)).unwrap_err(), "[EnumExpression] `E.B` takes 1 values but 0 were given");
+    assert_eq!(parse_program(&format!("{}match n:\n        case E.A:\n            return E.A;", source)).unwrap_err(), "[MatchStatement] `E.B` is not covered");
+    assert_eq!(parse_program(&format!("{}match n:\n        case E.B(a, b):\n            return E.A;", source)).unwrap_err(), "[MatchStatement] `E.B` has 1 values but 2 are bound");
+    assert_eq!(parse_program(&format!("{}match n:\n        case _:\n            return E.A;\n        case E.A:\n            return E.A;", source)).unwrap_err(), "[MatchStatement] case `E.A` is unreachable");
+    assert_eq!(parse_program(&format!("{}match n:\n        case 1:\n            return E.A;\n        case E.A:\n            return E.A;", source)).unwrap_err(), "[MatchStatement] case `E.A` cannot match the same value as `1`");
+    assert_eq!(parse_program(&format!("{}match n:\n        case 1:\n            return E.A;\n        case -2:\n            return E.A;", source)).unwrap_err(), "[MatchStatement] integers are only covered with `case _`");
+    assert_eq!(parse_program("enum E:\n    A = 1;\n    B = 1;").unwrap_err(), "[EnumStatement] `B` has the same discriminant as `A`");
+    assert_eq!(parse_program("enum E:\n    A;\n    A;").unwrap_err(), "[EnumStatement] variant `A` is declared twice");
+}
+
+#[test]
+fn test_tuples() {
+    let ast = Parser::new(get_tokens("def f(a: i32) -> (i32, bool):\n    let (q, _): (i32, bool) = (a, True);\n    q.0;")).parse_program().unwrap();
+    assert!(ast[0].to_string().contains("{ type: let, names: (q, _), dataType: (i32, bool), expr: { type: tuple, elements: { a, (Boolean(true), bool) } } }"));
+    assert!(ast[0].to_string().ends_with("{ type: member, expr: q, field: 0 } }"));
+
+    assert_eq!(parse_program("def f() -> void:\n    let x: (i32) = 1;").unwrap_err(), "[Parser::parse_type] a tuple needs at least two elements");
+    assert_eq!(parse_program("def f() -> void:\n    let (a) = 1;").unwrap_err(), "[DestructureStatement] a tuple needs at least two elements");
+    assert_eq!(parse_program("def f() -> void:\n    let (a, a) = (1, 2);").unwrap_err(), "[DestructureStatement] `a` is bound twice");
+    assert_eq!(parse_program("def f() -> void:\n    let (a, b): (i32, i32, i32) = (1, 2, 3);").unwrap_err(), "[DestructureStatement] (i32, i32, i32) cannot be destructured into 2 names");
+}
+
+#[test]
+fn test_generics() {
+    let ast = Parser::new(get_tokens("def max[T: int, U](a: T, b: (T, U)) -> T:\n    let x: T = 0;\n    return a;")).parse_program().unwrap();
+    assert!(ast[0].to_string().starts_with("{ type: fnDef, name: max[T: int, U], returnType: T, args: "));
+    assert!(ast[0].to_string().contains("{ type: let, name: x, dataType: T, expr: (SignedInteger((0, i32)), SignedInteger(i32)) }"));
+
+    // the type parameters are only known inside their function
+    assert_eq!(parse_program("def f[T](a: T) -> T:\n    return a;\n\ndef g(a: T) -> void:\n    return;").unwrap_err(), "[DefStatement] expected keyword, found Identifier(\"T\")");
+
+    assert_eq!(parse_program("def f[T](a: i32) -> T:\n    return a;").unwrap_err(), "[DefStatement] type parameter `T` of `f` is not used by its parameters");
+    assert_eq!(parse_program("def f[T, T](a: T) -> T:\n    return a;").unwrap_err(), "[DefStatement] type parameter `T` is declared twice");
+    assert_eq!(parse_program("def f[T: float](a: T) -> T:\n    return a;").unwrap_err(), "[DefStatement] unknown constraint `float`, expected `int`, `signed` or `unsigned`");
+    assert_eq!(parse_program("struct T:\n    x: i32;\n\ndef f[T](a: T) -> T:\n    return a;").unwrap_err(), "[DefStatement] type parameter `T` has the name of a type");
+    assert_eq!(parse_program("struct P:\n    x: i32;\n    def m[T](self, a: T) -> T:\n        return a;").unwrap_err(), "[DefStatement] method `m` cannot take type parameters");
+}
+
+#[test]
+fn test_imports() {
+    let mut parser = Parser::new(get_tokens("import a.b;\nfrom geo import P, area;\n\ndef main() -> i32:\n    return 0;"));
+    let imports = parser.parse_imports().unwrap();
+
+    assert_eq!(imports.iter().map(|import| import.to_string()).collect::<Vec<String>>(), ["{ type: import, module: a.b }", "{ type: from, module: geo, names: [P, area] }"]);
+    assert_eq!(parser.parse_program().unwrap().len(), 1);
+
+    // a module names its types after itself, methods included
+    let ast = Parser::new(get_tokens("struct P:\n    x: i32;\n    def get(self) -> i32:\n        return self.x;")).with_module("geo").parse_program().unwrap();
+    assert_eq!(ast[0].to_string(), "{ type: struct, name: geo.P, fields: [(\"x\", SignedInteger(i32))] }");
+    assert!(ast[1].to_string().starts_with("{ type: fnDef, name: geo.P.get, "));
+
+    let (point, shape) = match (&ast[0], &Parser::new(get_tokens("enum S:\n    A(i32);\n    B;")).with_module("geo").parse_program().unwrap()[0]) {
+        (Statement::Struct(point), Statement::Enum(shape)) => (point.dtype, shape.dtype),
+        _ => unreachable!(),
+    };
+
+    let mut parser = Parser::new(get_tokens("def f(p: geo.P, s: geo.S) -> i32:\n    let q: geo.P = geo.P { x: 1 };\n    match geo.S.A(2):\n        case geo.S.A(n):\n            return n;\n        case _:\n            return q.x;"));
+    parser.import_type("geo.P".into(), point);
+    parser.import_type("geo.S".into(), shape);
+    let ast = parser.parse_program().unwrap();
+
+    assert!(ast[0].to_string().contains("{ type: let, name: q, dataType: %geo.P, expr: { type: struct, name: geo.P, fields: { x: (SignedInteger((1, i32)), SignedInteger(i32)) } } }"));
+    assert!(ast[0].to_string().contains("{ type: enum, name: geo.S, variant: A, values: { (SignedInteger((2, i32)), SignedInteger(i32)) } }"));
+    assert!(ast[0].to_string().contains("{ pattern: geo.S.A(n), "));
+
+    assert_eq!(parse_program("def main() -> i32:\n    return 0;\n\nimport geo;").unwrap_err(), "[Parser::parse_stmt] `import` and `from` must come before every other statement");
+    assert_eq!(Parser::new(get_tokens("from geo import P, P;")).parse_imports().unwrap_err().0, "[ImportStatement] `P` is imported twice");
+    assert_eq!(Parser::new(get_tokens("from geo;")).parse_imports().unwrap_err().0, "[ImportStatement] expected `import`, found Semicolon");
+}
+
+#[test]
+fn test_format_strings() {
+    let stmt = parse_program("def f(n: i32) -> str:\n    let s: str = f\"n = {n + 1:05} {{}}\";\n    return s;").unwrap();
+    assert!(stmt.contains("expr: { type: formatString, pieces: { \"n = \", { operator: Plus, left: n, right: (SignedInteger((1, i32)), SignedInteger(i32)) }:05, \" {}\" } }"));
+
+    assert_eq!(parse_program("def f(n: i32) -> str:\n    return f\"{n n}\";").unwrap_err(), "[PrattParser::parse_format_string] expected `}` after the expression, found Identifier(\"n\")");
 }
\ No newline at end of file
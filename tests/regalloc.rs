@@ -0,0 +1,323 @@
+use std::collections::{BTreeSet, HashMap};
+
+use mamba::codegen::regalloc::liveness::{analyze, intervals};
+use mamba::codegen::regalloc::record::{resolve, Access};
+use mamba::codegen::regalloc::{allocate, Allocation, Block, Function, Inst, InstKind, Location, PhysReg, Reg, TargetRegisters, VirtReg};
+use mamba::codegen::riscv::abi::REGISTERS as RISCV;
+use mamba::codegen::x86_64::abi::REGISTERS as X86_64;
+
+// four registers, the first two overwritten by calls; `r4` and `r5` are the scratch registers
+const TINY: TargetRegisters = TargetRegisters {
+    names: &["r0", "r1", "r2", "r3", "r4", "r5"],
+    allocatable: &[PhysReg(0), PhysReg(1), PhysReg(2), PhysReg(3)],
+    caller_saved: &[PhysReg(0), PhysReg(1), PhysReg(4), PhysReg(5)],
+};
+const SCRATCH: [PhysReg; 2] = [PhysReg(4), PhysReg(5)];
+
+fn v(n: u32) -> Reg {
+    Reg::Virt(VirtReg(n))
+}
+
+fn p(n: u8) -> Reg {
+    Reg::Phys(PhysReg(n))
+}
+
+fn block(insts: Vec<Inst>, succs: &[usize]) -> Block {
+    Block { insts, succs: succs.to_vec() }
+}
+
+// `const N` defines N, `dec` its use minus 1, `brnz` picks the first successor when its use is not 0, `ret` ends the
+// function with its use, and every other instruction and call defines the sum of its uses plus 1
+fn simulate(function: &Function, target: &TargetRegisters) -> u64 {
+    let mut regs: HashMap<Reg, u64> = HashMap::new();
+    let mut slots: HashMap<u32, u64> = HashMap::new();
+    let mut block = 0;
+
+    for _ in 0..10000 {
+        let mut next = function.blocks[block].succs.first().copied();
+
+        for inst in &function.blocks[block].insts {
+            let uses = inst.uses.iter().map(|reg| regs.get(reg).copied().unwrap_or_else(|| panic!("{} is read before it is written", reg))).collect::<Vec<u64>>();
+
+            let value = match &inst.kind {
+                InstKind::Op(name) if name.starts_with("const ") => name[6..].parse().unwrap(),
+                InstKind::Op(name) if name == "dec" => uses[0] - 1,
+                InstKind::Op(name) if name == "ret" => return uses[0],
+                InstKind::Op(name) if name == "brnz" => {
+                    next = Some(function.blocks[block].succs[(uses[0] == 0) as usize]);
+                    continue;
+                },
+                InstKind::Op(_) | InstKind::Call(_) => uses.iter().fold(1u64, |sum, value| sum.wrapping_add(*value)),
+                InstKind::Spill(slot) => {
+                    slots.insert(*slot, uses[0]);
+                    continue;
+                },
+                InstKind::Reload(slot) => slots[slot],
+            };
+
+            // a call leaves garbage in every caller-saved register
+            if inst.is_call() {
+                for reg in target.caller_saved {
+                    regs.insert(Reg::Phys(*reg), 0xdead);
+                }
+            }
+
+            for reg in &inst.defs {
+                regs.insert(*reg, value);
+            }
+        }
+
+        block = next.expect("the function falls off its last block");
+    }
+
+    panic!("the function does not terminate")
+}
+
+// no two virtual registers live at the same time share a location, and none sits in a
+// physical register the instructions use while it is live or a call overwrites
+fn check(function: &Function, allocation: &Allocation, target: &TargetRegisters) {
+    let intervals = intervals(function);
+    let overlaps = |a: (u32, u32), b: (u32, u32)| a.0 <= b.1 && b.0 <= a.1;
+
+    for (vreg, range) in &intervals.virtuals {
+        let location = allocation.locations[vreg];
+
+        for (other, other_range) in &intervals.virtuals {
+            if other != vreg && overlaps(*range, *other_range) {
+                assert_ne!(location, allocation.locations[other], "{:?} and {:?}", vreg, other);
+            }
+        }
+
+        if let Location::Reg(reg) = location {
+            assert!(target.allocatable.contains(&reg));
+            assert!(!intervals.fixed.get(&reg).is_some_and(|ranges| ranges.iter().any(|fixed| overlaps(*fixed, *range))));
+            assert!(!target.is_caller_saved(reg) || !intervals.calls.iter().any(|call| range.0 < *call && *call < range.1));
+        }
+    }
+}
+
+// defines `n` values and then adds all of them up, so every one of them is live at once
+fn pressure(n: u32) -> Function {
+    let mut insts = (0..n).map(|i| Inst::op(&format!("const {}", i + 1), &[v(i)], &[])).collect::<Vec<Inst>>();
+    let mut sum = v(0);
+
+    for i in 1..n {
+        insts.push(Inst::op("add", &[v(n + i)], &[sum, v(i)]));
+        sum = v(n + i);
+    }
+
+    insts.push(Inst::op("ret", &[], &[sum]));
+    Function { blocks: vec![block(insts, &[])] }
+}
+
+#[test]
+fn test_liveness() {
+    // %0 = 3; loop: %1 = %0 - 1; brnz %2 ... exit: ret %1
+    let function = Function {
+        blocks: vec![
+            block(vec![Inst::op("const 3", &[v(0)], &[])], &[1]),
+            block(vec![Inst::op("dec", &[v(1)], &[v(0)]), Inst::op("brnz", &[], &[v(2)])], &[1, 2]),
+            block(vec![Inst::op("ret", &[], &[v(1)])], &[]),
+        ],
+    };
+
+    let liveness = analyze(&function);
+    assert_eq!(liveness.live_in[1], BTreeSet::from([v(0), v(2)]));
+    assert_eq!(liveness.live_out[1], BTreeSet::from([v(0), v(1), v(2)]));
+    assert_eq!(liveness.live_in[2], BTreeSet::from([v(1)]));
+
+    // %0 is live around the back edge, up to the end of the loop
+    let intervals = intervals(&function);
+    assert_eq!(intervals.virtuals[&VirtReg(0)], (3, 11));
+    assert_eq!(intervals.virtuals[&VirtReg(1)], (7, 13));
+    // %2 is never written, so it is live from the entry
+    assert_eq!(intervals.virtuals[&VirtReg(2)], (0, 11));
+}
+
+#[test]
+fn test_reuse() {
+    // every value dies where the next one is defined, so one register does for all of them
+    let function = Function {
+        blocks: vec![block(vec![
+            Inst::op("const 1", &[v(0)], &[]),
+            Inst::op("add", &[v(1)], &[v(0)]),
+            Inst::op("add", &[v(2)], &[v(1)]),
+            Inst::op("ret", &[], &[v(2)]),
+        ], &[])],
+    };
+
+    let allocation = allocate(&function, &TINY);
+    assert!(allocation.locations.values().all(|location| *location == Location::Reg(PhysReg(0))));
+    assert_eq!((allocation.slots, allocation.callee_saved.clone()), (0, vec![]));
+    assert_eq!(simulate(&allocation.apply(&function, &SCRATCH).unwrap(), &TINY), 3);
+}
+
+#[test]
+fn test_spilling() {
+    // x86-64 has a single scratch register, where two-operand instructions would take one from memory
+    for (target, n, scratch) in [(&TINY, 6, &SCRATCH[..]), (&TINY, 30, &SCRATCH), (&RISCV, 40, &[PhysReg(5), PhysReg(6)]), (&X86_64, 40, &[])] {
+        let function = pressure(n);
+        let allocation = allocate(&function, target);
+        check(&function, &allocation, target);
+
+        let spilled = allocation.locations.values().filter(|location| matches!(location, Location::Stack(_))).count() as u32;
+        assert!(spilled >= n - target.allocatable.len() as u32, "{} values, {} spilled", n, spilled);
+        assert!(allocation.slots <= spilled);
+
+        if !scratch.is_empty() {
+            assert_eq!(simulate(&allocation.apply(&function, scratch).unwrap(), target), simulate(&function, target));
+        }
+    }
+}
+
+#[test]
+fn test_spill_choice() {
+    // the value used last goes to the stack, the short-lived ones keep their registers
+    let mut insts = vec![Inst::op("const 100", &[v(9)], &[])];
+
+    for i in 0..4 {
+        insts.push(Inst::op(&format!("const {}", i), &[v(i)], &[]));
+    }
+
+    insts.push(Inst::op("add", &[v(4)], &[v(0), v(1), v(2), v(3)]));
+    insts.push(Inst::op("add", &[v(5)], &[v(4), v(9)]));
+    insts.push(Inst::op("ret", &[], &[v(5)]));
+
+    let function = Function { blocks: vec![block(insts, &[])] };
+    let allocation = allocate(&function, &TINY);
+
+    assert_eq!(allocation.locations[&VirtReg(9)], Location::Stack(0));
+    assert!((0..6).all(|i| matches!(allocation.locations[&VirtReg(i)], Location::Reg(_))));
+    assert_eq!(allocation.slots, 1);
+
+    check(&function, &allocation, &TINY);
+    assert_eq!(simulate(&allocation.apply(&function, &SCRATCH).unwrap(), &TINY), simulate(&function, &TINY));
+}
+
+#[test]
+fn test_calls() {
+    // %0 and %1 live across the call, %2 is only its argument and %3 its result
+    let function = Function {
+        blocks: vec![block(vec![
+            Inst::op("const 1", &[v(0)], &[]),
+            Inst::op("const 2", &[v(1)], &[]),
+            Inst::op("const 3", &[v(2)], &[]),
+            Inst::call("f", &[v(3)], &[v(2)]),
+            Inst::op("add", &[v(4)], &[v(0), v(1), v(3)]),
+            Inst::op("ret", &[], &[v(4)]),
+        ], &[])],
+    };
+
+    let allocation = allocate(&function, &TINY);
+    check(&function, &allocation, &TINY);
+
+    assert_eq!(allocation.locations[&VirtReg(0)], Location::Reg(PhysReg(2)));
+    assert_eq!(allocation.locations[&VirtReg(1)], Location::Reg(PhysReg(3)));
+    assert_eq!(allocation.locations[&VirtReg(2)], Location::Reg(PhysReg(0)));
+    assert_eq!(allocation.locations[&VirtReg(3)], Location::Reg(PhysReg(0)));
+    assert_eq!(allocation.callee_saved, vec![PhysReg(2), PhysReg(3)]);
+    assert_eq!(simulate(&allocation.apply(&function, &SCRATCH).unwrap(), &TINY), 8);
+
+    // without callee-saved registers the values living across the call are spilled
+    const VOLATILE: TargetRegisters = TargetRegisters { caller_saved: &[PhysReg(0), PhysReg(1), PhysReg(2), PhysReg(3)], ..TINY };
+    let allocation = allocate(&function, &VOLATILE);
+    check(&function, &allocation, &VOLATILE);
+
+    assert!(matches!(allocation.locations[&VirtReg(0)], Location::Stack(_)));
+    assert!(matches!(allocation.locations[&VirtReg(1)], Location::Stack(_)));
+    assert!(allocation.callee_saved.is_empty());
+    assert_eq!(simulate(&allocation.apply(&function, &SCRATCH).unwrap(), &VOLATILE), 8);
+}
+
+#[test]
+fn test_fixed_registers() {
+    // the arguments are moved into `r0` and `r1` for the call, which %0 must stay out of
+    let function = Function {
+        blocks: vec![block(vec![
+            Inst::op("const 5", &[v(0)], &[]),
+            Inst::op("const 6", &[v(1)], &[]),
+            Inst::op("mov", &[p(0)], &[v(1)]),
+            Inst::op("mov", &[p(1)], &[v(1)]),
+            Inst::op("use", &[v(2)], &[p(0), p(1)]),
+            Inst::op("add", &[v(3)], &[v(0), v(2)]),
+            Inst::op("ret", &[], &[v(3)]),
+        ], &[])],
+    };
+
+    let allocation = allocate(&function, &TINY);
+    check(&function, &allocation, &TINY);
+
+    assert_eq!(allocation.locations[&VirtReg(0)], Location::Reg(PhysReg(2)));
+    // %1 is still read after `r0` is written, but dies where `r1` is
+    assert_eq!(allocation.locations[&VirtReg(1)], Location::Reg(PhysReg(1)));
+    assert_eq!(simulate(&allocation.apply(&function, &SCRATCH).unwrap(), &TINY), simulate(&function, &TINY));
+}
+
+#[test]
+fn test_loop() {
+    // a counter and an accumulator live around the back edge next to four values that do not
+    let function = Function {
+        blocks: vec![
+            block(vec![Inst::op("const 0", &[v(0)], &[]), Inst::op("const 9", &[v(1)], &[]), Inst::op("const 7", &[v(9)], &[])], &[1]),
+            block(vec![
+                Inst::op("const 1", &[v(2)], &[]),
+                Inst::op("const 2", &[v(3)], &[]),
+                Inst::op("const 3", &[v(4)], &[]),
+                Inst::op("add", &[v(5)], &[v(2), v(3), v(4)]),
+                Inst::op("add", &[v(0)], &[v(0), v(5)]),
+                Inst::op("dec", &[v(1)], &[v(1)]),
+                Inst::op("brnz", &[], &[v(1)]),
+            ], &[1, 2]),
+            block(vec![Inst::op("add", &[v(7)], &[v(0), v(9)]), Inst::op("ret", &[], &[v(7)])], &[]),
+        ],
+    };
+
+    let allocation = allocate(&function, &TINY);
+    check(&function, &allocation, &TINY);
+    assert!(allocation.slots >= 1);
+
+    let rewritten = allocation.apply(&function, &SCRATCH).unwrap();
+    assert!(rewritten.blocks.iter().flat_map(|block| &block.insts).all(|inst| inst.defs.iter().chain(&inst.uses).all(|reg| matches!(reg, Reg::Phys(_)))));
+    assert_eq!(simulate(&rewritten, &TINY), simulate(&function, &TINY));
+}
+
+#[test]
+fn test_apply() {
+    let function = pressure(6);
+    let allocation = allocate(&function, &TINY);
+    let rewritten = allocation.apply(&function, &SCRATCH).unwrap();
+
+    // spilled operands go through the scratch registers
+    let lines = rewritten.blocks[0].insts.iter().map(|inst| inst.to_string()).collect::<Vec<String>>();
+    assert!(lines.windows(2).any(|window| window[0].starts_with("$4 = const ") && window[1].starts_with("spill [")), "{}", lines.join("\n"));
+    assert!(lines.iter().any(|line| line.starts_with("$4 = reload [")));
+
+    assert_eq!(allocation.apply(&function, &[]).unwrap_err().0, "[Regalloc] %4 = const 5 needs more than 0 scratch registers");
+    assert_eq!(Allocation::default().apply(&function, &SCRATCH).unwrap_err().0, "[Regalloc] %0 has no location");
+}
+
+#[test]
+fn test_targets() {
+    for target in [&RISCV, &X86_64] {
+        // every allocatable register has a name, and the ones the code generators work in are left out
+        let reserved = ["rax", "rcx", "rdx", "rsi", "rdi", "r8", "r9", "rsp", "rbp", "zero", "ra", "sp", "t0", "t1", "t2", "t6", "s0", "a0", "a7"];
+        assert!(target.allocatable.iter().all(|reg| (reg.0 as usize) < target.names.len()));
+        assert!(!target.allocatable.iter().any(|reg| reserved.contains(&target.name(*reg))));
+    }
+
+    let callee_saved = |target: &TargetRegisters| target.allocatable.iter().filter(|reg| !target.is_caller_saved(**reg)).map(|reg| target.name(*reg)).collect::<Vec<&str>>();
+    assert_eq!(callee_saved(&X86_64), ["rbx", "r12", "r13", "r14", "r15"]);
+    assert_eq!(callee_saved(&RISCV), ["s1", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11"]);
+}
+
+#[test]
+fn test_resolve() {
+    let place = |code: &mut String, access: Access, vreg: VirtReg, reg: &str| code.push_str(&format!("{:?} {} {}\n", access, vreg.0, reg));
+
+    assert_eq!(resolve("    movq $1, %rax\n    @def 0 rax\n    @use 0 rdi\n", place).unwrap(), "    movq $1, %rax\nDef 0 rax\nUse 0 rdi\n");
+
+    // a marker the recorder does not write is not taken for a use
+    assert_eq!(resolve("    @spill 0 rax", place).unwrap_err().0, "[Regalloc] unknown marker `@spill`");
+    assert_eq!(resolve("    @def x rax", place).unwrap_err().0, "[Regalloc] expected virtual register, found `x`");
+    assert_eq!(resolve("    @use 0", place).unwrap_err().0, "[Regalloc] malformed marker `@use 0`");
+}
\ No newline at end of file
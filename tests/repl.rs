@@ -0,0 +1,95 @@
+use std::env;
+use std::fs;
+
+use mamba::codegen::target::Target;
+use mamba::driver::repl::Repl;
+
+fn eval(repl: &mut Repl, input: &str) -> Result<String, String> {
+    repl.eval(input).map_err(|err| err.to_string())
+}
+
+#[test]
+fn test_is_complete() {
+    assert!(Repl::is_complete("let a: i32 = 1;\n"));
+    assert!(Repl::is_complete(":ir\n"));
+    assert!(!Repl::is_complete("def f() -> i32:\n"));
+    assert!(!Repl::is_complete("def f() -> i32:\n    return 1;\n"));
+    assert!(!Repl::is_complete("def f(n: i32) -> i32:\n    if n > 0:\n        return 1;\n    return 0;\n"));
+    assert!(Repl::is_complete("def f() -> i32:\n    return 1;\n\n"));
+    assert!(Repl::is_complete("def f() -> i32:\n\n"));
+}
+
+#[test]
+fn test_persistent_declarations() {
+    let mut repl = Repl::new(Target::from_name("x86_64").unwrap());
+
+    assert_eq!(eval(&mut repl, "let n: i32 = 2;\n"), Ok("defined `n`\n".into()));
+    assert_eq!(eval(&mut repl, "def twice(x: i32) -> i32:\n    return x * n;\n\n"), Ok("defined `twice`\n".into()));
+    assert_eq!(eval(&mut repl, "def f() -> i32:\n    return twice(21);\n\n"), Ok("defined `f`\n".into()));
+    assert_eq!(eval(&mut repl, "def twice(x: i32) -> i32:\n    return x + x;\n\n"), Ok("redefined `twice`\n".into()));
+
+    let ir = eval(&mut repl, ":ir").unwrap();
+    assert!(ir.contains("@n = global i32 2, align 4\n"));
+    assert!(ir.contains("define i32 @twice(i32 %x) {\n"));
+    assert!(ir.contains("define i32 @f() {\n"));
+    assert!(!ir.contains("mul"));
+
+    assert_eq!(eval(&mut repl, ":reset"), Ok("forgot every declaration\n".into()));
+    assert!(!eval(&mut repl, ":ir").unwrap().contains("define"));
+}
+
+#[test]
+fn test_rejected_input_is_forgotten() {
+    let mut repl = Repl::new(Target::from_name("x86_64").unwrap());
+
+    assert_eq!(eval(&mut repl, "def f() -> i32:\n    return g();\n\n"), Err("IR Generator Error: [IRGen] unable to find function `g`".into()));
+    assert!(eval(&mut repl, "return 1;").unwrap_err().starts_with("only declarations and expressions can be entered at the top level"));
+    assert_eq!(eval(&mut repl, ":ast"), Ok(String::new()));
+    assert_eq!(eval(&mut repl, ":fly"), Err("usage error: unknown command `:fly`, see `:help`".into()));
+}
+
+#[test]
+fn test_commands() {
+    let mut repl = Repl::new(Target::from_name("x86_64").unwrap());
+
+    assert_eq!(eval(&mut repl, ":tokens let a: i32 = 1;").unwrap().lines().take(2).collect::<Vec<&str>>(), vec!["1:1 Keyword(Let)", "1:5 Identifier(\"a\")"]);
+    assert_eq!(eval(&mut repl, ":ast return 1;"), Ok("{ type: return, expr: Literal((SignedInteger((1, i32)), SignedInteger(i32))) }\n".into()));
+
+    let path = env::temp_dir().join(format!("mamba-repl-{}.mamba", std::process::id()));
+    fs::write(&path, "extern puts(text: str) -> i32;\n\ndef hello() -> i32:\n    return puts(\"hello\");").unwrap();
+
+    assert_eq!(eval(&mut repl, &format!(":load {}", path.display())), Ok("defined `puts`\ndefined `hello`\n".into()));
+    assert!(eval(&mut repl, ":ir").unwrap().contains("declare i32 @puts(ptr)"));
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_evaluate_expressions() {
+    let mut repl = Repl::new(Target::from_name("x86_64").unwrap());
+
+    assert_eq!(eval(&mut repl, "extern puts(text: str) -> i32;\nlet n: u8 = 250;"), Ok("defined `puts`\ndefined `n`\n".into()));
+    assert_eq!(eval(&mut repl, "def add(a: u8, b: u8) -> u8:\n    return a + b;\n\n"), Ok("defined `add`\n".into()));
+    assert_eq!(eval(&mut repl, "add(n, 10);"), Ok("4: u8\n".into()));
+    assert_eq!(eval(&mut repl, "puts(\"hi\"); 3 < 4;"), Ok("hi\n3: i32\nTrue: bool\n".into()));
+    assert_eq!(eval(&mut repl, "1 / 0;"), Err("runtime error: [Interpreter] attempt to divide by zero".into()));
+}
+
+#[test]
+fn test_prelude_and_imports() {
+    let mut repl = Repl::new(Target::from_name("x86_64").unwrap());
+
+    assert_eq!(eval(&mut repl, "println(\"x\"); print_int(-7);"), Ok("x\n-7".into()));
+
+    // like in a program, a declaration of the same name takes precedence over the prelude
+    assert_eq!(eval(&mut repl, "def println(n: i32) -> i32:\n    return n + 1;\n\n"), Ok("defined `println`\n".into()));
+    assert_eq!(eval(&mut repl, "println(41);"), Ok("42: i32\n".into()));
+    assert!(eval(&mut repl, ":ir").unwrap().contains("define i32 @println(i32 %n) {\n"));
+
+    assert_eq!(eval(&mut repl, "import std;\nfrom std import print_bool;"), Ok(String::new()));
+    assert_eq!(eval(&mut repl, "from std import print_bool;\nstd.print_int(3); print_bool(True);"), Ok("3True".into()));
+
+    let missing = eval(&mut repl, "import nowhere;\n1;").unwrap_err();
+    assert!(missing.contains("[Module] cannot find module `nowhere`"), "{}", missing);
+    assert_eq!(eval(&mut repl, "2;"), Ok("2: i32\n".into()));
+}
\ No newline at end of file
@@ -0,0 +1,204 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use mamba::codegen::llvm::entry::Entry;
+use mamba::codegen::riscv::abi::{assign, ArgLoc};
+use mamba::codegen::riscv::RiscvGen;
+use mamba::fold::ConstFolder;
+use mamba::lexer::Lexer;
+use mamba::module::Loader;
+use mamba::parser::{Parser, AST};
+use mamba::types::{DataType, SignedInteger, UnsignedInteger};
+
+fn generate_asm(source: &str) -> Result<String, String> {
+    let mut lexer = Lexer::new(source.into());
+    let tokens = lexer.get_tokens().unwrap();
+
+    let mut ast = Parser::with_spans(tokens, lexer.get_spans()).parse_all();
+    ConstFolder::new().fold(&mut ast).unwrap();
+
+    RiscvGen::new(ast).with_entry(Entry::C).generate_asm().map_err(|err| err.0)
+}
+
+// a corpus program, linked with the parts of `std` it uses like the driver links it
+fn load(path: &Path) -> AST {
+    let mut lexer = Lexer::new(fs::read_to_string(path).unwrap());
+    let tokens = lexer.get_tokens().unwrap();
+
+    let mut parser = Parser::with_spans(tokens, lexer.get_spans());
+    let mut loader = Loader::new(path);
+    let imports = parser.parse_imports().unwrap();
+    loader.load(imports, &mut parser).unwrap();
+
+    let mut ast = loader.link(parser.parse_program().unwrap()).unwrap();
+    ConstFolder::new().fold(&mut ast).unwrap();
+    ast
+}
+
+// the lines of the function `name`, without its label and directives
+fn function<'a>(asm: &'a str, name: &str) -> Vec<&'a str> {
+    asm.lines()
+        .skip_while(|line| *line != format!("{}:", name))
+        .skip(1)
+        .take_while(|line| !line.starts_with("    .size"))
+        .map(|line| line.trim())
+        .collect()
+}
+
+#[test]
+fn test_function() {
+    let asm = generate_asm("def add(a: i32, b: i32) -> i32:\n    return a + b;\n\ndef main() -> void:\n    add(1, 2);").unwrap();
+
+    assert_eq!(function(&asm, "add"), vec![
+        "addi sp, sp, -16",
+        "sd ra, 8(sp)",
+        "sd s0, 0(sp)",
+        "addi s0, sp, 16",
+        "mv t3, a0",
+        "mv t4, a1",
+        "mv a0, t3",
+        "mv t3, a0",
+        "mv a0, t4",
+        "mv a2, a0",
+        "mv a0, t3",
+        "addw a0, a0, a2",
+        "j .L0",
+        "unimp",
+        ".L0:",
+        "ld ra, -8(s0)",
+        "mv t0, s0",
+        "ld s0, -16(s0)",
+        "mv sp, t0",
+        "ret",
+    ]);
+
+    // the C entry point calls the renamed `main` and exits with 0 when it returns nothing
+    assert_eq!(function(&asm, "main"), vec!["addi sp, sp, -16", "sd ra, 8(sp)", "call mamba.main", "li a0, 0", "ld ra, 8(sp)", "addi sp, sp, 16", "ret"]);
+    assert!(function(&asm, "mamba.main").contains(&"call add"));
+}
+
+// every width is kept extended in its register the way the ABI passes it
+#[test]
+fn test_integer_lowering() {
+    let cases = [
+        ("i8", "a + b", vec!["add a0, a0, a2", "slli a0, a0, 56", "srai a0, a0, 56"]),
+        ("u8", "a * b", vec!["mul a0, a0, a2", "andi a0, a0, 255"]),
+        ("u16", "a << b", vec!["sll a0, a0, a2", "slli a0, a0, 48", "srli a0, a0, 48"]),
+        ("i32", "a >> b", vec!["sraw a0, a0, a2"]),
+        ("u32", "a / b", vec!["divuw a0, a0, a2"]),
+        ("i64", "a % b", vec!["rem a0, a0, a2"]),
+        ("u64", "a >> b", vec!["srl a0, a0, a2"]),
+        ("i128", "a + b", vec!["add a0, a0, a2", "sltu t0, a0, a2", "add a1, a1, a3", "add a1, a1, t0"]),
+        ("u128", "a / b", vec!["call __udivti3"]),
+        ("i128", "a >> b", vec!["call __ashrti3"]),
+    ];
+
+    for (dtype, expr, expected) in cases {
+        let asm = generate_asm(&format!("def f(a: {dtype}, b: {dtype}) -> {dtype}:\n    return {expr};\n\ndef main() -> i32:\n    return 0;")).unwrap();
+        let lines = function(&asm, "f");
+
+        assert!(lines.windows(expected.len()).any(|window| window == expected), "{dtype}: {expr}:\n{}", lines.join("\n"));
+    }
+
+    let asm = generate_asm("def f(a: u32, b: u32) -> bool:\n    return a < b;\n\ndef g(a: i8) -> u64:\n    return a;\n\ndef h(a: u32) -> i128:\n    return a;\n\ndef main() -> i32:\n    return 0;").unwrap();
+
+    assert!(function(&asm, "f").contains(&"sltu a0, a0, a2"));
+    // an `i8` is already sign-extended to 64 bits
+    assert!(!function(&asm, "g").iter().any(|line| line.starts_with("slli")));
+    assert!(function(&asm, "h").windows(3).any(|window| window == ["slli a0, a0, 32", "srli a0, a0, 32", "li a1, 0"]));
+}
+
+#[test]
+fn test_calling_convention() {
+    let i64 = DataType::SignedInteger(SignedInteger::i64);
+    let u128 = DataType::UnsignedInteger(UnsignedInteger::u128);
+
+    assert_eq!(assign(&[i64, u128, i64]), (vec![ArgLoc::Reg(0), ArgLoc::Pair(1), ArgLoc::Reg(3)], 0));
+    assert_eq!(assign(&[i64; 7].iter().copied().chain([u128, i64]).collect::<Vec<DataType>>()), (
+        vec![ArgLoc::Reg(0), ArgLoc::Reg(1), ArgLoc::Reg(2), ArgLoc::Reg(3), ArgLoc::Reg(4), ArgLoc::Reg(5), ArgLoc::Reg(6), ArgLoc::Split(0), ArgLoc::Stack(8)],
+        16,
+    ));
+    assert_eq!(assign(&[i64; 9].iter().copied().chain([u128]).collect::<Vec<DataType>>()).1, 32);
+
+    // the ninth argument goes onto the stack, where the callee finds it above its frame pointer
+    let params = (0..9).map(|n| format!("p{n}: i64")).collect::<Vec<String>>().join(", ");
+    let asm = generate_asm(&format!("def f({params}) -> i64:\n    return p8;\n\ndef main() -> i32:\n    return f(1, 2, 3, 4, 5, 6, 7, 8, 9);")).unwrap();
+
+    assert!(function(&asm, "f").windows(2).any(|window| window == ["ld a0, 0(s0)", "mv t3, a0"]));
+    // the arguments are kept in registers until the last one is stored into a 16-byte area
+    let main = function(&asm, "mamba.main");
+    assert!(main.windows(4).any(|window| window == ["addi sp, sp, -16", "mv t0, s6", "sd t0, 0(sp)", "mv a0, t3"]));
+    assert!(main.windows(3).any(|window| window == ["mv a7, s5", "call f", "addi sp, sp, 16"]));
+}
+
+// values are kept in registers, those living across a call in callee-saved ones
+#[test]
+fn test_register_allocation() {
+    let asm = generate_asm("def g(a: i64) -> i64:\n    return a * 2;\n\ndef f(n: i64) -> i64:\n    let a: i64 = g(n);\n    let b: i64 = g(a);\n    return a + b;\n\ndef main() -> i32:\n    return 0;").unwrap();
+    let lines = function(&asm, "f");
+
+    assert!(lines.windows(3).any(|window| window == ["call g", "mv s1, a0", "mv a0, s1"]));
+    assert!(lines.windows(2).any(|window| window == ["call g", "mv t3, a0"]));
+    assert_eq!(lines.iter().filter(|line| line.contains("(s0)") && !line.contains("ra,") && !line.contains("s0,")).collect::<Vec<_>>(), [&"sd s1, -24(s0)", &"ld s1, -24(s0)"]);
+
+    // with more values living across a call than callee-saved registers, the rest are spilled into the frame
+    let lets = (0..13).map(|n| format!("    let v{n}: i64 = n + {n};\n")).collect::<String>();
+    let sum = (0..13).map(|n| format!("v{n}")).collect::<Vec<String>>().join(" + ");
+    let asm = generate_asm(&format!("def g(a: i64) -> i64:\n    return a;\n\ndef f(n: i64) -> i64:\n{lets}    g(n);\n    return {sum};\n\ndef main() -> i32:\n    return 0;")).unwrap();
+    let lines = function(&asm, "f");
+
+    assert!(lines.contains(&"sd a0, -24(s0)"));
+    assert!(lines.contains(&"ld a0, -24(s0)"));
+}
+
+#[test]
+fn test_data() {
+    let asm = generate_asm("let a: i16 = -2;\nlet b: u128 = 1;\nlet c: bool = True;\nlet d: str = \"a\\b\";\n\ndef main() -> i32:\n    return 0;").unwrap();
+
+    assert!(asm.contains("\n    .data\n    .globl a\n    .p2align 1\na:\n    .half 65534\n"));
+    assert!(asm.contains("\nb:\n    .dword 1\n    .dword 0\n"));
+    assert!(asm.contains("\nc:\n    .byte 1\n"));
+    assert!(asm.contains("\n    .section .rodata\n    .globl d\nd:\n    .asciz \"a\\\\b\"\n"));
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(generate_asm("def main() -> i32:\n    return g();"), Err("[RISC-V] unable to find function `g`".into()));
+    assert_eq!(generate_asm("def main() -> i32:\n    return x;"), Err("[RISC-V] identifier `x` not found".into()));
+    assert_eq!(generate_asm("def f(a: str) -> i32:\n    return -a;\n\ndef main() -> i32:\n    return 0;"), Err("[RISC-V] operator UnaryMinus cannot be applied to str".into()));
+    assert_eq!(generate_asm("def main() -> i32:\n    if 1:\n        return 1;\n    return 0;"), Err("[RISC-V] expected a bool condition, found i32".into()));
+    assert_eq!(generate_asm("def f(a: str) -> i32:\n    return 0;\n\ndef main() -> i32:\n    return f(1);"), Err("[RISC-V] `f` expects str, found i32".into()));
+    assert_eq!(generate_asm("def f() -> i32:\n    return 0;"), Err("[Entry] the program has no `main` function".into()));
+}
+
+// the corpus must at least assemble, but for what the backend leaves to LLVM; `llvm-mc` stands in for a RISC-V assembler when there is none
+#[test]
+fn test_corpus_assembles() {
+    if Command::new("llvm-mc").arg("--version").output().is_err() {
+        eprintln!("skipping: llvm-mc is not installed");
+        return;
+    }
+
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    let object = env::temp_dir().join(format!("mamba-riscv-{}.o", std::process::id()));
+
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let asm = match RiscvGen::new(load(&path)).with_entry(Entry::C).generate_asm().map_err(|err| err.0) {
+            Ok(asm) => asm,
+            Err(err) if err.ends_with("use `--backend llvm`") => continue,
+            Err(err) => panic!("{}: {}", path.display(), err),
+        };
+        let source = object.with_extension("s");
+        fs::write(&source, asm).unwrap();
+
+        let output = Command::new("llvm-mc").args(["-triple=riscv64", "-mattr=+m", "-filetype=obj", "-o"]).arg(&object).arg(&source).output().unwrap();
+        assert!(output.status.success(), "{}:\n{}", path.display(), String::from_utf8_lossy(&output.stderr));
+
+        fs::remove_file(source).unwrap();
+    }
+
+    let _ = fs::remove_file(object);
+}
\ No newline at end of file
@@ -0,0 +1,189 @@
+use mamba::error::RuntimeError;
+use mamba::fold::ConstFolder;
+use mamba::interp::{Interpreter, Value};
+use mamba::lexer::Lexer;
+use mamba::parser::{Operator, Parser, AST};
+use mamba::types::{DataType, SignedInteger, UnsignedInteger};
+use mamba::vm::{disassemble, Compiler, Instr, Program, Vm};
+
+fn parse(source: &str) -> AST {
+    let mut lexer = Lexer::new(source.into());
+    let tokens = lexer.get_tokens().unwrap();
+
+    let mut ast = Parser::with_spans(tokens, lexer.get_spans()).parse_all();
+    ConstFolder::new().fold(&mut ast).unwrap();
+    ast
+}
+
+fn compile(source: &str) -> Result<Program, String> {
+    Compiler::new().compile(&parse(source)).map_err(|err| err.0)
+}
+
+// runs `source` on the VM and returns its exit code and everything it printed
+fn run(source: &str) -> Result<(i32, String), String> {
+    let program = compile(source)?;
+
+    let mut vm = Vm::new().capture_output();
+    let code = vm.run(&program).map_err(|err| err.0)?;
+
+    Ok((code, vm.take_output()))
+}
+
+#[test]
+fn test_run() {
+    let source = "extern puts(text: str) -> i32;\n\ndef fibo(n: i32) -> i32:\n    if n <= 1:\n        return n;\n    else:\n        return fibo(n - 2) + fibo(n - 1);\n\ndef main() -> i32:\n    puts(\"fibo\");\n    return fibo(15);";
+    assert_eq!(run(source), Ok((610, "fibo\n".into())));
+
+    let source = "extern putchar(c: i32) -> i32;\nlet base: u8 = 60;\n\ndef main() -> void:\n    let c: i32 = base + 5;\n    if c > 70:\n        putchar(33);\n    elif c == 65:\n        putchar(c);\n    else:\n        putchar(63);\n    putchar(10);";
+    assert_eq!(run(source), Ok((0, "A\n".into())));
+
+    let source = "def count(n: i32) -> i32:\n    while n > 0:\n        return count(n - 1) + 1;\n    return 0;\n\ndef main() -> i32:\n    return count(5000) - 4958;";
+    assert_eq!(run(source), Ok((42, String::new())));
+
+    // a variable whose address is taken is kept in a cell, which the host function writes through
+    let source = "extern open_memstream(buffer: **u8, size: *u64) -> *void;\nextern fputc(c: i32, stream: *void) -> i32;\nextern fclose(stream: *void) -> i32;\nextern puts(text: str) -> i32;\n\ndef collect(size: u64) -> u64:\n    let text: *u8 = null;\n    let stream: *void = open_memstream(&text, &size);\n    fputc(104, stream);\n    fputc(105, stream);\n    fclose(stream);\n    puts(text);\n    return size;\n\ndef main() -> i32:\n    return collect(7);";
+    assert_eq!(run(source), Ok((2, "hi\n".into())));
+}
+
+// the VM shares the interpreter's arithmetic, so both agree on every typed operation
+#[test]
+fn test_matches_interpreter() {
+    let cases = [
+        ("i8", "100", "100", "a + b"),
+        ("i8", "0", "1", "~a - b"),
+        ("i16", "300", "300", "a * b"),
+        ("i32", "7", "2", "-a % b"),
+        ("i64", "1", "62", "(a << b) >> 61"),
+        ("u8", "250", "10", "a + b"),
+        ("u16", "3", "5", "a - b"),
+        ("u32", "0", "0", "~a >> 28"),
+        ("u128", "0", "1", "a - b"),
+        ("i8", "-128", "-1", "a / b"),
+        ("u8", "1", "0", "a % b"),
+        ("i32", "1", "32", "a << b"),
+    ];
+
+    for (dtype, a, b, expr) in cases {
+        let source = format!("def f(a: {dtype}, b: {dtype}) -> {dtype}:\n    return {expr};");
+        let ast = parse(&source);
+        let args = || vec![Value::Signed(a.parse().unwrap(), SignedInteger::i128), Value::Signed(b.parse().unwrap(), SignedInteger::i128)];
+
+        let mut interp = Interpreter::new();
+        interp.load(&ast).unwrap();
+        let expected = interp.call("f", args()).map_err(|err| err.0);
+
+        let program = Compiler::new().compile(&ast).unwrap();
+        let mut vm = Vm::new();
+        vm.load(&program).unwrap();
+
+        assert_eq!(vm.call(&program, "f", args()).map_err(|err| err.0), expected, "{dtype}: {expr} with a = {a}, b = {b}");
+    }
+}
+
+#[test]
+fn test_compile_errors() {
+    assert_eq!(compile("def f() -> i32:\n    return g();"), Err("[Bytecode] unable to find function `g`".into()));
+    assert_eq!(compile("def f() -> i32:\n    return x;"), Err("[Bytecode] identifier `x` not found".into()));
+    assert_eq!(compile("def f(a: bool) -> i32:\n    return a + 1;"), Err("[Bytecode] operator Plus cannot be applied to bool and i32".into()));
+    assert_eq!(compile("def f(a: u8) -> u8:\n    return -a;"), Err("[Bytecode] operator UnaryMinus cannot be applied to u8".into()));
+    assert_eq!(compile("def f(a: i32) -> i32:\n    if a:\n        return 1;\n    return 0;"), Err("[Bytecode] expected a bool condition, found i32".into()));
+    assert_eq!(compile("def f(a: str) -> i32:\n    return a;"), Err("[Bytecode] `f` expects i32, found str".into()));
+    assert_eq!(compile("def f(a: i32) -> i32:\n    return f(1, 2);"), Err("[Bytecode] `f` takes 1 arguments but 2 were given".into()));
+    assert_eq!(compile("def f(p: *i32) -> i32:\n    return *p;"), Err("[Bytecode] operator Dereference is not supported".into()));
+    assert_eq!(compile("let g: i32 = 1;\n\ndef f() -> *i32:\n    return &g;"), Err("[Bytecode] taking the address of global `g` is not supported".into()));
+    assert_eq!(compile("def f(p: *i32) -> *u8:\n    return p;"), Err("[Bytecode] `f` expects *u8, found *i32".into()));
+}
+
+#[test]
+fn test_runtime_errors() {
+    assert_eq!(run("def f() -> i32:\n    return 1;"), Err("[Entry] the program has no `main` function".into()));
+    assert_eq!(run("extern abort() -> void;\n\ndef main() -> i32:\n    abort();\n    return 0;"), Err("[VM] extern `abort` has no host implementation".into()));
+    assert_eq!(run("def f(n: i32) -> i32:\n    return f(n + 1);\n\ndef main() -> i32:\n    return f(0);"), Err("[VM] stack overflow while calling `f`".into()));
+    assert_eq!(run("def f(n: i32) -> i32:\n    if n > 0:\n        return n;\n\ndef main() -> i32:\n    return f(0);"), Err("[VM] `f` reached its end without returning a value".into()));
+    assert_eq!(run("def main() -> i32:\n    let zero: i32 = 0;\n    return 1 / zero;"), Err("[Interpreter] attempt to divide by zero".into()));
+}
+
+#[test]
+fn test_host_function() {
+    fn twice(_: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+        match args {
+            [Value::Signed(n, dtype)] => Ok(Value::Signed(n * 2, *dtype)),
+            _ => Err(RuntimeError("twice expects an integer".into())),
+        }
+    }
+
+    let program = compile("extern twice(n: i64) -> i64;\n\ndef main() -> i32:\n    return twice(21);").unwrap();
+
+    assert_eq!(Vm::new().with_host_function("twice", twice).run(&program).map_err(|err| err.0), Ok(42));
+}
+
+#[test]
+fn test_disassemble() {
+    let program = compile("let n: u8 = 2;\n\ndef f(a: i32, b: u8) -> u8:\n    return a * b;").unwrap();
+
+    assert_eq!(program.functions[0].code, vec![
+        Instr::LoadLocal(0),
+        Instr::Cast(DataType::UnsignedInteger(UnsignedInteger::u8)),
+        Instr::LoadLocal(1),
+        Instr::Infix(Operator::Multiply, DataType::UnsignedInteger(UnsignedInteger::u8)),
+        Instr::Return,
+        Instr::MissingReturn,
+    ]);
+
+    assert_eq!(disassemble(&program), "\
+const 0: 2: u8
+global 0: n: u8
+
+<init>() -> void, 0 locals:
+    0000  const 0                 ; 2
+    0001  store_global 0          ; n
+    0002  return_void
+
+f(i32, u8) -> u8, 2 locals:
+    0000  load_local 0
+    0001  cast u8
+    0002  load_local 1
+    0003  infix Multiply u8
+    0004  return
+    0005  missing_return
+");
+}
+
+#[test]
+fn test_mbc_round_trip() {
+    let source = "extern puts(text: str) -> i32;\nextern fflush(stream: *void) -> i32;\nlet big: i16 = -1234;\nlet flag: bool = True;\n\ndef main() -> i32:\n    if flag:\n        puts(\"round trip\");\n    fflush(null);\n    return 3;";
+    let program = compile(source).unwrap();
+    let bytes = program.encode();
+
+    assert_eq!(&bytes[..4], b"MBC\x01");
+    assert_eq!(Program::decode(&bytes).map_err(|err| err.0), Ok(program.clone()));
+
+    let mut vm = Vm::new().capture_output();
+    assert_eq!(vm.run(&Program::decode(&bytes).unwrap()).map_err(|err| err.0), Ok(3));
+    assert_eq!(vm.take_output(), "round trip\n");
+
+    assert_eq!(Program::decode(b"ELF\x01").map_err(|err| err.0), Err("[Bytecode] not a mamba bytecode file".into()));
+    assert_eq!(Program::decode(b"MBC\x09").map_err(|err| err.0), Err("[Bytecode] unsupported bytecode version 9".into()));
+    assert_eq!(Program::decode(&bytes[..bytes.len() - 1]).map_err(|err| err.0), Err("[Bytecode] unexpected end of file".into()));
+    assert_eq!(Program::decode(&[&bytes[..], &[0]].concat()).map_err(|err| err.0), Err("[Bytecode] 1 trailing bytes".into()));
+
+    // a parameter whose address is taken moves to a cell, which it is then read through
+    let boxed = compile("def f(n: i32) -> i32:\n    let p: *i32 = &n;\n    return n;").unwrap();
+    assert_eq!(boxed.functions[0].code, vec![
+        Instr::LoadLocal(0),
+        Instr::Box,
+        Instr::StoreLocal(0),
+        Instr::LoadLocal(0),
+        Instr::StoreLocal(1),
+        Instr::LoadLocal(0),
+        Instr::Load,
+        Instr::Return,
+        Instr::MissingReturn,
+    ]);
+    assert!(disassemble(&boxed).contains("\n    0001  box\n"));
+    assert_eq!(Program::decode(&boxed.encode()).map_err(|err| err.0), Ok(boxed));
+
+    let mut broken = program;
+    broken.functions[0].code[0] = Instr::Jump(99);
+    assert_eq!(Program::decode(&broken.encode()).map_err(|err| err.0), Err("[Bytecode] instruction 0 of `main` refers to missing instruction 99".into()));
+}
\ No newline at end of file
@@ -0,0 +1,245 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use mamba::codegen::llvm::entry::Entry;
+use mamba::codegen::wasm::WasmGen;
+use mamba::fold::ConstFolder;
+use mamba::lexer::Lexer;
+use mamba::module::Loader;
+use mamba::parser::{Parser, AST};
+
+fn generate_wat_with(source: &str, entry: Entry) -> Result<String, String> {
+    let mut lexer = Lexer::new(source.into());
+    let tokens = lexer.get_tokens().unwrap();
+
+    let mut ast = Parser::with_spans(tokens, lexer.get_spans()).parse_all();
+    ConstFolder::new().fold(&mut ast).unwrap();
+
+    WasmGen::new(ast).with_entry(entry).generate_wat().map_err(|err| err.0)
+}
+
+fn generate_wat(source: &str) -> Result<String, String> {
+    generate_wat_with(source, Entry::C)
+}
+
+// a corpus program, linked with the parts of `std` it uses like the driver links it
+fn load(path: &Path) -> AST {
+    let mut lexer = Lexer::new(fs::read_to_string(path).unwrap());
+    let tokens = lexer.get_tokens().unwrap();
+
+    let mut parser = Parser::with_spans(tokens, lexer.get_spans());
+    let mut loader = Loader::new(path);
+    let imports = parser.parse_imports().unwrap();
+    loader.load(imports, &mut parser).unwrap();
+
+    let mut ast = loader.link(parser.parse_program().unwrap()).unwrap();
+    ConstFolder::new().fold(&mut ast).unwrap();
+    ast
+}
+
+// the instructions of the function `name`, without its header and local declarations
+fn function<'a>(wat: &'a str, name: &str) -> Vec<&'a str> {
+    wat.lines()
+        .skip_while(|line| !line.starts_with(&format!("  (func ${} ", name)))
+        .skip(1)
+        .take_while(|line| *line != "  )")
+        .map(|line| line.trim())
+        .filter(|line| !line.starts_with("(local "))
+        .collect()
+}
+
+#[test]
+fn test_function() {
+    let wat = generate_wat("def add(a: i32, b: i64) -> i64:\n    let c: i64 = a;\n    return b + c;\n\ndef main() -> void:\n    add(1, 2);").unwrap();
+
+    assert!(wat.contains("\n  (func $add (export \"add\") (param $a i32) (param $b i64) (result i64)\n    (local $c i64)\n"));
+    assert_eq!(function(&wat, "add"), vec![
+        "local.get $a",
+        "i64.extend_i32_s",
+        "local.set $c",
+        "local.get $b",
+        "local.get $c",
+        "i64.add",
+        "return",
+        "unreachable",
+    ]);
+
+    // a void function has no result and drops what it does not use
+    assert!(wat.contains("\n  (func $main (export \"main\")\n"));
+    assert_eq!(function(&wat, "main"), vec!["i32.const 1", "i32.const 2", "i64.extend_i32_s", "call $add", "drop"]);
+}
+
+// narrow integers are brought back to their width after every operation that can leave it
+#[test]
+fn test_integer_lowering() {
+    let cases = [
+        ("i8", "a + b", vec!["i32.add", "i32.extend8_s"]),
+        ("u8", "a * b", vec!["i32.mul", "i32.const 255", "i32.and"]),
+        ("i16", "a << b", vec!["i32.shl", "i32.extend16_s"]),
+        ("u16", "a >> b", vec!["i32.shr_u", "i32.const 65535", "i32.and"]),
+        ("i32", "a / b", vec!["i32.div_s", "return"]),
+        ("u32", "a % b", vec!["i32.rem_u", "return"]),
+        ("i64", "a >> b", vec!["i64.shr_s", "return"]),
+        ("u64", "a < b", vec!["i64.lt_u", "return"]),
+    ];
+
+    for (dtype, expr, expected) in cases {
+        let r#type = if expr.contains('<') && !expr.contains("<<") { "bool" } else { dtype };
+        let wat = generate_wat(&format!("def f(a: {dtype}, b: {dtype}) -> {type}:\n    return {expr};\n\ndef main() -> i32:\n    return 0;", type = r#type)).unwrap();
+        let lines = function(&wat, "f");
+
+        assert!(lines.windows(expected.len()).any(|window| window == expected), "{dtype}: {expr}:\n{}", lines.join("\n"));
+    }
+
+    let wat = generate_wat("def f(a: u32) -> u64:\n    return a;\n\ndef g(a: i64) -> u8:\n    return a;\n\ndef h(a: i8) -> i8:\n    return ~a;\n\ndef main() -> i32:\n    return 0;").unwrap();
+
+    assert_eq!(function(&wat, "f"), vec!["local.get $a", "i64.extend_i32_u", "return", "unreachable"]);
+    assert_eq!(function(&wat, "g"), vec!["local.get $a", "i32.wrap_i64", "i32.const 255", "i32.and", "return", "unreachable"]);
+    // the host may pass any bits above a narrow parameter
+    assert_eq!(function(&wat, "h")[..3], ["local.get $a", "i32.extend8_s", "local.set $a"]);
+    assert!(function(&wat, "h").windows(3).any(|window| window == ["i32.const -1", "i32.xor", "i32.extend8_s"]));
+}
+
+#[test]
+fn test_control_flow() {
+    let wat = generate_wat("def f(n: i32) -> i32:\n    while n > 10:\n        let n: i32 = 1;\n    if n == 0:\n        return 1;\n    elif n == 1:\n        return 2;\n    else:\n        return 3;\n\ndef main() -> i32:\n    return 0;").unwrap();
+
+    assert!(wat.contains("\n    (local $n.1 i32)\n"));
+    assert_eq!(function(&wat, "f"), vec![
+        "block $break0",
+        "loop $continue0",
+        "local.get $n",
+        "i32.const 10",
+        "i32.gt_s",
+        "i32.eqz",
+        "br_if $break0",
+        "i32.const 1",
+        "local.set $n.1",
+        "br $continue0",
+        "end",
+        "end",
+        "block $end1",
+        "block $else2",
+        "local.get $n",
+        "i32.const 0",
+        "i32.eq",
+        "i32.eqz",
+        "br_if $else2",
+        "i32.const 1",
+        "return",
+        "br $end1",
+        "end",
+        "block $else3",
+        "local.get $n",
+        "i32.const 1",
+        "i32.eq",
+        "i32.eqz",
+        "br_if $else3",
+        "i32.const 2",
+        "return",
+        "br $end1",
+        "end",
+        "i32.const 3",
+        "return",
+        "end",
+        "unreachable",
+    ]);
+}
+
+#[test]
+fn test_imports_and_data() {
+    let wat = generate_wat("extern puts(text: str) -> i32;\nextern exit(code: u8) -> void;\n\nlet a: i16 = -2;\nlet b: u64 = 7;\nlet c: str = \"a\\b\";\n\ndef main() -> i32:\n    puts(c);\n    puts(\"a\\b\");\n    puts(\"d\");\n    exit(a);\n    return 0;").unwrap();
+
+    assert!(wat.starts_with("(module\n  (import \"env\" \"puts\" (func $puts (param i32) (result i32)))\n  (import \"env\" \"exit\" (func $exit (param i32)))\n  (memory (export \"memory\") 1)\n"));
+    // every string is stored once, null-terminated
+    assert!(wat.contains("\n  (data (i32.const 1024) \"a\\\\b\\00d\\00\")\n"));
+    assert!(wat.contains("\n  (global $a i32 (i32.const -2))\n  (global $b i64 (i64.const 7))\n  (global $c i32 (i32.const 1024))\n"));
+
+    let main = function(&wat, "main");
+    assert!(main.windows(3).any(|window| window == ["global.get $c", "call $puts", "drop"]));
+    assert!(main.windows(2).any(|window| window == ["i32.const 1024", "call $puts"]));
+    assert!(main.windows(2).any(|window| window == ["i32.const 1028", "call $puts"]));
+    assert!(main.windows(4).any(|window| window == ["i32.const -2", "i32.const 255", "i32.and", "call $exit"]));
+}
+
+#[test]
+fn test_entry() {
+    let wat = generate_wat_with("def main() -> i32:\n    return 3;", Entry::Freestanding).unwrap();
+
+    assert!(wat.contains("\n  (import \"wasi_snapshot_preview1\" \"proc_exit\" (func $proc_exit (param i32)))\n"));
+    assert!(wat.ends_with("\n  (func $_start (export \"_start\")\n    call $main\n    call $proc_exit\n  )\n)\n"));
+
+    // a `main` without a result exits with 0
+    let wat = generate_wat_with("extern work() -> void;\n\ndef main() -> void:\n    work();", Entry::Freestanding).unwrap();
+    assert!(wat.contains("\n    call $main\n    i32.const 0\n    call $proc_exit\n"));
+    assert!(!generate_wat("def main() -> i32:\n    return 0;").unwrap().contains("_start"));
+}
+
+// the variables whose address is taken live in a frame the function takes from `$sp` and gives back on return
+#[test]
+fn test_address_of() {
+    let wat = generate_wat("extern fill(n: *u8, m: *i64) -> void;\n\ndef f(n: u8) -> i64:\n    let m: i64 = 0;\n    fill(&n, &m);\n    return m;\n\ndef main() -> i32:\n    return 0;").unwrap();
+
+    assert!(wat.contains("\n  (memory (export \"memory\") 2)\n  (global $sp (mut i32) (i32.const 66560))\n"));
+    assert!(wat.contains("\n  (func $f (export \"f\") (param $n i32) (result i64)\n    (local $.frame i32)\n"));
+    assert_eq!(function(&wat, "f"), vec![
+        "global.get $sp", "local.tee $.frame", "i32.const 16", "i32.sub", "global.set $sp",
+        "local.get $n", "i32.const 255", "i32.and", "local.set $n",
+        "local.get $.frame", "i32.const 8", "i32.sub", "local.get $n", "i32.store8",
+        "local.get $.frame", "i32.const 16", "i32.sub", "i64.const 0", "i64.store",
+        "local.get $.frame", "i32.const 8", "i32.sub", "local.get $.frame", "i32.const 16", "i32.sub", "call $fill",
+        "local.get $.frame", "i32.const 16", "i32.sub", "i64.load",
+        "local.get $.frame", "global.set $sp", "return",
+        "unreachable",
+    ]);
+
+    // without a frame, there is neither a stack nor `$sp`
+    assert_eq!(function(&wat, "main"), vec!["i32.const 0", "return", "unreachable"]);
+    assert!(!generate_wat("def main() -> i32:\n    return 0;").unwrap().contains("$sp"));
+
+    let global = generate_wat("extern fill(n: *i32) -> void;\n\nlet g: i32 = 1;\n\ndef main() -> void:\n    fill(&g);");
+    assert_eq!(global, Err("[Wasm] taking the address of global `g` is not supported".into()));
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(generate_wat("def main() -> i32:\n    return g();"), Err("[Wasm] unable to find function `g`".into()));
+    assert_eq!(generate_wat("def main() -> i32:\n    return x;"), Err("[Wasm] identifier `x` not found".into()));
+    assert_eq!(generate_wat("def f(a: str) -> i32:\n    return -a;\n\ndef main() -> i32:\n    return 0;"), Err("[Wasm] operator UnaryMinus cannot be applied to str".into()));
+    assert_eq!(generate_wat("def main() -> i32:\n    if 1:\n        return 1;\n    return 0;"), Err("[Wasm] expected a bool condition, found i32".into()));
+    assert_eq!(generate_wat("def f(a: str) -> i32:\n    return 0;\n\ndef main() -> i32:\n    return f(1);"), Err("[Wasm] `f` expects str, found i32".into()));
+    assert_eq!(generate_wat("def f(a: i128) -> i32:\n    return 0;\n\ndef main() -> i32:\n    return 0;"), Err("[Wasm] i128 of `a` is not supported".into()));
+    assert_eq!(generate_wat("def f() -> i32:\n    return 0;"), Err("[Entry] the program has no `main` function".into()));
+}
+
+// every program of the corpus without 128-bit integers must be a valid module
+#[test]
+fn test_corpus_validates() {
+    if Command::new("wat2wasm").arg("--version").output().is_err() {
+        eprintln!("skipping: wat2wasm is not installed");
+        return;
+    }
+
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    let wat = env::temp_dir().join(format!("mamba-wasm-{}.wat", std::process::id()));
+    let wasm = wat.with_extension("wasm");
+
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+
+        let module = match WasmGen::new(load(&path)).with_entry(Entry::C).generate_wat().map_err(|err| err.0) {
+            Ok(module) => module,
+            Err(err) if err.ends_with("is not supported") => continue,
+            Err(err) => panic!("{}: {}", path.display(), err),
+        };
+        fs::write(&wat, module).unwrap();
+
+        let output = Command::new("wat2wasm").arg(&wat).arg("-o").arg(&wasm).output().unwrap();
+        assert!(output.status.success(), "{}:\n{}", path.display(), String::from_utf8_lossy(&output.stderr));
+    }
+
+    let _ = fs::remove_file(wat);
+    let _ = fs::remove_file(wasm);
+}
\ No newline at end of file
@@ -0,0 +1,157 @@
+use mamba::codegen::llvm::entry::Entry;
+use mamba::codegen::x86_64::abi::{assign, ArgLoc};
+use mamba::codegen::x86_64::X86Gen;
+use mamba::fold::ConstFolder;
+use mamba::lexer::Lexer;
+use mamba::parser::Parser;
+use mamba::types::{DataType, SignedInteger, UnsignedInteger};
+
+fn generate_asm(source: &str) -> Result<String, String> {
+    let mut lexer = Lexer::new(source.into());
+    let tokens = lexer.get_tokens().unwrap();
+
+    let mut ast = Parser::with_spans(tokens, lexer.get_spans()).parse_all();
+    ConstFolder::new().fold(&mut ast).unwrap();
+
+    X86Gen::new(ast).with_entry(Entry::C).generate_asm().map_err(|err| err.0)
+}
+
+// the lines of the function `name`, without its label and directives
+fn function<'a>(asm: &'a str, name: &str) -> Vec<&'a str> {
+    asm.lines()
+        .skip_while(|line| *line != format!("{}:", name))
+        .skip(1)
+        .take_while(|line| !line.starts_with("    .size"))
+        .map(|line| line.trim())
+        .collect()
+}
+
+#[test]
+fn test_function() {
+    let asm = generate_asm("def add(a: i32, b: i32) -> i32:\n    return a + b;\n\ndef main() -> void:\n    add(1, 2);").unwrap();
+
+    assert_eq!(function(&asm, "add"), vec![
+        "pushq %rbp",
+        "movq %rsp, %rbp",
+        "movq %rdi, %rax",
+        "movslq %eax, %rax",
+        "movq %rax, %r10",
+        "movq %rsi, %rax",
+        "movslq %eax, %rax",
+        "movq %rax, %r11",
+        "movq %r10, %rax",
+        "movq %rax, %r10",
+        "movq %r11, %rax",
+        "movq %rax, %rcx",
+        "movq %r10, %rax",
+        "addq %rcx, %rax",
+        "movslq %eax, %rax",
+        "jmp .L0",
+        "ud2",
+        ".L0:",
+        "leave",
+        "ret",
+    ]);
+
+    // the C entry point calls the renamed `main` and exits with 0 when it returns nothing
+    assert_eq!(function(&asm, "main"), vec!["subq $8, %rsp", "call mamba.main", "xorl %eax, %eax", "addq $8, %rsp", "ret"]);
+    assert!(function(&asm, "mamba.main").windows(3).any(|window| window == ["movq %r11, %rsi", "call add@PLT", "movslq %eax, %rax"]));
+    assert!(asm.ends_with("\n    .section .note.GNU-stack,\"\",@progbits\n"));
+}
+
+// every width is kept extended to 64 bits by its signedness
+#[test]
+fn test_integer_lowering() {
+    let cases = [
+        ("i8", "a + b", vec!["addq %rcx, %rax", "movsbq %al, %rax"]),
+        ("u8", "a * b", vec!["imulq %rcx, %rax", "movzbl %al, %eax"]),
+        ("u16", "a << b", vec!["shlq %cl, %rax", "movzwl %ax, %eax"]),
+        ("i32", "a >> b", vec!["sarl %cl, %eax", "movslq %eax, %rax"]),
+        ("u32", "a / b", vec!["xorl %edx, %edx", "divq %rcx", "movl %eax, %eax"]),
+        ("i64", "a % b", vec!["cqto", "idivq %rcx", "movq %rdx, %rax"]),
+        ("u64", "a >> b", vec!["shrq %cl, %rax"]),
+        ("i128", "a + b", vec!["addq %rcx, %rax", "adcq %r8, %rdx"]),
+        ("u128", "a / b", vec!["movq %r8, %rcx", "call __udivti3@PLT"]),
+        ("i128", "a > b", vec!["cmpq %rax, %rcx", "sbbq %rdx, %r8", "setl %al", "movzbl %al, %eax"]),
+        ("u64", "a <= b", vec!["cmpq %rcx, %rax", "setbe %al", "movzbl %al, %eax"]),
+    ];
+
+    for (dtype, expr, expected) in cases {
+        let asm = generate_asm(&format!("def f(a: {dtype}, b: {dtype}) -> bool:\n    return ({expr}) == ({expr});\n\ndef main() -> i32:\n    return 0;")).unwrap();
+        let lines = function(&asm, "f");
+
+        assert!(lines.windows(expected.len()).any(|window| window == expected), "{dtype}: {expr}:\n{}", lines.join("\n"));
+    }
+
+    let asm = generate_asm("def f(a: i8) -> u64:\n    return a;\n\ndef g(a: u32) -> i128:\n    return a;\n\ndef h(a: i64) -> i128:\n    return a;\n\ndef main() -> i32:\n    return 0;").unwrap();
+
+    // an `i8` is already sign-extended to 64 bits
+    assert!(function(&asm, "f").windows(2).any(|window| window == ["movq %r10, %rax", "jmp .L0"]));
+    assert!(function(&asm, "g").windows(2).any(|window| window == ["xorl %edx, %edx", "jmp .L1"]));
+    assert!(function(&asm, "h").windows(2).any(|window| window == ["cqto", "jmp .L2"]));
+}
+
+#[test]
+fn test_calling_convention() {
+    let i64 = DataType::SignedInteger(SignedInteger::i64);
+    let u128 = DataType::UnsignedInteger(UnsignedInteger::u128);
+
+    assert_eq!(assign(&[i64, u128, i64]), (vec![ArgLoc::Reg(0), ArgLoc::Pair(1), ArgLoc::Reg(3)], 0));
+    // a 128-bit value is not split between the last register and the stack, the next one still gets a register
+    assert_eq!(assign(&[i64; 5].iter().copied().chain([u128, i64]).collect::<Vec<DataType>>()), (
+        vec![ArgLoc::Reg(0), ArgLoc::Reg(1), ArgLoc::Reg(2), ArgLoc::Reg(3), ArgLoc::Reg(4), ArgLoc::Stack(0), ArgLoc::Reg(5)],
+        16,
+    ));
+    assert_eq!(assign(&[i64; 7].iter().copied().chain([u128]).collect::<Vec<DataType>>()).1, 32);
+
+    // the seventh argument goes onto the stack, where the callee finds it above the return address
+    let params = (0..7).map(|n| format!("p{n}: i64")).collect::<Vec<String>>().join(", ");
+    let asm = generate_asm(&format!("def f({params}) -> i64:\n    return p6;\n\ndef main() -> i32:\n    return f(1, 2, 3, 4, 5, 6, 7);")).unwrap();
+
+    assert!(function(&asm, "f").windows(2).any(|window| window == ["movq 16(%rbp), %rax", "movq %rax, %r10"]));
+    // the arguments are kept in registers until the last one is stored into a 16-byte area
+    let main = function(&asm, "mamba.main");
+    assert!(main.windows(4).any(|window| window == ["subq $16, %rsp", "movq %r15, %rax", "movq %rax, 0(%rsp)", "movq %r10, %rdi"]));
+    assert!(main.windows(3).any(|window| window == ["movq %r14, %r9", "call f@PLT", "addq $16, %rsp"]));
+}
+
+// values are kept in registers, those living across a call in callee-saved ones
+#[test]
+fn test_register_allocation() {
+    let asm = generate_asm("def g(a: i64) -> i64:\n    return a * 2;\n\ndef f(n: i64) -> i64:\n    let a: i64 = g(n);\n    let b: i64 = g(a);\n    return a + b;\n\ndef main() -> i32:\n    return 0;").unwrap();
+    let lines = function(&asm, "f");
+
+    assert!(lines.windows(3).any(|window| window == ["call g@PLT", "movq %rax, %rbx", "movq %rbx, %rax"]));
+    assert!(lines.windows(2).any(|window| window == ["call g@PLT", "movq %rax, %r10"]));
+    assert_eq!(lines.iter().filter(|line| line.contains("(%rbp)")).collect::<Vec<_>>(), [&"movq %rbx, -8(%rbp)", &"movq -8(%rbp), %rbx"]);
+
+    // with more values living across a call than callee-saved registers, the rest are spilled into the frame
+    let lets = (0..8).map(|n| format!("    let v{n}: i64 = n + {n};\n")).collect::<String>();
+    let sum = (0..8).map(|n| format!("v{n}")).collect::<Vec<String>>().join(" + ");
+    let asm = generate_asm(&format!("def g(a: i64) -> i64:\n    return a;\n\ndef f(n: i64) -> i64:\n{lets}    g(n);\n    return {sum};\n\ndef main() -> i32:\n    return 0;")).unwrap();
+    let lines = function(&asm, "f");
+
+    assert!(lines.contains(&"movq %rax, -8(%rbp)"));
+    assert!(lines.contains(&"movq -8(%rbp), %rax"));
+}
+
+#[test]
+fn test_data() {
+    let asm = generate_asm("let a: i16 = -2;\nlet b: u128 = 1;\nlet c: bool = True;\nlet d: str = \"a\\b\";\n\ndef f() -> str:\n    return d;\n\ndef main() -> i32:\n    return 0;").unwrap();
+
+    assert!(asm.contains("\n    .data\n    .globl a\n    .p2align 1\na:\n    .short 65534\n"));
+    assert!(asm.contains("\nb:\n    .quad 1\n    .quad 0\n"));
+    assert!(asm.contains("\nc:\n    .byte 1\n"));
+    assert!(asm.contains("\n    .section .rodata\n    .globl d\nd:\n    .asciz \"a\\\\b\"\n"));
+    assert!(function(&asm, "f").contains(&"leaq d(%rip), %rax"));
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(generate_asm("def main() -> i32:\n    return g();"), Err("[x86-64] unable to find function `g`".into()));
+    assert_eq!(generate_asm("def main() -> i32:\n    return x;"), Err("[x86-64] identifier `x` not found".into()));
+    assert_eq!(generate_asm("def f(a: str) -> i32:\n    return -a;\n\ndef main() -> i32:\n    return 0;"), Err("[x86-64] operator UnaryMinus cannot be applied to str".into()));
+    assert_eq!(generate_asm("def main() -> i32:\n    if 1:\n        return 1;\n    return 0;"), Err("[x86-64] expected a bool condition, found i32".into()));
+    assert_eq!(generate_asm("def f(a: str) -> i32:\n    return 0;\n\ndef main() -> i32:\n    return f(1);"), Err("[x86-64] `f` expects str, found i32".into()));
+    assert_eq!(generate_asm("def f() -> i32:\n    return 0;"), Err("[Entry] the program has no `main` function".into()));
+}
\ No newline at end of file